@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::io;
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use ttx_eng::cli::{self, FileArchive, SegmentArchive};
+use ttx_eng::{testing, Engine, EngineConfig, Retention, TxArchive, TxInput};
+
+const SEED: u64 = 1;
+const TXS: usize = 100_000;
+const CLIENTS: u16 = 1_000;
+
+/// transactions processed before a retained one is archived
+const RETAIN_TXS: u64 = 1_000;
+
+fn csv_input() -> Vec<u8> {
+    let mut input = Vec::new();
+    cli::generate(SEED, CLIENTS, TXS, &mut input).expect("failed to generate the input");
+    input
+}
+
+fn archive_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ttx-eng-bench-{}.archive", name))
+}
+
+fn parse(c: &mut Criterion) {
+    let input = csv_input();
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Elements(TXS as u64));
+    group.sample_size(20);
+
+    group.bench_function("csv", |b| {
+        b.iter(|| cli::parse_input(black_box(input.as_slice())).expect("failed to parse"))
+    });
+    group.finish();
+}
+
+/// engine of a store backend: the transaction map alone, or with the transactions archived
+/// once retained and loaded back by the disputes
+fn engine_with(archive: Option<Box<dyn TxArchive>>) -> Engine {
+    let mut config = EngineConfig::default();
+    if archive.is_some() {
+        config.retention = Retention {
+            after_txs: Some(RETAIN_TXS),
+            window: None,
+        };
+    }
+
+    let mut engine = Engine::with_config(config);
+    if let Some(archive) = archive {
+        engine.set_archive(archive);
+    }
+    engine
+}
+
+fn apply(mut engine: Engine, txs: &[TxInput]) -> Engine {
+    for tx in txs {
+        let _ = engine.process_tx(tx);
+    }
+    engine
+}
+
+fn engine(c: &mut Criterion) {
+    let txs = testing::workload(SEED, CLIENTS, TXS);
+    let mut group = c.benchmark_group("engine");
+    group.throughput(Throughput::Elements(TXS as u64));
+    group.sample_size(20);
+
+    group.bench_function("memory", |b| {
+        b.iter(|| apply(engine_with(None), black_box(&txs)))
+    });
+    group.bench_function("file-archive", |b| {
+        b.iter(|| {
+            let archive = FileArchive::open(&archive_path("file"), false)
+                .expect("failed to open the archive");
+            apply(engine_with(Some(Box::new(archive))), black_box(&txs))
+        })
+    });
+    group.bench_function("segment-archive", |b| {
+        b.iter(|| {
+            let archive = SegmentArchive::open(&archive_path("segment"), false)
+                .expect("failed to open the archive");
+            apply(engine_with(Some(Box::new(archive))), black_box(&txs))
+        })
+    });
+    group.finish();
+
+    let _ = std::fs::remove_file(archive_path("file"));
+    let _ = std::fs::remove_file(archive_path("segment"));
+}
+
+/// inserts and then looks up every tx id in a map of the hasher, like the transaction map of
+/// the engine
+fn tx_map<S: BuildHasher + Default>(txs: &[TxInput]) -> usize {
+    let mut map: HashMap<u32, (u16, Option<rust_decimal::Decimal>), S> =
+        HashMap::with_capacity_and_hasher(txs.len(), S::default());
+    for tx in txs {
+        map.entry(tx.id).or_insert((tx.client_id, tx.amount));
+    }
+    txs.iter().filter(|tx| map.contains_key(&tx.id)).count()
+}
+
+fn hashers(c: &mut Criterion) {
+    let txs = testing::workload(SEED, CLIENTS, TXS);
+    let mut group = c.benchmark_group("tx-map");
+    group.throughput(Throughput::Elements(TXS as u64));
+
+    group.bench_function("std", |b| {
+        b.iter(|| tx_map::<std::hash::RandomState>(black_box(&txs)))
+    });
+    group.bench_function("ahash", |b| {
+        b.iter(|| tx_map::<ahash::RandomState>(black_box(&txs)))
+    });
+    group.bench_function("fx", |b| {
+        b.iter(|| tx_map::<rustc_hash::FxBuildHasher>(black_box(&txs)))
+    });
+    group.finish();
+}
+
+fn end_to_end(c: &mut Criterion) {
+    let input = csv_input();
+    let mut group = c.benchmark_group("end-to-end");
+    group.throughput(Throughput::Elements(TXS as u64));
+    group.sample_size(20);
+
+    group.bench_function("csv", |b| {
+        b.iter(|| {
+            cli::process_input(black_box(input.as_slice()), io::sink()).expect("failed to process")
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse, engine, hashers, end_to_end);
+criterion_main!(benches);
@@ -0,0 +1,114 @@
+use ahash::AHashMap;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::models::tx::{ClientId, TxId};
+
+/// why a deposit was reported as suspicious
+#[derive(Serialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AmlTrigger {
+    /// the deposit alone exceeds the threshold
+    Single,
+    /// the deposits of the client add up to more than the threshold
+    Cumulative,
+}
+
+/// suspicious activity row, one per triggering deposit
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct AmlFlag {
+    pub(crate) client: ClientId,
+    pub(crate) tx: TxId,
+    pub(crate) amount: Decimal,
+    pub(crate) cumulative: Decimal,
+    pub(crate) trigger: AmlTrigger,
+}
+
+/// sums the accepted deposits of each client against the aml threshold, once a client
+/// is over the threshold each of its later deposits is reported too
+pub(crate) struct AmlMonitor {
+    threshold: Decimal,
+    deposits: AHashMap<ClientId, Decimal>,
+}
+
+impl AmlMonitor {
+    pub(crate) fn new(threshold: Decimal) -> Self {
+        Self {
+            threshold,
+            deposits: AHashMap::new(),
+        }
+    }
+
+    /// records an accepted deposit, returns the flag when it triggers the threshold
+    pub(crate) fn deposit(
+        &mut self,
+        client: ClientId,
+        tx: TxId,
+        amount: Decimal,
+    ) -> Option<AmlFlag> {
+        let cumulative = self.deposits.entry(client).or_default();
+        *cumulative = cumulative.saturating_add(amount);
+
+        let trigger = if amount > self.threshold {
+            AmlTrigger::Single
+        } else if *cumulative > self.threshold {
+            AmlTrigger::Cumulative
+        } else {
+            return None;
+        };
+
+        Some(AmlFlag {
+            client,
+            tx,
+            amount,
+            cumulative: *cumulative,
+            trigger,
+        })
+    }
+
+    /// deposit sums of the clients, saved in checkpoints
+    pub(crate) fn deposits(&self) -> Vec<(ClientId, Decimal)> {
+        self.deposits
+            .iter()
+            .map(|(client, sum)| (*client, *sum))
+            .collect()
+    }
+
+    pub(crate) fn restore(&mut self, deposits: Vec<(ClientId, Decimal)>) {
+        self.deposits = deposits.into_iter().collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn deposit_flags() {
+        let mut monitor = AmlMonitor::new(dec!(10000));
+
+        assert_eq!(monitor.deposit(1, 1, dec!(6000)), None);
+        assert_eq!(monitor.deposit(2, 2, dec!(10000)), None);
+        assert_eq!(
+            monitor.deposit(3, 3, dec!(10000.01)),
+            Some(AmlFlag {
+                client: 3,
+                tx: 3,
+                amount: dec!(10000.01),
+                cumulative: dec!(10000.01),
+                trigger: AmlTrigger::Single,
+            })
+        );
+        assert_eq!(
+            monitor.deposit(1, 4, dec!(4000.5)).map(|flag| flag.trigger),
+            Some(AmlTrigger::Cumulative)
+        );
+        // later deposits of a client over the threshold are reported too
+        assert_eq!(
+            monitor.deposit(1, 5, dec!(1)).map(|flag| flag.cumulative),
+            Some(dec!(10001.5))
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::VecDeque;
+
+use ahash::AHashMap;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::tx::{ClientId, TxId};
+use crate::models::tx_type::TxType;
+
+/// when a deposit or withdrawal is an outlier against the history of its client
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct AnomalyThresholds {
+    /// multiple of the trailing average a transaction must exceed to be flagged
+    pub factor: Decimal,
+
+    /// previous transactions of the same type the trailing average is taken over
+    pub window: usize,
+
+    /// previous transactions of the same type a client needs before it's checked
+    pub min_history: usize,
+}
+
+impl Default for AnomalyThresholds {
+    fn default() -> Self {
+        Self {
+            factor: Decimal::TEN,
+            window: 10,
+            min_history: 3,
+        }
+    }
+}
+
+/// outlier row, one per flagged transaction
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct AnomalyFlag {
+    pub(crate) client: ClientId,
+    pub(crate) tx: TxId,
+    #[serde(rename = "type")]
+    pub(crate) tx_type: TxType,
+    pub(crate) amount: Decimal,
+    /// trailing average of the previous transactions of the same type
+    pub(crate) average: Decimal,
+}
+
+/// trailing amounts of the accepted deposits or withdrawals of a client, saved in checkpoints
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub(crate) struct AmountHistory {
+    client: ClientId,
+    tx_type: TxType,
+    amounts: Vec<Decimal>,
+}
+
+/// compares the accepted deposits and withdrawals of each client with the trailing average of
+/// its previous ones of the same type, flags never reject a transaction
+pub(crate) struct AnomalyMonitor {
+    thresholds: AnomalyThresholds,
+    history: AHashMap<(ClientId, TxType), (VecDeque<Decimal>, Decimal)>,
+}
+
+impl AnomalyMonitor {
+    pub(crate) fn new(thresholds: AnomalyThresholds) -> Self {
+        Self {
+            thresholds,
+            history: AHashMap::new(),
+        }
+    }
+
+    /// records an accepted deposit or withdrawal, returns the flag when it's an outlier
+    pub(crate) fn record(
+        &mut self,
+        client: ClientId,
+        tx: TxId,
+        tx_type: TxType,
+        amount: Decimal,
+    ) -> Option<AnomalyFlag> {
+        let (amounts, sum) = self.history.entry((client, tx_type)).or_default();
+
+        let flag = (amounts.len() >= self.thresholds.min_history.max(1))
+            .then(|| *sum / Decimal::from(amounts.len()))
+            .filter(|average| {
+                average
+                    .checked_mul(self.thresholds.factor)
+                    .is_some_and(|limit| amount > limit)
+            })
+            .map(|average| AnomalyFlag {
+                client,
+                tx,
+                tx_type,
+                amount,
+                average: average.round_dp(4).normalize(),
+            });
+
+        amounts.push_back(amount);
+        *sum = sum.saturating_add(amount);
+        if amounts.len() > self.thresholds.window {
+            if let Some(oldest) = amounts.pop_front() {
+                *sum -= oldest;
+            }
+        }
+
+        flag
+    }
+
+    pub(crate) fn history(&self) -> Vec<AmountHistory> {
+        self.history
+            .iter()
+            .map(|((client, tx_type), (amounts, _))| AmountHistory {
+                client: *client,
+                tx_type: *tx_type,
+                amounts: amounts.iter().copied().collect(),
+            })
+            .collect()
+    }
+
+    pub(crate) fn restore(&mut self, history: Vec<AmountHistory>) {
+        self.history = history
+            .into_iter()
+            .map(|history| {
+                let sum = history
+                    .amounts
+                    .iter()
+                    .fold(Decimal::ZERO, |sum, amount| sum.saturating_add(*amount));
+                (
+                    (history.client, history.tx_type),
+                    (history.amounts.into(), sum),
+                )
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn outlier_flags() {
+        let mut monitor = AnomalyMonitor::new(AnomalyThresholds {
+            window: 3,
+            ..Default::default()
+        });
+
+        for (tx, amount) in [(1, dec!(10)), (2, dec!(20)), (3, dec!(30))] {
+            assert_eq!(monitor.record(1, tx, TxType::Withdrawal, amount), None);
+        }
+        // the first transactions of another type or client have no history
+        assert_eq!(monitor.record(1, 4, TxType::Deposit, dec!(1000)), None);
+        assert_eq!(monitor.record(2, 5, TxType::Withdrawal, dec!(1000)), None);
+
+        assert_eq!(monitor.record(1, 6, TxType::Withdrawal, dec!(200)), None);
+        // the average of 20, 30 and 200 is 83.3333
+        assert_eq!(
+            monitor.record(1, 7, TxType::Withdrawal, dec!(833.34)),
+            Some(AnomalyFlag {
+                client: 1,
+                tx: 7,
+                tx_type: TxType::Withdrawal,
+                amount: dec!(833.34),
+                average: dec!(83.3333),
+            })
+        );
+
+        let mut restored = AnomalyMonitor::new(AnomalyThresholds::default());
+        restored.restore(monitor.history());
+        assert_eq!(
+            restored
+                .record(1, 8, TxType::Withdrawal, dec!(10000))
+                .map(|flag| flag.average),
+            Some(dec!(354.4467))
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use std::error::Error;
+use std::fs::File;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use ttx_eng::difftest;
+
+/// Diffs the engine against a reference model on generated workloads
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// seed of the first workload, the next ones take the following seeds
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// number of workloads
+    #[arg(long, default_value_t = 100)]
+    runs: u64,
+
+    /// transactions per workload
+    #[arg(long, default_value_t = 1000)]
+    txs: usize,
+
+    /// clients of the transactions
+    #[arg(long, default_value_t = 20)]
+    clients: u16,
+
+    /// threads checking the workloads, the lowest diverging seed is reported whatever their number
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    jobs: u64,
+
+    /// csv the transactions of a diverging workload are written to, up to the divergence
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let args = Args::parse();
+
+    let seeds = args.seed..args.seed.saturating_add(args.runs);
+    if let Some((seed, divergence)) =
+        difftest::first_divergence(seeds, usize::try_from(args.jobs)?, args.clients, args.txs)
+    {
+        let txs = difftest::workload(seed, args.clients, args.txs);
+        let tx = &txs[divergence.index()];
+        println!(
+            "seed {}: {} ({} of client {}, tx {})",
+            seed, divergence, tx.tx_type, tx.client_id, tx.id
+        );
+        if let Some(path) = args.out.as_ref() {
+            let mut writer = csv::Writer::from_writer(File::create(path)?);
+            for tx in &txs[..=divergence.index()] {
+                writer.serialize(tx)?;
+            }
+            writer.flush()?;
+        }
+        return Ok(ExitCode::FAILURE);
+    }
+
+    println!(
+        "{} workloads of {} transactions matched the reference",
+        args.runs, args.txs
+    );
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::models::tx::ClientId;
+
+/// accepted deposits and chargebacks of a client, a chargeback counts the amount of its deposit
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub(crate) struct ChargebackTotals {
+    deposits: u64,
+    deposit_amount: Decimal,
+    chargebacks: u64,
+    chargeback_amount: Decimal,
+}
+
+impl ChargebackTotals {
+    fn add(&mut self, other: &Self) {
+        self.deposits += other.deposits;
+        self.deposit_amount = self.deposit_amount.saturating_add(other.deposit_amount);
+        self.chargebacks += other.chargebacks;
+        self.chargeback_amount = self
+            .chargeback_amount
+            .saturating_add(other.chargeback_amount);
+    }
+}
+
+/// row of the chargeback report, the client is its id or `total` for the overall row
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct ChargebackRow<C> {
+    pub(crate) client: C,
+    pub(crate) deposits: u64,
+    pub(crate) deposit_amount: Decimal,
+    pub(crate) chargebacks: u64,
+    pub(crate) chargeback_amount: Decimal,
+    /// chargebacks per deposit
+    pub(crate) chargeback_rate: Decimal,
+    /// charged back amount per deposited amount
+    pub(crate) chargeback_amount_rate: Decimal,
+}
+
+impl<C> ChargebackRow<C> {
+    fn new(client: C, totals: &ChargebackTotals) -> Self {
+        Self {
+            client,
+            deposits: totals.deposits,
+            deposit_amount: totals.deposit_amount,
+            chargebacks: totals.chargebacks,
+            chargeback_amount: totals.chargeback_amount,
+            chargeback_rate: rate(
+                Decimal::from(totals.chargebacks),
+                Decimal::from(totals.deposits),
+            ),
+            chargeback_amount_rate: rate(totals.chargeback_amount, totals.deposit_amount),
+        }
+    }
+}
+
+/// ratio rounded to 4 decimal places, zero without a denominator
+fn rate(value: Decimal, of: Decimal) -> Decimal {
+    value
+        .checked_div(of)
+        .map_or(Decimal::ZERO, |rate| rate.round_dp(4).normalize())
+}
+
+/// chargeback counts and amounts of each client, relative to its deposits
+#[derive(Debug, Default)]
+pub(crate) struct ChargebackReport {
+    clients: BTreeMap<ClientId, ChargebackTotals>,
+}
+
+impl ChargebackReport {
+    pub(crate) fn deposit(&mut self, client: ClientId, amount: Decimal) {
+        let totals = self.clients.entry(client).or_default();
+        totals.deposits += 1;
+        totals.deposit_amount = totals.deposit_amount.saturating_add(amount);
+    }
+
+    /// counts an accepted chargeback of a deposit of the amount
+    pub(crate) fn chargeback(&mut self, client: ClientId, amount: Decimal) {
+        let totals = self.clients.entry(client).or_default();
+        totals.chargebacks += 1;
+        totals.chargeback_amount = totals.chargeback_amount.saturating_add(amount);
+    }
+
+    /// one row per client sorted by client id, and the overall row
+    pub(crate) fn rows(&self) -> (Vec<ChargebackRow<ClientId>>, ChargebackRow<&'static str>) {
+        let mut overall = ChargebackTotals::default();
+        let rows = self
+            .clients
+            .iter()
+            .map(|(client, totals)| {
+                overall.add(totals);
+                ChargebackRow::new(*client, totals)
+            })
+            .collect();
+
+        (rows, ChargebackRow::new("total", &overall))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn rates() {
+        let mut report = ChargebackReport::default();
+        report.deposit(2, dec!(10));
+        report.deposit(1, dec!(30));
+        report.deposit(1, dec!(10));
+        report.deposit(1, dec!(20));
+        report.chargeback(1, dec!(10));
+
+        let (rows, overall) = report.rows();
+        assert_eq!(
+            rows,
+            vec![
+                ChargebackRow {
+                    client: 1,
+                    deposits: 3,
+                    deposit_amount: dec!(60),
+                    chargebacks: 1,
+                    chargeback_amount: dec!(10),
+                    chargeback_rate: dec!(0.3333),
+                    chargeback_amount_rate: dec!(0.1667),
+                },
+                ChargebackRow {
+                    client: 2,
+                    deposits: 1,
+                    deposit_amount: dec!(10),
+                    chargebacks: 0,
+                    chargeback_amount: dec!(0),
+                    chargeback_rate: dec!(0),
+                    chargeback_amount_rate: dec!(0),
+                },
+            ]
+        );
+        assert_eq!(overall.deposits, 4);
+        assert_eq!(overall.chargeback_rate, dec!(0.25));
+        assert_eq!(overall.chargeback_amount_rate, dec!(0.1429));
+
+        // clients without deposits have zero rates
+        report.chargeback(3, dec!(5));
+        assert_eq!(report.rows().0[2].chargeback_rate, dec!(0));
+    }
+}
@@ -1,58 +1,2494 @@
+mod alerts;
+mod archive;
+mod checkpoint;
+#[cfg(feature = "clickhouse")]
+mod clickhouse;
+mod cross_check;
+#[cfg(feature = "tui")]
+mod dashboard;
+#[cfg(feature = "encrypt")]
+mod encryption;
+mod filters;
+mod manifest;
+mod output;
+mod pipeline;
+mod pipeline_config;
+mod profile;
+mod pseudonyms;
+mod sinks;
+mod snapshots;
+
+pub use crate::anomaly::AnomalyThresholds;
+pub use crate::import::encoding::InputEncoding;
+pub use crate::import::fixed_width::{FieldRange, FixedWidthLayout};
+pub use crate::import::records::DecimalSeparator;
+pub use crate::import::xml::XmlMapping;
+pub use crate::interest::{DayCount, InterestRates};
+pub use crate::models::report_transform::{ReportColumn, ReportTransform, Rounding};
+pub use crate::report::OutputFormat;
+pub use crate::rollup::Rollup;
+pub use alerts::AlertLog;
+#[cfg(feature = "webhook")]
+pub use alerts::AlertWebhook;
+pub use archive::{ArchiveLayout, FileArchive, SegmentArchive};
+pub use checkpoint::CheckpointOptions;
+#[cfg(feature = "clickhouse")]
+pub use clickhouse::ClickHouseInserts;
+#[cfg(feature = "encrypt")]
+pub use encryption::Encryption;
+pub use filters::InputFilter;
+pub use manifest::{InputChecksum, ManifestOptions, OutputKind};
+pub use output::FlushInterval;
+pub use pipeline_config::{EngineSection, PipelineConfig, SinkConfig, SourceConfig};
+pub use pseudonyms::Pseudonyms;
+pub use sinks::ReportSink;
+use sinks::SinkInput;
+pub use snapshots::{ReportInterval, SnapshotOptions};
+
+use crate::aml::AmlMonitor;
+use crate::anomaly::AnomalyMonitor;
+use crate::chargebacks::ChargebackReport;
+use crate::cli::checkpoint::Checkpoint;
+use crate::cli::cross_check::CrossCheck;
+#[cfg(feature = "tui")]
+use crate::cli::dashboard::Dashboard;
+use crate::cli::manifest::{DigestWriter, FileDigest, Manifest};
+use crate::cli::output::{FlushOnWrite, REPORT_BUFFER};
+use crate::cli::pipeline::{Rows, CHANNEL_BATCHES};
+use crate::cli::profile::Profile;
+use crate::cli::snapshots::{Breakdown, Snapshots, Stats};
+use crate::disputes::{Decision, DisputeAging};
 use crate::engine;
+use crate::engine::admin::{AdminAudit, AdminCommand};
+use crate::engine::alerts::AlertObserver;
+use crate::engine::archive::TxArchive;
+use crate::engine::config::{DuplicatePolicy, EngineConfig, WithdrawalPolicy};
+use crate::engine::state::EngineState;
+use crate::import;
+use crate::import::encoding::Decoder;
+use crate::import::records::RecordParser;
+use crate::interest::Accruals;
+use crate::models::client::{
+    ClientReport, LockedRow, RejectionsRow, ReportColumns, ReportRow, LOCKED_HEADER,
+    REJECTIONS_HEADER,
+};
+use crate::models::dispute_state::DisputeState;
+use crate::models::errors::{self, ErrorKind};
 use crate::models::tx::TxInput;
-use clap::Parser;
+use crate::models::tx_result::{ResultBalances, TxResult, TxStatus};
+use crate::models::tx_type::TxType;
+use crate::reconcile;
+use crate::reconcile::ExternalBalance;
+use crate::rollup::{BalanceHistory, BalanceRow, DailyRollup};
+use crate::settlements::SettlementRow;
+use crate::shard;
+use crate::simulate;
+use crate::workload;
+use crate::workload::{Rng, WorkloadOptions};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
 use csv::Trim;
+use memmap2::Mmap;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::error::Error;
+use std::fs::File;
 use std::io;
-use tracing::debug;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use tracing::{debug, trace};
 
 /// Simple toy payments engine
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[command(group(ArgGroup::new("retention").multiple(true)))]
+#[command(group(ArgGroup::new("alert_rules").multiple(true).requires("alert_outputs")))]
+#[command(group(ArgGroup::new("alert_outputs").multiple(true).requires("alert_rules")))]
 pub struct Cli {
-    /// path of the input file
-    pub file_path: String,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// path of the input file, `-` reads stdin
+    #[arg(required = true)]
+    pub file_path: Option<String>,
+
+    /// memory map the input file instead of reading it through a buffer
+    #[arg(long)]
+    pub mmap: bool,
+
+    /// input format, inferred from the file extension when omitted
+    #[arg(long, value_enum)]
+    pub format: Option<InputFormat>,
+
+    /// client id for statement formats, defaults to the statement account id
+    #[arg(long)]
+    pub client: Option<u16>,
+
+    /// json file of the field offsets and lengths of fixed width input, or of the element mapping of xml input
+    #[arg(long)]
+    pub layout: Option<String>,
+
+    /// path of the per record results output (accepted / rejected / parse_error)
+    #[arg(long)]
+    pub results: Option<String>,
+
+    /// add the available, held and total funds of the client after each record to the per record results
+    #[arg(long)]
+    pub result_balances: bool,
+
+    /// clickhouse http interface the per record results are inserted into, e.g. http://localhost:8123
+    #[cfg(feature = "clickhouse")]
+    #[arg(long, conflicts_with_all = ["results", "pseudonymize"])]
+    pub results_clickhouse: Option<String>,
+
+    /// clickhouse table of the per record results, with the columns of the --results csv
+    #[cfg(feature = "clickhouse")]
+    #[arg(long, default_value = "tx_results")]
+    pub results_table: String,
+
+    /// handling of transactions reusing an already processed tx id
+    #[arg(long, value_enum, default_value_t = DuplicatePolicy::Reject)]
+    pub duplicate_policy: DuplicatePolicy,
+
+    /// funds withdrawals can take, the total takes the funds held by disputes last
+    #[arg(long, value_enum, default_value_t = WithdrawalPolicy::Available)]
+    pub withdrawal_policy: WithdrawalPolicy,
+
+    /// reject withdrawals while a dispute of the client is open
+    #[arg(long)]
+    pub no_withdrawal_on_dispute: bool,
+
+    /// percent of each withdrawal charged as a fee with it
+    #[arg(long)]
+    pub withdrawal_fee_pct: Option<Decimal>,
+
+    /// flat fee charged with each chargeback
+    #[arg(long)]
+    pub chargeback_fee: Option<Decimal>,
+
+    /// flat fee charged with each withdrawal, with the percent one
+    #[arg(long)]
+    pub withdrawal_fee: Option<Decimal>,
+
+    /// withdrawals of each client charged no withdrawal fee before the others
+    #[arg(long)]
+    pub free_withdrawals: Option<u32>,
+
+    /// basis points of the monthly deposits of each client over the threshold charged as a fee
+    #[arg(long)]
+    pub deposit_fee_bps: Option<Decimal>,
+
+    /// deposits of each client per utc month charged no deposit fee
+    #[arg(long, default_value_t = Decimal::ZERO, requires = "deposit_fee_bps")]
+    pub deposit_fee_threshold: Decimal,
+
+    /// write the fees charged with the transactions as csv while processing, linked by tx id
+    #[arg(long)]
+    pub fees: Option<PathBuf>,
+
+    /// available funds withdrawals can't take a client below
+    #[arg(long)]
+    pub min_balance: Option<Decimal>,
+
+    /// limits file, a csv of `client,min_balance` overriding --min-balance for single clients
+    #[arg(long)]
+    pub min_balances: Option<PathBuf>,
+
+    /// number of recent transactions remembered to reject redeliveries of replayed streams
+    #[arg(long)]
+    pub dedup_window: Option<usize>,
+
+    /// seconds a transaction is remembered by the dedup window, measured with the transaction timestamps
+    #[arg(long, requires = "dedup_window")]
+    pub dedup_ttl: Option<u64>,
+
+    /// number of clients to allocate up front
+    #[arg(long)]
+    pub expected_clients: Option<usize>,
+
+    /// number of deposits and withdrawals to allocate up front, estimated from the size of csv files when omitted
+    #[arg(long)]
+    pub expected_txs: Option<usize>,
+
+    /// memory budget of the engine in MiB, processing aborts once storing a transaction would exceed it
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_memory_mb: Option<u64>,
+
+    /// maximum number of disputes per transaction
+    #[arg(long)]
+    pub max_disputes: Option<u32>,
+
+    /// reject disputes of transactions that were already disputed and resolved
+    #[arg(long)]
+    pub no_redispute: bool,
+
+    /// also run the input through a second engine with the options of a toml file of `[engine]` pipeline
+    /// keys, failing when its report differs
+    #[arg(long, conflicts_with = "resume")]
+    pub cross_check: Option<PathBuf>,
+
+    /// drop deposits that can't be disputed again from memory N transactions after they settled
+    #[arg(long, group = "retention")]
+    pub settled_retention_txs: Option<u64>,
+
+    /// drop deposits that can't be disputed again from memory SECS after they settled, by the transaction timestamps
+    #[arg(long, group = "retention")]
+    pub settled_retention_secs: Option<u64>,
+
+    /// seconds an authorization can be captured by the transaction timestamps, authorizations don't expire when unset
+    #[arg(long)]
+    pub authorization_ttl: Option<u64>,
+
+    /// archive file of the transactions dropped from memory, every transaction is then dropped
+    /// by the retention policy and loaded back from the archive when disputed
+    #[arg(long, requires = "retention")]
+    pub archive: Option<PathBuf>,
+
+    /// layout of the archive file
+    #[arg(long, value_enum, requires = "archive", default_value_t = ArchiveLayout::Slots)]
+    pub archive_layout: ArchiveLayout,
+
+    /// flag clients whose single or cumulative deposits exceed this amount
+    #[arg(long)]
+    pub aml_threshold: Option<Decimal>,
+
+    /// path of the suspicious activity report of --aml-threshold
+    #[arg(
+        long,
+        requires = "aml_threshold",
+        default_value = "suspicious_activity.csv"
+    )]
+    pub aml_report: PathBuf,
+
+    /// alert when a transaction brings the held funds of a client above this amount
+    #[arg(long, group = "alert_rules")]
+    pub alert_held_above: Option<Decimal>,
+
+    /// alert when a transaction brings the available funds of a client below zero
+    #[arg(long, group = "alert_rules")]
+    pub alert_negative_available: bool,
+
+    /// alert when a chargeback locks a client
+    #[arg(long, group = "alert_rules")]
+    pub alert_locked: bool,
+
+    /// file the alerts are appended to as json lines while processing
+    #[arg(long, group = "alert_outputs")]
+    pub alerts: Option<PathBuf>,
+
+    /// url each alert is posted to as json while processing
+    #[cfg(feature = "webhook")]
+    #[arg(long, group = "alert_outputs")]
+    pub alert_webhook: Option<String>,
+
+    /// flag deposits and withdrawals over this multiple of the trailing average of the client's previous ones of the same type
+    #[arg(long)]
+    pub anomaly_factor: Option<Decimal>,
+
+    /// previous transactions of the same type the trailing average of --anomaly-factor is taken over
+    #[arg(long, requires = "anomaly_factor", default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    pub anomaly_window: u64,
+
+    /// previous transactions of the same type a client needs before --anomaly-factor checks it
+    #[arg(long, requires = "anomaly_factor", default_value_t = 3)]
+    pub anomaly_min_history: u64,
+
+    /// path of the outlier report of --anomaly-factor
+    #[arg(long, requires = "anomaly_factor", default_value = "anomalies.csv")]
+    pub anomaly_report: PathBuf,
+
+    /// aggregate the deposits, withdrawals, chargebacks, net flow and closing total per period of the timestamps
+    #[arg(long, value_enum)]
+    pub rollup: Option<Rollup>,
+
+    /// path of the report of --rollup, written once the run ends
+    #[arg(long, requires = "rollup", default_value = "rollup.csv")]
+    pub rollup_report: PathBuf,
+
+    /// write the available and held funds of every client at the end of each day of the timestamps as csv
+    #[arg(long)]
+    pub balance_history: Option<PathBuf>,
+
+    /// write the interest accrued on the total funds of every client from the end of each day of the timestamps
+    /// to the next one as csv
+    #[arg(long)]
+    pub interest_accruals: Option<PathBuf>,
+
+    /// annual interest rate in percent of the clients without the rate of a segment
+    #[arg(long, requires = "interest_accruals")]
+    pub interest_rate: Option<Decimal>,
+
+    /// csv of the annual interest rates in percent of client segments, with segment and rate columns
+    #[arg(long, requires = "interest_accruals")]
+    pub interest_segments: Option<PathBuf>,
+
+    /// csv of the segments of the clients, with client and segment columns
+    #[arg(long, requires = "interest_segments")]
+    pub client_segments: Option<PathBuf>,
+
+    /// day count convention of the interest accruals
+    #[arg(long, value_enum, default_value_t = DayCount::Act365, requires = "interest_accruals")]
+    pub day_count: DayCount,
+
+    /// maximum number of withdrawals per client, within --withdrawal-window or the whole input
+    #[arg(long)]
+    pub max_withdrawals: Option<u32>,
+
+    /// seconds of the --max-withdrawals window, measured with the transaction timestamps
+    #[arg(long, requires = "max_withdrawals")]
+    pub withdrawal_window: Option<u64>,
+
+    /// maximum cumulative withdrawal amount per client and utc day of the transaction timestamps
+    #[arg(long)]
+    pub max_daily_withdrawal: Option<Decimal>,
+
+    /// drop the transactions of a client before the engine, can be repeated
+    #[arg(long)]
+    pub block_client: Vec<u16>,
+
+    /// lower amounts over this value to it before the engine
+    #[arg(long)]
+    pub clamp_amount: Option<Decimal>,
+
+    /// partner name of a csv transaction type, e.g. `payment=deposit`, can be repeated
+    #[arg(long, value_parser = parse_type_alias)]
+    pub type_alias: Vec<(String, TxType)>,
+
+    /// replace the client ids of the report, results and aml outputs with hmac tokens keyed by this file
+    #[arg(long, conflicts_with = "output")]
+    pub pseudonymize: Option<PathBuf>,
+
+    /// seal the report and snapshot files to this x25519 public key (hex), opened with libsodium's crypto_box_seal_open
+    #[cfg(feature = "encrypt")]
+    #[arg(long)]
+    pub encrypt_to: Option<String>,
+
+    /// check the sha-256 of the input file before processing: `sha256:<hex>`, a `sha256sum` file, or without a value
+    /// the `<input>.sha256` file next to the input
+    #[arg(long, num_args = 0..=1, value_name = "CHECKSUM")]
+    pub verify_input: Option<Option<String>>,
+
+    /// write the stats of the run, with the rejections per error code and the transactions per type, as json once it ends
+    #[arg(long)]
+    pub stats: Option<PathBuf>,
+
+    /// write the rejected transactions of each client per type as csv once the run ends
+    #[arg(long)]
+    pub rejections_by_client: Option<PathBuf>,
+
+    /// write the locked accounts with the chargeback that locked them as csv once the run ends
+    #[arg(long)]
+    pub locked_report: Option<PathBuf>,
+
+    /// value date of the run, transactions with a later effective_date are queued instead of applied
+    #[arg(long)]
+    pub as_of: Option<NaiveDate>,
+
+    /// write the transactions still queued after --as-of as csv once the run ends, to be processed by a later run
+    #[arg(long)]
+    pub queued: Option<PathBuf>,
+
+    /// write a manifest of the row counts and sha-256 checksums of the input and output files once the run completes
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// file of the hex ed25519 secret key signing the manifest into a `.sig` file next to it
+    #[arg(long, requires = "manifest")]
+    pub manifest_key: Option<PathBuf>,
+
+    /// file of the checkpoints of csv input, written every --checkpoint-every rows and when interrupted
+    #[arg(long)]
+    pub checkpoint: Option<PathBuf>,
+
+    /// number of rows between checkpoints
+    #[arg(long, requires = "checkpoint", value_parser = clap::value_parser!(u64).range(1..))]
+    pub checkpoint_every: Option<u64>,
+
+    /// continue an interrupted run from its checkpoint, appending to its results and aml report
+    #[arg(long, requires = "checkpoint")]
+    pub resume: bool,
+
+    /// report only locked clients
+    #[arg(long)]
+    pub only_locked: bool,
+
+    /// report only clients with available or held funds
+    #[arg(long)]
+    pub only_nonzero: bool,
+
+    /// report only clients with a total of at least this amount
+    #[arg(long)]
+    pub min_total: Option<Decimal>,
+
+    /// write an intermediate report every N rows or Ns seconds while processing
+    #[arg(long)]
+    pub report_every: Option<ReportInterval>,
+
+    /// directory of the intermediate reports and of the snapshots requested with SIGUSR1
+    #[arg(long, default_value = ".")]
+    pub report_dir: PathBuf,
+
+    /// columns of the report
+    #[arg(long, value_enum, default_value_t = ReportDetail::Basic)]
+    pub report_detail: ReportDetail,
+
+    /// add the chargebacks, disputes opened and chargeback to deposit ratio of each client to the report
+    #[arg(long)]
+    pub risk_columns: bool,
+
+    /// add why each client is locked or frozen to the report
+    #[arg(long)]
+    pub lock_reason: bool,
+
+    /// number of decimal places of all report amounts
+    #[arg(long, value_parser = clap::value_parser!(u32).range(0..=28))]
+    pub output_scale: Option<u32>,
+
+    /// destination of the final report instead of stdout, postgres://... upserts it into --output-table
+    #[arg(long)]
+    pub output: Option<String>,
+
+    /// table of the database outputs
+    #[arg(long, default_value = "clients")]
+    pub output_table: String,
+
+    /// format of the report written to stdout, intermediate reports are always csv
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    pub output_format: OutputFormat,
+
+    /// flush the report output every N rows or Nb bytes
+    #[arg(long)]
+    pub flush_every: Option<FlushInterval>,
+
+    /// intermediate reports contain only clients changed since the previous one
+    #[arg(long, requires = "report_every")]
+    pub report_delta: bool,
+
+    /// csv input without a header row, columns are read in order: type, client, tx, amount
+    #[arg(long)]
+    pub no_header: bool,
+
+    /// decimal separator of input amounts, comma implies semicolon delimited files
+    #[arg(long, value_enum, default_value_t = DecimalSeparator::Dot)]
+    pub decimal_separator: DecimalSeparator,
+
+    /// character encoding of the input file, a leading byte order mark is skipped
+    #[arg(long, value_enum, default_value_t = InputEncoding::Utf8)]
+    pub encoding: InputEncoding,
+
+    /// show a live dashboard on stderr while processing
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    pub tui: bool,
 }
 
-pub fn process_input<R: io::Read, W: io::Write>(input: R, output: W) -> Result<(), Box<dyn Error>> {
-    let mut engine = engine::Engine::new();
+fn parse_type_alias(alias: &str) -> Result<(String, TxType), String> {
+    let (name, tx_type) = alias
+        .split_once('=')
+        .ok_or("expected name=type".to_string())?;
+    Ok((name.to_string(), import::tx_type(tx_type, &HashMap::new())?))
+}
+
+#[derive(ValueEnum, Deserialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub enum InputFormat {
+    Csv,
+    Ofx,
+    Mt940,
+    OpenBanking,
+    /// one record per line at the offsets of a `--layout` file
+    FixedWidth,
+    /// record elements mapped to transactions by a `--layout` file
+    Xml,
+}
+
+impl InputFormat {
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("ofx") => InputFormat::Ofx,
+            Some(ext) if ext.eq_ignore_ascii_case("sta") || ext.eq_ignore_ascii_case("mt940") => {
+                InputFormat::Mt940
+            }
+            Some(ext) if ext.eq_ignore_ascii_case("json") => InputFormat::OpenBanking,
+            Some(ext) if ext.eq_ignore_ascii_case("xml") => InputFormat::Xml,
+            _ => InputFormat::Csv,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// match a client report against an external balances file (client, total)
+    Reconcile {
+        /// path of the client report
+        report_path: String,
+
+        /// path of the external balances file
+        external_path: String,
+    },
+    /// apply a hypothetical batch on top of a base state and report the resulting balance changes
+    Simulate {
+        /// path of the transactions the base state is built from
+        #[arg(long)]
+        base: String,
+
+        /// path of the hypothetical transactions
+        extra_path: String,
+    },
+    /// split a csv input by client id into shard-<n>.csv files, each processed by its own engine instance
+    Shard {
+        /// path of the transactions
+        input_path: String,
+
+        /// number of shards
+        #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+        shards: u16,
+
+        /// directory of the shard files
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+    },
+    /// merge the client reports of the shards into a single report sorted by client id
+    ReportCollect {
+        /// paths of the shard reports
+        #[arg(required = true)]
+        report_paths: Vec<String>,
+    },
+    /// count the chargebacks and charged back amounts of each client and overall, with their rates
+    /// relative to the accepted deposits
+    ChargebackReport {
+        /// path of the transactions
+        input_path: String,
+    },
+    /// check each settlement against the net of the accepted transactions of its batch, and list
+    /// the batches left without a settlement
+    SettlementReport {
+        /// path of the transactions
+        input_path: String,
+    },
+    /// list the disputes still open at the end of the input with their age in days and held amount,
+    /// oldest first
+    DisputeAging {
+        /// path of the transactions
+        input_path: String,
+
+        /// rfc3339 time the disputes are aged at, the current time when unset
+        #[arg(long)]
+        as_of: Option<DateTime<Utc>>,
+    },
+    /// apply the unlock, lock, force_resolve, adjust, reverse_after, freeze and unfreeze commands of
+    /// a csv file (action, client, tx, amount, after, reason) to the engine state of a checkpoint, writing
+    /// the adjusted checkpoint and an audit record of each command
+    Admin {
+        /// path of the checkpoint
+        checkpoint_path: PathBuf,
+
+        /// path of the admin commands
+        commands_path: String,
+
+        /// path of the adjusted checkpoint, resumed from with `--checkpoint <PATH> --resume`
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// go through the open disputes of a checkpoint and resolve or charge back each one, the
+    /// decisions are appended as transactions to a csv file to process in the next run
+    Disputes {
+        /// path of the checkpoint
+        checkpoint_path: PathBuf,
+
+        /// path of the decided transactions, appended to
+        #[arg(long)]
+        out: PathBuf,
+    },
+    /// run the sources, filter, engine options and sinks of a toml pipeline definition
+    RunPipeline {
+        /// path of the pipeline definition
+        config_path: String,
+    },
+    /// write a csv of random deposits, withdrawals and disputes determined by the seed, the same
+    /// seed always gives the same file
+    Generate {
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        /// number of transactions
+        #[arg(long, default_value_t = 1000)]
+        txs: usize,
+
+        /// number of clients, with ids from 1
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u16).range(1..))]
+        clients: u16,
+    },
+}
+
+/// processing options of the input pipeline
+#[derive(Default)]
+pub struct ProcessOptions {
+    pub engine: EngineConfig,
+
+    pub csv: CsvOptions,
+
+    /// layout of fixed width input
+    pub fixed_width: Option<FixedWidthLayout>,
+
+    /// element mapping of xml input
+    pub xml: Option<XmlMapping>,
+
+    /// transactions dropped before the engine
+    pub input_filter: InputFilter,
+
+    /// destination of one result row per input record
+    pub results: Option<Box<dyn io::Write>>,
+
+    /// the result rows carry the balances of their client after the record
+    pub result_balances: bool,
+
+    /// destination of one row per fee charged by the engine fee schedule
+    pub fees: Option<Box<dyn io::Write>>,
+
+    pub report_filter: ReportFilter,
+
+    pub report_detail: ReportDetail,
+
+    /// add the risk indicator columns to the report
+    pub risk_columns: bool,
+
+    /// add the lock reason column to the report
+    pub lock_reason: bool,
+
+    /// decimal places of report amounts, amounts keep their own scale when unset
+    pub output_scale: Option<u32>,
+
+    pub output_format: OutputFormat,
+
+    /// destinations of the final report instead of the output
+    pub sinks: Vec<ReportSink>,
 
+    /// report output flush interval, the output is flushed once at the end when unset
+    pub flush_every: Option<FlushInterval>,
+
+    /// intermediate report snapshots written while processing
+    pub snapshots: Option<SnapshotOptions>,
+
+    /// suspicious activity report of the deposits over an aml threshold
+    pub aml: Option<AmlOptions>,
+
+    /// report of the deposits and withdrawals far over the history of their client
+    pub anomalies: Option<AnomalyOptions>,
+
+    /// aggregates of the accepted transactions per period, written once the run ends
+    pub rollup: Option<RollupOptions>,
+
+    /// balances of every client at the end of each day, written once a later day starts
+    pub balance_history: Option<Box<dyn io::Write>>,
+
+    /// interest accrued by every client between the days, written once a later day starts
+    pub interest: Option<InterestOptions>,
+
+    /// notified of the alerts of the engine alert rules while processing
+    pub alert_observers: Vec<Box<dyn AlertObserver>>,
+
+    /// tokens replacing the client ids of the outputs
+    pub pseudonyms: Option<Pseudonyms>,
+
+    /// checksums of the inputs and outputs, written once the run completes
+    pub manifest: Option<ManifestOptions>,
+
+    /// offset and engine state saved while processing csv input, to resume an interrupted run
+    pub checkpoint: Option<CheckpointOptions>,
+
+    /// file of the stats written once the run ends, also when it's interrupted
+    pub stats: Option<PathBuf>,
+
+    /// file of the rejections per client and transaction type written once the run ends
+    pub rejections_by_client: Option<PathBuf>,
+
+    /// file of the locked accounts with their chargeback written once the run ends
+    pub locked_report: Option<PathBuf>,
+
+    /// file of the transactions queued after the as-of date written once the run ends
+    pub queued: Option<PathBuf>,
+
+    /// configuration of a second engine applied to the same transactions in parallel, a
+    /// completed run fails when the balances of its report differ
+    pub cross_check: Option<EngineConfig>,
+
+    /// archive of the transactions dropped from memory by the retention policy
+    pub archive: Option<Box<dyn TxArchive>>,
+
+    /// key the report and snapshot outputs are sealed to
+    #[cfg(feature = "encrypt")]
+    pub encryption: Option<Encryption>,
+
+    /// character encoding of the input
+    pub encoding: InputEncoding,
+
+    /// set to a signal number to stop reading and write a partial report
+    pub stop: Option<Arc<AtomicUsize>>,
+
+    /// draw a live dashboard on stderr while processing
+    #[cfg(feature = "tui")]
+    pub tui: bool,
+}
+
+/// deposits over the threshold, alone or summed per client, are written to the output
+pub struct AmlOptions {
+    pub threshold: Decimal,
+
+    /// destination of one row per triggering deposit
+    pub output: Box<dyn io::Write>,
+}
+
+/// outliers are written to the output, they're still applied
+pub struct AnomalyOptions {
+    pub thresholds: AnomalyThresholds,
+
+    /// destination of one row per flagged transaction
+    pub output: Box<dyn io::Write>,
+}
+
+/// the interest accrued by the clients is written to the output
+pub struct InterestOptions {
+    pub rates: InterestRates,
+
+    /// destination of one row per client and accrual period
+    pub output: Box<dyn io::Write>,
+}
+
+/// the aggregates of each period are written as csv to the path
+pub struct RollupOptions {
+    pub period: Rollup,
+    pub path: PathBuf,
+}
+
+/// reading options of csv input
+#[derive(Debug, Default, Clone)]
+pub struct CsvOptions {
+    /// parse records positionally instead of by header name
+    pub no_header: bool,
+
+    pub decimal_separator: DecimalSeparator,
+}
+
+/// columns of the report
+#[derive(ValueEnum, Debug, Default, PartialEq, Copy, Clone)]
+pub enum ReportDetail {
+    /// client, available, held, total, locked
+    #[default]
+    Basic,
+    /// basic columns plus deposit, withdrawal, open dispute, chargeback and rejected counts, a
+    /// pending withdrawal is counted once settled
+    Extended,
+}
+
+/// selects which clients appear in the report, all clients are reported by default
+#[derive(Debug, Default, Clone)]
+pub struct ReportFilter {
+    pub only_locked: bool,
+
+    /// skip clients without available or held funds
+    pub only_nonzero: bool,
+
+    pub min_total: Option<Decimal>,
+}
+
+impl ReportFilter {
+    fn matches(&self, report: &ClientReport) -> bool {
+        if self.only_locked && !report.locked {
+            return false;
+        }
+
+        if self.only_nonzero && report.available.is_zero() && report.held.is_zero() {
+            return false;
+        }
+
+        match self.min_total {
+            Some(min_total) => report.total >= min_total,
+            None => true,
+        }
+    }
+}
+
+/// processing stopped by a signal, the report written is partial
+#[derive(thiserror::Error, Debug)]
+#[error("interrupted by signal {signal} after {records} records, the report is partial")]
+pub struct Interrupted {
+    pub signal: i32,
+    pub records: u64,
+}
+
+/// estimates the number of transactions of a csv input from its size,
+/// None when the size is unknown (stdin, pipes)
+pub fn estimate_txs(path: &str) -> Option<usize> {
+    // a short row like `deposit,1,1,1.0` is about 20 bytes
+    const ESTIMATED_ROW_BYTES: u64 = 24;
+
+    let metadata = std::fs::metadata(path).ok().filter(|m| m.is_file())?;
+    usize::try_from(metadata.len() / ESTIMATED_ROW_BYTES).ok()
+}
+
+/// opens the input file, `-` reads stdin; with mmap regular files are memory mapped
+/// and anything that can't be mapped (pipes, empty files) falls back to buffered reads
+pub fn open_input(path: &str, mmap: bool) -> io::Result<Box<dyn io::Read + Send>> {
+    if path == "-" {
+        return Ok(Box::new(io::stdin()));
+    }
+
+    let file = File::open(path)?;
+    if mmap && file.metadata()?.is_file() && file.metadata()?.len() > 0 {
+        // SAFETY: the input is only read, it must not be modified while it's processed
+        match unsafe { Mmap::map(&file) } {
+            Ok(map) => return Ok(Box::new(io::Cursor::new(map))),
+            Err(err) => debug!(
+                "failed to map input, falling back to buffered reads: {}",
+                err
+            ),
+        }
+    }
+
+    Ok(Box::new(BufReader::new(file)))
+}
+
+pub fn process_input<R: io::Read + Send, W: io::Write>(
+    input: R,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    process_input_with_options(input, output, ProcessOptions::default())
+}
+
+pub fn process_input_with_options<R: io::Read + Send, W: io::Write>(
+    input: R,
+    output: W,
+    options: ProcessOptions,
+) -> Result<(), Box<dyn Error>> {
+    process_statement(input, InputFormat::Csv, None, output, options)
+}
+
+/// processes a bank statement, the client id defaults to the statement account id
+pub fn process_statement<R: io::Read + Send, W: io::Write>(
+    input: R,
+    format: InputFormat,
+    client_id: Option<u16>,
+    output: W,
+    mut options: ProcessOptions,
+) -> Result<(), Box<dyn Error>> {
+    if options.checkpoint.is_some() && format != InputFormat::Csv {
+        return Err("checkpoints are only supported for csv input".into());
+    }
+
+    let source = Source {
+        format,
+        client_id,
+        encoding: options.encoding,
+        csv: options.csv.clone(),
+        fixed_width: options.fixed_width.take(),
+        xml: options.xml.take(),
+    };
+
+    let mut processor = Processor::new(options)?;
+    read_source(input, &source, &mut processor)?;
+    processor.finish(output)
+}
+
+/// processes the sources of a pipeline definition one after the other into a single engine,
+/// the report is written to the output when the pipeline has no sinks
+pub fn run_pipeline<W: io::Write>(
+    config: PipelineConfig,
+    output: W,
+    stop: Option<Arc<AtomicUsize>>,
+) -> Result<(), Box<dyn Error>> {
+    let sources = config.sources()?;
+    let mut options = config.into_options()?;
+    options.stop = stop;
+
+    let mut processor = Processor::new(options)?;
+    for (path, source) in sources {
+        if processor.stopped().is_some() {
+            break;
+        }
+
+        debug!("reading pipeline source {}", path.display());
+        read_source(BufReader::new(File::open(&path)?), &source, &mut processor)?;
+    }
+
+    processor.finish(output)
+}
+
+/// an input and how its records are read
+pub(crate) struct Source {
+    pub(crate) format: InputFormat,
+    /// client id of statement formats
+    pub(crate) client_id: Option<u16>,
+    pub(crate) encoding: InputEncoding,
+    pub(crate) csv: CsvOptions,
+    pub(crate) fixed_width: Option<FixedWidthLayout>,
+    pub(crate) xml: Option<XmlMapping>,
+}
+
+/// applies the records of an input to the processor, until the end of the input or a stop signal
+fn read_source<R: io::Read + Send>(
+    input: R,
+    source: &Source,
+    processor: &mut Processor,
+) -> Result<(), Box<dyn Error>> {
+    let input = Decoder::new(input, source.encoding);
+    let txs = match source.format {
+        InputFormat::Csv => {
+            let type_aliases = processor.input_filter.type_aliases.clone();
+            return read_csv(input, &source.csv, type_aliases, processor);
+        }
+        InputFormat::FixedWidth => {
+            let layout = source
+                .fixed_width
+                .as_ref()
+                .ok_or("fixed width input requires a layout")?;
+            return read_fixed_width(input, layout, processor);
+        }
+        InputFormat::Xml => {
+            let mapping = source
+                .xml
+                .as_ref()
+                .ok_or("xml input requires an element mapping")?;
+            return read_xml(input, mapping, processor);
+        }
+        InputFormat::Ofx => processor
+            .profile
+            .parse(|| import::ofx::parse(input, source.client_id))?,
+        InputFormat::Mt940 => processor
+            .profile
+            .parse(|| import::mt940::parse(input, source.client_id))?,
+        InputFormat::OpenBanking => processor
+            .profile
+            .parse(|| import::open_banking::parse(input, source.client_id))?,
+    };
+
+    for tx in txs {
+        if processor.stopped().is_some() {
+            break;
+        }
+
+        processor.process_tx(None, &tx)?;
+    }
+
+    Ok(())
+}
+
+fn read_csv<R: io::Read + Send>(
+    input: R,
+    options: &CsvOptions,
+    type_aliases: HashMap<String, TxType>,
+    processor: &mut Processor,
+) -> Result<(), Box<dyn Error>> {
     // read from input
+    let decimal_separator = options.decimal_separator;
     let mut rdr = csv::ReaderBuilder::new()
         .trim(Trim::All)
         .flexible(true)
+        .has_headers(!options.no_header)
+        .delimiter(decimal_separator.delimiter())
         .from_reader(input);
 
-    for result in rdr.deserialize::<TxInput>() {
-        let tx = match result {
-            Ok(tx) => tx,
-            Err(err) => {
-                debug!("failed to parse record: {}", err);
-                continue
+    let parser = match options.no_header {
+        true => RecordParser::positional(decimal_separator),
+        false => RecordParser::new(rdr.byte_headers()?, decimal_separator)?,
+    }
+    .with_type_aliases(type_aliases);
+
+    let mut rows = Rows::new(rdr, parser);
+    let offset = std::mem::take(&mut processor.offset);
+    if rows.skip_records(offset)? < offset {
+        return Err(format!(
+            "the input ends before the checkpoint offset of {} records",
+            offset
+        )
+        .into());
+    }
+    let rows = processor.profile.timed(rows);
+
+    // with more than one core records are parsed on a reader thread while the engine applies
+    // the previous batches, the bounded channel blocks the reader when the engine falls behind
+    if thread::available_parallelism().map_or(1, usize::from) < 2 {
+        for row in rows {
+            if processor.stopped().is_some() {
+                break;
             }
-        };
 
-        engine.process_tx(&tx)
+            let (line, row) = row?;
+            processor.process_row(line, row)?;
+        }
+
+        return Ok(());
     }
 
-    //write to std out
-    let mut wtr = csv::Writer::from_writer(output);
-    let mut counter = 0;
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_BATCHES);
+        scope.spawn(move || pipeline::send_batches(rows, sender));
+
+        for batch in receiver {
+            for (line, row) in batch? {
+                if processor.stopped().is_some() {
+                    return Ok(());
+                }
+
+                processor.process_row(line, row)?;
+            }
+        }
+
+        Ok(())
+    })
+}
 
-    for v in engine.report() {
-        wtr.serialize(v)?;
+/// reads fixed width records line by line, blank lines are skipped
+fn read_fixed_width<R: io::Read>(
+    input: R,
+    layout: &FixedWidthLayout,
+    processor: &mut Processor,
+) -> Result<(), Box<dyn Error>> {
+    for (index, line) in BufReader::new(input).lines().enumerate() {
+        if processor.stopped().is_some() {
+            break;
+        }
 
-        //flush every 1000 lines
-        if counter >= 1000 {
-            wtr.flush()?;
-            counter = 0;
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
         }
 
-        counter += 1;
+        let row = processor.profile.parse(|| layout.parse(&line));
+        processor.process_row(Some(index as u64 + 1), row)?;
     }
 
-    match wtr.flush() {
-        Ok(_) => Ok(()),
-        Err(err) => Err(err.into()),
+    Ok(())
+}
+
+/// reads the record elements of xml input as they are parsed
+fn read_xml<R: io::Read>(
+    input: R,
+    mapping: &XmlMapping,
+    processor: &mut Processor,
+) -> Result<(), Box<dyn Error>> {
+    let rows = import::xml::Records::new(BufReader::new(input), mapping);
+    for row in processor.profile.timed(rows) {
+        if processor.stopped().is_some() {
+            break;
+        }
+
+        processor.process_row(None, row?)?;
     }
+
+    Ok(())
+}
+
+/// event of a transaction applied or rejected by the engine, with the fields of the transaction
+/// and the code of the error so log pipelines can filter on them
+pub(crate) fn log_tx(tx: &TxInput, result: &Result<(), errors::Error>) {
+    match result {
+        Ok(()) => trace!(
+            tx_id = tx.id,
+            client_id = tx.client_id,
+            tx_type = %tx.tx_type,
+            "transaction applied"
+        ),
+        Err(err) => debug!(
+            tx_id = tx.id,
+            client_id = tx.client_id,
+            tx_type = %tx.tx_type,
+            error_code = err.code(),
+            "transaction rejected: {}",
+            err.kind()
+        ),
+    }
+}
+
+/// applies parsed records to the engine and writes the per record outputs
+struct Processor {
+    engine: engine::Engine,
+    results: Option<csv::Writer<Box<dyn io::Write>>>,
+    result_balances: bool,
+    fees: Option<csv::Writer<Box<dyn io::Write>>>,
+    report: ReportFormat,
+    snapshots: Option<Snapshots>,
+    aml: Option<(AmlMonitor, csv::Writer<Box<dyn io::Write>>)>,
+    anomalies: Option<(AnomalyMonitor, csv::Writer<Box<dyn io::Write>>)>,
+    rollup: Option<(DailyRollup, PathBuf)>,
+    balance_history: Option<(BalanceHistory, csv::Writer<Box<dyn io::Write>>)>,
+    interest: Option<(Accruals, csv::Writer<Box<dyn io::Write>>)>,
+    sinks: Vec<ReportSink>,
+    rejects: Option<Vec<TxResult>>,
+    input_filter: InputFilter,
+    manifest: Option<ManifestOptions>,
+    checkpoint: Option<CheckpointOptions>,
+    /// records of a resumed input processed before its checkpoint
+    offset: u64,
+    stop: Option<Arc<AtomicUsize>>,
+    records: u64,
+    result_rows: u64,
+    aml_rows: u64,
+    anomaly_rows: u64,
+    filtered: u64,
+    clamped: u64,
+    breakdown: Breakdown,
+    stats: Option<PathBuf>,
+    rejections_by_client: Option<PathBuf>,
+    locked_report: Option<PathBuf>,
+    queued: Option<PathBuf>,
+    cross_check: Option<CrossCheck>,
+    profile: Profile,
+    #[cfg(feature = "tui")]
+    dashboard: Option<Dashboard<ratatui::backend::CrosstermBackend<io::Stderr>>>,
+}
+
+/// how the reports are written
+pub(crate) struct ReportFormat {
+    filter: ReportFilter,
+    columns: ReportColumns,
+    scale: Option<u32>,
+    flush_every: Option<FlushInterval>,
+    output: OutputFormat,
+    pseudonyms: Option<Pseudonyms>,
+    #[cfg(feature = "encrypt")]
+    encryption: Option<Encryption>,
+}
+
+impl ReportFormat {
+    /// runs the writes of a report output through its encryption, if any
+    fn encrypted<W: io::Write>(
+        &self,
+        mut output: W,
+        write: impl FnOnce(&mut dyn io::Write) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        #[cfg(feature = "encrypt")]
+        if let Some(encryption) = self.encryption.as_ref() {
+            return encryption.write(output, write);
+        }
+
+        write(&mut output)
+    }
+
+    /// path of a report file, with the extension of sealed files when encrypted
+    fn file_path(&self, path: PathBuf) -> PathBuf {
+        #[cfg(feature = "encrypt")]
+        if self.encryption.is_some() {
+            return Encryption::path(path);
+        }
+
+        path
+    }
+
+    #[cfg(feature = "encrypt")]
+    fn encrypts(&self) -> bool {
+        self.encryption.is_some()
+    }
+
+    #[cfg(not(feature = "encrypt"))]
+    fn encrypts(&self) -> bool {
+        false
+    }
+}
+
+impl Processor {
+    fn new(options: ProcessOptions) -> Result<Self, Box<dyn Error>> {
+        if options.pseudonyms.is_some()
+            && (options.output_format.types_client_ids()
+                || options.sinks.iter().any(ReportSink::types_client_ids))
+        {
+            return Err("pseudonymized client ids can only be written to text outputs".into());
+        }
+
+        // a resumed run appends to the per record outputs of the interrupted one
+        let resume = options
+            .checkpoint
+            .as_ref()
+            .filter(|checkpoint| checkpoint.resume)
+            .map(|checkpoint| Checkpoint::read(&checkpoint.path))
+            .transpose()?;
+        let writer = |output: Box<dyn io::Write>| {
+            csv::WriterBuilder::new()
+                .has_headers(resume.is_none())
+                .from_writer(output)
+        };
+
+        if resume.is_some() && options.cross_check.is_some() {
+            return Err("a resumed run cannot be cross-checked".into());
+        }
+
+        let mut engine = engine::Engine::with_config(options.engine);
+        for observer in options.alert_observers {
+            engine.add_observer(observer);
+        }
+        if let Some(archive) = options.archive {
+            engine.set_archive(archive);
+        }
+
+        let mut processor = Self {
+            engine,
+            results: options.results.map(writer),
+            result_balances: options.result_balances,
+            fees: options.fees.map(writer),
+            report: ReportFormat {
+                filter: options.report_filter,
+                columns: ReportColumns {
+                    activity: options.report_detail == ReportDetail::Extended,
+                    risk: options.risk_columns,
+                    last_activity: false,
+                    lock_reason: options.lock_reason,
+                },
+                scale: options.output_scale,
+                flush_every: options.flush_every,
+                output: options.output_format,
+                pseudonyms: options.pseudonyms,
+                #[cfg(feature = "encrypt")]
+                encryption: options.encryption,
+            },
+            snapshots: options.snapshots.map(Snapshots::new),
+            aml: options
+                .aml
+                .map(|aml| (AmlMonitor::new(aml.threshold), writer(aml.output))),
+            anomalies: options.anomalies.map(|anomalies| {
+                (
+                    AnomalyMonitor::new(anomalies.thresholds),
+                    writer(anomalies.output),
+                )
+            }),
+            rollup: options.rollup.map(|rollup| match rollup.period {
+                Rollup::Daily => (DailyRollup::default(), rollup.path),
+            }),
+            balance_history: options
+                .balance_history
+                .map(|output| (BalanceHistory::default(), writer(output))),
+            interest: options
+                .interest
+                .map(|interest| (Accruals::new(interest.rates), writer(interest.output))),
+            rejects: options
+                .sinks
+                .iter()
+                .any(|sink| sink.keeps_rejects())
+                .then(Vec::new),
+            sinks: options.sinks,
+            input_filter: options.input_filter,
+            manifest: options.manifest,
+            checkpoint: options.checkpoint,
+            offset: 0,
+            stop: options.stop,
+            records: 0,
+            result_rows: 0,
+            aml_rows: 0,
+            anomaly_rows: 0,
+            filtered: 0,
+            clamped: 0,
+            breakdown: Breakdown::default(),
+            stats: options.stats,
+            rejections_by_client: options.rejections_by_client,
+            locked_report: options.locked_report,
+            queued: options.queued,
+            cross_check: options.cross_check.map(CrossCheck::spawn),
+            profile: Profile::new(),
+            #[cfg(feature = "tui")]
+            dashboard: match options.tui {
+                true => Some(Dashboard::stderr()?),
+                false => None,
+            },
+        };
+
+        if let Some(checkpoint) = resume {
+            processor.resume(checkpoint);
+        }
+
+        Ok(processor)
+    }
+
+    /// continues from the state of a checkpoint, its records are skipped from the input
+    fn resume(&mut self, checkpoint: Checkpoint) {
+        debug!("resuming after {} records", checkpoint.records);
+
+        self.engine.restore(checkpoint.engine);
+        if let Some((monitor, _)) = self.aml.as_mut() {
+            monitor.restore(checkpoint.aml);
+        }
+        if let Some((monitor, _)) = self.anomalies.as_mut() {
+            monitor.restore(checkpoint.anomalies);
+        }
+        if let (Some((rollup, _)), Some(saved)) = (self.rollup.as_mut(), checkpoint.rollup) {
+            *rollup = saved;
+        }
+        if let Some((history, _)) = self.balance_history.as_mut() {
+            history.restore(checkpoint.balance_day);
+        }
+        if let Some((accruals, _)) = self.interest.as_mut() {
+            accruals.restore(checkpoint.interest_day);
+        }
+        self.report.columns.last_activity = checkpoint.timestamps;
+        self.offset = checkpoint.records;
+        self.records = checkpoint.records;
+        self.result_rows = checkpoint.result_rows;
+        self.aml_rows = checkpoint.aml_rows;
+        self.anomaly_rows = checkpoint.anomaly_rows;
+        self.filtered = checkpoint.filtered;
+        self.clamped = checkpoint.clamped;
+        self.breakdown = checkpoint.breakdown;
+    }
+
+    /// writes a checkpoint of the records processed so far, the per record outputs and the archive
+    /// are flushed first so they hold the rows and transactions of the checkpointed records
+    fn save_checkpoint(&mut self) -> Result<(), Box<dyn Error>> {
+        let path = match self.checkpoint.as_ref() {
+            Some(checkpoint) => checkpoint.path.clone(),
+            None => return Ok(()),
+        };
+
+        if let Some(results) = self.results.as_mut() {
+            results.flush()?;
+        }
+
+        if let Some((_, output)) = self.aml.as_mut() {
+            output.flush()?;
+        }
+
+        if let Some((_, output)) = self.anomalies.as_mut() {
+            output.flush()?;
+        }
+
+        if let Some(output) = self.fees.as_mut() {
+            output.flush()?;
+        }
+
+        if let Some((_, output)) = self.balance_history.as_mut() {
+            output.flush()?;
+        }
+
+        if let Some((_, output)) = self.interest.as_mut() {
+            output.flush()?;
+        }
+
+        self.engine.flush_archive()?;
+
+        Checkpoint {
+            records: self.records,
+            result_rows: self.result_rows,
+            aml_rows: self.aml_rows,
+            anomaly_rows: self.anomaly_rows,
+            filtered: self.filtered,
+            clamped: self.clamped,
+            breakdown: self.breakdown.clone(),
+            timestamps: self.report.columns.last_activity,
+            engine: self.engine.state(),
+            aml: self
+                .aml
+                .as_ref()
+                .map(|(monitor, _)| monitor.deposits())
+                .unwrap_or_default(),
+            anomalies: self
+                .anomalies
+                .as_ref()
+                .map(|(monitor, _)| monitor.history())
+                .unwrap_or_default(),
+            rollup: self.rollup.as_ref().map(|(rollup, _)| rollup.clone()),
+            balance_day: self
+                .balance_history
+                .as_ref()
+                .and_then(|(history, _)| history.day()),
+            interest_day: self
+                .interest
+                .as_ref()
+                .and_then(|(accruals, _)| accruals.day()),
+        }
+        .write(&path)
+    }
+
+    /// writes the periodic checkpoint and the intermediate reports due after a record
+    fn processed(&mut self) -> Result<(), Box<dyn Error>> {
+        if self
+            .checkpoint
+            .as_ref()
+            .and_then(|checkpoint| checkpoint.every)
+            .is_some_and(|every| self.records.is_multiple_of(every))
+        {
+            self.save_checkpoint()?;
+        }
+
+        self.snapshot()
+    }
+
+    /// signal that stopped processing, if any
+    fn stopped(&self) -> Option<i32> {
+        let signal = self.stop.as_ref()?.load(Ordering::Relaxed);
+        (signal != 0).then_some(signal as i32)
+    }
+
+    fn process_tx(&mut self, line: Option<u64>, tx: &TxInput) -> Result<(), Box<dyn Error>> {
+        self.records += 1;
+
+        if !self.input_filter.keeps(tx.client_id) {
+            self.filtered += 1;
+            return Ok(());
+        }
+
+        let clamped;
+        let tx = match self.input_filter.clamp(tx.amount) {
+            Some(amount) => {
+                self.clamped += 1;
+                clamped = TxInput {
+                    amount: Some(amount),
+                    idempotency_key: tx.idempotency_key.clone(),
+                    batch: tx.batch.clone(),
+                    ..*tx
+                };
+                &clamped
+            }
+            None => tx,
+        };
+
+        // the last activity column is only reported once the input carries timestamps
+        self.report.columns.last_activity |= tx.timestamp.is_some();
+
+        if let Some(day) = self
+            .balance_history
+            .as_mut()
+            .and_then(|(history, _)| history.advance(tx.timestamp))
+        {
+            self.write_balances(day)?;
+        }
+
+        if let Some(period) = self
+            .interest
+            .as_mut()
+            .and_then(|(accruals, _)| accruals.advance(tx.timestamp))
+        {
+            self.write_accruals(period)?;
+        }
+
+        let total = |engine: &engine::Engine| {
+            engine
+                .client(tx.client_id)
+                .map_or(Decimal::ZERO, |client| client.total())
+        };
+        let before = self.rollup.is_some().then(|| total(&self.engine));
+
+        if let Some(cross_check) = self.cross_check.as_ref() {
+            cross_check.send(tx);
+        }
+
+        let result = self
+            .profile
+            .apply(tx.tx_type, || self.engine.process_tx(tx))
+            .map_err(|err| match line {
+                Some(line) => err.with_line(line),
+                None => err,
+            });
+
+        let dispute_state = self
+            .engine
+            .transaction(tx.id)
+            .filter(|stored| stored.client_id() == tx.client_id)
+            .map(|stored| stored.dispute_state());
+        self.write_result(TxResult::new(line, tx, &result, dispute_state))?;
+
+        if let (Ok(_), Some(snapshots)) = (&result, self.snapshots.as_mut()) {
+            snapshots.record_change(tx.client_id);
+        }
+
+        for fee in self.engine.take_fees() {
+            if let Some(output) = self.fees.as_mut() {
+                match self.report.pseudonyms.as_ref() {
+                    Some(pseudonyms) => {
+                        output.serialize(pseudonyms.row(&fee, Some(fee.client_id)))?
+                    }
+                    None => output.serialize(fee)?,
+                }
+            }
+        }
+
+        if let (Ok(_), Some(before)) = (&result, before) {
+            let change = total(&self.engine).saturating_sub(before);
+            if let Some((rollup, _)) = self.rollup.as_mut() {
+                rollup.record(tx.tx_type, tx.timestamp, change);
+            }
+        }
+
+        if let (Ok(_), TxType::Deposit, Some(amount), Some((monitor, output))) =
+            (&result, tx.tx_type, tx.amount, self.aml.as_mut())
+        {
+            if let Some(flag) = monitor.deposit(tx.client_id, tx.id, amount) {
+                self.aml_rows += 1;
+                match self.report.pseudonyms.as_ref() {
+                    Some(pseudonyms) => {
+                        output.serialize(pseudonyms.row(&flag, Some(flag.client)))?
+                    }
+                    None => output.serialize(flag)?,
+                }
+            }
+        }
+
+        if let (
+            Ok(_),
+            TxType::Deposit | TxType::Withdrawal,
+            Some(amount),
+            Some((monitor, output)),
+        ) = (&result, tx.tx_type, tx.amount, self.anomalies.as_mut())
+        {
+            if let Some(flag) = monitor.record(tx.client_id, tx.id, tx.tx_type, amount) {
+                self.anomaly_rows += 1;
+                match self.report.pseudonyms.as_ref() {
+                    Some(pseudonyms) => {
+                        output.serialize(pseudonyms.row(&flag, Some(flag.client)))?
+                    }
+                    None => output.serialize(flag)?,
+                }
+            }
+        }
+
+        #[cfg(feature = "tui")]
+        if let Some(dashboard) = self.dashboard.as_mut() {
+            match (&result, tx.tx_type) {
+                (Err(_), _) => dashboard.reject(),
+                (Ok(_), TxType::Chargeback) => dashboard.lock(tx.client_id),
+                _ => {}
+            }
+            dashboard.refresh(self.records, &self.engine)?;
+        }
+
+        if let Some(err) = self.engine.archive_error() {
+            return Err(format!("transaction archive failed: {}", err).into());
+        }
+
+        log_tx(tx, &result);
+        if let Err(err) = result {
+            // surface tx id conflicts as a processing error under the abort duplicate policy
+            if self.engine.config().duplicate_policy == DuplicatePolicy::Abort
+                && err.kind() == &ErrorKind::TxIdConflict
+            {
+                return Err(err.into());
+            }
+
+            if err.kind() == &ErrorKind::MemoryBudgetExceeded {
+                return Err(err.into());
+            }
+        }
+
+        self.processed()
+    }
+
+    fn process_row(
+        &mut self,
+        line: Option<u64>,
+        row: Result<TxInput, String>,
+    ) -> Result<(), Box<dyn Error>> {
+        match row {
+            Ok(tx) => self.process_tx(line, &tx),
+            Err(reason) => self.parse_error(line, reason),
+        }
+    }
+
+    fn parse_error(&mut self, line: Option<u64>, reason: String) -> Result<(), Box<dyn Error>> {
+        self.records += 1;
+        debug!(line, reason = %reason, "record not parsed");
+        self.write_result(TxResult::parse_error(line, reason))?;
+
+        #[cfg(feature = "tui")]
+        if let Some(dashboard) = self.dashboard.as_mut() {
+            dashboard.parse_error();
+            dashboard.refresh(self.records, &self.engine)?;
+        }
+
+        self.processed()
+    }
+
+    fn write_result(&mut self, mut result: TxResult) -> Result<(), Box<dyn Error>> {
+        self.breakdown.record(&result);
+
+        if self.result_balances {
+            let client = result.client_id.and_then(|id| self.engine.client(id));
+            result.balances = Some(ResultBalances {
+                available: client.map(|client| client.available()),
+                held: client.map(|client| client.held()),
+                total: client.map(|client| client.total()),
+            });
+        }
+
+        if let Some(results) = self.results.as_mut() {
+            self.result_rows += 1;
+            match self.report.pseudonyms.as_ref() {
+                Some(pseudonyms) => results.serialize(pseudonyms.row(&result, result.client_id))?,
+                None => results.serialize(&result)?,
+            }
+        }
+
+        if let Some(rejects) = self
+            .rejects
+            .as_mut()
+            .filter(|_| result.status != TxStatus::Accepted)
+        {
+            rejects.push(result);
+        }
+
+        Ok(())
+    }
+
+    fn write_stats(&self, path: PathBuf) -> Result<(), Box<dyn Error>> {
+        let stats = Stats {
+            records: self.records,
+            clients: self.engine.report().count(),
+            locked_clients: self.engine.report().filter(|r| r.locked).count(),
+            transactions: self.engine.transaction_count(),
+            duplicates: self.engine.duplicates(),
+            replays: self.engine.replays(),
+            velocity_rejections: self.engine.velocity_rejections(),
+            filtered: self.filtered,
+            clamped: self.clamped,
+            breakdown: self.breakdown.clone(),
+        };
+
+        snapshots::write_atomic(&self.report.file_path(path), |file| {
+            self.report.encrypted(file, |output| {
+                Ok(serde_json::to_writer_pretty(output, &stats)?)
+            })
+        })
+    }
+
+    /// one row per client with rejected transactions
+    fn write_rejections(&self, path: PathBuf) -> Result<(), Box<dyn Error>> {
+        snapshots::write_atomic(&self.report.file_path(path), |file| {
+            self.report.encrypted(file, |output| {
+                // the header is written without rows too
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(output);
+                writer.write_record(REJECTIONS_HEADER)?;
+                for report in self.engine.report().filter(|r| r.activity.rejected > 0) {
+                    match self.report.pseudonyms.as_ref() {
+                        Some(pseudonyms) => writer.serialize(RejectionsRow::new(
+                            pseudonyms.token(report.id),
+                            &report.activity,
+                        ))?,
+                        None => {
+                            writer.serialize(RejectionsRow::new(report.id, &report.activity))?
+                        }
+                    }
+                }
+
+                Ok(writer.flush()?)
+            })
+        })
+    }
+
+    fn write_locked(&self, path: PathBuf) -> Result<(), Box<dyn Error>> {
+        snapshots::write_atomic(&self.report.file_path(path), |file| {
+            self.report.encrypted(file, |output| {
+                // the header is written without rows too
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(output);
+                writer.write_record(LOCKED_HEADER)?;
+                let locked = self
+                    .engine
+                    .report()
+                    .filter_map(|r| r.locked.then_some((r.id, r.locked_by?)));
+                for (id, locked_by) in locked {
+                    match self.report.pseudonyms.as_ref() {
+                        Some(pseudonyms) => {
+                            writer.serialize(LockedRow::new(pseudonyms.token(id), &locked_by))?
+                        }
+                        None => writer.serialize(LockedRow::new(id, &locked_by))?,
+                    }
+                }
+
+                Ok(writer.flush()?)
+            })
+        })
+    }
+
+    /// writes an intermediate report when one is due, and a full report
+    /// with a stats dump when one was requested
+    fn snapshot(&mut self) -> Result<(), Box<dyn Error>> {
+        // a requested snapshot keeps the changes pending for the next delta snapshot
+        if let Some(path) = self
+            .snapshots
+            .as_mut()
+            .filter(|snapshots| snapshots.requested())
+            .map(Snapshots::next_path)
+        {
+            snapshots::write_atomic(&self.report.file_path(path.clone()), |file| {
+                write_report(
+                    self.engine.report(),
+                    &self.report,
+                    OutputFormat::Csv,
+                    None,
+                    file,
+                )
+            })?;
+
+            self.write_stats(Snapshots::stats_path(&path))?;
+        }
+
+        let snapshots = match self.snapshots.as_mut() {
+            Some(snapshots) => snapshots,
+            None => return Ok(()),
+        };
+
+        if !snapshots.tick() {
+            return Ok(());
+        }
+
+        let path = self.report.file_path(snapshots.next_path());
+        match snapshots.take_changed() {
+            Some(changed) => snapshots::write_atomic(&path, |file| {
+                write_report(
+                    changed
+                        .into_iter()
+                        .filter_map(|id| self.engine.client_report(id)),
+                    &self.report,
+                    OutputFormat::Csv,
+                    None,
+                    file,
+                )
+            }),
+            None => snapshots::write_atomic(&path, |file| {
+                write_report(
+                    self.engine.report(),
+                    &self.report,
+                    OutputFormat::Csv,
+                    None,
+                    file,
+                )
+            }),
+        }
+    }
+
+    /// writes the balances of every client at the end of the day to the balance history
+    fn write_balances(&mut self, date: NaiveDate) -> Result<(), Box<dyn Error>> {
+        let Some((_, output)) = self.balance_history.as_mut() else {
+            return Ok(());
+        };
+
+        for report in self.engine.report() {
+            let row = BalanceRow {
+                client: report.id,
+                date,
+                available: report.available,
+                held: report.held,
+            };
+            match self.report.pseudonyms.as_ref() {
+                Some(pseudonyms) => output.serialize(pseudonyms.row(&row, Some(row.client)))?,
+                None => output.serialize(row)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// writes the interest every client accrued over the period to the interest accruals
+    fn write_accruals(&mut self, period: (NaiveDate, NaiveDate)) -> Result<(), Box<dyn Error>> {
+        let Some((accruals, output)) = self.interest.as_mut() else {
+            return Ok(());
+        };
+
+        for report in self.engine.report() {
+            let Some(row) = accruals.row(report.id, report.total, period) else {
+                continue;
+            };
+            match self.report.pseudonyms.as_ref() {
+                Some(pseudonyms) => output.serialize(pseudonyms.row(&row, Some(row.client)))?,
+                None => output.serialize(row)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn finish<W: io::Write>(mut self, output: W) -> Result<(), Box<dyn Error>> {
+        let mut output = DigestWriter::new(output);
+
+        // the open day of a completed run ends with the input, a stopped one is resumed
+        if let (None, Some(day)) = (
+            self.stopped(),
+            self.balance_history
+                .as_ref()
+                .and_then(|(history, _)| history.day()),
+        ) {
+            self.write_balances(day)?;
+        }
+
+        // a completed run with an as-of date accrues the open day up to it
+        if let (None, Some(day), Some(as_of)) = (
+            self.stopped(),
+            self.interest
+                .as_ref()
+                .and_then(|(accruals, _)| accruals.day()),
+            self.engine.config().as_of,
+        ) {
+            if as_of > day {
+                self.write_accruals((day, as_of))?;
+            }
+        }
+
+        // leave the dashboard screen before the report is written
+        #[cfg(feature = "tui")]
+        drop(self.dashboard.take());
+
+        if let Some(results) = self.results.as_mut() {
+            results.flush()?;
+        }
+
+        if let Some((_, output)) = self.aml.as_mut() {
+            output.flush()?;
+        }
+
+        if let Some((_, output)) = self.anomalies.as_mut() {
+            output.flush()?;
+        }
+
+        if let Some(output) = self.fees.as_mut() {
+            output.flush()?;
+        }
+
+        if let Some((_, output)) = self.balance_history.as_mut() {
+            output.flush()?;
+        }
+
+        if let Some((_, output)) = self.interest.as_mut() {
+            output.flush()?;
+        }
+
+        // a stopped run is checkpointed where it stopped, a completed one has nothing to resume
+        let stopped = self.stopped();
+        match (stopped, self.checkpoint.as_ref()) {
+            (Some(_), Some(_)) => self.save_checkpoint()?,
+            (None, Some(checkpoint)) => match std::fs::remove_file(&checkpoint.path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+                _ => {}
+            },
+            (_, None) => {}
+        }
+
+        debug!(
+            records = self.records,
+            duplicates = self.engine.duplicates(),
+            replays = self.engine.replays(),
+            retention_drops = self.engine.retention_drops(),
+            velocity_rejections = self.engine.velocity_rejections(),
+            queued = self.engine.queued().count(),
+            filtered = self.filtered,
+            clamped = self.clamped,
+            "run finished"
+        );
+
+        // a stopped run is resumed without its cross-check
+        if let (None, Some(cross_check)) = (stopped, self.cross_check.take()) {
+            cross_check.finish(self.engine.report())?;
+        }
+
+        if let Some(path) = self.stats.clone() {
+            self.write_stats(path)?;
+        }
+
+        if let Some(path) = self.rejections_by_client.clone() {
+            self.write_rejections(path)?;
+        }
+
+        if let Some(path) = self.locked_report.clone() {
+            self.write_locked(path)?;
+        }
+
+        if let Some((rollup, path)) = self.rollup.as_ref() {
+            snapshots::write_atomic(path, |file| {
+                let mut writer = csv::Writer::from_writer(file);
+                for row in rollup.rows() {
+                    writer.serialize(row)?;
+                }
+                Ok(writer.flush()?)
+            })?;
+        }
+
+        if let Some(path) = self.queued.as_ref() {
+            snapshots::write_atomic(path, |file| {
+                let mut writer = csv::Writer::from_writer(file);
+                for tx in self.engine.queued() {
+                    writer.serialize(tx)?;
+                }
+                Ok(writer.flush()?)
+            })?;
+        }
+
+        // json and binary outputs have no comments, the interruption is only reported by the error
+        self.profile.report(|| -> Result<(), Box<dyn Error>> {
+            if !self.sinks.is_empty() {
+                for sink in &self.sinks {
+                    sink.write(SinkInput {
+                        engine: &self.engine,
+                        report: &self.report,
+                        rejects: self.rejects.as_deref().unwrap_or_default(),
+                    })?;
+                }
+                return Ok(());
+            }
+
+            // a comment before the sealed report would corrupt it
+            if let Some(signal) =
+                stopped.filter(|_| self.report.output.has_comments() && !self.report.encrypts())
+            {
+                writeln!(
+                    output,
+                    "# partial report: interrupted by signal {} after {} records",
+                    signal, self.records
+                )?;
+            }
+
+            write_report(
+                self.engine.report(),
+                &self.report,
+                self.report.output,
+                None,
+                &mut output,
+            )
+        })?;
+
+        // a partial run has no manifest
+        if let (None, Some(manifest)) = (stopped, self.manifest.as_ref()) {
+            self.write_manifest(manifest, output)?;
+        }
+        self.profile.write(io::stderr().lock(), self.records)?;
+
+        match stopped {
+            Some(signal) => Err(Interrupted {
+                signal,
+                records: self.records,
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Processor {
+    fn write_manifest<W: io::Write>(
+        &self,
+        options: &ManifestOptions,
+        output: DigestWriter<W>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut manifest = Manifest::new(options)?;
+
+        let report_rows = self
+            .engine
+            .report()
+            .filter(|report| self.report.filter.matches(report))
+            .count() as u64;
+        if self.sinks.is_empty() {
+            manifest.output(
+                OutputKind::Report,
+                report_rows,
+                output.digest("-".to_string()),
+            );
+        }
+        for path in self.sinks.iter().filter_map(ReportSink::path) {
+            manifest.output(OutputKind::Report, report_rows, FileDigest::of_file(path)?);
+        }
+
+        for (kind, path) in &options.outputs {
+            let rows = match kind {
+                OutputKind::Report => report_rows,
+                OutputKind::Results => self.result_rows,
+                OutputKind::Aml => self.aml_rows,
+                OutputKind::Anomalies => self.anomaly_rows,
+            };
+            manifest.output(*kind, rows, FileDigest::of_file(path)?);
+        }
+
+        manifest.write(options)
+    }
+}
+
+pub(crate) fn write_report<W: io::Write>(
+    reports: impl Iterator<Item = ClientReport>,
+    format: &ReportFormat,
+    output_format: OutputFormat,
+    transform: Option<&ReportTransform>,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    format.encrypted(output, |output| {
+        write_rows(reports, format, output_format, transform, output)
+    })
+}
+
+fn write_rows<W: io::Write>(
+    reports: impl Iterator<Item = ClientReport>,
+    format: &ReportFormat,
+    output_format: OutputFormat,
+    transform: Option<&ReportTransform>,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    let (capacity, output): (_, Box<dyn io::Write + '_>) = match format.flush_every {
+        Some(FlushInterval::Bytes(bytes)) => (bytes as usize, Box::new(FlushOnWrite(output))),
+        _ => (REPORT_BUFFER, Box::new(output)),
+    };
+    let mut formatter = output_format.formatter(output, capacity);
+
+    let reports = reports.filter(|report| format.filter.matches(report));
+    for (row, v) in (1..).zip(reports) {
+        let token = format
+            .pseudonyms
+            .as_ref()
+            .map(|pseudonyms| pseudonyms.token(v.id));
+        formatter.write_row(
+            &ReportRow::new(&v, format.columns, format.scale)
+                .with_transform(transform)
+                .with_client(token.as_deref()),
+        )?;
+
+        if let Some(FlushInterval::Rows(rows)) = format.flush_every {
+            if row % rows == 0 {
+                formatter.flush()?;
+            }
+        }
+    }
+
+    formatter.finish()
+}
+
+pub fn reconcile<R: io::Read, E: io::Read, W: io::Write>(
+    report: R,
+    external: E,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    let report = csv::ReaderBuilder::new()
+        .trim(Trim::All)
+        .comment(Some(b'#'))
+        .from_reader(report)
+        .into_deserialize::<ClientReport>()
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let external = csv::ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_reader(external)
+        .into_deserialize::<ExternalBalance>()
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut wtr = csv::Writer::from_writer(output);
+
+    for row in reconcile::reconcile(report, external) {
+        wtr.serialize(row)?;
+    }
+
+    match wtr.flush() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// applies the extra transactions on a copy of the engine built from the base transactions,
+/// and writes the clients whose balances changed, the base state is left untouched
+pub fn simulate<B: io::Read, E: io::Read, W: io::Write>(
+    base: B,
+    extra: E,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    let mut engine = engine::Engine::new();
+    apply_csv(base, &mut engine)?;
+
+    let mut simulated = engine.clone();
+    apply_csv(extra, &mut simulated)?;
+
+    let mut wtr = csv::Writer::from_writer(output);
+
+    for row in simulate::changes(engine.report(), simulated.report()) {
+        wtr.serialize(row)?;
+    }
+
+    match wtr.flush() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// routes the records of a csv input to the shard of their client, each shard gets the input header,
+/// records without a valid client id go to the first shard where they're reported as parse errors
+pub fn shard<R: io::Read, W: io::Write>(input: R, shards: &mut [W]) -> Result<(), Box<dyn Error>> {
+    let count = u16::try_from(shards.len()).map_err(|_| "too many shards")?;
+    let mut rdr = csv::ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(input);
+    let header = rdr.byte_headers()?.clone();
+    let client = header
+        .iter()
+        .position(|field| field == b"client")
+        .ok_or("missing client column")?;
+
+    let mut writers: Vec<_> = shards
+        .iter_mut()
+        .map(|shard| csv::WriterBuilder::new().flexible(true).from_writer(shard))
+        .collect();
+    for wtr in writers.iter_mut() {
+        wtr.write_byte_record(&header)?;
+    }
+
+    let mut record = csv::ByteRecord::new();
+    while rdr.read_byte_record(&mut record)? {
+        let shard = record
+            .get(client)
+            .and_then(|id| std::str::from_utf8(id).ok()?.parse::<u16>().ok())
+            .map_or(0, |id| shard::shard_of(id, count));
+        writers[shard].write_byte_record(&record)?;
+    }
+
+    for wtr in writers.iter_mut() {
+        wtr.flush()?;
+    }
+
+    Ok(())
+}
+
+/// writes the transactions of the workload generated from the seed
+pub fn generate<W: io::Write>(
+    seed: u64,
+    clients: u16,
+    txs: usize,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    let mut wtr = csv::Writer::from_writer(output);
+
+    for tx in workload::generate(&mut Rng::new(seed), WorkloadOptions { clients, txs }) {
+        wtr.serialize(tx)?;
+    }
+
+    match wtr.flush() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// merges the client reports of the shards, partial report comments are skipped
+pub fn report_collect<R: io::Read, W: io::Write>(
+    reports: Vec<R>,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    let mut header = None;
+    let mut shards = Vec::with_capacity(reports.len());
+
+    for report in reports {
+        let mut rdr = csv::ReaderBuilder::new()
+            .trim(Trim::All)
+            .comment(Some(b'#'))
+            .from_reader(report);
+        let report_header = rdr.headers()?.clone();
+        let rows = rdr.into_records().collect::<Result<Vec<_>, _>>()?;
+
+        header.get_or_insert_with(|| report_header.clone());
+        shards.push((report_header, rows));
+    }
+
+    let header = header.ok_or("missing shard reports")?;
+    let mut wtr = csv::Writer::from_writer(output);
+    wtr.write_record(&header)?;
+
+    for row in shard::collect(&header, shards)? {
+        wtr.write_record(&row)?;
+    }
+
+    match wtr.flush() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// applies the transactions of a csv input and writes the chargeback report of its clients,
+/// ending with the overall row
+pub fn chargeback_report<R: io::Read, W: io::Write>(
+    input: R,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    let mut engine = engine::Engine::new();
+    let mut report = ChargebackReport::default();
+    apply_csv_with(input, &mut engine, |engine, tx| {
+        match (tx.tx_type, tx.amount) {
+            (TxType::Deposit, Some(amount)) => report.deposit(tx.client_id, amount),
+            (TxType::Capture, _) => {
+                if let Some(captured) = engine.transaction(tx.id) {
+                    report.deposit(tx.client_id, captured.amount());
+                }
+            }
+            (TxType::Chargeback, _) => {
+                if let Some(deposit) = engine.transaction(tx.id) {
+                    report.chargeback(tx.client_id, deposit.amount());
+                }
+            }
+            _ => {}
+        }
+    })?;
+
+    let (rows, overall) = report.rows();
+    let mut wtr = csv::Writer::from_writer(output);
+    for row in rows {
+        wtr.serialize(row)?;
+    }
+    wtr.serialize(overall)?;
+
+    match wtr.flush() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// applies the transactions of a csv input and writes each settlement with the net of its batch
+/// before it, then the batches still open at the end sorted by batch id
+pub fn settlement_report<R: io::Read, W: io::Write>(
+    input: R,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(input);
+    let parser = RecordParser::new(rdr.byte_headers()?, DecimalSeparator::Dot)?;
+    let mut engine = engine::Engine::new();
+    let mut wtr = csv::Writer::from_writer(output);
+
+    for row in Rows::new(rdr, parser) {
+        let tx = match row? {
+            (_, Ok(tx)) => tx,
+            (line, Err(reason)) => {
+                debug!(line, reason = %reason, "record not parsed");
+                continue;
+            }
+        };
+
+        let batch = tx.batch.as_deref();
+        let computed = batch.and_then(|batch| engine.batch_net(batch));
+        log_tx(&tx, &engine.process_tx(&tx));
+
+        if tx.tx_type == TxType::Settlement {
+            wtr.serialize(SettlementRow::settlement(batch, tx.id, tx.amount, computed))?;
+        }
+    }
+
+    let mut open: Vec<_> = engine.open_batches().collect();
+    open.sort_unstable_by_key(|(batch, _)| *batch);
+    for (batch, computed) in open {
+        wtr.serialize(SettlementRow::unsettled(batch, computed))?;
+    }
+
+    match wtr.flush() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// applies the transactions of a csv input and writes its disputes still open at the end, aged at
+/// the time with the timestamps of the disputes
+pub fn dispute_aging<R: io::Read, W: io::Write>(
+    input: R,
+    as_of: DateTime<Utc>,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    let mut engine = engine::Engine::new();
+    let mut aging = DisputeAging::default();
+    apply_csv_with(input, &mut engine, |engine, tx| match tx.tx_type {
+        TxType::Dispute => {
+            if let Some(disputed) = engine.transaction(tx.id) {
+                aging.open(tx.client_id, tx.id, disputed.amount(), tx.timestamp);
+            }
+        }
+        TxType::Resolve | TxType::Chargeback => aging.close(tx.id),
+        _ => {}
+    })?;
+
+    let mut wtr = csv::Writer::from_writer(output);
+    for row in aging.rows(as_of) {
+        wtr.serialize(row)?;
+    }
+
+    match wtr.flush() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// applies admin commands to the engine state of a checkpoint and writes the adjusted checkpoint
+/// to `out`, the other state of the checkpoint is kept; the commands are all read before any is
+/// applied, and a rejected command is audited without stopping the next ones
+pub fn admin<R: io::Read, W: io::Write>(
+    checkpoint_path: &Path,
+    commands: R,
+    out: &Path,
+    at: DateTime<Utc>,
+    output: W,
+) -> Result<(), Box<dyn Error>> {
+    let commands = csv::ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_reader(commands)
+        .into_deserialize::<AdminCommand>()
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut checkpoint = Checkpoint::read(checkpoint_path)?;
+    // the velocity windows and retained transactions only exist with the engine options of the
+    // run, they're carried over unchanged
+    let velocity = checkpoint.engine.velocity.take();
+    let retained = checkpoint.engine.retained.take();
+    let mut engine = engine::Engine::new();
+    engine.restore(std::mem::take(&mut checkpoint.engine));
+
+    let mut wtr = csv::Writer::from_writer(output);
+    for (index, command) in commands.iter().enumerate() {
+        let result = engine.apply_admin(command);
+        if let Err(err) = &result {
+            debug!(
+                tx_id = command.tx_id,
+                client_id = command.client_id,
+                action = ?command.action,
+                error_code = err.code(),
+                "admin command rejected: {}",
+                err.kind()
+            );
+        }
+        // the header is the first line
+        let line = index as u64 + 2;
+        wtr.serialize(AdminAudit::new(
+            line,
+            at,
+            command,
+            &result,
+            engine.client(command.client_id),
+        ))?;
+    }
+
+    checkpoint.engine = EngineState {
+        velocity,
+        retained,
+        ..engine.state()
+    };
+    checkpoint.write(out)?;
+
+    match wtr.flush() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// columns of the decided transactions of the dispute review
+const DECISIONS_HEADER: [&str; 5] = ["type", "client", "tx", "amount", "timestamp"];
+
+/// prompts for a decision on each open dispute of a checkpoint, in tx id order, and writes a
+/// resolve or chargeback transaction for each decided one; answers are read a line at a time until
+/// quit or the end of the answers, the header is only written when `header` is set
+pub fn review_disputes<A: io::BufRead, P: io::Write, W: io::Write>(
+    checkpoint_path: &Path,
+    mut answers: A,
+    mut prompts: P,
+    decisions: W,
+    header: bool,
+    at: DateTime<Utc>,
+) -> Result<(), Box<dyn Error>> {
+    let mut engine = engine::Engine::new();
+    engine.restore(Checkpoint::read(checkpoint_path)?.engine);
+
+    let mut open: Vec<_> = engine
+        .transactions()
+        .filter(|(_, tx)| tx.dispute_state() == DisputeState::Open)
+        .collect();
+    open.sort_by_key(|(id, _)| *id);
+    writeln!(prompts, "{} open disputes", open.len())?;
+
+    let mut wtr = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(decisions);
+    if header {
+        wtr.write_record(DECISIONS_HEADER)?;
+    }
+
+    let mut answer = String::new();
+    'disputes: for (id, tx) in open {
+        let client = engine.client(tx.client_id());
+        let decision = loop {
+            write!(
+                prompts,
+                "client {} tx {} held {} (available {}, locked {}): {}? ",
+                tx.client_id(),
+                id,
+                tx.amount(),
+                client.map_or(Decimal::ZERO, |client| client.available()),
+                client.is_some_and(|client| client.locked()),
+                Decision::PROMPT
+            )?;
+            prompts.flush()?;
+
+            answer.clear();
+            if answers.read_line(&mut answer)? == 0 {
+                break 'disputes;
+            }
+            match Decision::parse(&answer) {
+                Some(decision) => break decision,
+                None => writeln!(prompts, "unknown answer {:?}", answer.trim())?,
+            }
+        };
+
+        match (decision, decision.tx_type()) {
+            (Decision::Quit, _) => break,
+            (_, Some(tx_type)) => {
+                wtr.serialize((tx_type, tx.client_id(), id, None::<Decimal>, at))?;
+                wtr.flush()?;
+            }
+            (_, None) => {}
+        }
+    }
+
+    match wtr.flush() {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// applies the transactions of a csv input, invalid and rejected records are skipped
+fn apply_csv<R: io::Read>(input: R, engine: &mut engine::Engine) -> Result<(), Box<dyn Error>> {
+    apply_csv_with(input, engine, |_, _| {})
+}
+
+/// parses the records of a csv input without applying them, returns the number of valid and
+/// invalid records; the parsing baseline of the benchmarks
+pub fn parse_input<R: io::Read>(input: R) -> Result<(u64, u64), Box<dyn Error>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(input);
+    let parser = RecordParser::new(rdr.byte_headers()?, DecimalSeparator::Dot)?;
+
+    let (mut valid, mut invalid) = (0, 0);
+    for row in Rows::new(rdr, parser) {
+        match row? {
+            (_, Ok(_)) => valid += 1,
+            (_, Err(_)) => invalid += 1,
+        }
+    }
+
+    Ok((valid, invalid))
+}
+
+/// applies the transactions of a csv input, each accepted one is passed to `applied` with the
+/// engine, invalid and rejected records are skipped
+fn apply_csv_with<R: io::Read>(
+    input: R,
+    engine: &mut engine::Engine,
+    mut applied: impl FnMut(&engine::Engine, &TxInput),
+) -> Result<(), Box<dyn Error>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(input);
+    let parser = RecordParser::new(rdr.byte_headers()?, DecimalSeparator::Dot)?;
+
+    for row in Rows::new(rdr, parser) {
+        match row? {
+            (_, Ok(tx)) => {
+                let result = engine.process_tx(&tx);
+                log_tx(&tx, &result);
+                if result.is_ok() {
+                    applied(engine, &tx);
+                }
+            }
+            (line, Err(reason)) => debug!(line, reason = %reason, "record not parsed"),
+        }
+    }
+
+    Ok(())
 }
@@ -0,0 +1,109 @@
+use std::io;
+use std::sync::{Mutex, PoisonError};
+
+use tracing::error;
+
+use crate::engine::alerts::{Alert, AlertObserver};
+
+/// writes each alert as a json line, flushed right away so the alerts can be followed while
+/// processing; a failed write is logged and processing continues
+pub struct AlertLog {
+    output: Mutex<Box<dyn io::Write + Send>>,
+}
+
+impl AlertLog {
+    pub fn new(output: Box<dyn io::Write + Send>) -> Self {
+        Self {
+            output: Mutex::new(output),
+        }
+    }
+}
+
+impl AlertObserver for AlertLog {
+    fn notify(&self, alert: &Alert) {
+        let mut output = self.output.lock().unwrap_or_else(PoisonError::into_inner);
+        let written = serde_json::to_writer(&mut *output, alert)
+            .map_err(io::Error::from)
+            .and_then(|_| output.write_all(b"\n"))
+            .and_then(|_| output.flush());
+
+        if let Err(err) = written {
+            error!("failed to write alert: {}", err);
+        }
+    }
+}
+
+/// posts each alert as json to a webhook url, a failed request is logged and processing continues
+#[cfg(feature = "webhook")]
+pub struct AlertWebhook {
+    agent: ureq::Agent,
+    url: String,
+}
+
+#[cfg(feature = "webhook")]
+impl AlertWebhook {
+    pub fn new(url: &str) -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            url: url.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "webhook")]
+impl AlertObserver for AlertWebhook {
+    fn notify(&self, alert: &Alert) {
+        let posted = serde_json::to_string(alert)
+            .map_err(|err| err.to_string())
+            .and_then(|body| {
+                self.agent
+                    .post(&self.url)
+                    .set("Content-Type", "application/json")
+                    .send_string(&body)
+                    .map_err(|err| err.to_string())
+            });
+
+        if let Err(err) = posted {
+            error!("failed to post alert to {}: {}", self.url, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::engine::alerts::AlertKind;
+
+    #[derive(Default, Clone)]
+    struct Shared(std::sync::Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_lines() {
+        let output = Shared::default();
+        let log = AlertLog::new(Box::new(output.clone()));
+        log.notify(&Alert {
+            kind: AlertKind::NegativeAvailable,
+            client_id: 2,
+            tx_id: 7,
+            available: dec!(-1.5),
+            held: dec!(3),
+        });
+
+        assert_eq!(
+            String::from_utf8(output.0.lock().unwrap().clone()).unwrap(),
+            "{\"alert\":\"negative_available\",\"client\":2,\"tx\":7,\"available\":\"-1.5\",\"held\":\"3\"}\n"
+        );
+    }
+}
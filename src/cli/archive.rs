@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::engine::archive::{ArchivedTx, TxArchive, ARCHIVED_TX_BYTES};
+use crate::models::tx::TxId;
+
+/// bytes of an archive slot, a presence byte then the archived transaction
+const SLOT_BYTES: u64 = 1 + ARCHIVED_TX_BYTES as u64;
+
+/// bytes of a segment record, tx id then the archived transaction
+const RECORD_BYTES: usize = size_of::<TxId>() + ARCHIVED_TX_BYTES;
+
+/// bytes of a segment header: first and last tx id, record count and compressed length
+const HEADER_BYTES: usize = 16;
+
+/// transactions buffered in memory before they're written as a segment
+const SEGMENT_RECORDS: usize = 4096;
+
+/// on-disk layout of the archive of the transactions dropped from memory
+#[derive(ValueEnum, Debug, Default, PartialEq, Copy, Clone)]
+pub enum ArchiveLayout {
+    /// a fixed slot per tx id in a sparse file, nothing is kept in memory
+    #[default]
+    Slots,
+    /// lz4 compressed segments of transactions sorted by tx id, with the tx id range of each
+    /// segment kept in memory
+    Segments,
+}
+
+/// archive file addressed by tx id, each transaction has a fixed slot at `id * 21` so nothing
+/// but the file is kept in memory; the file is sparse, it only takes the disk space of the
+/// slots written
+pub struct FileArchive {
+    file: File,
+}
+
+impl FileArchive {
+    /// creates the archive, or with `reopen` keeps the slots of an interrupted run
+    pub fn open(path: &Path, reopen: bool) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(!reopen)
+            .open(path)?;
+
+        Ok(Self { file })
+    }
+}
+
+impl TxArchive for FileArchive {
+    fn store(&mut self, id: TxId, tx: &ArchivedTx) -> io::Result<()> {
+        let mut slot = [1; SLOT_BYTES as usize];
+        slot[1..].copy_from_slice(tx.as_bytes());
+
+        self.file
+            .seek(SeekFrom::Start(u64::from(id) * SLOT_BYTES))?;
+        self.file.write_all(&slot)
+    }
+
+    fn load(&mut self, id: TxId) -> io::Result<Option<ArchivedTx>> {
+        let mut slot = [0; SLOT_BYTES as usize];
+        self.file
+            .seek(SeekFrom::Start(u64::from(id) * SLOT_BYTES))?;
+        match self.file.read_exact(&mut slot) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+
+        if slot[0] == 0 {
+            return Ok(None);
+        }
+
+        let mut tx = [0; ARCHIVED_TX_BYTES];
+        tx.copy_from_slice(&slot[1..]);
+        Ok(Some(ArchivedTx::from_bytes(tx)))
+    }
+}
+
+/// tx id range and position of a segment
+#[derive(Debug)]
+struct SegmentIndex {
+    first: TxId,
+    last: TxId,
+    offset: u64,
+    records: usize,
+    len: usize,
+}
+
+/// append-only archive of lz4 compressed segments, each holding up to 4096 transactions sorted by
+/// tx id; a load reads the latest segment whose tx id range holds the id, so the archive stays
+/// small and the sparse index holds one entry per segment
+pub struct SegmentArchive {
+    file: File,
+    end: u64,
+    index: Vec<SegmentIndex>,
+    /// transactions stored since the last segment was written
+    pending: BTreeMap<TxId, ArchivedTx>,
+    /// last segment read, disputes of close transactions read the same one
+    cached: Option<(usize, Vec<(TxId, ArchivedTx)>)>,
+}
+
+impl SegmentArchive {
+    /// creates the archive, or with `reopen` indexes the segments of an interrupted run and drops
+    /// a partly written last segment
+    pub fn open(path: &Path, reopen: bool) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(!reopen)
+            .open(path)?;
+
+        let size = file.metadata()?.len();
+        let mut index = Vec::new();
+        let mut end = 0;
+        let mut header = [0; HEADER_BYTES];
+        while end + HEADER_BYTES as u64 <= size {
+            file.seek(SeekFrom::Start(end))?;
+            file.read_exact(&mut header)?;
+
+            let field = |n: usize| u32::from_le_bytes(header[n * 4..n * 4 + 4].try_into().unwrap());
+            let segment = SegmentIndex {
+                first: field(0),
+                last: field(1),
+                offset: end + HEADER_BYTES as u64,
+                records: field(2) as usize,
+                len: field(3) as usize,
+            };
+            if segment.offset + segment.len as u64 > size {
+                break;
+            }
+
+            end = segment.offset + segment.len as u64;
+            index.push(segment);
+        }
+        file.set_len(end)?;
+
+        Ok(Self {
+            file,
+            end,
+            index,
+            pending: BTreeMap::new(),
+            cached: None,
+        })
+    }
+
+    /// appends the pending transactions as a segment
+    fn write_segment(&mut self) -> io::Result<()> {
+        let (Some((&first, _)), Some((&last, _))) = (
+            self.pending.first_key_value(),
+            self.pending.last_key_value(),
+        ) else {
+            return Ok(());
+        };
+
+        let mut records = Vec::with_capacity(self.pending.len() * RECORD_BYTES);
+        for (id, tx) in &self.pending {
+            records.extend_from_slice(&id.to_le_bytes());
+            records.extend_from_slice(tx.as_bytes());
+        }
+        let compressed = lz4_flex::block::compress(&records);
+
+        let mut segment = Vec::with_capacity(HEADER_BYTES + compressed.len());
+        for field in [
+            first,
+            last,
+            self.pending.len() as u32,
+            compressed.len() as u32,
+        ] {
+            segment.extend_from_slice(&field.to_le_bytes());
+        }
+        segment.extend_from_slice(&compressed);
+        self.file.seek(SeekFrom::Start(self.end))?;
+        self.file.write_all(&segment)?;
+
+        self.index.push(SegmentIndex {
+            first,
+            last,
+            offset: self.end + HEADER_BYTES as u64,
+            records: self.pending.len(),
+            len: compressed.len(),
+        });
+        self.end += segment.len() as u64;
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// records of the nth segment, sorted by tx id
+    fn segment(&mut self, n: usize) -> io::Result<&[(TxId, ArchivedTx)]> {
+        if self.cached.as_ref().is_none_or(|(cached, _)| *cached != n) {
+            let segment = &self.index[n];
+            let mut compressed = vec![0; segment.len];
+            self.file.seek(SeekFrom::Start(segment.offset))?;
+            self.file.read_exact(&mut compressed)?;
+
+            let records = lz4_flex::block::decompress(&compressed, segment.records * RECORD_BYTES)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            let records = records
+                .chunks_exact(RECORD_BYTES)
+                .map(|record| {
+                    let (id, tx) = record.split_at(size_of::<TxId>());
+                    (
+                        TxId::from_le_bytes(id.try_into().unwrap()),
+                        ArchivedTx::from_bytes(tx.try_into().unwrap()),
+                    )
+                })
+                .collect();
+            self.cached = Some((n, records));
+        }
+
+        Ok(self.cached.as_ref().map_or(&[], |(_, records)| records))
+    }
+}
+
+impl TxArchive for SegmentArchive {
+    fn store(&mut self, id: TxId, tx: &ArchivedTx) -> io::Result<()> {
+        self.pending.insert(id, *tx);
+        if self.pending.len() >= SEGMENT_RECORDS {
+            self.write_segment()?;
+        }
+
+        Ok(())
+    }
+
+    fn load(&mut self, id: TxId) -> io::Result<Option<ArchivedTx>> {
+        if let Some(tx) = self.pending.get(&id) {
+            return Ok(Some(*tx));
+        }
+
+        // the latest segment holding the id has its latest version
+        for n in (0..self.index.len()).rev() {
+            if !(self.index[n].first..=self.index[n].last).contains(&id) {
+                continue;
+            }
+
+            let records = self.segment(n)?;
+            if let Ok(found) = records.binary_search_by_key(&id, |(id, _)| *id) {
+                return Ok(Some(records[found].1));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_segment()?;
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::models::tx::{Tx, TxInput};
+    use crate::models::tx_type::TxType;
+
+    #[test]
+    fn store_and_load() {
+        let path = std::env::temp_dir().join(format!("ttx-archive-{}.bin", std::process::id()));
+        let tx = ArchivedTx::new(&Tx::new(&TxInput {
+            tx_type: TxType::Deposit,
+            client_id: 2,
+            id: 7,
+            amount: Some(dec!(1.5)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        }));
+
+        let mut archive = FileArchive::open(&path, false).expect("failed to create archive");
+        archive.store(7, &tx).expect("failed to store");
+        assert_eq!(archive.load(7).expect("failed to load"), Some(tx));
+        assert_eq!(archive.load(3).expect("failed to load"), None);
+        assert_eq!(archive.load(8).expect("failed to load"), None);
+
+        let mut reopened = FileArchive::open(&path, true).expect("failed to reopen archive");
+        assert_eq!(reopened.load(7).expect("failed to load"), Some(tx));
+        let mut created = FileArchive::open(&path, false).expect("failed to create archive");
+        assert_eq!(created.load(7).expect("failed to load"), None);
+
+        fs::remove_file(path).expect("failed to remove archive");
+    }
+
+    #[test]
+    fn segments() {
+        let path = std::env::temp_dir().join(format!("ttx-segments-{}.bin", std::process::id()));
+        let tx = |amount| {
+            ArchivedTx::new(&Tx::new(&TxInput {
+                tx_type: TxType::Deposit,
+                client_id: 1,
+                id: 0,
+                amount: Some(amount),
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
+            }))
+        };
+
+        let mut archive = SegmentArchive::open(&path, false).expect("failed to create archive");
+        for id in 0..SEGMENT_RECORDS as TxId + 10 {
+            archive.store(id, &tx(dec!(1))).expect("failed to store");
+        }
+        assert_eq!(archive.index.len(), 1);
+        archive.store(5, &tx(dec!(2))).expect("failed to store");
+        assert_eq!(archive.load(5).expect("failed to load"), Some(tx(dec!(2))));
+        assert_eq!(archive.load(6).expect("failed to load"), Some(tx(dec!(1))));
+        assert_eq!(
+            archive
+                .load(SEGMENT_RECORDS as TxId + 5)
+                .expect("failed to load"),
+            Some(tx(dec!(1)))
+        );
+        archive.flush().expect("failed to flush");
+
+        // a partly written segment is dropped when reopened
+        let size = fs::metadata(&path).expect("missing archive").len();
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(&[1; 20]))
+            .expect("failed to append");
+        let mut reopened = SegmentArchive::open(&path, true).expect("failed to reopen archive");
+        assert_eq!(fs::metadata(&path).expect("missing archive").len(), size);
+        assert_eq!(reopened.index.len(), 2);
+        assert_eq!(reopened.load(5).expect("failed to load"), Some(tx(dec!(2))));
+        assert_eq!(reopened.load(7).expect("failed to load"), Some(tx(dec!(1))));
+        assert_eq!(reopened.load(u32::MAX).expect("failed to load"), None);
+
+        fs::remove_file(path).expect("failed to remove archive");
+    }
+}
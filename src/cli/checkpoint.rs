@@ -0,0 +1,114 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::anomaly::AmountHistory;
+use crate::cli::snapshots;
+use crate::cli::snapshots::Breakdown;
+use crate::engine::state::EngineState;
+use crate::models::tx::ClientId;
+use crate::rollup::DailyRollup;
+
+/// checkpoints of the input offset and engine state, an interrupted run over a csv input
+/// continues from the last one instead of reprocessing the input from its first row
+#[derive(Debug, Clone)]
+pub struct CheckpointOptions {
+    pub path: PathBuf,
+
+    /// rows between checkpoints, a checkpoint is only written when processing is stopped when None
+    pub every: Option<u64>,
+
+    /// continue from the checkpoint at the path, the per record outputs are appended to
+    pub resume: bool,
+}
+
+/// records processed so far and the state they left, written atomically as json
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct Checkpoint {
+    /// input records processed, the resumed run skips this many csv records
+    pub(crate) records: u64,
+    pub(crate) result_rows: u64,
+    pub(crate) aml_rows: u64,
+    pub(crate) filtered: u64,
+    pub(crate) clamped: u64,
+    pub(crate) breakdown: Breakdown,
+    /// the input carried timestamps, the report has a last activity column
+    pub(crate) timestamps: bool,
+    pub(crate) engine: EngineState,
+    /// deposit sums of the aml monitor
+    pub(crate) aml: Vec<(ClientId, Decimal)>,
+    pub(crate) anomaly_rows: u64,
+    /// trailing amounts of the anomaly monitor
+    pub(crate) anomalies: Vec<AmountHistory>,
+    /// days aggregated by the rollup report
+    #[serde(default)]
+    pub(crate) rollup: Option<DailyRollup>,
+    /// open day of the balance history
+    #[serde(default)]
+    pub(crate) balance_day: Option<NaiveDate>,
+    /// open day of the interest accruals
+    #[serde(default)]
+    pub(crate) interest_day: Option<NaiveDate>,
+}
+
+impl Checkpoint {
+    pub(crate) fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let file = fs::File::open(path)
+            .map_err(|err| format!("failed to open checkpoint {}: {}", path.display(), err))?;
+
+        Ok(serde_json::from_reader(io::BufReader::new(file))?)
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        snapshots::write_atomic(path, |file| Ok(serde_json::to_writer(file, self)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::engine::Engine;
+    use crate::models::tx::TxInput;
+    use crate::models::tx_type::TxType;
+
+    #[test]
+    fn write_and_read() {
+        let mut engine = Engine::new();
+        engine
+            .process_tx(&TxInput {
+                tx_type: TxType::Deposit,
+                client_id: 1,
+                id: 1,
+                amount: Some(dec!(2.5)),
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
+            })
+            .expect("failed to deposit");
+
+        let checkpoint = Checkpoint {
+            records: 3,
+            result_rows: 3,
+            engine: engine.state(),
+            aml: vec![(1, dec!(2.5))],
+            ..Default::default()
+        };
+        let path = std::env::temp_dir().join(format!("ttx-checkpoint-{}.json", std::process::id()));
+        checkpoint.write(&path).expect("failed to write checkpoint");
+
+        assert_eq!(
+            Checkpoint::read(&path).expect("failed to read checkpoint"),
+            checkpoint
+        );
+        fs::remove_file(&path).expect("failed to remove checkpoint");
+        assert!(Checkpoint::read(&path).is_err());
+    }
+}
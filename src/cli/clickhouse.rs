@@ -0,0 +1,125 @@
+use std::io;
+
+use crate::cli::sinks::table_name;
+
+/// rows sent by a single insert, once buffered
+const BATCH_BYTES: usize = 1024 * 1024;
+
+/// csv output inserted into a clickhouse table in batches of complete rows,
+/// each batch repeats the header line for the CSVWithNames format
+pub struct ClickHouseInserts<F: FnMut(&[u8]) -> io::Result<()>> {
+    send: F,
+    header: Option<Vec<u8>>,
+    buffer: Vec<u8>,
+    batch_bytes: usize,
+}
+
+impl ClickHouseInserts<Box<dyn FnMut(&[u8]) -> io::Result<()>>> {
+    /// inserts into the table through the http interface at the url, e.g. http://localhost:8123
+    pub fn new(url: &str, table: &str) -> Result<Self, String> {
+        let query = format!("INSERT INTO {} FORMAT CSVWithNames", table_name(table)?);
+        let agent = ureq::Agent::new();
+        let url = url.to_string();
+
+        let send = move |batch: &[u8]| {
+            agent
+                .post(&url)
+                .query("query", &query)
+                .send_bytes(batch)
+                .map(|_| ())
+                .map_err(|err| io::Error::other(format!("clickhouse insert failed: {}", err)))
+        };
+
+        Ok(Self::with_sender(Box::new(send), BATCH_BYTES))
+    }
+}
+
+impl<F: FnMut(&[u8]) -> io::Result<()>> ClickHouseInserts<F> {
+    fn with_sender(send: F, batch_bytes: usize) -> Self {
+        Self {
+            send,
+            header: None,
+            buffer: Vec::with_capacity(batch_bytes),
+            batch_bytes,
+        }
+    }
+
+    /// inserts the complete rows buffered so far, a trailing partial row waits for the next batch
+    fn send_rows(&mut self) -> io::Result<()> {
+        let end = match self.buffer.iter().rposition(|b| *b == b'\n') {
+            Some(pos) => pos + 1,
+            None => return Ok(()),
+        };
+
+        let header = match self.header.as_ref() {
+            Some(header) => header,
+            None => return Ok(()),
+        };
+
+        let mut batch = Vec::with_capacity(header.len() + end);
+        batch.extend_from_slice(header);
+        batch.extend_from_slice(&self.buffer[..end]);
+        (self.send)(&batch)?;
+        self.buffer.drain(..end);
+
+        Ok(())
+    }
+}
+
+impl<F: FnMut(&[u8]) -> io::Result<()>> io::Write for ClickHouseInserts<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+
+        // the first line is the csv header
+        if self.header.is_none() {
+            if let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+                self.header = Some(self.buffer.drain(..=pos).collect());
+            }
+        }
+
+        if self.buffer.len() >= self.batch_bytes {
+            self.send_rows()?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_rows()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn write_batches() {
+        let mut batches = Vec::new();
+        let mut inserts = ClickHouseInserts::with_sender(
+            |batch: &[u8]| {
+                batches.push(String::from_utf8(batch.to_vec()).unwrap());
+                Ok(())
+            },
+            16,
+        );
+
+        inserts.write_all(b"line,status\n2,acc").unwrap();
+        inserts.write_all(b"epted\n3,rejected\n4,").unwrap();
+        inserts.write_all(b"accepted\n").unwrap();
+        inserts.flush().unwrap();
+        // nothing buffered, nothing sent
+        inserts.flush().unwrap();
+        drop(inserts);
+
+        assert_eq!(
+            batches,
+            [
+                "line,status\n2,accepted\n3,rejected\n",
+                "line,status\n4,accepted\n"
+            ]
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::sync::mpsc;
+use std::thread;
+
+use rust_decimal::Decimal;
+use tracing::debug_span;
+
+use crate::cli::log_tx;
+use crate::engine::config::EngineConfig;
+use crate::engine::Engine;
+use crate::models::client::ClientReport;
+use crate::models::tx::{ClientId, TxInput};
+
+/// clients listed in the error of a failed cross-check
+const LISTED_CLIENTS: usize = 10;
+
+/// report columns compared between the two engines
+type Balances = BTreeMap<ClientId, (Decimal, Decimal, Decimal, bool)>;
+
+fn balances(report: impl IntoIterator<Item = ClientReport>) -> Balances {
+    report
+        .into_iter()
+        .map(|row| (row.id, (row.available, row.held, row.total, row.locked)))
+        .collect()
+}
+
+/// second engine of another configuration applied to the same transactions on its own thread,
+/// its report has to match the report of the run
+pub(crate) struct CrossCheck {
+    txs: mpsc::Sender<TxInput>,
+    engine: thread::JoinHandle<Balances>,
+}
+
+impl CrossCheck {
+    pub(crate) fn spawn(config: EngineConfig) -> Self {
+        let (txs, received) = mpsc::channel::<TxInput>();
+        let engine = thread::spawn(move || {
+            // the events of the second engine are told apart by their span
+            let _span = debug_span!("cross_check").entered();
+            let mut engine = Engine::with_config(config);
+            for tx in received {
+                log_tx(&tx, &engine.process_tx(&tx));
+            }
+            balances(engine.report())
+        });
+
+        Self { txs, engine }
+    }
+
+    /// applies the transaction the run applies, a failed engine is reported by `finish`
+    pub(crate) fn send(&self, tx: &TxInput) {
+        let _ = self.txs.send(tx.clone());
+    }
+
+    /// fails with the clients whose balances or lock differ from the report of the run
+    pub(crate) fn finish(
+        self,
+        report: impl IntoIterator<Item = ClientReport>,
+    ) -> Result<(), Box<dyn Error>> {
+        drop(self.txs);
+        let checked = self
+            .engine
+            .join()
+            .map_err(|_| "cross-check engine panicked")?;
+        let report = balances(report);
+
+        let differ: BTreeSet<ClientId> = report
+            .keys()
+            .chain(checked.keys())
+            .filter(|client| report.get(client) != checked.get(client))
+            .copied()
+            .collect();
+        if differ.is_empty() {
+            return Ok(());
+        }
+
+        let mut listed = differ
+            .iter()
+            .take(LISTED_CLIENTS)
+            .map(ClientId::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        if differ.len() > LISTED_CLIENTS {
+            listed.push_str(&format!(" and {} more", differ.len() - LISTED_CLIENTS));
+        }
+        Err(format!(
+            "cross-check failed: the reports differ for clients {}",
+            listed
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::models::tx_type::TxType;
+
+    fn tx(tx_type: TxType, client_id: ClientId, id: u32, amount: Option<Decimal>) -> TxInput {
+        TxInput {
+            tx_type,
+            client_id,
+            id,
+            amount,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        }
+    }
+
+    #[test]
+    fn cross_check() {
+        let txs = [
+            tx(TxType::Deposit, 1, 1, Some(dec!(10))),
+            tx(TxType::Dispute, 1, 1, None),
+            tx(TxType::Resolve, 1, 1, None),
+            tx(TxType::Dispute, 1, 1, None),
+            tx(TxType::Deposit, 2, 2, Some(dec!(5))),
+        ];
+        let run = |config: EngineConfig| {
+            let check = CrossCheck::spawn(config);
+            let mut engine = Engine::new();
+            for tx in &txs {
+                check.send(tx);
+                let _ = engine.process_tx(tx);
+            }
+            check.finish(engine.report())
+        };
+
+        assert!(run(EngineConfig::default()).is_ok());
+        let err = run(EngineConfig {
+            allow_redispute: false,
+            ..Default::default()
+        })
+        .expect_err("a second dispute should only hold funds of the run");
+        assert_eq!(
+            err.to_string(),
+            "cross-check failed: the reports differ for clients 1"
+        );
+    }
+}
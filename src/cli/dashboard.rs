@@ -0,0 +1,252 @@
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::crossterm::{cursor, execute, terminal};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::widgets::{Block, Paragraph, Row, Table};
+use ratatui::{Frame, Terminal};
+use rust_decimal::Decimal;
+
+use crate::engine::Engine;
+use crate::models::tx::ClientId;
+
+/// time between redraws of the dashboard
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// clients listed by the top held funds and recently locked panels
+const LISTED_CLIENTS: usize = 10;
+
+/// live view of the processing, drawn on stderr while the report goes to stdout
+pub(crate) struct Dashboard<B: Backend> {
+    terminal: Terminal<B>,
+    _screen: Option<AlternateScreen>,
+    started: Instant,
+    last_draw: Option<Instant>,
+    rejected: u64,
+    parse_errors: u64,
+    locked: VecDeque<ClientId>,
+}
+
+/// leaves the alternate screen of stderr when dropped
+struct AlternateScreen;
+
+impl Drop for AlternateScreen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), cursor::Show, terminal::LeaveAlternateScreen);
+    }
+}
+
+impl Dashboard<CrosstermBackend<io::Stderr>> {
+    /// draws on the alternate screen of stderr until the dashboard is dropped
+    pub(crate) fn stderr() -> io::Result<Self> {
+        execute!(io::stderr(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        let screen = AlternateScreen;
+
+        let mut dashboard = Self::new(CrosstermBackend::new(io::stderr()))?;
+        dashboard._screen = Some(screen);
+        Ok(dashboard)
+    }
+}
+
+impl<B: Backend> Dashboard<B> {
+    pub(crate) fn new(backend: B) -> io::Result<Self> {
+        Ok(Self {
+            terminal: Terminal::new(backend)?,
+            _screen: None,
+            started: Instant::now(),
+            last_draw: None,
+            rejected: 0,
+            parse_errors: 0,
+            locked: VecDeque::with_capacity(LISTED_CLIENTS + 1),
+        })
+    }
+
+    pub(crate) fn reject(&mut self) {
+        self.rejected += 1;
+    }
+
+    pub(crate) fn parse_error(&mut self) {
+        self.parse_errors += 1;
+    }
+
+    /// records a client locked by a chargeback, the most recent first
+    pub(crate) fn lock(&mut self, client_id: ClientId) {
+        self.locked.retain(|id| *id != client_id);
+        self.locked.push_front(client_id);
+        self.locked.truncate(LISTED_CLIENTS);
+    }
+
+    /// redraws the dashboard when the redraw interval elapsed
+    pub(crate) fn refresh(&mut self, records: u64, engine: &Engine) -> io::Result<()> {
+        if self
+            .last_draw
+            .is_some_and(|last| last.elapsed() < REDRAW_INTERVAL)
+        {
+            return Ok(());
+        }
+
+        self.draw(records, engine)
+    }
+
+    pub(crate) fn draw(&mut self, records: u64, engine: &Engine) -> io::Result<()> {
+        let view = View::new(self, records, engine);
+        self.terminal.draw(|frame| view.render(frame))?;
+        self.last_draw = Some(Instant::now());
+
+        Ok(())
+    }
+}
+
+/// values shown by one frame of the dashboard
+struct View {
+    summary: String,
+    top_held: Vec<(ClientId, Decimal, Decimal)>,
+    locked: Vec<ClientId>,
+}
+
+impl View {
+    fn new<B: Backend>(dashboard: &Dashboard<B>, records: u64, engine: &Engine) -> Self {
+        let elapsed = dashboard.started.elapsed().as_secs_f64();
+        let rate = match elapsed > 0.0 {
+            true => records as f64 / elapsed,
+            false => 0.0,
+        };
+
+        let mut clients = 0;
+        let mut top_held = Vec::new();
+        for report in engine.report() {
+            clients += 1;
+            if !report.held.is_zero() {
+                top_held.push((report.id, report.held, report.total));
+            }
+        }
+        top_held.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        top_held.truncate(LISTED_CLIENTS);
+
+        Self {
+            summary: format!(
+                "records {} | {:.0} records/s | rejected {} | parse errors {} | clients {}",
+                records, rate, dashboard.rejected, dashboard.parse_errors, clients
+            ),
+            top_held,
+            locked: dashboard.locked.iter().copied().collect(),
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [summary, body] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(frame.area());
+        let [held, locked] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(body);
+
+        frame.render_widget(
+            Paragraph::new(self.summary.as_str()).block(Block::bordered().title("ttx-eng")),
+            summary,
+        );
+
+        let rows = self.top_held.iter().map(|(id, held, total)| {
+            Row::new([id.to_string(), held.to_string(), total.to_string()])
+        });
+        frame.render_widget(
+            Table::new(
+                rows,
+                [
+                    Constraint::Length(8),
+                    Constraint::Fill(1),
+                    Constraint::Fill(1),
+                ],
+            )
+            .header(Row::new(["client", "held", "total"]))
+            .block(Block::bordered().title("top held funds")),
+            held,
+        );
+
+        let rows = self.locked.iter().map(|id| Row::new([id.to_string()]));
+        frame.render_widget(
+            Table::new(rows, [Constraint::Fill(1)])
+                .header(Row::new(["client"]))
+                .block(Block::bordered().title("recently locked")),
+            locked,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::backend::TestBackend;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::models::tx::TxInput;
+    use crate::models::tx_type::TxType;
+
+    fn tx(tx_type: TxType, client_id: ClientId, id: u32, amount: Option<Decimal>) -> TxInput {
+        TxInput {
+            tx_type,
+            client_id,
+            id,
+            amount,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        }
+    }
+
+    #[test]
+    fn draw() {
+        let mut engine = Engine::new();
+        for tx in [
+            tx(TxType::Deposit, 1, 1, Some(dec!(5))),
+            tx(TxType::Deposit, 2, 2, Some(dec!(7))),
+            tx(TxType::Dispute, 2, 2, None),
+            tx(TxType::Deposit, 3, 3, Some(dec!(1))),
+            tx(TxType::Dispute, 3, 3, None),
+            tx(TxType::Chargeback, 3, 3, None),
+        ] {
+            engine.process_tx(&tx).expect("failed to process tx");
+        }
+
+        let mut dashboard = Dashboard::new(TestBackend::new(100, 12)).unwrap();
+        dashboard.reject();
+        dashboard.parse_error();
+        dashboard.lock(3);
+        dashboard.draw(8, &engine).unwrap();
+
+        // words of each screen line, without the borders
+        let buffer = dashboard.terminal.backend().buffer();
+        let lines: Vec<String> = buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|row| {
+                let line: String = row.iter().map(|cell| cell.symbol()).collect();
+                line.split(|c: char| c.is_whitespace() || "│─┌┐└┘".contains(c))
+                    .filter(|word| !word.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        assert!(lines[1].starts_with("records 8 |"));
+        assert!(lines[1].ends_with("| rejected 1 | parse errors 1 | clients 3"));
+        assert_eq!(lines[4], "client held total client");
+        // client 1 holds no funds, client 3 was locked
+        assert_eq!(lines[5], "2 7 7 3");
+        assert_eq!(lines[6], "");
+    }
+
+    #[test]
+    fn lock_most_recent_first() {
+        let mut dashboard = Dashboard::new(TestBackend::new(10, 10)).unwrap();
+        for id in 0..LISTED_CLIENTS as ClientId + 2 {
+            dashboard.lock(id);
+        }
+        dashboard.lock(5);
+
+        assert_eq!(dashboard.locked.len(), LISTED_CLIENTS);
+        assert_eq!(dashboard.locked.front(), Some(&5));
+        assert_eq!(dashboard.locked.back(), Some(&2));
+    }
+}
@@ -0,0 +1,88 @@
+use std::error::Error;
+use std::io;
+use std::path::PathBuf;
+
+use crypto_box::aead::OsRng;
+use crypto_box::{PublicKey, KEY_SIZE};
+
+/// seals report files to the x25519 public key of their reader, the file is a libsodium sealed box
+/// (`crypto_box_seal`) of its whole contents, opened with the matching secret key
+#[derive(Clone)]
+pub struct Encryption {
+    recipient: PublicKey,
+}
+
+impl Encryption {
+    /// recipient key as 64 hex characters
+    pub fn from_hex(key: &str) -> Result<Self, Box<dyn Error>> {
+        let mut bytes = [0; KEY_SIZE];
+        hex::decode_to_slice(key.trim(), &mut bytes)
+            .map_err(|err| format!("invalid recipient key: {}", err))?;
+
+        Ok(Self {
+            recipient: PublicKey::from(bytes),
+        })
+    }
+
+    /// writes the sealed box of everything written by `write`, the contents are held in memory until sealed
+    pub(crate) fn write<W: io::Write>(
+        &self,
+        mut output: W,
+        write: impl FnOnce(&mut dyn io::Write) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut contents = Vec::new();
+        write(&mut contents)?;
+
+        let sealed = self
+            .recipient
+            .seal(&mut OsRng, &contents)
+            .map_err(|_| "failed to encrypt the report")?;
+        output.write_all(&sealed)?;
+
+        Ok(output.flush()?)
+    }
+
+    /// path of a sealed file
+    pub(crate) fn path(path: PathBuf) -> PathBuf {
+        let mut path = path.into_os_string();
+        path.push(".sealed");
+        path.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto_box::SecretKey;
+
+    use super::*;
+
+    #[test]
+    fn seal_and_open() {
+        let secret = SecretKey::generate(&mut OsRng);
+        let encryption = Encryption::from_hex(&hex::encode(secret.public_key().as_bytes()))
+            .expect("invalid key");
+
+        let mut sealed = Vec::new();
+        encryption
+            .write(&mut sealed, |output| {
+                Ok(output.write_all(b"client,total\n1,5\n")?)
+            })
+            .expect("failed to encrypt");
+
+        assert_ne!(sealed, b"client,total\n1,5\n");
+        assert_eq!(
+            secret.unseal(&sealed).expect("failed to decrypt"),
+            b"client,total\n1,5\n"
+        );
+    }
+
+    #[test]
+    fn invalid_key() {
+        assert!(Encryption::from_hex("abcd").is_err());
+        assert!(Encryption::from_hex(&"zz".repeat(KEY_SIZE)).is_err());
+        assert_eq!(
+            Encryption::path(PathBuf::from("report-1.csv")),
+            PathBuf::from("report-1.csv.sealed")
+        );
+    }
+}
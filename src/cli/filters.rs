@@ -0,0 +1,78 @@
+use std::collections::{HashMap, HashSet};
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::models::tx::ClientId;
+use crate::models::tx_type::TxType;
+
+/// filter chain applied to the input before the engine, dropped transactions get no per record result
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputFilter {
+    /// only the transactions of these clients are processed, e.g. the clients of a tenant
+    pub clients: Option<HashSet<ClientId>>,
+
+    /// clients whose transactions are dropped
+    pub blocked_clients: HashSet<ClientId>,
+
+    /// amounts over it are lowered to it
+    pub clamp_amount: Option<Decimal>,
+
+    /// partner names of the csv type column, e.g. `payment` for deposits
+    pub type_aliases: HashMap<String, TxType>,
+}
+
+impl InputFilter {
+    /// whether the transactions of the client reach the engine
+    pub(crate) fn keeps(&self, client_id: ClientId) -> bool {
+        !self.blocked_clients.contains(&client_id)
+            && self
+                .clients
+                .as_ref()
+                .is_none_or(|clients| clients.contains(&client_id))
+    }
+
+    /// the clamped amount, None when the amount is within the limit
+    pub(crate) fn clamp(&self, amount: Option<Decimal>) -> Option<Decimal> {
+        match (amount, self.clamp_amount) {
+            (Some(amount), Some(max)) if amount > max => Some(max),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn keeps_clients() {
+        let filter = InputFilter {
+            clients: Some(HashSet::from([1, 2])),
+            blocked_clients: HashSet::from([2, 3]),
+            ..Default::default()
+        };
+
+        assert!(filter.keeps(1));
+        assert!(!filter.keeps(2));
+        assert!(!filter.keeps(3));
+        assert!(!filter.keeps(4));
+        assert!(InputFilter::default().keeps(4));
+    }
+
+    #[test]
+    fn clamp_amount() {
+        let filter = InputFilter {
+            clamp_amount: Some(dec!(100)),
+            ..Default::default()
+        };
+
+        assert_eq!(filter.clamp(Some(dec!(100.01))), Some(dec!(100)));
+        assert_eq!(filter.clamp(Some(dec!(100))), None);
+        assert_eq!(filter.clamp(None), None);
+        assert_eq!(InputFilter::default().clamp(Some(dec!(1e9))), None);
+    }
+}
@@ -0,0 +1,320 @@
+use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signer, SigningKey, SECRET_KEY_LENGTH};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// manifest written once a run completes, with the checksums of its inputs and outputs
+pub struct ManifestOptions {
+    pub path: PathBuf,
+
+    /// signs the manifest into a `.sig` file next to it
+    pub signing_key: Option<SigningKey>,
+
+    /// input files with the checksums they were verified against, stdin is not checksummed
+    pub inputs: Vec<(PathBuf, Option<InputChecksum>)>,
+
+    /// per record output files, the report files are known from the sinks
+    pub outputs: Vec<(OutputKind, PathBuf)>,
+}
+
+impl ManifestOptions {
+    /// signing key file holding the 32 byte secret key in hex
+    pub fn read_signing_key(path: &Path) -> Result<SigningKey, Box<dyn Error>> {
+        let mut key = [0; SECRET_KEY_LENGTH];
+        hex::decode_to_slice(fs::read_to_string(path)?.trim(), &mut key)
+            .map_err(|err| format!("invalid signing key: {}", err))?;
+
+        Ok(SigningKey::from_bytes(&key))
+    }
+}
+
+/// expected sha-256 of an input file
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputChecksum {
+    sha256: String,
+}
+
+impl InputChecksum {
+    /// `sha256:<hex>`, or the path of a checksum file in the `sha256sum` format
+    pub fn parse(checksum: &str) -> Result<Self, Box<dyn Error>> {
+        match checksum.strip_prefix("sha256:") {
+            Some(sha256) => Self::from_hex(sha256),
+            None => Self::from_hex(
+                fs::read_to_string(checksum)?
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+
+    fn from_hex(sha256: &str) -> Result<Self, Box<dyn Error>> {
+        let mut bytes = [0; 32];
+        hex::decode_to_slice(sha256, &mut bytes)
+            .map_err(|err| format!("invalid sha256 checksum: {}", err))?;
+
+        Ok(Self {
+            sha256: hex::encode(bytes),
+        })
+    }
+
+    /// fails unless the file has the checksum
+    pub fn verify(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.check(&FileDigest::of_file(path)?)
+    }
+
+    fn check(&self, digest: &FileDigest) -> Result<(), Box<dyn Error>> {
+        match digest.sha256 == self.sha256 {
+            true => Ok(()),
+            false => Err(format!(
+                "checksum mismatch of {}: expected sha256:{}, found sha256:{}",
+                digest.path, self.sha256, digest.sha256
+            )
+            .into()),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    Report,
+    Results,
+    Aml,
+    Anomalies,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct Manifest {
+    /// name and version of the engine that produced the outputs
+    engine: String,
+    created_at: DateTime<Utc>,
+    inputs: Vec<InputDigest>,
+    outputs: Vec<OutputDigest>,
+    /// public key of the `.sig` signature, in hex
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signer: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct FileDigest {
+    path: String,
+    bytes: u64,
+    sha256: String,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct InputDigest {
+    #[serde(flatten)]
+    file: FileDigest,
+    /// the checksum was verified before processing
+    verified: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct OutputDigest {
+    kind: OutputKind,
+    rows: u64,
+    #[serde(flatten)]
+    file: FileDigest,
+}
+
+impl FileDigest {
+    pub(crate) fn of_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut writer = DigestWriter::new(io::sink());
+        io::copy(&mut File::open(path)?, &mut writer)?;
+
+        Ok(writer.digest(path.to_string_lossy().into_owned()))
+    }
+}
+
+impl Manifest {
+    pub(crate) fn new(options: &ManifestOptions) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            engine: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            created_at: SystemTime::now().into(),
+            inputs: options
+                .inputs
+                .iter()
+                .map(|(path, checksum)| Manifest::input(path, checksum.as_ref()))
+                .collect::<Result<_, _>>()?,
+            outputs: Vec::new(),
+            signer: options
+                .signing_key
+                .as_ref()
+                .map(|key| hex::encode(key.verifying_key().as_bytes())),
+        })
+    }
+
+    /// digest of an input, a verified input must not have changed while it was processed
+    fn input(path: &Path, checksum: Option<&InputChecksum>) -> Result<InputDigest, Box<dyn Error>> {
+        let file = FileDigest::of_file(path)?;
+        if let Some(checksum) = checksum {
+            checksum.check(&file)?;
+        }
+
+        Ok(InputDigest {
+            file,
+            verified: checksum.is_some(),
+        })
+    }
+
+    pub(crate) fn output(&mut self, kind: OutputKind, rows: u64, file: FileDigest) {
+        self.outputs.push(OutputDigest { kind, rows, file });
+    }
+
+    /// writes the manifest and, with a signing key, the hex signature of its exact bytes
+    pub(crate) fn write(&self, options: &ManifestOptions) -> Result<(), Box<dyn Error>> {
+        let manifest = serde_json::to_vec_pretty(self)?;
+        fs::write(&options.path, &manifest)?;
+
+        if let Some(key) = options.signing_key.as_ref() {
+            let mut path = options.path.clone().into_os_string();
+            path.push(".sig");
+            fs::write(path, hex::encode(key.sign(&manifest).to_bytes()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// passes writes through while hashing them
+pub(crate) struct DigestWriter<W> {
+    inner: W,
+    hasher: Sha256,
+    bytes: u64,
+}
+
+impl<W: io::Write> DigestWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            bytes: 0,
+        }
+    }
+
+    pub(crate) fn digest(self, path: String) -> FileDigest {
+        FileDigest {
+            path,
+            bytes: self.bytes,
+            sha256: hex::encode(self.hasher.finalize()),
+        }
+    }
+}
+
+impl<W: io::Write> io::Write for DigestWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use ed25519_dalek::{Signature, Verifier};
+
+    use super::*;
+
+    #[test]
+    fn digest_writer() {
+        let mut writer = DigestWriter::new(Vec::new());
+        writer.write_all(b"abc").expect("failed to write");
+
+        assert_eq!(
+            writer.digest("-".to_string()),
+            FileDigest {
+                path: "-".to_string(),
+                bytes: 3,
+                sha256: "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+                    .to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn input_checksum() {
+        let path = std::env::temp_dir().join(format!("ttx-checksum-{}.csv", std::process::id()));
+        fs::write(&path, "abc").expect("failed to write input");
+
+        let sha256 = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert!(
+            InputChecksum::parse(&format!("sha256:{}", sha256.to_uppercase()))
+                .expect("invalid checksum")
+                .verify(&path)
+                .is_ok()
+        );
+        assert!(InputChecksum::parse(&format!("sha256:{}", "0".repeat(64)))
+            .expect("invalid checksum")
+            .verify(&path)
+            .is_err());
+        assert!(InputChecksum::parse("sha256:abc").is_err());
+
+        let sidecar = path.with_extension("csv.sha256");
+        fs::write(&sidecar, format!("{}  input.csv\n", sha256)).expect("failed to write sidecar");
+        assert!(InputChecksum::parse(&sidecar.to_string_lossy())
+            .expect("invalid checksum")
+            .verify(&path)
+            .is_ok());
+
+        fs::remove_file(path).expect("failed to remove input");
+        fs::remove_file(sidecar).expect("failed to remove sidecar");
+    }
+
+    #[test]
+    fn signed_manifest() {
+        let dir = std::env::temp_dir().join(format!("ttx-manifest-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("failed to create dir");
+        let input = dir.join("input.csv");
+        fs::write(&input, "type,client,tx,amount\n").expect("failed to write input");
+        let key_path = dir.join("key");
+        fs::write(&key_path, format!("{}\n", "01".repeat(SECRET_KEY_LENGTH)))
+            .expect("failed to write key");
+
+        let options = ManifestOptions {
+            path: dir.join("manifest.json"),
+            signing_key: Some(ManifestOptions::read_signing_key(&key_path).expect("invalid key")),
+            inputs: vec![(input, None)],
+            outputs: vec![],
+        };
+        let mut manifest = Manifest::new(&options).expect("failed to hash inputs");
+        manifest.output(
+            OutputKind::Report,
+            0,
+            DigestWriter::new(io::sink()).digest("-".into()),
+        );
+        manifest.write(&options).expect("failed to write manifest");
+
+        let written = fs::read(&options.path).expect("missing manifest");
+        let json: serde_json::Value = serde_json::from_slice(&written).expect("invalid manifest");
+        assert_eq!(json["inputs"][0]["bytes"], 22);
+        assert_eq!(json["inputs"][0]["verified"], false);
+        assert_eq!(json["outputs"][0]["kind"], "report");
+        assert_eq!(json["outputs"][0]["rows"], 0);
+
+        let signature =
+            fs::read_to_string(dir.join("manifest.json.sig")).expect("missing signature");
+        let mut bytes = [0; 64];
+        hex::decode_to_slice(signature, &mut bytes).expect("invalid signature");
+        let key = options.signing_key.as_ref().unwrap().verifying_key();
+        assert!(key.verify(&written, &Signature::from_bytes(&bytes)).is_ok());
+        assert_eq!(json["signer"], hex::encode(key.as_bytes()));
+
+        fs::remove_dir_all(dir).expect("failed to remove dir");
+    }
+}
@@ -0,0 +1,61 @@
+use std::io;
+use std::str::FromStr;
+
+/// size of the report write buffer
+pub(crate) const REPORT_BUFFER: usize = 64 * 1024;
+
+/// interval between flushes of the report output, `N` rows or `Nb` bytes
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum FlushInterval {
+    Rows(u64),
+    Bytes(u64),
+}
+
+impl FromStr for FlushInterval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, interval): (_, fn(u64) -> Self) = match s.strip_suffix('b') {
+            Some(value) => (value, FlushInterval::Bytes),
+            None => (s, FlushInterval::Rows),
+        };
+
+        match value.parse::<u64>() {
+            Ok(0) | Err(_) => Err(format!(
+                "invalid flush interval '{}', expected N rows or Nb bytes",
+                s
+            )),
+            Ok(value) => Ok(interval(value)),
+        }
+    }
+}
+
+/// flushes the inner writer after every write, the csv writer only writes
+/// once its buffer (sized to the byte interval) is full
+pub(crate) struct FlushOnWrite<W>(pub(crate) W);
+
+impl<W: io::Write> io::Write for FlushOnWrite<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.0.write(buf)?;
+        self.0.flush()?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flush_interval_from_str() {
+        assert_eq!("100".parse(), Ok(FlushInterval::Rows(100)));
+        assert_eq!("4096b".parse(), Ok(FlushInterval::Bytes(4096)));
+        assert!("0".parse::<FlushInterval>().is_err());
+        assert!("b".parse::<FlushInterval>().is_err());
+        assert!("10kb".parse::<FlushInterval>().is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use std::io;
+use std::sync::mpsc::SyncSender;
+
+use csv::ByteRecord;
+
+use crate::import::records::RecordParser;
+use crate::models::tx::TxInput;
+
+/// input line and the parsed transaction or the reason it couldn't be parsed
+pub(crate) type Row = (Option<u64>, Result<TxInput, String>);
+
+/// rows sent to the engine at once
+const BATCH_ROWS: usize = 1024;
+
+/// batches buffered between the reader and the engine, the reader blocks once they're full
+pub(crate) const CHANNEL_BATCHES: usize = 16;
+
+/// parsed rows of a csv input, reusing a single record buffer, ends after an io error
+pub(crate) struct Rows<R> {
+    rdr: csv::Reader<R>,
+    parser: RecordParser,
+    record: ByteRecord,
+    done: bool,
+}
+
+impl<R: io::Read> Rows<R> {
+    pub(crate) fn new(rdr: csv::Reader<R>, parser: RecordParser) -> Self {
+        Self {
+            rdr,
+            parser,
+            record: ByteRecord::new(),
+            done: false,
+        }
+    }
+
+    /// skips records processed by an interrupted run without parsing them and returns the
+    /// number skipped, a record the reader fails on counts as one like it does for the processor
+    pub(crate) fn skip_records(&mut self, records: u64) -> Result<u64, csv::Error> {
+        for skipped in 0..records {
+            match self.rdr.read_byte_record(&mut self.record) {
+                Ok(true) => {}
+                Ok(false) => return Ok(skipped),
+                Err(err) if err.is_io_error() => return Err(err),
+                Err(_) => {}
+            }
+        }
+
+        Ok(records)
+    }
+}
+
+impl<R: io::Read> Iterator for Rows<R> {
+    type Item = Result<Row, csv::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.rdr.read_byte_record(&mut self.record) {
+            Ok(true) => Some(Ok((
+                self.record.position().map(|pos| pos.line()),
+                self.parser.parse(&self.record),
+            ))),
+            Ok(false) => {
+                self.done = true;
+                None
+            }
+            Err(err) if err.is_io_error() => {
+                self.done = true;
+                Some(Err(err))
+            }
+            Err(err) => Some(Ok((
+                err.position().map(|pos| pos.line()),
+                Err(err.to_string()),
+            ))),
+        }
+    }
+}
+
+/// sends the rows in batches until the end of the input, an io error,
+/// or the engine side hanging up
+pub(crate) fn send_batches(
+    rows: impl Iterator<Item = Result<Row, csv::Error>>,
+    sender: SyncSender<Result<Vec<Row>, csv::Error>>,
+) {
+    let mut batch = Vec::with_capacity(BATCH_ROWS);
+
+    for row in rows {
+        let row = match row {
+            Ok(row) => row,
+            Err(err) => {
+                if sender.send(Ok(batch)).is_ok() {
+                    let _ = sender.send(Err(err));
+                }
+                return;
+            }
+        };
+
+        batch.push(row);
+        if batch.len() == BATCH_ROWS {
+            let full = std::mem::replace(&mut batch, Vec::with_capacity(BATCH_ROWS));
+            if sender.send(Ok(full)).is_err() {
+                return;
+            }
+        }
+    }
+
+    let _ = sender.send(Ok(batch));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::import::records::DecimalSeparator;
+
+    fn rows(input: &str) -> Rows<&[u8]> {
+        let mut rdr = csv::Reader::from_reader(input.as_bytes());
+        let parser = RecordParser::new(rdr.byte_headers().unwrap(), DecimalSeparator::Dot)
+            .expect("invalid headers");
+        Rows::new(rdr, parser)
+    }
+
+    #[test]
+    fn send_batches_in_order() {
+        let mut input = String::from("type,client,tx,amount\n");
+        for id in 1..=BATCH_ROWS + 2 {
+            input.push_str(&format!("deposit,1,{},1.0\n", id));
+        }
+        input.push_str("deposit,x,1,1.0\n");
+
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_BATCHES);
+        send_batches(rows(&input), sender);
+
+        let batches: Vec<_> = receiver
+            .into_iter()
+            .map(|batch| batch.expect("failed to read batch"))
+            .collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].len(), BATCH_ROWS);
+        assert_eq!(batches[1].len(), 3);
+
+        let rows: Vec<_> = batches.into_iter().flatten().collect();
+        assert!(rows
+            .iter()
+            .take(BATCH_ROWS + 2)
+            .enumerate()
+            .all(|(i, (line, tx))| *line == Some(i as u64 + 2)
+                && tx.as_ref().is_ok_and(|tx| tx.id == i as u32 + 1)));
+        assert!(rows[BATCH_ROWS + 2].1.is_err());
+    }
+
+    #[test]
+    fn send_batches_receiver_gone() {
+        let input = "type,client,tx,amount\ndeposit,1,1,1.0\n".repeat(BATCH_ROWS * 4);
+        let (sender, receiver) = mpsc::sync_channel(0);
+        drop(receiver);
+
+        // returns instead of blocking on the full channel
+        send_batches(rows(&input), sender);
+    }
+}
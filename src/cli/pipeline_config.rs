@@ -0,0 +1,319 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::cli::filters::InputFilter;
+use crate::cli::{
+    CsvOptions, DecimalSeparator, FixedWidthLayout, InputEncoding, InputFormat, ProcessOptions,
+    ReportSink, Source, XmlMapping,
+};
+use crate::engine::config::{DuplicatePolicy, EngineConfig, WithdrawalPolicy};
+use crate::models::report_transform::ReportTransform;
+use crate::report::OutputFormat;
+
+/// job of the `run-pipeline` command, read from a toml file:
+/// sources are processed in order into a single engine, filtered before the engine,
+/// and the final report is written to every sink
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineConfig {
+    #[serde(rename = "source")]
+    pub sources: Vec<SourceConfig>,
+
+    #[serde(default)]
+    pub filter: InputFilter,
+
+    #[serde(default)]
+    pub engine: EngineSection,
+
+    /// the report is written to the output when there are no sinks
+    #[serde(rename = "sink", default)]
+    pub sinks: Vec<SinkConfig>,
+}
+
+/// input file of a pipeline, with the reading options of the command line
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SourceConfig {
+    pub path: PathBuf,
+
+    /// inferred from the file extension when omitted
+    #[serde(default)]
+    pub format: Option<InputFormat>,
+
+    /// client id of statement formats
+    #[serde(default)]
+    pub client: Option<u16>,
+
+    /// json file of the fixed width layout or xml element mapping
+    #[serde(default)]
+    pub layout: Option<PathBuf>,
+
+    #[serde(default)]
+    pub encoding: InputEncoding,
+
+    #[serde(default)]
+    pub no_header: bool,
+
+    #[serde(default)]
+    pub decimal_separator: DecimalSeparator,
+}
+
+/// engine options of a pipeline, named after their command line flags
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineSection {
+    pub duplicate_policy: DuplicatePolicy,
+    pub dedup_window: Option<usize>,
+    pub max_disputes: Option<u32>,
+    pub no_redispute: bool,
+    pub withdrawal_policy: WithdrawalPolicy,
+    pub no_withdrawal_on_dispute: bool,
+}
+
+/// a report file or, with `url`, one of the `--output` sinks
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SinkConfig {
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+
+    /// format of the report file
+    #[serde(default)]
+    pub format: OutputFormat,
+
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// table of the postgres sink
+    #[serde(default = "default_table")]
+    pub table: String,
+
+    /// conversion, rounding and columns of the rows of a report file
+    #[serde(default)]
+    pub transform: ReportTransform,
+}
+
+fn default_table() -> String {
+    "clients".to_string()
+}
+
+impl PipelineConfig {
+    pub fn from_toml(config: &str) -> Result<Self, Box<dyn Error>> {
+        let config: Self = toml::from_str(config)?;
+        if config.sources.is_empty() {
+            return Err("pipeline has no sources".into());
+        }
+
+        Ok(config)
+    }
+
+    /// paths of the sources with their layouts loaded
+    pub(crate) fn sources(&self) -> Result<Vec<(PathBuf, Source)>, Box<dyn Error>> {
+        self.sources.iter().map(SourceConfig::source).collect()
+    }
+
+    /// processing options of the pipeline
+    pub(crate) fn into_options(self) -> Result<ProcessOptions, Box<dyn Error>> {
+        let mut options = ProcessOptions {
+            input_filter: self.filter,
+            sinks: self
+                .sinks
+                .into_iter()
+                .map(SinkConfig::into_sink)
+                .collect::<Result<_, _>>()?,
+            ..Default::default()
+        };
+        self.engine.apply(&mut options.engine);
+
+        Ok(options)
+    }
+}
+
+impl EngineSection {
+    /// options of the `--cross-check` engine, read from a toml file of the section keys
+    pub fn from_toml(config: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(toml::from_str(config)?)
+    }
+
+    /// replaces the options of the section in the engine configuration
+    pub fn apply(&self, config: &mut EngineConfig) {
+        config.duplicate_policy = self.duplicate_policy;
+        config.dedup_window = self.dedup_window;
+        config.max_disputes = self.max_disputes;
+        config.allow_redispute = !self.no_redispute;
+        config.withdrawal_policy = self.withdrawal_policy;
+        config.block_withdrawals_on_dispute = self.no_withdrawal_on_dispute;
+    }
+}
+
+impl SourceConfig {
+    fn source(&self) -> Result<(PathBuf, Source), Box<dyn Error>> {
+        let format = match self.format {
+            Some(format) => format,
+            None => InputFormat::from_path(&self.path.to_string_lossy()),
+        };
+
+        let mut source = Source {
+            format,
+            client_id: self.client,
+            encoding: self.encoding,
+            csv: CsvOptions {
+                no_header: self.no_header,
+                decimal_separator: self.decimal_separator,
+            },
+            fixed_width: None,
+            xml: None,
+        };
+
+        if let Some(layout) = self.layout.as_ref() {
+            let layout = BufReader::new(File::open(layout)?);
+            match format {
+                InputFormat::Xml => source.xml = Some(XmlMapping::from_reader(layout)?),
+                _ => source.fixed_width = Some(FixedWidthLayout::from_reader(layout)?),
+            }
+        }
+
+        Ok((self.path.clone(), source))
+    }
+}
+
+impl SinkConfig {
+    fn into_sink(self) -> Result<ReportSink, Box<dyn Error>> {
+        match (self.path, self.url) {
+            // arrow columns are typed by the report options
+            #[cfg(feature = "arrow")]
+            (Some(_), None)
+                if self.format == OutputFormat::Arrow && self.transform.changes_columns() =>
+            {
+                Err("the columns of an arrow sink cannot be transformed".into())
+            }
+            (Some(path), None) => Ok(ReportSink::File {
+                path,
+                format: self.format,
+                transform: self.transform,
+            }),
+            (None, Some(_)) if !self.transform.is_identity() => {
+                Err("only report files can be transformed".into())
+            }
+            (None, Some(url)) => ReportSink::from_url(&url, &self.table),
+            _ => Err("a sink needs either a path or a url".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::models::report_transform::{ReportColumn, Rounding};
+
+    #[test]
+    fn from_toml() {
+        let config = PipelineConfig::from_toml(
+            r#"
+[[source]]
+path = "feed-a.csv"
+
+[[source]]
+path = "feed-b.txt"
+format = "csv"
+no_header = true
+decimal_separator = "comma"
+
+[filter]
+clients = [1, 2]
+
+[engine]
+duplicate_policy = "ignore-if-identical"
+max_disputes = 1
+
+[[sink]]
+path = "report.json"
+format = "json"
+"#,
+        )
+        .expect("invalid pipeline");
+
+        let sources = config.sources().expect("invalid pipeline");
+        let options = config.into_options().expect("invalid pipeline");
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].1.format, InputFormat::Csv);
+        assert!(sources[1].1.csv.no_header);
+        assert_eq!(sources[1].1.csv.decimal_separator, DecimalSeparator::Comma);
+        assert!(options.input_filter.clients.is_some_and(|c| c.len() == 2));
+        assert_eq!(
+            options.engine.duplicate_policy,
+            DuplicatePolicy::IgnoreIfIdentical
+        );
+        assert_eq!(options.engine.max_disputes, Some(1));
+        assert_eq!(
+            options.sinks,
+            vec![ReportSink::File {
+                path: PathBuf::from("report.json"),
+                format: OutputFormat::Json,
+                transform: ReportTransform::default(),
+            }]
+        );
+    }
+
+    #[test]
+    fn sink_transform() {
+        let config = PipelineConfig::from_toml(
+            r#"
+[[source]]
+path = "feed.csv"
+
+[[sink]]
+path = "report.csv"
+
+[sink.transform]
+rate = "0.92"
+scale = 2
+rounding = "half-up"
+columns = ["client", "total"]
+rename = { total = "total_eur" }
+"#,
+        )
+        .expect("invalid pipeline");
+
+        let options = config.into_options().expect("invalid pipeline");
+        assert_eq!(
+            options.sinks,
+            vec![ReportSink::File {
+                path: PathBuf::from("report.csv"),
+                format: OutputFormat::Csv,
+                transform: ReportTransform {
+                    rate: Some(rust_decimal_macros::dec!(0.92)),
+                    scale: Some(2),
+                    rounding: Rounding::HalfUp,
+                    columns: vec![ReportColumn::Client, ReportColumn::Total],
+                    rename: HashMap::from([(ReportColumn::Total, "total_eur")]),
+                },
+            }]
+        );
+
+        let config = PipelineConfig::from_toml(
+            "[[source]]\npath = \"a.csv\"\n[[sink]]\nurl = \"duckdb:a.db\"\n[sink.transform]\nscale = 2",
+        )
+        .expect("invalid pipeline");
+        assert!(config.into_options().is_err());
+    }
+
+    #[test]
+    fn from_toml_invalid() {
+        assert!(PipelineConfig::from_toml("").is_err());
+        assert!(
+            PipelineConfig::from_toml("[[source]]\npath = \"a.csv\"\nformat = \"pdf\"").is_err()
+        );
+
+        let config =
+            PipelineConfig::from_toml("[[source]]\npath = \"a.csv\"\n[[sink]]\nformat = \"json\"")
+                .expect("invalid pipeline");
+        assert!(config.into_options().is_err());
+    }
+}
@@ -0,0 +1,209 @@
+#[cfg(not(feature = "profiling"))]
+use std::io;
+
+#[cfg(not(feature = "profiling"))]
+use crate::models::tx_type::TxType;
+
+#[cfg(feature = "profiling")]
+pub(crate) use timed::Profile;
+
+/// without the profiling feature nothing is timed and nothing is printed
+#[cfg(not(feature = "profiling"))]
+#[derive(Debug)]
+pub(crate) struct Profile;
+
+#[cfg(not(feature = "profiling"))]
+impl Profile {
+    pub(crate) fn new() -> Self {
+        Self
+    }
+
+    pub(crate) fn timed<I: Iterator>(&self, rows: I) -> impl Iterator<Item = I::Item> {
+        rows
+    }
+
+    pub(crate) fn parse<T>(&self, parse: impl FnOnce() -> T) -> T {
+        parse()
+    }
+
+    pub(crate) fn apply<T>(&mut self, _: TxType, apply: impl FnOnce() -> T) -> T {
+        apply()
+    }
+
+    pub(crate) fn report<T>(&mut self, write: impl FnOnce() -> T) -> T {
+        write()
+    }
+
+    pub(crate) fn write<W: io::Write>(&self, _: W, _: u64) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "profiling")]
+mod timed {
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use crate::models::tx_type::TxType;
+
+    /// time spent parsing the records, applying each transaction type and writing the final
+    /// report, printed once the run ends; records are parsed on the reader thread while the
+    /// engine applies the previous ones, so the stages can add up to more than the run
+    #[derive(Debug)]
+    pub(crate) struct Profile {
+        started: Instant,
+        /// nanoseconds, added by the reader thread
+        parse: Arc<AtomicU64>,
+        apply: BTreeMap<TxType, (u64, Duration)>,
+        report: Duration,
+    }
+
+    /// rows whose parsing is timed, on whichever thread reads them
+    struct Timed<I> {
+        rows: I,
+        parse: Arc<AtomicU64>,
+    }
+
+    impl<I: Iterator> Iterator for Timed<I> {
+        type Item = I::Item;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let started = Instant::now();
+            let row = self.rows.next();
+            add(&self.parse, started.elapsed());
+            row
+        }
+    }
+
+    fn add(total: &AtomicU64, elapsed: Duration) {
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        total.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    impl Profile {
+        /// starts timing the run
+        pub(crate) fn new() -> Self {
+            Self {
+                started: Instant::now(),
+                parse: Arc::default(),
+                apply: BTreeMap::new(),
+                report: Duration::ZERO,
+            }
+        }
+
+        pub(crate) fn timed<I: Iterator>(&self, rows: I) -> impl Iterator<Item = I::Item> {
+            Timed {
+                rows,
+                parse: Arc::clone(&self.parse),
+            }
+        }
+
+        pub(crate) fn parse<T>(&self, parse: impl FnOnce() -> T) -> T {
+            let started = Instant::now();
+            let parsed = parse();
+            add(&self.parse, started.elapsed());
+            parsed
+        }
+
+        pub(crate) fn apply<T>(&mut self, tx_type: TxType, apply: impl FnOnce() -> T) -> T {
+            let started = Instant::now();
+            let applied = apply();
+            self.record_apply(tx_type, started.elapsed());
+            applied
+        }
+
+        fn record_apply(&mut self, tx_type: TxType, elapsed: Duration) {
+            let (count, total) = self.apply.entry(tx_type).or_default();
+            *count += 1;
+            *total += elapsed;
+        }
+
+        pub(crate) fn report<T>(&mut self, write: impl FnOnce() -> T) -> T {
+            let started = Instant::now();
+            let written = write();
+            self.report += started.elapsed();
+            written
+        }
+
+        /// breakdown of the run of the records, with the share of the run time of each stage
+        pub(crate) fn write<W: io::Write>(&self, output: W, records: u64) -> io::Result<()> {
+            self.write_run(output, records, self.started.elapsed())
+        }
+
+        fn write_run<W: io::Write>(
+            &self,
+            mut output: W,
+            records: u64,
+            run: Duration,
+        ) -> io::Result<()> {
+            let share = |elapsed: Duration| match run.is_zero() {
+                true => 0.0,
+                false => 100.0 * elapsed.as_secs_f64() / run.as_secs_f64(),
+            };
+            let line = |output: &mut W, stage: &str, elapsed: Duration| {
+                write!(
+                    output,
+                    "{:<20} {:>12.3?} {:>6.1}%",
+                    stage,
+                    elapsed,
+                    share(elapsed)
+                )
+            };
+
+            writeln!(output, "profile of {} records in {:.3?}", records, run)?;
+            line(
+                &mut output,
+                "parse",
+                Duration::from_nanos(self.parse.load(Ordering::Relaxed)),
+            )?;
+            writeln!(output)?;
+            for (tx_type, (count, elapsed)) in &self.apply {
+                line(&mut output, &format!("apply {}", tx_type), *elapsed)?;
+                let per_tx = elapsed.checked_div(u32::try_from(*count).unwrap_or(u32::MAX));
+                writeln!(
+                    output,
+                    " {:>10} txs {:>10.3?}/tx",
+                    count,
+                    per_tx.unwrap_or_default()
+                )?;
+            }
+            line(&mut output, "report", self.report)?;
+            writeln!(output)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn breakdown() {
+            let mut profile = Profile::new();
+            add(&profile.parse, Duration::from_millis(25));
+            profile.record_apply(TxType::Deposit, Duration::from_millis(10));
+            profile.record_apply(TxType::Deposit, Duration::from_millis(30));
+            profile.record_apply(TxType::Withdrawal, Duration::from_millis(5));
+            profile.report = Duration::from_millis(10);
+            assert_eq!(
+                profile.timed([1, 2].into_iter()).collect::<Vec<_>>(),
+                vec![1, 2]
+            );
+
+            let mut output = Vec::new();
+            profile
+                .write_run(&mut output, 3, Duration::from_millis(100))
+                .expect("failed to write the profile");
+            let output = String::from_utf8(output).unwrap();
+            let lines: Vec<&str> = output.lines().collect();
+            assert_eq!(lines[0], "profile of 3 records in 100.000ms");
+            assert!(lines[1].starts_with("parse") && lines[1].ends_with(" 25.0%"));
+            assert!(lines[2].starts_with("apply deposit") && lines[2].contains(" 40.0%"));
+            assert!(lines[2].ends_with("2 txs   20.000ms/tx"));
+            assert!(lines[3].starts_with("apply withdrawal"));
+            assert!(lines[4].starts_with("report") && lines[4].ends_with(" 10.0%"));
+        }
+    }
+}
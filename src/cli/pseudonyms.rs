@@ -0,0 +1,176 @@
+use std::error::Error;
+
+use hmac::{Hmac, Mac};
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use sha2::Sha256;
+
+use crate::aml::AmlFlag;
+use crate::anomaly::AnomalyFlag;
+use crate::engine::fees::Fee;
+use crate::interest::AccrualRow;
+use crate::models::tx::ClientId;
+use crate::models::tx_result::TxResult;
+use crate::rollup::BalanceRow;
+
+/// replaces the client ids of the outputs with tokens derived from a key: the first 8 bytes of
+/// the hmac-sha256 of the big endian client id, hex encoded, so a client keeps its token across runs
+#[derive(Clone)]
+pub struct Pseudonyms {
+    mac: Hmac<Sha256>,
+}
+
+impl Pseudonyms {
+    pub fn new(key: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if key.is_empty() {
+            return Err("empty pseudonymization key".into());
+        }
+
+        Ok(Self {
+            mac: Hmac::new_from_slice(key)?,
+        })
+    }
+
+    pub(crate) fn token(&self, client_id: ClientId) -> String {
+        let mut mac = self.mac.clone();
+        mac.update(&client_id.to_be_bytes());
+        hex::encode(&mac.finalize().into_bytes()[..8])
+    }
+
+    /// an output row with its client id replaced
+    pub(crate) fn row<'a, T>(
+        &self,
+        row: &'a T,
+        client_id: Option<ClientId>,
+    ) -> Pseudonymized<'a, T> {
+        Pseudonymized {
+            row,
+            client: client_id.map(|client_id| self.token(client_id)),
+        }
+    }
+}
+
+/// row serialized with the token of its client in the client column
+pub(crate) struct Pseudonymized<'a, T> {
+    row: &'a T,
+    client: Option<String>,
+}
+
+impl Serialize for Pseudonymized<'_, TxResult> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.row.serialize_with_client(&self.client, serializer)
+    }
+}
+
+impl Serialize for Pseudonymized<'_, AmlFlag> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let r = self.row;
+        let mut row = serializer.serialize_struct("AmlFlag", 5)?;
+        row.serialize_field("client", &self.client)?;
+        row.serialize_field("tx", &r.tx)?;
+        row.serialize_field("amount", &r.amount)?;
+        row.serialize_field("cumulative", &r.cumulative)?;
+        row.serialize_field("trigger", &r.trigger)?;
+        row.end()
+    }
+}
+
+impl Serialize for Pseudonymized<'_, AnomalyFlag> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let r = self.row;
+        let mut row = serializer.serialize_struct("AnomalyFlag", 5)?;
+        row.serialize_field("client", &self.client)?;
+        row.serialize_field("tx", &r.tx)?;
+        row.serialize_field("type", &r.tx_type)?;
+        row.serialize_field("amount", &r.amount)?;
+        row.serialize_field("average", &r.average)?;
+        row.end()
+    }
+}
+
+impl Serialize for Pseudonymized<'_, Fee> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let r = self.row;
+        let mut row = serializer.serialize_struct("Fee", 4)?;
+        row.serialize_field("client", &self.client)?;
+        row.serialize_field("tx", &r.tx_id)?;
+        row.serialize_field("kind", &r.kind)?;
+        row.serialize_field("amount", &r.amount)?;
+        row.end()
+    }
+}
+
+impl Serialize for Pseudonymized<'_, BalanceRow> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let r = self.row;
+        let mut row = serializer.serialize_struct("BalanceRow", 4)?;
+        row.serialize_field("client", &self.client)?;
+        row.serialize_field("date", &r.date)?;
+        row.serialize_field("available", &r.available)?;
+        row.serialize_field("held", &r.held)?;
+        row.end()
+    }
+}
+
+impl Serialize for Pseudonymized<'_, AccrualRow> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let r = self.row;
+        let mut row = serializer.serialize_struct("AccrualRow", 7)?;
+        row.serialize_field("client", &self.client)?;
+        row.serialize_field("from", &r.from)?;
+        row.serialize_field("to", &r.to)?;
+        row.serialize_field("days", &r.days)?;
+        row.serialize_field("balance", &r.balance)?;
+        row.serialize_field("rate", &r.rate)?;
+        row.serialize_field("interest", &r.interest)?;
+        row.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::aml::AmlTrigger;
+
+    #[test]
+    fn stable_tokens() {
+        let pseudonyms = Pseudonyms::new(b"secret").expect("invalid key");
+
+        let token = pseudonyms.token(1);
+        assert_eq!(token.len(), 16);
+        assert_eq!(token, pseudonyms.token(1));
+        assert_ne!(token, pseudonyms.token(2));
+        assert_ne!(
+            token,
+            Pseudonyms::new(b"other").expect("invalid key").token(1)
+        );
+        assert!(Pseudonyms::new(b"").is_err());
+    }
+
+    #[test]
+    fn pseudonymized_rows() {
+        let pseudonyms = Pseudonyms::new(b"secret").expect("invalid key");
+        let flag = AmlFlag {
+            client: 7,
+            tx: 3,
+            amount: dec!(20000),
+            cumulative: dec!(20000),
+            trigger: AmlTrigger::Single,
+        };
+
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        wtr.serialize(pseudonyms.row(&flag, Some(flag.client)))
+            .expect("failed to serialize");
+        let output = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+
+        assert_eq!(
+            output,
+            format!(
+                "client,tx,amount,cumulative,trigger\n{},3,20000,20000,single\n",
+                pseudonyms.token(7)
+            )
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use crate::cli::{write_report, ReportFormat};
+use crate::engine::Engine;
+use crate::models::report_transform::ReportTransform;
+use crate::models::tx_result::TxResult;
+use crate::report::OutputFormat;
+
+/// destination of the final report other than the output stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReportSink {
+    /// written to a file in the given format, with its rows transformed
+    File {
+        path: PathBuf,
+        format: OutputFormat,
+        transform: ReportTransform,
+    },
+    /// upserted into a table, in a single transaction
+    #[cfg(feature = "postgres")]
+    Postgres { url: String, table: String },
+    /// clients, transactions and rejects tables of a duckdb file, replaced on every run
+    #[cfg(feature = "duckdb")]
+    DuckDb { path: PathBuf },
+}
+
+/// state at the end of processing written by the sinks
+pub(crate) struct SinkInput<'a> {
+    pub(crate) engine: &'a Engine,
+    pub(crate) report: &'a ReportFormat,
+    /// rejected and unparsable records, only kept for the sinks that write them
+    #[cfg_attr(not(feature = "duckdb"), allow(dead_code))]
+    pub(crate) rejects: &'a [TxResult],
+}
+
+/// the table name is part of the statements, only plain (optionally schema qualified) identifiers are accepted
+#[cfg(any(feature = "postgres", feature = "clickhouse"))]
+pub(crate) fn table_name(table: &str) -> Result<&str, String> {
+    let valid = table.split('.').count() <= 2
+        && table.split('.').all(|part| {
+            part.chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+
+    match valid {
+        true => Ok(table),
+        false => Err(format!("invalid table name {}", table)),
+    }
+}
+
+impl ReportSink {
+    /// sink of an `--output` url, the table is used by the postgres sink
+    #[cfg_attr(not(feature = "postgres"), allow(unused_variables))]
+    pub fn from_url(url: &str, table: &str) -> Result<Self, Box<dyn Error>> {
+        match url.split_once(':') {
+            #[cfg(feature = "postgres")]
+            Some(("postgres" | "postgresql", _)) => Ok(ReportSink::Postgres {
+                url: url.to_string(),
+                table: table_name(table)?.to_string(),
+            }),
+            #[cfg(not(feature = "postgres"))]
+            Some(("postgres" | "postgresql", _)) => {
+                Err("postgres output requires the postgres feature".into())
+            }
+            #[cfg(feature = "duckdb")]
+            Some(("duckdb", path)) if !path.is_empty() => Ok(ReportSink::DuckDb {
+                path: PathBuf::from(path),
+            }),
+            #[cfg(not(feature = "duckdb"))]
+            Some(("duckdb", _)) => Err("duckdb output requires the duckdb feature".into()),
+            _ => Err(format!("unsupported output {}", url).into()),
+        }
+    }
+
+    /// whether the rejected records are written by the sink
+    pub(crate) fn keeps_rejects(&self) -> bool {
+        match *self {
+            ReportSink::File { .. } => false,
+            #[cfg(feature = "postgres")]
+            ReportSink::Postgres { .. } => false,
+            #[cfg(feature = "duckdb")]
+            ReportSink::DuckDb { .. } => true,
+        }
+    }
+
+    /// path of a report file
+    pub(crate) fn path(&self) -> Option<&Path> {
+        match *self {
+            ReportSink::File { ref path, .. } => Some(path),
+            #[cfg(feature = "postgres")]
+            ReportSink::Postgres { .. } => None,
+            #[cfg(feature = "duckdb")]
+            ReportSink::DuckDb { .. } => None,
+        }
+    }
+
+    /// whether the client ids are written into integer columns, which pseudonymized ids are not
+    pub(crate) fn types_client_ids(&self) -> bool {
+        match *self {
+            ReportSink::File { format, .. } => format.types_client_ids(),
+            #[cfg(feature = "postgres")]
+            ReportSink::Postgres { .. } => true,
+            #[cfg(feature = "duckdb")]
+            ReportSink::DuckDb { .. } => true,
+        }
+    }
+
+    pub(crate) fn write(&self, input: SinkInput) -> Result<(), Box<dyn Error>> {
+        match *self {
+            ReportSink::File {
+                ref path,
+                format,
+                ref transform,
+            } => write_report(
+                input.engine.report(),
+                input.report,
+                format,
+                Some(transform),
+                BufWriter::new(File::create(path)?),
+            ),
+            #[cfg(feature = "postgres")]
+            ReportSink::Postgres { ref url, ref table } => postgres::upsert(
+                url,
+                table,
+                input
+                    .engine
+                    .report()
+                    .filter(|report| input.report.filter.matches(report)),
+            ),
+            #[cfg(feature = "duckdb")]
+            ReportSink::DuckDb { ref path } => duckdb::export(path, input),
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use std::error::Error;
+
+    use ::postgres::{Client, NoTls};
+
+    use crate::models::client::ClientReport;
+
+    pub(super) fn statements(table: &str) -> (String, String) {
+        (
+            format!(
+                "CREATE TABLE IF NOT EXISTS {} (\
+                 client integer PRIMARY KEY, \
+                 available numeric NOT NULL, \
+                 held numeric NOT NULL, \
+                 total numeric NOT NULL, \
+                 locked boolean NOT NULL)",
+                table
+            ),
+            format!(
+                "INSERT INTO {} (client, available, held, total, locked) \
+                 VALUES ($1, $2, $3, $4, $5) \
+                 ON CONFLICT (client) DO UPDATE SET \
+                 available = EXCLUDED.available, \
+                 held = EXCLUDED.held, \
+                 total = EXCLUDED.total, \
+                 locked = EXCLUDED.locked",
+                table
+            ),
+        )
+    }
+
+    /// creates the table when missing and upserts the reports, either all of them or none are written
+    pub(super) fn upsert(
+        url: &str,
+        table: &str,
+        reports: impl Iterator<Item = ClientReport>,
+    ) -> Result<(), Box<dyn Error>> {
+        let (create, upsert) = statements(table);
+        let mut client = Client::connect(url, NoTls)?;
+        let mut tx = client.transaction()?;
+
+        tx.batch_execute(&create)?;
+        let upsert = tx.prepare(&upsert)?;
+        for report in reports {
+            tx.execute(
+                &upsert,
+                &[
+                    &i32::from(report.id),
+                    &report.available,
+                    &report.held,
+                    &report.total,
+                    &report.locked,
+                ],
+            )?;
+        }
+
+        Ok(tx.commit()?)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn upsert_statement() {
+            let (_, upsert) = statements("clients");
+            assert!(upsert.starts_with(
+                "INSERT INTO clients (client, available, held, total, locked) VALUES ($1, $2, $3, $4, $5)"
+            ));
+            assert!(upsert
+                .contains("ON CONFLICT (client) DO UPDATE SET available = EXCLUDED.available"));
+        }
+    }
+}
+
+#[cfg(feature = "duckdb")]
+mod duckdb {
+    use std::error::Error;
+    use std::path::Path;
+
+    use ::duckdb::{params, Connection};
+
+    use crate::cli::sinks::SinkInput;
+
+    const SCHEMA: &str = "\
+        CREATE OR REPLACE TABLE clients (\
+            client USMALLINT PRIMARY KEY, \
+            available DECIMAL(38, 4) NOT NULL, \
+            held DECIMAL(38, 4) NOT NULL, \
+            total DECIMAL(38, 4) NOT NULL, \
+            locked BOOLEAN NOT NULL); \
+        CREATE OR REPLACE TABLE transactions (\
+            tx UINTEGER PRIMARY KEY, \
+            client USMALLINT NOT NULL, \
+            type VARCHAR NOT NULL, \
+            amount DECIMAL(38, 4) NOT NULL, \
+            dispute_state VARCHAR NOT NULL, \
+            disputes UINTEGER NOT NULL); \
+        CREATE OR REPLACE TABLE rejects (\
+            line UBIGINT, \
+            type VARCHAR, \
+            client USMALLINT, \
+            tx UINTEGER, \
+            code VARCHAR NOT NULL, \
+            reason VARCHAR);";
+
+    /// replaces the tables of the file with the state of the run, in a single transaction
+    pub(super) fn export(path: &Path, input: SinkInput) -> Result<(), Box<dyn Error>> {
+        let mut conn = Connection::open(path)?;
+        let tx = conn.transaction()?;
+        tx.execute_batch(SCHEMA)?;
+
+        // decimals are appended as strings and cast by the appender
+        let mut clients = tx.appender("clients")?;
+        for report in input
+            .engine
+            .report()
+            .filter(|r| input.report.filter.matches(r))
+        {
+            clients.append_row(params![
+                report.id,
+                report.available.to_string(),
+                report.held.to_string(),
+                report.total.to_string(),
+                report.locked,
+            ])?;
+        }
+        clients.flush()?;
+        drop(clients);
+
+        let mut transactions = tx.appender("transactions")?;
+        for (id, stored) in input.engine.transactions() {
+            transactions.append_row(params![
+                id,
+                stored.client_id(),
+                stored.tx_type().to_string(),
+                stored.amount().to_string(),
+                stored.dispute_state().to_string(),
+                stored.disputes(),
+            ])?;
+        }
+        transactions.flush()?;
+        drop(transactions);
+
+        let mut rejects = tx.appender("rejects")?;
+        for reject in input.rejects {
+            rejects.append_row(params![
+                reject.line,
+                reject.tx_type.map(|tx_type| tx_type.to_string()),
+                reject.client_id,
+                reject.id,
+                reject.code.unwrap_or_default(),
+                reject.reason,
+            ])?;
+        }
+        rejects.flush()?;
+        drop(rejects);
+
+        Ok(tx.commit()?)
+    }
+}
+
+#[cfg(all(test, any(feature = "postgres", feature = "clickhouse")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_name_validation() {
+        assert_eq!(table_name("clients"), Ok("clients"));
+        assert_eq!(
+            table_name("ledger.client_report_2"),
+            Ok("ledger.client_report_2")
+        );
+        assert!(table_name("").is_err());
+        assert!(table_name("1clients").is_err());
+        assert!(table_name("a.b.c").is_err());
+        assert!(table_name("clients; DROP TABLE clients").is_err());
+    }
+}
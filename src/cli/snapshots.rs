@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::tx::ClientId;
+use crate::models::tx_result::{TxResult, TxStatus};
+use crate::models::tx_type::TxType;
+
+/// interval between intermediate report snapshots, `N` rows or `Ns` seconds
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ReportInterval {
+    Rows(u64),
+    Seconds(u64),
+}
+
+impl FromStr for ReportInterval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (value, interval): (_, fn(u64) -> Self) = match s.strip_suffix('s') {
+            Some(value) => (value, ReportInterval::Seconds),
+            None => (s, ReportInterval::Rows),
+        };
+
+        match value.parse::<u64>() {
+            Ok(0) | Err(_) => Err(format!(
+                "invalid report interval '{}', expected N rows or Ns seconds",
+                s
+            )),
+            Ok(value) => Ok(interval(value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SnapshotOptions {
+    /// interval of the scheduled snapshots, only requested snapshots are written when None
+    pub every: Option<ReportInterval>,
+
+    /// directory the timestamped report snapshots are written to
+    pub dir: PathBuf,
+
+    /// report only clients changed since the previous snapshot
+    pub delta: bool,
+
+    /// set to request a full snapshot and a stats dump, cleared once written
+    pub request: Option<Arc<AtomicBool>>,
+}
+
+/// decides when an intermediate report is due and where it's written
+pub(crate) struct Snapshots {
+    options: SnapshotOptions,
+    rows: u64,
+    last: Instant,
+    seq: u64,
+    changed: HashSet<ClientId>,
+}
+
+impl Snapshots {
+    pub(crate) fn new(options: SnapshotOptions) -> Self {
+        Self {
+            options,
+            rows: 0,
+            last: Instant::now(),
+            seq: 0,
+            changed: HashSet::new(),
+        }
+    }
+
+    /// counts a processed row and returns whether a snapshot is due
+    pub(crate) fn tick(&mut self) -> bool {
+        self.rows += 1;
+
+        let due = match self.options.every {
+            Some(ReportInterval::Rows(rows)) => self.rows >= rows,
+            Some(ReportInterval::Seconds(secs)) => self.last.elapsed() >= Duration::from_secs(secs),
+            None => false,
+        };
+
+        if due {
+            self.rows = 0;
+            self.last = Instant::now();
+        }
+
+        due
+    }
+
+    /// whether a snapshot was requested since the last call
+    pub(crate) fn requested(&self) -> bool {
+        self.options
+            .request
+            .as_ref()
+            .is_some_and(|request| request.swap(false, Ordering::Relaxed))
+    }
+
+    /// records a client changed by a processed transaction, tracked only for delta snapshots
+    pub(crate) fn record_change(&mut self, client_id: ClientId) {
+        if self.options.delta {
+            self.changed.insert(client_id);
+        }
+    }
+
+    /// clients changed since the previous snapshot sorted by id, None when snapshots are not deltas
+    pub(crate) fn take_changed(&mut self) -> Option<Vec<ClientId>> {
+        if !self.options.delta {
+            return None;
+        }
+
+        let mut changed: Vec<_> = self.changed.drain().collect();
+        changed.sort_unstable();
+        Some(changed)
+    }
+
+    /// path of the next snapshot, named after the current unix time in milliseconds
+    pub(crate) fn next_path(&mut self) -> PathBuf {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis());
+
+        self.seq += 1;
+        self.options
+            .dir
+            .join(format!("report-{}-{:06}.csv", millis, self.seq))
+    }
+
+    /// path of the stats dump written along a snapshot
+    pub(crate) fn stats_path(report_path: &Path) -> PathBuf {
+        let name = report_path
+            .file_name()
+            .map(|name| name.to_string_lossy().replacen("report-", "stats-", 1))
+            .unwrap_or_default();
+
+        report_path.with_file_name(name).with_extension("json")
+    }
+}
+
+/// engine counters dumped along a requested snapshot
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct Stats {
+    pub(crate) records: u64,
+    pub(crate) clients: usize,
+    pub(crate) locked_clients: usize,
+    pub(crate) transactions: usize,
+    pub(crate) duplicates: u64,
+    /// retries acknowledged under the idempotency key of an accepted transaction
+    pub(crate) replays: u64,
+    pub(crate) velocity_rejections: u64,
+    /// transactions dropped by the input filter
+    pub(crate) filtered: u64,
+    /// amounts lowered by the input filter
+    pub(crate) clamped: u64,
+    #[serde(flatten)]
+    pub(crate) breakdown: Breakdown,
+}
+
+/// rejected records per error code and processed transactions per type
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub(crate) struct Breakdown {
+    /// rejected transactions and parse errors per error code
+    errors: BTreeMap<String, u64>,
+    tx_types: BTreeMap<TxType, TxTypeCounts>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+struct TxTypeCounts {
+    accepted: u64,
+    rejected: u64,
+}
+
+impl Breakdown {
+    /// counts the result of a processed record
+    pub(crate) fn record(&mut self, result: &TxResult) {
+        if let Some(code) = result.code {
+            *self.errors.entry(code.to_string()).or_default() += 1;
+        }
+
+        if let Some(tx_type) = result.tx_type {
+            let counts = self.tx_types.entry(tx_type).or_default();
+            match result.status {
+                TxStatus::Accepted => counts.accepted += 1,
+                _ => counts.rejected += 1,
+            }
+        }
+    }
+}
+
+/// writes a file under a temporary name and renames it once complete,
+/// readers of the snapshot directory never see a partially written file
+pub(crate) fn write_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let tmp = path.with_extension("tmp");
+    let mut file = BufWriter::new(File::create(&tmp)?);
+    write(&mut file)?;
+    file.flush()?;
+    fs::rename(tmp, path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_interval_from_str() {
+        assert_eq!("100".parse(), Ok(ReportInterval::Rows(100)));
+        assert_eq!("30s".parse(), Ok(ReportInterval::Seconds(30)));
+        assert!("0".parse::<ReportInterval>().is_err());
+        assert!("1m".parse::<ReportInterval>().is_err());
+    }
+
+    #[test]
+    fn tick_rows() {
+        let mut snapshots = Snapshots::new(SnapshotOptions {
+            every: Some(ReportInterval::Rows(2)),
+            dir: PathBuf::from("."),
+            delta: false,
+            request: None,
+        });
+
+        assert!(!snapshots.tick());
+        assert!(snapshots.tick());
+        assert!(!snapshots.tick());
+        assert!(snapshots.tick());
+        assert!(snapshots
+            .next_path()
+            .to_string_lossy()
+            .ends_with("-000001.csv"));
+    }
+
+    #[test]
+    fn take_changed() {
+        let mut snapshots = Snapshots::new(SnapshotOptions {
+            every: Some(ReportInterval::Rows(2)),
+            dir: PathBuf::from("."),
+            delta: true,
+            request: None,
+        });
+        snapshots.record_change(3);
+        snapshots.record_change(1);
+        snapshots.record_change(3);
+
+        assert_eq!(snapshots.take_changed(), Some(vec![1, 3]));
+        assert_eq!(snapshots.take_changed(), Some(vec![]));
+    }
+
+    #[test]
+    fn requested() {
+        let request = Arc::new(AtomicBool::new(false));
+        let snapshots = Snapshots::new(SnapshotOptions {
+            every: None,
+            dir: PathBuf::from("."),
+            delta: false,
+            request: Some(Arc::clone(&request)),
+        });
+
+        assert!(!snapshots.requested());
+        request.store(true, Ordering::Relaxed);
+        assert!(snapshots.requested());
+        assert!(!snapshots.requested());
+    }
+
+    #[test]
+    fn stats_path() {
+        assert_eq!(
+            Snapshots::stats_path(Path::new("dir/report-1700000000000-000001.csv")),
+            PathBuf::from("dir/stats-1700000000000-000001.json")
+        );
+    }
+}
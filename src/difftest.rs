@@ -0,0 +1,314 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Range;
+
+use rust_decimal::Decimal;
+
+use crate::engine::Engine;
+use crate::models::tx::{ClientId, TxId, TxInput};
+use crate::models::tx_type::TxType;
+pub use crate::testing::workload;
+use crate::workload::first_failure;
+
+/// balances and lock of a client
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct Balances {
+    pub available: Decimal,
+    pub held: Decimal,
+    pub locked: bool,
+}
+
+impl fmt::Display for Balances {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "available {}, held {}, locked {}",
+            self.available, self.held, self.locked
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Disputed {
+    No,
+    Open,
+    Resolved,
+    ChargedBack,
+}
+
+#[derive(Debug)]
+struct Recorded {
+    client_id: ClientId,
+    deposit: bool,
+    amount: Decimal,
+    disputed: Disputed,
+}
+
+/// slow but obviously correct model of the default engine configuration, for deposits,
+/// withdrawals, disputes, resolves and chargebacks only:
+/// - every transaction opens the account of its client, even a rejected one
+/// - a deposit or withdrawal only takes its tx id once it's applied
+/// - a locked account rejects everything
+/// - a withdrawal can't take more than the available funds
+/// - only a deposit of the same client can be disputed, again once it's resolved
+/// - only an open dispute can be resolved or charged back, a chargeback locks the account
+#[derive(Debug, Default)]
+pub struct Reference {
+    clients: BTreeMap<ClientId, Balances>,
+    txs: HashMap<TxId, Recorded>,
+}
+
+impl Reference {
+    /// whether the transaction is accepted
+    pub fn apply(&mut self, tx: &TxInput) -> bool {
+        let mut client = *self.clients.entry(tx.client_id).or_default();
+        let accepted = match tx.tx_type {
+            TxType::Deposit | TxType::Withdrawal => {
+                let deposit = tx.tx_type == TxType::Deposit;
+                match tx.amount {
+                    Some(amount) if !self.txs.contains_key(&tx.id) => {
+                        let applied = if client.locked {
+                            false
+                        } else if deposit {
+                            client.available += amount;
+                            true
+                        } else if amount > client.available.max(Decimal::ZERO) {
+                            false
+                        } else {
+                            client.available -= amount;
+                            true
+                        };
+                        if applied {
+                            self.txs.insert(
+                                tx.id,
+                                Recorded {
+                                    client_id: tx.client_id,
+                                    deposit,
+                                    amount,
+                                    disputed: Disputed::No,
+                                },
+                            );
+                        }
+                        applied
+                    }
+                    _ => false,
+                }
+            }
+            TxType::Dispute | TxType::Resolve | TxType::Chargeback => {
+                match self.txs.get_mut(&tx.id) {
+                    Some(recorded)
+                        if recorded.client_id == tx.client_id
+                            && recorded.deposit
+                            && !client.locked =>
+                    {
+                        let amount = recorded.amount;
+                        match (tx.tx_type, recorded.disputed) {
+                            (TxType::Dispute, Disputed::No | Disputed::Resolved) => {
+                                recorded.disputed = Disputed::Open;
+                                client.available -= amount;
+                                client.held += amount;
+                                true
+                            }
+                            (TxType::Resolve, Disputed::Open) => {
+                                recorded.disputed = Disputed::Resolved;
+                                client.available += amount;
+                                client.held -= amount;
+                                true
+                            }
+                            (TxType::Chargeback, Disputed::Open) => {
+                                recorded.disputed = Disputed::ChargedBack;
+                                client.held -= amount;
+                                client.locked = true;
+                                true
+                            }
+                            _ => false,
+                        }
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+
+        self.clients.insert(tx.client_id, client);
+        accepted
+    }
+
+    pub fn client(&self, client_id: ClientId) -> Option<Balances> {
+        self.clients.get(&client_id).copied()
+    }
+}
+
+/// first difference between the engine and the reference
+#[derive(Debug, PartialEq)]
+pub enum Divergence {
+    /// only one of them accepted the transaction at the index
+    Outcome { index: usize, engine: bool },
+    /// the client of the transaction at the index has other balances after it
+    Balances {
+        index: usize,
+        engine: Option<Balances>,
+        reference: Option<Balances>,
+    },
+}
+
+impl Divergence {
+    /// index of the transaction the engine and the reference diverge at
+    pub fn index(&self) -> usize {
+        match self {
+            Divergence::Outcome { index, .. } | Divergence::Balances { index, .. } => *index,
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::Outcome { index, engine } => write!(
+                f,
+                "transaction {} was {} by the engine but {} by the reference",
+                index,
+                if *engine { "accepted" } else { "rejected" },
+                if *engine { "rejected" } else { "accepted" },
+            ),
+            Divergence::Balances {
+                index,
+                engine,
+                reference,
+            } => {
+                let show = |balances: &Option<Balances>| {
+                    balances.map_or("no account".to_string(), |b| b.to_string())
+                };
+                write!(
+                    f,
+                    "after transaction {} the engine has {} but the reference {}",
+                    index,
+                    show(engine),
+                    show(reference),
+                )
+            }
+        }
+    }
+}
+
+/// applies the transactions to a default engine and to the reference, comparing the outcome
+/// of each one and the balances of its client after it
+pub fn check(txs: &[TxInput]) -> Result<(), Divergence> {
+    let mut engine = Engine::new();
+    let mut reference = Reference::default();
+
+    for (index, tx) in txs.iter().enumerate() {
+        let accepted = engine.process_tx(tx).is_ok();
+        if accepted != reference.apply(tx) {
+            return Err(Divergence::Outcome {
+                index,
+                engine: accepted,
+            });
+        }
+
+        let balances = engine.client(tx.client_id).map(|client| Balances {
+            available: client.available(),
+            held: client.held(),
+            locked: client.locked(),
+        });
+        if balances != reference.client(tx.client_id) {
+            return Err(Divergence::Balances {
+                index,
+                engine: balances,
+                reference: reference.client(tx.client_id),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// lowest seed of the range whose workload diverges, the workloads are checked on `jobs`
+/// threads and the result is the same for any number of them
+pub fn first_divergence(
+    seeds: Range<u64>,
+    jobs: usize,
+    clients: ClientId,
+    txs: usize,
+) -> Option<(u64, Divergence)> {
+    first_failure(seeds, jobs, |seed| check(&workload(seed, clients, txs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn tx(tx_type: TxType, client_id: ClientId, id: TxId, amount: Option<Decimal>) -> TxInput {
+        TxInput {
+            tx_type,
+            client_id,
+            id,
+            amount,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        }
+    }
+
+    #[test]
+    fn reference() {
+        let mut reference = Reference::default();
+        let outcomes: Vec<bool> = [
+            tx(TxType::Deposit, 1, 1, Some(dec!(10))),
+            tx(TxType::Withdrawal, 1, 2, Some(dec!(11))),
+            tx(TxType::Deposit, 2, 2, Some(dec!(5))),
+            tx(TxType::Dispute, 2, 1, None),
+            tx(TxType::Dispute, 1, 1, None),
+            tx(TxType::Chargeback, 1, 1, None),
+            tx(TxType::Deposit, 1, 3, Some(dec!(1))),
+        ]
+        .iter()
+        .map(|tx| reference.apply(tx))
+        .collect();
+
+        assert_eq!(outcomes, vec![true, false, true, false, true, true, false]);
+        assert_eq!(
+            reference.client(1),
+            Some(Balances {
+                available: dec!(0),
+                held: dec!(0),
+                locked: true,
+            })
+        );
+        assert_eq!(
+            reference.client(2),
+            Some(Balances {
+                available: dec!(5),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn engine_matches_reference() {
+        for seed in 0..50 {
+            if let Err(divergence) = check(&workload(seed, 10, 400)) {
+                panic!("seed {}: {}", seed, divergence);
+            }
+        }
+    }
+
+    #[test]
+    fn divergence() {
+        // the reference only models the default configuration
+        let txs = [
+            tx(TxType::Deposit, 1, 1, Some(dec!(10))),
+            tx(TxType::Authorize, 1, 2, Some(dec!(5))),
+        ];
+        assert_eq!(
+            check(&txs),
+            Err(Divergence::Outcome {
+                index: 1,
+                engine: true,
+            })
+        );
+    }
+}
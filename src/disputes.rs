@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::models::tx::{ClientId, TxId};
+use crate::models::tx_type::TxType;
+
+/// age range of an open dispute in days
+#[derive(Serialize, Debug, PartialEq, Copy, Clone)]
+pub(crate) enum AgeBucket {
+    #[serde(rename = "0-7")]
+    Week,
+    #[serde(rename = "8-30")]
+    Month,
+    #[serde(rename = "30+")]
+    Older,
+    /// opened by a dispute without a timestamp
+    #[serde(rename = "unknown")]
+    Unknown,
+}
+
+impl AgeBucket {
+    fn of(days: Option<i64>) -> Self {
+        match days {
+            None => AgeBucket::Unknown,
+            Some(..=7) => AgeBucket::Week,
+            Some(8..=30) => AgeBucket::Month,
+            Some(_) => AgeBucket::Older,
+        }
+    }
+}
+
+/// row of the dispute aging report, one per open dispute
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct DisputeAgeRow {
+    pub(crate) client: ClientId,
+    pub(crate) tx: TxId,
+    /// amount held by the dispute
+    pub(crate) held: Decimal,
+    pub(crate) opened: Option<DateTime<Utc>>,
+    pub(crate) age_days: Option<i64>,
+    pub(crate) bucket: AgeBucket,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+struct OpenDispute {
+    client: ClientId,
+    held: Decimal,
+    opened: Option<DateTime<Utc>>,
+}
+
+/// disputes opened and not yet resolved or charged back, with the time they were opened
+#[derive(Debug, Default)]
+pub(crate) struct DisputeAging {
+    open: BTreeMap<TxId, OpenDispute>,
+}
+
+impl DisputeAging {
+    /// records an accepted dispute, a redispute after a resolve starts a new age
+    pub(crate) fn open(
+        &mut self,
+        client: ClientId,
+        tx: TxId,
+        held: Decimal,
+        opened: Option<DateTime<Utc>>,
+    ) {
+        self.open.insert(
+            tx,
+            OpenDispute {
+                client,
+                held,
+                opened,
+            },
+        );
+    }
+
+    /// removes the dispute of a resolved or charged back transaction
+    pub(crate) fn close(&mut self, tx: TxId) {
+        self.open.remove(&tx);
+    }
+
+    /// open disputes aged at the time, oldest first, disputes without a timestamp come last
+    pub(crate) fn rows(&self, as_of: DateTime<Utc>) -> Vec<DisputeAgeRow> {
+        let mut rows: Vec<_> = self
+            .open
+            .iter()
+            .map(|(tx, dispute)| {
+                let age_days = dispute
+                    .opened
+                    .map(|opened| (as_of - opened).num_days().max(0));
+                DisputeAgeRow {
+                    client: dispute.client,
+                    tx: *tx,
+                    held: dispute.held,
+                    opened: dispute.opened,
+                    age_days,
+                    bucket: AgeBucket::of(age_days),
+                }
+            })
+            .collect();
+        rows.sort_by_key(|row| (row.opened.is_none(), row.opened, row.tx));
+        rows
+    }
+}
+
+/// operator answer for an open dispute in the dispute review
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub(crate) enum Decision {
+    Resolve,
+    Chargeback,
+    Skip,
+    Quit,
+}
+
+impl Decision {
+    pub(crate) const PROMPT: &'static str = "[r]esolve, [c]hargeback, [s]kip or [q]uit";
+
+    /// parses an answer line, an empty line skips the dispute
+    pub(crate) fn parse(answer: &str) -> Option<Self> {
+        match answer.trim().to_ascii_lowercase().as_str() {
+            "r" | "resolve" => Some(Decision::Resolve),
+            "c" | "chargeback" => Some(Decision::Chargeback),
+            "" | "s" | "skip" => Some(Decision::Skip),
+            "q" | "quit" => Some(Decision::Quit),
+            _ => None,
+        }
+    }
+
+    /// type of the transaction carrying out the decision
+    pub(crate) fn tx_type(self) -> Option<TxType> {
+        match self {
+            Decision::Resolve => Some(TxType::Resolve),
+            Decision::Chargeback => Some(TxType::Chargeback),
+            Decision::Skip | Decision::Quit => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn day(day: u32) -> Option<DateTime<Utc>> {
+        Some(format!("2024-03-{:02}T12:00:00Z", day).parse().unwrap())
+    }
+
+    #[test]
+    fn age_buckets() {
+        let mut aging = DisputeAging::default();
+        aging.open(1, 10, dec!(5), day(30));
+        aging.open(2, 11, dec!(1.5), day(1));
+        aging.open(1, 12, dec!(2), None);
+        aging.open(3, 13, dec!(7), day(20));
+        aging.open(3, 14, dec!(9), day(2));
+        aging.close(14);
+
+        let rows = aging.rows(day(31).unwrap());
+        assert_eq!(
+            rows.iter()
+                .map(|row| (row.tx, row.age_days, row.bucket))
+                .collect::<Vec<_>>(),
+            vec![
+                (11, Some(30), AgeBucket::Month),
+                (13, Some(11), AgeBucket::Month),
+                (10, Some(1), AgeBucket::Week),
+                (12, None, AgeBucket::Unknown),
+            ]
+        );
+        assert_eq!(rows[0].held, dec!(1.5));
+
+        // a redispute starts a new age
+        aging.open(2, 11, dec!(1.5), day(31));
+        assert_eq!(aging.rows(day(31).unwrap())[2].age_days, Some(0));
+        assert_eq!(AgeBucket::of(Some(31)), AgeBucket::Older);
+    }
+
+    #[test]
+    fn decisions() {
+        assert_eq!(Decision::parse(" R\n"), Some(Decision::Resolve));
+        assert_eq!(Decision::parse("chargeback"), Some(Decision::Chargeback));
+        assert_eq!(Decision::parse("\n"), Some(Decision::Skip));
+        assert_eq!(Decision::parse("q"), Some(Decision::Quit));
+        assert_eq!(Decision::parse("maybe"), None);
+        assert_eq!(Decision::Skip.tx_type(), None);
+    }
+}
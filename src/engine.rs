@@ -1,191 +1,2561 @@
-use std::collections::HashMap;
+pub(crate) mod admin;
+pub(crate) mod alerts;
+pub(crate) mod archive;
+pub(crate) mod authorizations;
+pub(crate) mod batches;
+pub(crate) mod clients;
+pub(crate) mod config;
+pub(crate) mod dedup;
+pub(crate) mod fees;
+pub(crate) mod metrics;
+pub(crate) mod min_balance;
+pub(crate) mod retention;
+pub(crate) mod rules;
+#[cfg(any(feature = "cli", test))]
+pub(crate) mod state;
+pub(crate) mod value_dates;
+pub(crate) mod velocity;
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+use ahash::{AHashMap, AHashSet};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+
+use crate::engine::admin::{AdminAction, AdminCommand, Cutoff};
+use crate::engine::alerts::{Alert, AlertObserver, Balances};
+use crate::engine::archive::{ArchivedTx, TxArchive};
+use crate::engine::authorizations::Authorizations;
+use crate::engine::batches::Batches;
+use crate::engine::clients::ClientTable;
+use crate::engine::config::{DuplicatePolicy, EngineConfig};
+use crate::engine::dedup::DedupWindow;
+use crate::engine::fees::{Fee, FeeKind};
+use crate::engine::metrics::{Counters, Metrics};
+use crate::engine::retention::Retained;
+use crate::engine::rules::{EngineView, ValidationRule};
+#[cfg(any(feature = "cli", test))]
+use crate::engine::state::EngineState;
+use crate::engine::value_dates::ValueDated;
+use crate::engine::velocity::Velocity;
+#[cfg(any(feature = "cli", feature = "wasm", test))]
+use crate::models::client::ClientReport;
+use crate::models::client::{ClientView, LockedBy};
+use crate::models::dispute_state::DisputeState;
+use crate::models::errors::ErrorKind::{
+    AccountUnderDispute, ArchiveFailed, AuthorizationExpired, BatchNotFound, ClientIdNoMatch,
+    ClientNotFound, DisputeLimitReached, DuplicateTx, MemoryBudgetExceeded, ReversalIncomplete,
+    TxAlreadyUnderDispute, TxChargedBack, TxIdConflict, TxInvalidAmount, TxNotADeposit,
+    TxNotAuthorized, TxNotFound, TxNotPending, TxReversed, VelocityLimitExceeded,
+};
+use crate::models::errors::{BatchError, Error, ErrorKind};
+use crate::models::money::Money;
+use crate::models::tx::{ClientId, Tx, TxId, TxInput, TxView};
+use crate::models::tx_type::TxType;
+
+/// estimated bytes of a stored transaction slot, entry plus hash control byte
+const TX_ENTRY_BYTES: usize = size_of::<(TxId, Tx)>() + 1;
+
+/// applies transactions to the client accounts and stores deposits and withdrawals for disputes
+#[derive(Clone)]
+pub struct Engine {
+    config: EngineConfig,
+    dedup: Option<DedupWindow>,
+    velocity: Option<Velocity>,
+    retained: Option<Retained>,
+    authorizations: Option<Authorizations>,
+    batches: Batches,
+    value_dated: ValueDated,
+    archive: Option<Arc<Mutex<dyn TxArchive>>>,
+    /// first failure to archive a dropped transaction, the transaction is kept in memory
+    archive_error: Option<String>,
+    clients: ClientTable,
+    transactions: AHashMap<TxId, Tx>,
+    /// idempotency keys of the accepted transactions
+    keys: AHashSet<String>,
+    key_bytes: usize,
+    /// transactions acknowledged as retries of an accepted idempotency key
+    replays: u64,
+    rules: Vec<Arc<dyn ValidationRule>>,
+    observers: Vec<Arc<dyn AlertObserver>>,
+    /// alerts of the transactions applied since the observers were last notified
+    alerts: Vec<Alert>,
+    /// fees charged since they were last taken
+    fees: Vec<Fee>,
+    counters: Counters,
+    /// store order of the last stored transaction
+    seq: u32,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self::with_config(EngineConfig::default())
+    }
+
+    pub fn with_config(config: EngineConfig) -> Self {
+        // the up front allocation is limited to half of the memory budget
+        let expected_txs = match config.max_memory {
+            Some(max_memory) => config
+                .expected_txs
+                .map(|txs| txs.min(max_memory / 2 / TX_ENTRY_BYTES)),
+            None => config.expected_txs,
+        };
+
+        Self {
+            dedup: config
+                .dedup_window
+                .map(|capacity| DedupWindow::new(capacity, config.dedup_ttl)),
+            velocity: config
+                .velocity
+                .is_enabled()
+                .then(|| Velocity::new(config.velocity)),
+            retained: config
+                .retention
+                .is_enabled()
+                .then(|| Retained::new(config.retention)),
+            authorizations: config.authorization_ttl.map(Authorizations::new),
+            batches: Batches::default(),
+            value_dated: ValueDated::default(),
+            archive: None,
+            archive_error: None,
+            clients: ClientTable::with_capacity(config.expected_clients.unwrap_or_default()),
+            transactions: AHashMap::with_capacity(expected_txs.unwrap_or_default()),
+            keys: AHashSet::new(),
+            key_bytes: 0,
+            replays: 0,
+            rules: Vec::new(),
+            observers: Vec::new(),
+            alerts: Vec::new(),
+            fees: Vec::new(),
+            counters: Counters::default(),
+            seq: 0,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    /// registers a rule checked before every transaction, in the order the rules were added
+    pub fn add_rule(&mut self, rule: impl ValidationRule + 'static) {
+        self.rules.push(Arc::new(rule));
+    }
+
+    /// registers an observer notified of the alerts of the configured alert rules
+    pub fn add_observer(&mut self, observer: impl AlertObserver + 'static) {
+        self.observers.push(Arc::new(observer));
+    }
+
+    /// stores the transactions dropped by the retention policy, so every stored transaction is
+    /// dropped in time and loaded back by a later dispute, resolve or chargeback of it
+    pub fn set_archive(&mut self, archive: impl TxArchive + 'static) {
+        self.archive = Some(Arc::new(Mutex::new(archive)));
+    }
+
+    pub fn process_tx(&mut self, tx: &TxInput) -> Result<(), Error> {
+        let result = self.apply_tx(tx);
+        self.drop_retained(tx.timestamp);
+        self.notify();
+        result
+    }
+
+    fn notify(&mut self) {
+        for alert in self.alerts.drain(..) {
+            for observer in &self.observers {
+                observer.notify(&alert);
+            }
+        }
+    }
+
+    fn apply_tx(&mut self, tx: &TxInput) -> Result<(), Error> {
+        // a value-dated transaction is checked once it's released
+        if let (Some(as_of), Some(date)) = (self.config.as_of, tx.effective_date) {
+            if date > as_of {
+                self.value_dated.queue(tx);
+                return Ok(());
+            }
+        }
+
+        if let Some(retained) = self.retained.as_mut() {
+            retained.tick();
+        }
+
+        // a retry of an accepted transaction is acknowledged, whatever its tx id
+        if tx
+            .idempotency_key
+            .as_ref()
+            .is_some_and(|key| self.keys.contains(key))
+        {
+            self.replays += 1;
+            return Ok(());
+        }
+
+        let before = (self.config.alerts.is_enabled() && !self.observers.is_empty())
+            .then(|| Balances::of(self.client(tx.client_id)));
+
+        let duplicate = self
+            .dedup
+            .as_mut()
+            .is_some_and(|dedup| dedup.check(tx.tx_type, tx.id, tx.timestamp));
+
+        let result = if duplicate {
+            Err(DuplicateTx)
+        } else if !self.fits_new_tx(tx) {
+            Err(MemoryBudgetExceeded)
+        } else if !self.within_velocity(tx) {
+            Err(VelocityLimitExceeded)
+        } else if let Err(kind) = self.load_archived(tx) {
+            Err(kind)
+        } else if let Err(err) = self.validate(tx) {
+            Err(err.kind().clone())
+        } else {
+            self.process_tx_inner(tx)
+        };
+
+        if let (
+            Ok(_),
+            TxType::Withdrawal | TxType::WithdrawalPending,
+            Some(amount),
+            Some(velocity),
+        ) = (&result, tx.tx_type, tx.amount, self.velocity.as_mut())
+        {
+            velocity.record(tx.client_id, amount, tx.timestamp);
+        }
+
+        if let (Ok(_), Some(timestamp)) = (&result, tx.timestamp) {
+            if let Some(client) = self.clients.get_mut(tx.client_id) {
+                client.touch(timestamp);
+            }
+        }
+
+        if let (Ok(_), Some(before)) = (&result, before) {
+            let after = Balances::of(self.client(tx.client_id));
+            for kind in self.config.alerts.entered(before, after) {
+                self.alerts.push(Alert {
+                    kind,
+                    client_id: tx.client_id,
+                    tx_id: tx.id,
+                    available: after.available,
+                    held: after.held,
+                });
+            }
+        }
+
+        if let (Ok(_), Some(key)) = (&result, tx.idempotency_key.as_ref()) {
+            self.key_bytes += key.len();
+            self.keys.insert(key.clone());
+        }
+
+        if let (Ok(_), Some(retained)) = (&result, self.retained.as_mut()) {
+            let retain = match tx.tx_type {
+                TxType::Deposit
+                | TxType::Withdrawal
+                | TxType::WithdrawalPending
+                | TxType::Authorize => self.archive.is_some(),
+                TxType::Dispute | TxType::Capture | TxType::Settlement => false,
+                // nothing refers to a settled or cancelled withdrawal or a reversed transaction anymore
+                TxType::WithdrawalSettle | TxType::WithdrawalCancel | TxType::Reversal => {
+                    self.archive.is_none()
+                }
+                TxType::Resolve | TxType::Chargeback => {
+                    self.archive.is_none()
+                        && self
+                            .transactions
+                            .get(&tx.id)
+                            .is_some_and(|stored| self.config.is_settled(stored))
+                }
+            };
+
+            if retain {
+                retained.retain(tx.id, tx.timestamp);
+            }
+        }
+
+        if result.is_ok() {
+            self.counters.applied(tx.tx_type);
+        }
+
+        result.map_err(|kind| {
+            if let Some(client) = self.clients.get_mut(tx.client_id) {
+                client.reject(tx.tx_type);
+            }
+            self.counters.rejected(kind.code());
+
+            Error::from(kind).with_tx(tx.id, tx.client_id)
+        })
+    }
+
+    /// applies all the transactions or none of them, the first rejected transaction
+    /// rolls back the changes of the previous ones and is still counted in the activity of its
+    /// client; retained transactions are only dropped once the whole batch is applied
+    pub fn apply_batch(&mut self, txs: &[TxInput]) -> Result<(), BatchError> {
+        // each transaction changes at most its client and the stored transaction of its id
+        let mut clients = Vec::with_capacity(txs.len());
+        let mut transactions = Vec::with_capacity(txs.len());
+        let mut keys = Vec::new();
+        let dedup = self.dedup.clone();
+        let velocity = self.velocity.clone();
+        let retained = self.retained.clone();
+        let authorizations = self.authorizations.clone();
+        let batches = self.batches.clone();
+        let queued = self.value_dated.len();
+        let fees = self.fees.len();
+        let replays = self.replays;
+        let counters = self.counters.clone();
+
+        for (index, tx) in txs.iter().enumerate() {
+            clients.push((tx.client_id, self.clients.get(tx.client_id).cloned()));
+            transactions.push((tx.id, self.transactions.get(&tx.id).cloned()));
+            if let Some(key) = tx
+                .idempotency_key
+                .as_ref()
+                .filter(|key| !self.keys.contains(*key))
+            {
+                keys.push(key);
+            }
+
+            if let Err(err) = self.apply_tx(tx) {
+                for (id, client) in clients.into_iter().rev() {
+                    self.clients.restore(id, client);
+                }
+                // the balances are rolled back but the rejection stays in the client activity
+                if let Some(client) = self.clients.get_mut(tx.client_id) {
+                    client.reject(tx.tx_type);
+                }
+
+                for (id, stored) in transactions.into_iter().rev() {
+                    match stored {
+                        Some(stored) => self.transactions.insert(id, stored),
+                        None => self.transactions.remove(&id),
+                    };
+                }
+
+                for key in keys {
+                    if self.keys.remove(key) {
+                        self.key_bytes -= key.len();
+                    }
+                }
+
+                self.dedup = dedup;
+                self.velocity = velocity;
+                self.retained = retained;
+                self.authorizations = authorizations;
+                self.batches = batches;
+                self.value_dated.truncate(queued);
+                self.fees.truncate(fees);
+                self.replays = replays;
+                // only the rejection of the batch is counted, its other transactions weren't applied
+                self.counters = counters;
+                self.counters.rejected(err.code());
+                self.alerts.clear();
+                return Err(BatchError::new(index, err));
+            }
+        }
+
+        self.drop_retained(txs.last().and_then(|tx| tx.timestamp));
+        self.notify();
+        Ok(())
+    }
+
+    /// removes the transactions the retention policy no longer keeps, archiving them first when
+    /// the engine has an archive; a transaction the archive fails to store is kept in memory
+    fn drop_retained(&mut self, now: Option<DateTime<Utc>>) {
+        let Some(retained) = self.retained.as_mut() else {
+            return;
+        };
+
+        while let Some(id) = retained.pop_expired(now) {
+            // retained again after it was loaded back, or already dropped
+            let Some(tx) = self.transactions.get(&id) else {
+                continue;
+            };
+
+            if let Some(archive) = self.archive.as_ref() {
+                let stored = archive
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .store(id, &ArchivedTx::new(tx));
+                if let Err(err) = stored {
+                    self.archive_error.get_or_insert_with(|| err.to_string());
+                    continue;
+                }
+            }
+
+            if let Some(client) = self.clients.get_mut(tx.client_id) {
+                client.record_drop(tx.seq(), tx.timestamp_millis());
+            }
+            self.transactions.remove(&id);
+            retained.record_drop();
+        }
+    }
+
+    /// loads the transaction named by a dispute, resolve, chargeback, settle, cancel, capture or
+    /// reversal back from the archive when the retention policy dropped it
+    fn load_archived(&mut self, tx: &TxInput) -> Result<(), ErrorKind> {
+        let archive = match (tx.tx_type, self.archive.as_ref()) {
+            (
+                TxType::Dispute
+                | TxType::Resolve
+                | TxType::Chargeback
+                | TxType::WithdrawalSettle
+                | TxType::WithdrawalCancel
+                | TxType::Capture
+                | TxType::Reversal,
+                Some(archive),
+            ) if !self.transactions.contains_key(&tx.id) => archive,
+            _ => return Ok(()),
+        };
+
+        let loaded = archive
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .load(tx.id)
+            .map_err(|err| {
+                self.archive_error.get_or_insert_with(|| err.to_string());
+                ArchiveFailed
+            })?;
+
+        if let Some(archived) = loaded {
+            self.transactions.insert(tx.id, archived.tx());
+            if let Some(retained) = self.retained.as_mut() {
+                retained.retain(tx.id, tx.timestamp);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// applies a manual intervention, the balance and lock checks of the transactions don't apply
+    pub fn apply_admin(&mut self, command: &AdminCommand) -> Result<(), Error> {
+        // a bulk reversal names the transaction it fails on, not its cutoff
+        if command.action == AdminAction::ReverseAfter {
+            return self.reverse_after(command);
+        }
+
+        self.apply_admin_inner(command).map_err(|kind| {
+            let err = Error::from(kind);
+            match command.tx_id {
+                Some(tx_id) => err.with_tx(tx_id, command.client_id),
+                None => err,
+            }
+        })
+    }
+
+    fn apply_admin_inner(&mut self, command: &AdminCommand) -> Result<(), ErrorKind> {
+        let client = self
+            .clients
+            .get_mut(command.client_id)
+            .ok_or(ClientNotFound)?;
+
+        match command.action {
+            AdminAction::Unlock => client.unlock(),
+            AdminAction::Lock => client.lock(),
+            AdminAction::ForceResolve => {
+                let tx = command
+                    .tx_id
+                    .and_then(|id| self.transactions.get_mut(&id))
+                    .ok_or(TxNotFound)?;
+                if tx.client_id != command.client_id {
+                    return Err(ClientIdNoMatch);
+                }
+
+                let dispute_state = tx.dispute_state().resolve()?;
+                client.release(tx.money()?)?;
+                tx.set_dispute_state(dispute_state);
+                Ok(())
+            }
+            AdminAction::Adjust => client.adjust(&command.amount.ok_or(TxInvalidAmount)?),
+            AdminAction::Freeze => client.freeze(),
+            AdminAction::Unfreeze => client.unfreeze(),
+            AdminAction::ReverseAfter => unreachable!("bulk reversals return before the lookup"),
+        }
+    }
+
+    /// reverses the deposits and withdrawals of the client after the cutoff of the command, all
+    /// of them or none; the error names the transaction that can't be reversed, or the cutoff
+    /// when a transaction may have been dropped from memory
+    fn reverse_after(&mut self, command: &AdminCommand) -> Result<(), Error> {
+        let with_tx = |kind: ErrorKind, id: Option<TxId>| match id {
+            Some(id) => Error::from(kind).with_tx(id, command.client_id),
+            None => Error::from(kind),
+        };
+
+        let seq = match command.tx_id {
+            Some(id) => {
+                let tx = self
+                    .transactions
+                    .get(&id)
+                    .ok_or_else(|| with_tx(TxNotFound, Some(id)))?;
+                if tx.client_id != command.client_id {
+                    return Err(with_tx(ClientIdNoMatch, Some(id)));
+                }
+                // loaded back from the archive, its place in the store order is lost
+                Some(
+                    tx.seq()
+                        .ok_or_else(|| with_tx(ReversalIncomplete, Some(id)))?,
+                )
+            }
+            None => None,
+        };
+        let cutoff = Cutoff {
+            seq,
+            timestamp: command.after.map(|after| after.timestamp_millis()),
+        };
+        if cutoff.seq.is_none() && cutoff.timestamp.is_none() {
+            return Err(with_tx(TxNotFound, None));
+        }
+
+        let client = self
+            .clients
+            .get_mut(command.client_id)
+            .ok_or_else(|| with_tx(ClientNotFound, command.tx_id))?;
+        if cutoff.misses(client.dropped()) {
+            return Err(with_tx(ReversalIncomplete, command.tx_id));
+        }
+
+        // deposits and withdrawals are only stored once applied, a rejected one isn't walked
+        let mut txs = Vec::new();
+        for (id, tx) in &self.transactions {
+            if tx.client_id != command.client_id
+                || tx.reversed()
+                || !matches!(tx.tx_type(), TxType::Deposit | TxType::Withdrawal)
+            {
+                continue;
+            }
+
+            match cutoff.includes(tx.seq(), tx.timestamp_millis()) {
+                Some(true) => txs.push((tx.seq(), *id)),
+                Some(false) => {}
+                None => return Err(with_tx(ReversalIncomplete, Some(*id))),
+            }
+        }
+        // newest first, a transaction of unknown order was loaded back from the archive and
+        // goes last; all of them or none
+        txs.sort_unstable_by(|a, b| b.cmp(a));
+        let mut reversed = client.clone();
+        for (_, id) in &txs {
+            let tx = &self.transactions[id];
+            let reverse = match tx.dispute_state() {
+                DisputeState::Open => Err(TxAlreadyUnderDispute),
+                DisputeState::ChargedBack => Err(TxChargedBack),
+                DisputeState::None | DisputeState::Resolved => tx
+                    .money()
+                    .and_then(|money| reversed.force_reverse(tx.tx_type(), money)),
+            };
+            reverse.map_err(|kind| with_tx(kind, Some(*id)))?;
+        }
+
+        *client = reversed;
+        for (_, id) in txs {
+            if let Some(tx) = self.transactions.get_mut(&id) {
+                tx.set_reversed();
+            }
+        }
+        Ok(())
+    }
+
+    /// writes the transactions the archive buffers
+    pub fn flush_archive(&mut self) -> std::io::Result<()> {
+        match self.archive.as_ref() {
+            Some(archive) => archive
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// moves the as-of date and applies the queued transactions effective by it, ordered by
+    /// effective date and then input order; returns the errors of the rejected ones
+    pub fn advance_to(&mut self, as_of: NaiveDate) -> Vec<Error> {
+        self.config.as_of = Some(as_of);
+        self.value_dated
+            .release(as_of)
+            .iter()
+            .filter_map(|tx| self.process_tx(tx).err())
+            .collect()
+    }
+
+    /// fees charged by the fee schedule since the last call, in the order of their transactions
+    pub fn take_fees(&mut self) -> Vec<Fee> {
+        std::mem::take(&mut self.fees)
+    }
+
+    /// transactions dated after the as-of date, waiting to be applied
+    pub fn queued(&self) -> impl Iterator<Item = &TxInput> + '_ {
+        self.value_dated.iter()
+    }
+
+    /// transactions dropped from memory by the retention policy
+    pub fn retention_drops(&self) -> u64 {
+        self.retained.as_ref().map_or(0, Retained::dropped)
+    }
+
+    /// first failure of the archive to store or load a transaction
+    pub fn archive_error(&self) -> Option<&str> {
+        self.archive_error.as_deref()
+    }
+
+    fn validate(&self, tx: &TxInput) -> Result<(), Error> {
+        let view = EngineView(self);
+        self.rules
+            .iter()
+            .try_for_each(|rule| rule.validate(tx, &view))
+    }
+
+    /// transactions dropped by the dedup window
+    pub fn duplicates(&self) -> u64 {
+        self.dedup.as_ref().map_or(0, DedupWindow::duplicates)
+    }
+
+    /// transactions acknowledged without being applied as retries of an accepted idempotency key
+    pub fn replays(&self) -> u64 {
+        self.replays
+    }
+
+    /// withdrawals rejected by the velocity limits
+    pub fn velocity_rejections(&self) -> u64 {
+        self.velocity.as_ref().map_or(0, Velocity::rejected)
+    }
+
+    /// whether a withdrawal stays within the velocity limits of its client
+    fn within_velocity(&mut self, tx: &TxInput) -> bool {
+        match (tx.tx_type, tx.amount, self.velocity.as_mut()) {
+            (TxType::Withdrawal | TxType::WithdrawalPending, Some(amount), Some(velocity)) => {
+                velocity.check(tx.client_id, amount, tx.timestamp)
+            }
+            _ => true,
+        }
+    }
+
+    /// estimated heap bytes of the client table, transaction map, idempotency keys and dedup window
+    pub fn memory_usage(&self) -> usize {
+        self.transactions.capacity() * TX_ENTRY_BYTES
+            + self.keys.capacity() * (size_of::<String>() + 1)
+            + self.key_bytes
+            + self.clients.memory_usage()
+            + self.batches.memory_usage()
+            + self.retained.as_ref().map_or(0, Retained::memory_usage)
+            + self.dedup.as_ref().map_or(0, DedupWindow::memory_usage)
+    }
+
+    /// whether storing the transaction keeps the engine within its memory budget,
+    /// a full transaction map holds both the old and the doubled allocation while it grows
+    fn fits_new_tx(&self, tx: &TxInput) -> bool {
+        let max_memory = match self.config.max_memory {
+            Some(max_memory) => max_memory,
+            None => return true,
+        };
+
+        if !matches!(
+            tx.tx_type,
+            TxType::Deposit | TxType::Withdrawal | TxType::WithdrawalPending | TxType::Authorize
+        ) || self.transactions.contains_key(&tx.id)
+            || self.transactions.len() < self.transactions.capacity()
+        {
+            return self.memory_usage() <= max_memory;
+        }
+
+        let grown = (self.transactions.capacity() * 2).max(4) * TX_ENTRY_BYTES;
+        self.memory_usage() + grown <= max_memory
+    }
+
+    fn process_tx_inner(&mut self, tx_input: &TxInput) -> Result<(), ErrorKind> {
+        // a settlement checks a partner batch, it doesn't open an account
+        if tx_input.tx_type == TxType::Settlement {
+            return self.settle(tx_input);
+        }
+
+        let client = self.clients.get_or_insert(tx_input.client_id);
+
+        match tx_input.tx_type {
+            TxType::Deposit => {
+                if let Some(tx) = self.transactions.get(&tx_input.id) {
+                    return self.duplicate(tx, tx_input);
+                }
+
+                let amount = match &tx_input.amount {
+                    Some(val) => val,
+                    None => return Err(TxInvalidAmount),
+                };
+
+                client.deposit(Money::new(*amount)?)?;
+                // only stored once applied, a rejected deposit can't be disputed or reversed
+                self.transactions.insert(
+                    tx_input.id,
+                    Tx::new(tx_input).stored_as(next_seq(&mut self.seq)),
+                );
+                if self.config.fees.deposit_bps.is_some() {
+                    let before = client.count_monthly_deposit(*amount, tx_input.timestamp);
+                    // the deposit is applied either way, a fee the client can't pay is skipped
+                    if let Some(fee) = self.config.fees.deposit(before, *amount) {
+                        if client.charge_fee(fee).is_ok() {
+                            self.fees.push(Fee::new(tx_input, FeeKind::Deposit, fee));
+                        }
+                    }
+                }
+                self.record_batch(tx_input, *amount);
+                Ok(())
+            }
+            TxType::Withdrawal => {
+                if let Some(tx) = self.transactions.get(&tx_input.id) {
+                    return self.duplicate(tx, tx_input);
+                }
+
+                let amount = match &tx_input.amount {
+                    Some(val) => val,
+                    None => return Err(TxInvalidAmount),
+                };
+
+                if self.config.block_withdrawals_on_dispute && client.has_open_disputes() {
+                    return Err(AccountUnderDispute);
+                }
+                let fee = self.config.fees.withdrawal(*amount, client.withdrawals());
+                client.withdraw(
+                    Money::new(amount.saturating_add(fee.unwrap_or_default()))?,
+                    self.config.withdrawal_policy,
+                    self.config.min_balance.of(tx_input.client_id),
+                )?;
+                // only stored once applied, a rejected withdrawal can't be reversed
+                self.transactions.insert(
+                    tx_input.id,
+                    Tx::new(tx_input).stored_as(next_seq(&mut self.seq)),
+                );
+                if let Some(fee) = fee {
+                    self.fees.push(Fee::new(tx_input, FeeKind::Withdrawal, fee));
+                }
+                self.record_batch(tx_input, -amount);
+                Ok(())
+            }
+            TxType::Dispute => {
+                let tx = self.transactions.get_mut(&tx_input.id);
+                match tx {
+                    None => Err(TxNotFound),
+                    Some(tx) => {
+                        if tx.client_id != tx_input.client_id {
+                            return Err(ClientIdNoMatch);
+                        }
+
+                        if tx.tx_type() != TxType::Deposit {
+                            return Err(TxNotADeposit);
+                        }
+
+                        if tx.reversed() {
+                            return Err(TxReversed);
+                        }
+
+                        let dispute_state = tx.dispute_state().dispute()?;
+
+                        if (!self.config.allow_redispute
+                            && tx.dispute_state() == DisputeState::Resolved)
+                            || self
+                                .config
+                                .max_disputes
+                                .is_some_and(|max| tx.disputes() >= max)
+                        {
+                            return Err(DisputeLimitReached);
+                        }
+
+                        // the state only changes once the funds are held
+                        client.dispute(tx.money()?)?;
+                        tx.set_dispute_state(dispute_state);
+                        tx.add_dispute();
+                        Ok(())
+                    }
+                }
+            }
+            TxType::Resolve => {
+                let tx = self.transactions.get_mut(&tx_input.id);
+                match tx {
+                    None => Err(TxNotFound),
+                    Some(tx) => {
+                        if tx.client_id != tx_input.client_id {
+                            return Err(ClientIdNoMatch);
+                        }
+
+                        let dispute_state = tx.dispute_state().resolve()?;
+                        client.resolve(tx.money()?)?;
+                        tx.set_dispute_state(dispute_state);
+                        Ok(())
+                    }
+                }
+            }
+            TxType::Chargeback => {
+                let tx = self.transactions.get_mut(&tx_input.id);
+                match tx {
+                    None => Err(TxNotFound),
+                    Some(tx) => {
+                        if tx.client_id != tx_input.client_id {
+                            return Err(ClientIdNoMatch);
+                        }
+
+                        let dispute_state = tx.dispute_state().chargeback()?;
+                        client.chargeback(tx.money()?)?;
+                        tx.set_dispute_state(dispute_state);
+                        client.lock_by(LockedBy {
+                            tx: tx_input.id,
+                            amount: tx.amount,
+                            timestamp: tx_input.timestamp,
+                        });
+                        // the chargeback is applied either way, a fee the client can't pay is skipped
+                        if let Some(fee) = self.config.fees.chargeback() {
+                            if client.charge_fee(fee).is_ok() {
+                                self.fees.push(Fee::new(tx_input, FeeKind::Chargeback, fee));
+                            }
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            TxType::WithdrawalPending => {
+                if let Some(tx) = self.transactions.get(&tx_input.id) {
+                    return self.duplicate(tx, tx_input);
+                }
+
+                let amount = match &tx_input.amount {
+                    Some(val) => val,
+                    None => return Err(TxInvalidAmount),
+                };
+
+                if self.config.block_withdrawals_on_dispute && client.has_open_disputes() {
+                    return Err(AccountUnderDispute);
+                }
+                client.hold_withdrawal(
+                    Money::new(*amount)?,
+                    self.config.min_balance.of(tx_input.client_id),
+                )?;
+                // only stored once held, a rejected one can't be settled or cancelled
+                self.transactions.insert(
+                    tx_input.id,
+                    Tx::new(tx_input).stored_as(next_seq(&mut self.seq)),
+                );
+                self.record_batch(tx_input, -amount);
+                Ok(())
+            }
+            TxType::Authorize => {
+                if let Some(tx) = self.transactions.get(&tx_input.id) {
+                    return self.duplicate(tx, tx_input);
+                }
+
+                let amount = match &tx_input.amount {
+                    Some(val) => val,
+                    None => return Err(TxInvalidAmount),
+                };
+
+                // the authorized amount is only checked, the open authorization is the stored
+                // tx and the client balances don't change until it's captured
+                Money::new(*amount)?;
+                client.authorize()?;
+                self.transactions.insert(
+                    tx_input.id,
+                    Tx::new(tx_input).stored_as(next_seq(&mut self.seq)),
+                );
+                if let Some(authorizations) = self.authorizations.as_mut() {
+                    authorizations.open(tx_input.id, tx_input.timestamp);
+                }
+                Ok(())
+            }
+            TxType::Capture => {
+                let tx = self.transactions.get_mut(&tx_input.id);
+                match tx {
+                    None => Err(TxNotFound),
+                    Some(tx) => {
+                        if tx.client_id != tx_input.client_id {
+                            return Err(ClientIdNoMatch);
+                        }
+
+                        if tx.tx_type() != TxType::Authorize {
+                            return Err(TxNotAuthorized);
+                        }
+
+                        if self
+                            .authorizations
+                            .as_ref()
+                            .is_some_and(|auths| auths.expired(tx_input.id, tx_input.timestamp))
+                        {
+                            return Err(AuthorizationExpired);
+                        }
+
+                        // a partial capture credits less than authorized, never more
+                        let amount = match tx_input.amount {
+                            None => tx.amount,
+                            Some(amount) if amount <= tx.amount => amount,
+                            Some(_) => return Err(TxInvalidAmount),
+                        };
+                        client.deposit(Money::new(amount)?)?;
+
+                        // the captured authorization is a deposit from now on, and can be disputed
+                        tx.amount = amount;
+                        tx.set_tx_type(TxType::Deposit);
+                        if let Some(authorizations) = self.authorizations.as_mut() {
+                            authorizations.close(tx_input.id);
+                        }
+                        self.record_batch(tx_input, amount);
+                        Ok(())
+                    }
+                }
+            }
+            TxType::WithdrawalSettle | TxType::WithdrawalCancel => {
+                let tx = self.transactions.get_mut(&tx_input.id);
+                match tx {
+                    None => Err(TxNotFound),
+                    Some(tx) => {
+                        if tx.client_id != tx_input.client_id {
+                            return Err(ClientIdNoMatch);
+                        }
+
+                        if tx.tx_type() != TxType::WithdrawalPending {
+                            return Err(TxNotPending);
+                        }
+
+                        match tx_input.tx_type {
+                            TxType::WithdrawalSettle => client.settle_withdrawal(tx.money()?)?,
+                            _ => client.cancel_withdrawal(tx.money()?)?,
+                        }
+                        tx.set_tx_type(tx_input.tx_type);
+                        Ok(())
+                    }
+                }
+            }
+            TxType::Reversal => {
+                let tx = self.transactions.get_mut(&tx_input.id);
+                match tx {
+                    None => Err(TxNotFound),
+                    Some(tx) => {
+                        if tx.client_id != tx_input.client_id {
+                            return Err(ClientIdNoMatch);
+                        }
+
+                        if tx.reversed() {
+                            return Err(TxReversed);
+                        }
+
+                        match tx.dispute_state() {
+                            DisputeState::Open => return Err(TxAlreadyUnderDispute),
+                            DisputeState::ChargedBack => return Err(TxChargedBack),
+                            DisputeState::None | DisputeState::Resolved => {}
+                        }
+
+                        client.reverse(tx.tx_type(), tx.money()?)?;
+                        tx.set_reversed();
+
+                        // the undone amount counts against the batch of the reversal, if any
+                        let net = match tx.tx_type() {
+                            TxType::Deposit => -tx.amount,
+                            _ => tx.amount,
+                        };
+                        self.record_batch(tx_input, net);
+                        Ok(())
+                    }
+                }
+            }
+            TxType::Settlement => unreachable!("settlements return before the client lookup"),
+        }
+    }
+
+    /// closes the batch of a settlement whose amount matches the net of the batch
+    fn settle(&mut self, tx_input: &TxInput) -> Result<(), ErrorKind> {
+        let batch = tx_input.batch.as_deref().ok_or(BatchNotFound)?;
+        let declared = tx_input.amount.ok_or(TxInvalidAmount)?;
+        self.batches.settle(batch, declared)
+    }
+
+    /// adds the signed amount of an accepted transaction to the net of its batch, if any
+    fn record_batch(&mut self, tx_input: &TxInput, amount: Decimal) {
+        if let Some(batch) = tx_input.batch.as_deref() {
+            self.batches.record(batch, amount);
+        }
+    }
+
+    /// net of the accepted transactions of a batch not settled yet
+    pub fn batch_net(&self, batch: &str) -> Option<Decimal> {
+        self.batches.net(batch)
+    }
+
+    /// batches with accepted transactions and no matching settlement yet, in no particular order
+    pub fn open_batches(&self) -> impl Iterator<Item = (&str, Decimal)> + '_ {
+        self.batches.iter()
+    }
+
+    /// applies the duplicate policy to a transaction reusing the id of a stored one
+    fn duplicate(&self, tx: &Tx, tx_input: &TxInput) -> Result<(), ErrorKind> {
+        match self.config.duplicate_policy {
+            DuplicatePolicy::IgnoreIfIdentical
+                if tx.client_id == tx_input.client_id
+                    && tx.tx_type() == tx_input.tx_type
+                    && Some(tx.amount) == tx_input.amount =>
+            {
+                Ok(())
+            }
+            _ => Err(TxIdConflict),
+        }
+    }
+
+    /// client with the id, None until its first transaction
+    pub fn client(&self, id: ClientId) -> Option<ClientView<'_>> {
+        self.clients.get(id).map(ClientView)
+    }
+
+    /// stored deposit or withdrawal with the id
+    pub fn transaction(&self, id: TxId) -> Option<TxView<'_>> {
+        self.transactions.get(&id).map(TxView)
+    }
+
+    /// counters of the applied and rejected transactions with the client, stored transaction and
+    /// memory gauges
+    pub fn metrics(&self) -> Metrics {
+        self.counters.metrics(
+            self.clients.len(),
+            self.transactions.len(),
+            self.memory_usage(),
+        )
+    }
+
+    /// stored deposits and withdrawals in no particular order
+    pub fn transactions(&self) -> impl Iterator<Item = (TxId, TxView<'_>)> + '_ {
+        self.transactions.iter().map(|(id, tx)| (*id, TxView(tx)))
+    }
+
+    /// number of stored deposits and withdrawals
+    pub fn transaction_count(&self) -> usize {
+        self.transactions.len()
+    }
+
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn client_report(&self, id: ClientId) -> Option<ClientReport> {
+        self.clients.get(id).map(ClientReport::new)
+    }
+
+    #[cfg(any(feature = "cli", feature = "wasm"))]
+    #[allow(clippy::needless_return)]
+    pub(crate) fn report(&self) -> impl Iterator<Item = ClientReport> + '_ {
+        return self.clients.iter().map(ClientReport::new);
+    }
+
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn state(&self) -> EngineState {
+        EngineState {
+            clients: self.clients.iter().cloned().collect(),
+            transactions: self
+                .transactions
+                .iter()
+                .map(|(id, tx)| (*id, tx.clone()))
+                .collect(),
+            velocity: self.velocity.as_ref().map(Velocity::state),
+            retained: self.retained.as_ref().map(Retained::state),
+            authorizations: self.authorizations.as_ref().map(Authorizations::state),
+            batches: self.batches.state(),
+            queued: self.value_dated.state(),
+            idempotency_keys: self.keys.iter().cloned().collect(),
+            replays: self.replays,
+            seq: self.seq,
+            dedup: self.dedup.as_ref().map(DedupWindow::state),
+        }
+    }
+
+    /// continues from a saved state, the configuration and rules are kept;
+    /// velocity and dedup windows are only restored when the engine has them
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn restore(&mut self, state: EngineState) {
+        self.clients = ClientTable::with_capacity(state.clients.len());
+        for client in state.clients {
+            self.clients.insert(client);
+        }
+
+        self.transactions.clear();
+        self.transactions.extend(state.transactions);
+
+        self.key_bytes = state.idempotency_keys.iter().map(String::len).sum();
+        self.keys = state.idempotency_keys.into_iter().collect();
+        self.replays = state.replays;
+        self.seq = state.seq;
+        self.batches.restore(state.batches);
+        self.value_dated.restore(state.queued);
+
+        if let (Some(dedup), Some(saved)) = (self.dedup.as_mut(), state.dedup) {
+            dedup.restore(saved);
+        }
+
+        if let (Some(velocity), Some(saved)) = (self.velocity.as_mut(), state.velocity) {
+            velocity.restore(saved);
+        }
+
+        if let (Some(retained), Some(saved)) = (self.retained.as_mut(), state.retained) {
+            retained.restore(saved);
+        }
+
+        if let (Some(authorizations), Some(saved)) =
+            (self.authorizations.as_mut(), state.authorizations)
+        {
+            authorizations.restore(saved);
+        }
+    }
+}
+
+/// store order of the next stored transaction, unknown once the order is exhausted
+fn next_seq(seq: &mut u32) -> u32 {
+    match seq.checked_add(1) {
+        Some(next) => {
+            *seq = next;
+            next
+        }
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::engine::alerts::{AlertKind, AlertRules};
+    use crate::engine::archive::MemoryArchive;
+    use crate::engine::config::WithdrawalPolicy;
+    use crate::engine::fees::FeeSchedule;
+    use crate::engine::min_balance::MinBalances;
+    use crate::engine::retention::Retention;
+    use crate::engine::velocity::VelocityLimits;
+    use crate::models::client::{Client, LockReason};
+    use crate::models::errors::ErrorKind::{
+        BelowMinimumBalance, SettlementMismatch, TxAlreadyUnderDispute, TxChargedBack,
+        TxNotReversible, TxNotUnderDispute,
+    };
+
+    #[test]
+    fn with_config_capacity() {
+        let e = Engine::with_config(EngineConfig {
+            expected_txs: Some(1000),
+            expected_clients: Some(10),
+            ..Default::default()
+        });
+
+        assert!(e.transactions.capacity() >= 1000);
+    }
+
+    #[test]
+    fn with_config_capacity_within_budget() {
+        let e = Engine::with_config(EngineConfig {
+            expected_txs: Some(1_000_000),
+            max_memory: Some(64 * 1024),
+            ..Default::default()
+        });
+
+        assert!(e.memory_usage() <= 64 * 1024);
+    }
+
+    #[test]
+    fn process_tx_memory_budget_exceeded() {
+        let mut e = Engine::with_config(EngineConfig {
+            max_memory: Some(64 * 1024),
+            ..Default::default()
+        });
+        let deposit = |id| TxInput {
+            tx_type: TxType::Deposit,
+            client_id: 1,
+            id,
+            amount: Some(dec!(1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        };
+
+        let mut id = 1;
+        while e.process_tx(&deposit(id)).is_ok() {
+            id += 1;
+        }
+
+        let err = e.process_tx(&deposit(id)).unwrap_err();
+        assert_eq!(err.kind(), &MemoryBudgetExceeded);
+        assert!(id > 1);
+        assert!(e.memory_usage() <= 64 * 1024);
+        assert_eq!(e.transaction_count(), id as usize - 1);
+
+        // disputes of stored transactions don't grow the maps
+        e.process_tx(&TxInput {
+            tx_type: TxType::Dispute,
+            client_id: 1,
+            id: 1,
+            amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        })
+        .expect("failed to dispute");
+    }
+
+    #[test]
+    fn process_tx_velocity_limits() {
+        let mut e = Engine::with_config(EngineConfig {
+            velocity: VelocityLimits {
+                max_withdrawals: Some(1),
+                max_daily_amount: Some(dec!(20)),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let withdrawal = |id, amount| TxInput {
+            tx_type: TxType::Withdrawal,
+            client_id: 1,
+            id,
+            amount: Some(amount),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        };
+
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        let err = e.process_tx(&withdrawal(2, dec!(21))).unwrap_err();
+        assert_eq!(err.kind(), &VelocityLimitExceeded);
+        // a withdrawal rejected for insufficient funds doesn't count towards the limits
+        e.process_tx(&withdrawal(3, dec!(11))).unwrap_err();
+        e.process_tx(&withdrawal(4, dec!(5)))
+            .expect("failed to withdraw");
+        let err = e.process_tx(&withdrawal(5, dec!(1))).unwrap_err();
+        assert_eq!(err.code(), "velocity_limit_exceeded");
+
+        assert_eq!(e.velocity_rejections(), 2);
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(5)));
+    }
+
+    #[test]
+    fn process_tx_retention() {
+        let mut e = Engine::with_config(EngineConfig {
+            allow_redispute: false,
+            retention: Retention {
+                after_txs: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let resolve = |id| TxInput {
+            tx_type: TxType::Resolve,
+            ..dispute(1, id)
+        };
+
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&dispute(1, 1)).expect("failed to dispute");
+        e.process_tx(&resolve(1)).expect("failed to resolve");
+        e.process_tx(&deposit(1, 2, dec!(5)))
+            .expect("failed to deposit");
+        assert!(e.transaction(1).is_some());
+
+        // dropped two transactions after it settled, the undisputed deposit is kept
+        e.process_tx(&deposit(1, 3, dec!(5)))
+            .expect("failed to deposit");
+        assert!(e.transaction(1).is_none());
+        assert_eq!(e.transaction_count(), 2);
+        assert_eq!(e.retention_drops(), 1);
+        assert_eq!(
+            e.process_tx(&dispute(1, 1)).unwrap_err().kind(),
+            &TxNotFound
+        );
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(20)));
+    }
+
+    #[test]
+    fn process_tx_archive() {
+        let mut e = Engine::with_config(EngineConfig {
+            retention: Retention {
+                after_txs: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        e.set_archive(MemoryArchive::default());
+
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(1, 2, dec!(5)))
+            .expect("failed to deposit");
+        assert!(e.transaction(1).is_none());
+        assert_eq!(e.retention_drops(), 1);
+
+        // the dropped deposit is loaded back from the archive
+        e.process_tx(&dispute(1, 1)).expect("failed to dispute");
+        assert_eq!(e.client(1).map(|c| c.held()), Some(dec!(10)));
+        assert_eq!(e.transaction_count(), 1);
+        assert_eq!(
+            e.process_tx(&dispute(1, 3)).unwrap_err().kind(),
+            &TxNotFound
+        );
+
+        // archived again while disputed
+        assert!(e.transaction(1).is_none());
+        e.process_tx(&TxInput {
+            tx_type: TxType::Chargeback,
+            ..dispute(1, 1)
+        })
+        .expect("failed to chargeback");
+        assert_eq!(e.client(1).map(|c| c.locked()), Some(true));
+        assert_eq!(e.archive_error(), None);
+    }
+
+    #[test]
+    fn process_tx_archive_failure() {
+        struct Unavailable;
+
+        impl TxArchive for Unavailable {
+            fn store(&mut self, _: TxId, _: &ArchivedTx) -> std::io::Result<()> {
+                Err(std::io::Error::other("disk full"))
+            }
+
+            fn load(&mut self, _: TxId) -> std::io::Result<Option<ArchivedTx>> {
+                Err(std::io::Error::other("disk full"))
+            }
+        }
+
+        let mut e = Engine::with_config(EngineConfig {
+            retention: Retention {
+                after_txs: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        e.set_archive(Unavailable);
+
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(1, 2, dec!(5)))
+            .expect("failed to deposit");
+        assert!(e.transaction(1).is_some());
+        assert_eq!(e.retention_drops(), 0);
+        assert_eq!(e.archive_error(), Some("disk full"));
+        assert_eq!(
+            e.process_tx(&dispute(1, 3)).unwrap_err().kind(),
+            &ArchiveFailed
+        );
+    }
+
+    #[test]
+    fn process_tx_alerts() {
+        #[derive(Default, Clone)]
+        struct Collected(Arc<Mutex<Vec<(AlertKind, TxId)>>>);
+
+        impl AlertObserver for Collected {
+            fn notify(&self, alert: &Alert) {
+                self.0.lock().unwrap().push((alert.kind, alert.tx_id));
+            }
+        }
+
+        let mut e = Engine::with_config(EngineConfig {
+            alerts: AlertRules {
+                held_above: Some(dec!(5)),
+                negative_available: true,
+                locked: true,
+            },
+            ..Default::default()
+        });
+        let collected = Collected::default();
+        e.add_observer(collected.clone());
+
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&TxInput {
+            tx_type: TxType::Withdrawal,
+            ..deposit(1, 2, dec!(8))
+        })
+        .expect("failed to withdraw");
+        e.process_tx(&dispute(1, 1)).expect("failed to dispute");
+
+        // alerts of a rolled back batch aren't sent
+        e.apply_batch(&[
+            TxInput {
+                tx_type: TxType::Chargeback,
+                ..dispute(1, 1)
+            },
+            dispute(1, 3),
+        ])
+        .expect_err("batch should fail");
+        e.process_tx(&TxInput {
+            tx_type: TxType::Chargeback,
+            ..dispute(1, 1)
+        })
+        .expect("failed to chargeback");
+
+        assert_eq!(
+            *collected.0.lock().unwrap(),
+            vec![
+                (AlertKind::HeldAbove, 1),
+                (AlertKind::NegativeAvailable, 1),
+                (AlertKind::Locked, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn process_tx_withdrawal_phases() {
+        let mut e = Engine::new();
+        let phase = |tx_type, id, amount| TxInput {
+            tx_type,
+            amount,
+            ..dispute(1, id)
+        };
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&phase(TxType::WithdrawalPending, 2, Some(dec!(4))))
+            .expect("failed to hold withdrawal");
+        e.process_tx(&phase(TxType::WithdrawalPending, 3, Some(dec!(5))))
+            .expect("failed to hold withdrawal");
+
+        // held funds can't be withdrawn again
+        let err = e
+            .process_tx(&phase(TxType::WithdrawalPending, 4, Some(dec!(2))))
+            .expect_err("withdrawal over the available funds should fail");
+        assert_eq!(err.kind(), &ErrorKind::InsufficientFunds);
+        // the rejected withdrawal held nothing, it can't be settled or cancelled
+        for tx_type in [TxType::WithdrawalSettle, TxType::WithdrawalCancel] {
+            let err = e
+                .process_tx(&phase(tx_type, 4, None))
+                .expect_err("rejected withdrawal should not be pending");
+            assert_eq!(err.kind(), &TxNotFound);
+        }
+        let client = e.client(1).unwrap();
+        assert_eq!((client.available(), client.held()), (dec!(1), dec!(9)));
+
+        e.process_tx(&phase(TxType::WithdrawalSettle, 2, None))
+            .expect("failed to settle");
+        e.process_tx(&phase(TxType::WithdrawalCancel, 3, None))
+            .expect("failed to cancel");
+        let client = e.client(1).unwrap();
+        assert_eq!((client.available(), client.held()), (dec!(6), dec!(0)));
+        assert_eq!(
+            e.transaction(2).map(|tx| tx.tx_type()),
+            Some(TxType::WithdrawalSettle)
+        );
+
+        // a settled, cancelled or plain transaction isn't pending
+        for (tx_type, id) in [
+            (TxType::WithdrawalCancel, 2),
+            (TxType::WithdrawalSettle, 3),
+            (TxType::WithdrawalSettle, 1),
+        ] {
+            let err = e
+                .process_tx(&phase(tx_type, id, None))
+                .expect_err("transaction should not be pending");
+            assert_eq!(err.kind(), &TxNotPending);
+        }
+        let err = e
+            .process_tx(&TxInput {
+                client_id: 2,
+                ..phase(TxType::WithdrawalSettle, 2, None)
+            })
+            .expect_err("settle of another client should fail");
+        assert_eq!(err.kind(), &ClientIdNoMatch);
+    }
+
+    #[test]
+    fn process_tx_withdrawal_policies() {
+        let phase = |tx_type, id, amount| TxInput {
+            tx_type,
+            amount,
+            ..dispute(1, id)
+        };
+        let mut e = Engine::with_config(EngineConfig {
+            withdrawal_policy: WithdrawalPolicy::Total,
+            ..Default::default()
+        });
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(1, 2, dec!(4)))
+            .expect("failed to deposit");
+        e.process_tx(&dispute(1, 2)).expect("failed to dispute");
+
+        // held funds are taken last
+        e.process_tx(&phase(TxType::Withdrawal, 3, Some(dec!(12))))
+            .expect("failed to withdraw");
+        let client = e.client(1).unwrap();
+        assert_eq!((client.available(), client.held()), (dec!(-2), dec!(4)));
+        let err = e
+            .process_tx(&phase(TxType::Withdrawal, 4, Some(dec!(3))))
+            .expect_err("withdrawal over the total funds should fail");
+        assert_eq!(err.kind(), &ErrorKind::InsufficientFunds);
+
+        // the hold stays whole for the resolve or chargeback of the dispute
+        for (follow_up, available, locked) in [
+            (TxType::Resolve, dec!(5), false),
+            (TxType::Chargeback, dec!(-5), true),
+        ] {
+            let mut e = Engine::with_config(EngineConfig {
+                withdrawal_policy: WithdrawalPolicy::Total,
+                ..Default::default()
+            });
+            e.process_tx(&deposit(1, 1, dec!(10)))
+                .expect("failed to deposit");
+            e.process_tx(&dispute(1, 1)).expect("failed to dispute");
+            e.process_tx(&phase(TxType::Withdrawal, 2, Some(dec!(5))))
+                .expect("failed to withdraw");
+            e.process_tx(&phase(follow_up, 1, None))
+                .expect("failed to follow up");
+            let client = e.client(1).unwrap();
+            assert_eq!((client.available(), client.held()), (available, dec!(0)));
+            assert_eq!(client.total(), available);
+            assert_eq!(client.locked(), locked);
+        }
+
+        let mut e = Engine::with_config(EngineConfig {
+            block_withdrawals_on_dispute: true,
+            ..Default::default()
+        });
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(1, 2, dec!(4)))
+            .expect("failed to deposit");
+        e.process_tx(&dispute(1, 2)).expect("failed to dispute");
+
+        // no withdrawal while a dispute is open
+        for (tx_type, id) in [(TxType::Withdrawal, 3), (TxType::WithdrawalPending, 4)] {
+            let err = e
+                .process_tx(&phase(tx_type, id, Some(dec!(1))))
+                .expect_err("withdrawal under dispute should fail");
+            assert_eq!(err.kind(), &AccountUnderDispute);
+        }
+        e.process_tx(&phase(TxType::Resolve, 2, None))
+            .expect("failed to resolve");
+        e.process_tx(&phase(TxType::Withdrawal, 5, Some(dec!(1))))
+            .expect("failed to withdraw");
+    }
 
-use tracing::debug;
+    #[test]
+    fn process_tx_min_balance() {
+        let mut e = Engine::with_config(EngineConfig {
+            min_balance: MinBalances {
+                global: Some(dec!(5)),
+                clients: [(2, dec!(0))].into_iter().collect(),
+            },
+            ..Default::default()
+        });
+        let withdrawal = |client_id, id, amount| TxInput {
+            tx_type: TxType::Withdrawal,
+            ..deposit(client_id, id, amount)
+        };
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(2, 2, dec!(10)))
+            .expect("failed to deposit");
+
+        let err = e
+            .process_tx(&withdrawal(1, 3, dec!(6)))
+            .expect_err("withdrawal below the floor should fail");
+        assert_eq!(err.kind(), &BelowMinimumBalance);
+        e.process_tx(&withdrawal(1, 4, dec!(5)))
+            .expect("failed to withdraw");
+        e.process_tx(&withdrawal(2, 5, dec!(10)))
+            .expect("failed to withdraw");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(5)));
+        assert_eq!(e.client(2).map(|c| c.available()), Some(dec!(0)));
+    }
 
-use crate::models::client::{Client, ClientReport};
-use crate::models::errors::Error;
-use crate::models::errors::Error::{
-    ClientIdNoMatch, TxIdConflict, TxInvalidAmount, TxNotADeposit, TxNotFound, TxNotUnderDispute,
-};
-use crate::models::tx::{ClientId, Tx, TxId, TxInput};
-use crate::models::tx_type::TxType;
+    #[test]
+    fn process_tx_value_dated() {
+        let day = |day| NaiveDate::from_ymd_opt(2024, 3, day);
+        let mut e = Engine::with_config(EngineConfig {
+            as_of: day(1),
+            ..Default::default()
+        });
+        let dated = |id, amount, date| TxInput {
+            effective_date: date,
+            ..deposit(1, id, amount)
+        };
+        e.process_tx(&dated(1, dec!(5), day(1)))
+            .expect("failed to deposit");
+        e.process_tx(&dated(2, dec!(2), day(3)))
+            .expect("failed to queue");
+        e.process_tx(&dated(3, dec!(1), day(2)))
+            .expect("failed to queue");
+        e.process_tx(&TxInput {
+            tx_type: TxType::Withdrawal,
+            ..dated(4, dec!(9), day(2))
+        })
+        .expect("failed to queue");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(5)));
+        assert_eq!(e.queued().count(), 3);
+
+        // released by effective date, the withdrawal is only checked once it's applied
+        let errors = e.advance_to(day(2).unwrap());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind(), &ErrorKind::InsufficientFunds);
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(6)));
+        assert_eq!(e.queued().map(|tx| tx.id).collect::<Vec<_>>(), vec![2]);
+
+        let mut restored = Engine::with_config(e.config().clone());
+        restored.restore(e.state());
+        assert!(restored.advance_to(day(3).unwrap()).is_empty());
+        assert_eq!(restored.client(1).map(|c| c.available()), Some(dec!(8)));
+        assert_eq!(restored.queued().count(), 0);
+    }
 
-pub struct Engine {
-    clients: HashMap<ClientId, Client>,
-    transactions: HashMap<TxId, Tx>,
-}
+    #[test]
+    fn process_tx_fees() {
+        let mut e = Engine::with_config(EngineConfig {
+            fees: FeeSchedule {
+                withdrawal_percent: Some(dec!(2)),
+                chargeback_flat: Some(dec!(5)),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let phase = |tx_type, id, amount| TxInput {
+            tx_type,
+            amount,
+            ..dispute(1, id)
+        };
+        e.process_tx(&deposit(1, 1, dec!(100)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(1, 2, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&phase(TxType::Withdrawal, 3, Some(dec!(50))))
+            .expect("failed to withdraw");
+
+        // the fee has to fit in the available funds with the withdrawal
+        let err = e
+            .process_tx(&phase(TxType::Withdrawal, 4, Some(dec!(59))))
+            .expect_err("withdrawal without the funds of its fee should fail");
+        assert_eq!(err.kind(), &ErrorKind::InsufficientFunds);
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(59)));
+
+        e.process_tx(&dispute(1, 2)).expect("failed to dispute");
+        e.process_tx(&phase(TxType::Chargeback, 2, None))
+            .expect("failed to chargeback");
+        let client = e.client(1).unwrap();
+        assert_eq!((client.available(), client.held()), (dec!(44), dec!(0)));
+
+        // without the available funds for its fee the chargeback is applied without it
+        e.process_tx(&deposit(2, 5, dec!(4)))
+            .expect("failed to deposit");
+        e.process_tx(&dispute(2, 5)).expect("failed to dispute");
+        e.process_tx(&TxInput {
+            tx_type: TxType::Chargeback,
+            ..dispute(2, 5)
+        })
+        .expect("failed to chargeback");
+        let client = e.client(2).unwrap();
+        assert_eq!(
+            (client.available(), client.held(), client.locked()),
+            (dec!(0), dec!(0), true)
+        );
+
+        assert_eq!(
+            e.take_fees(),
+            vec![
+                Fee {
+                    client_id: 1,
+                    tx_id: 3,
+                    kind: FeeKind::Withdrawal,
+                    amount: dec!(1),
+                },
+                Fee {
+                    client_id: 1,
+                    tx_id: 2,
+                    kind: FeeKind::Chargeback,
+                    amount: dec!(5),
+                },
+            ]
+        );
+        assert!(e.take_fees().is_empty());
+    }
 
-impl Engine {
-    pub(crate) fn new() -> Self {
-        Self {
-            clients: HashMap::new(),
-            transactions: HashMap::new(),
-        }
+    #[test]
+    fn process_tx_fee_tiers() {
+        let mut e = Engine::with_config(EngineConfig {
+            fees: FeeSchedule {
+                free_withdrawals: Some(1),
+                withdrawal_flat: Some(dec!(1)),
+                deposit_bps: Some(dec!(100)),
+                deposit_threshold: dec!(100),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let at = |tx: TxInput, date: &str| TxInput {
+            timestamp: Some(format!("{}T12:00:00Z", date).parse().unwrap()),
+            ..tx
+        };
+        let withdrawal = |id| TxInput {
+            tx_type: TxType::Withdrawal,
+            amount: Some(dec!(10)),
+            ..dispute(1, id)
+        };
+        e.process_tx(&at(deposit(1, 1, dec!(80)), "2024-03-01"))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(1, 2, dec!(50)))
+            .expect("failed to deposit");
+        e.process_tx(&withdrawal(3)).expect("failed to withdraw");
+        e.process_tx(&withdrawal(4)).expect("failed to withdraw");
+
+        // a failed batch rolls the counters back with the balances
+        e.apply_batch(&[withdrawal(5), deposit(1, 4, dec!(1))])
+            .expect_err("batch reusing a tx id should fail");
+        e.process_tx(&at(deposit(1, 6, dec!(200)), "2024-04-01"))
+            .expect("failed to deposit");
+        e.process_tx(&withdrawal(7)).expect("failed to withdraw");
+
+        let fees: Vec<_> = e
+            .take_fees()
+            .into_iter()
+            .map(|fee| (fee.tx_id, fee.kind, fee.amount))
+            .collect();
+        assert_eq!(
+            fees,
+            vec![
+                (2, FeeKind::Deposit, dec!(0.3)),
+                (4, FeeKind::Withdrawal, dec!(1)),
+                (6, FeeKind::Deposit, dec!(1)),
+                (7, FeeKind::Withdrawal, dec!(1)),
+            ]
+        );
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(296.7)));
+    }
+
+    #[test]
+    fn process_tx_authorize_capture() {
+        let mut e = Engine::with_config(EngineConfig {
+            authorization_ttl: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        });
+        let at = |time: &str| Some(format!("2024-01-01T{}Z", time).parse().unwrap());
+        let tx = |tx_type, id, amount, timestamp| TxInput {
+            tx_type,
+            amount,
+            timestamp,
+            ..dispute(1, id)
+        };
+        e.process_tx(&tx(TxType::Authorize, 1, Some(dec!(10)), at("10:00:00")))
+            .expect("failed to authorize");
+        e.process_tx(&tx(TxType::Authorize, 2, Some(dec!(5)), at("10:00:00")))
+            .expect("failed to authorize");
+        assert_eq!(e.client(1).map(|c| c.total()), Some(dec!(0)));
+
+        // an authorization isn't a deposit until it's captured
+        let err = e
+            .process_tx(&dispute(1, 1))
+            .expect_err("dispute should fail");
+        assert_eq!(err.kind(), &TxNotADeposit);
+        let err = e
+            .process_tx(&tx(TxType::Capture, 1, Some(dec!(10.01)), at("10:30:00")))
+            .expect_err("capture over the authorized amount should fail");
+        assert_eq!(err.kind(), &TxInvalidAmount);
+        e.process_tx(&tx(TxType::Capture, 1, Some(dec!(7.5)), at("10:30:00")))
+            .expect("failed to capture");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(7.5)));
+
+        let err = e
+            .process_tx(&tx(TxType::Capture, 1, None, at("10:30:00")))
+            .expect_err("second capture should fail");
+        assert_eq!(err.kind(), &TxNotAuthorized);
+        let err = e
+            .process_tx(&tx(TxType::Capture, 2, None, at("11:00:01")))
+            .expect_err("expired capture should fail");
+        assert_eq!(err.kind(), &AuthorizationExpired);
+
+        // the captured amount is disputed like a deposit
+        e.process_tx(&dispute(1, 1)).expect("failed to dispute");
+        assert_eq!(e.client(1).map(|c| c.held()), Some(dec!(7.5)));
+    }
+
+    #[test]
+    fn process_tx_settlement() {
+        let mut e = Engine::new();
+        let batched = |tx: TxInput, batch: &str| TxInput {
+            batch: Some(batch.to_string()),
+            ..tx
+        };
+        let settlement = |id, amount, batch: Option<&str>| TxInput {
+            tx_type: TxType::Settlement,
+            amount,
+            batch: batch.map(str::to_string),
+            ..dispute(9, id)
+        };
+        let withdrawal = TxInput {
+            tx_type: TxType::Withdrawal,
+            ..deposit(1, 3, dec!(4))
+        };
+        e.process_tx(&batched(deposit(1, 1, dec!(10)), "a"))
+            .expect("failed to deposit");
+        e.process_tx(&batched(deposit(2, 2, dec!(5)), "a"))
+            .expect("failed to deposit");
+        e.process_tx(&batched(withdrawal, "a"))
+            .expect("failed to withdraw");
+        // rejected transactions aren't part of the net of the batch
+        e.process_tx(&batched(deposit(1, 1, dec!(3)), "a"))
+            .expect_err("deposit should fail");
+        assert_eq!(e.batch_net("a"), Some(dec!(11)));
+
+        let err = e
+            .process_tx(&settlement(10, Some(dec!(15)), Some("a")))
+            .expect_err("mismatching settlement should fail");
+        assert_eq!(err.kind(), &SettlementMismatch);
+        let err = e
+            .process_tx(&settlement(10, Some(dec!(11)), None))
+            .expect_err("settlement without a batch should fail");
+        assert_eq!(err.kind(), &BatchNotFound);
+        e.process_tx(&settlement(10, Some(dec!(11)), Some("a")))
+            .expect("failed to settle");
+        assert_eq!(e.batch_net("a"), None);
+        let err = e
+            .process_tx(&settlement(11, Some(dec!(11)), Some("a")))
+            .expect_err("settled batch should be closed");
+        assert_eq!(err.kind(), &BatchNotFound);
+
+        // a settlement doesn't open an account
+        assert!(e.client(9).is_none());
+        assert_eq!(e.client(1).map(|c| c.total()), Some(dec!(6)));
+    }
+
+    #[test]
+    fn process_tx_reversal() {
+        let mut e = Engine::new();
+        let tx = |tx_type, id| TxInput {
+            tx_type,
+            ..dispute(1, id)
+        };
+        let withdrawal = TxInput {
+            tx_type: TxType::Withdrawal,
+            ..deposit(1, 3, dec!(4))
+        };
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(1, 2, dec!(5)))
+            .expect("failed to deposit");
+        e.process_tx(&withdrawal).expect("failed to withdraw");
+
+        e.process_tx(&tx(TxType::Reversal, 3))
+            .expect("failed to reverse withdrawal");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(15)));
+        e.process_tx(&tx(TxType::Reversal, 1))
+            .expect("failed to reverse deposit");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(5)));
+        assert_eq!(e.transaction(1).map(|tx| tx.reversed()), Some(true));
+
+        let err = e
+            .process_tx(&tx(TxType::Reversal, 1))
+            .expect_err("second reversal should fail");
+        assert_eq!(err.kind(), &TxReversed);
+        let err = e
+            .process_tx(&dispute(1, 1))
+            .expect_err("dispute of a reversed deposit should fail");
+        assert_eq!(err.kind(), &TxReversed);
+
+        e.process_tx(&dispute(1, 2)).expect("failed to dispute");
+        let err = e
+            .process_tx(&tx(TxType::Reversal, 2))
+            .expect_err("reversal under dispute should fail");
+        assert_eq!(err.kind(), &TxAlreadyUnderDispute);
+        e.process_tx(&tx(TxType::Resolve, 2))
+            .expect("failed to resolve");
+
+        // the funds of the deposit must still be available
+        e.process_tx(&TxInput {
+            tx_type: TxType::Withdrawal,
+            ..deposit(1, 4, dec!(1))
+        })
+        .expect("failed to withdraw");
+        let err = e
+            .process_tx(&tx(TxType::Reversal, 2))
+            .expect_err("reversal without the funds should fail");
+        assert_eq!(err.kind(), &ErrorKind::InsufficientFunds);
+
+        e.process_tx(&TxInput {
+            tx_type: TxType::Authorize,
+            ..deposit(1, 5, dec!(1))
+        })
+        .expect("failed to authorize");
+        let err = e
+            .process_tx(&tx(TxType::Reversal, 5))
+            .expect_err("reversal of an authorization should fail");
+        assert_eq!(err.kind(), &TxNotReversible);
+    }
+
+    #[test]
+    fn process_tx_reversal_rejected_withdrawal() {
+        let mut e = Engine::new();
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        let err = e
+            .process_tx(&TxInput {
+                tx_type: TxType::Withdrawal,
+                ..deposit(1, 2, dec!(50))
+            })
+            .expect_err("withdrawal over the funds should fail");
+        assert_eq!(err.kind(), &ErrorKind::InsufficientFunds);
+        assert!(e.transaction(2).is_none());
+
+        // the funds of the rejected withdrawal never left
+        let err = e
+            .process_tx(&TxInput {
+                tx_type: TxType::Reversal,
+                ..dispute(1, 2)
+            })
+            .expect_err("reversal of a rejected withdrawal should fail");
+        assert_eq!(err.kind(), &TxNotFound);
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(10)));
     }
 
-    pub(crate) fn process_tx(&mut self, tx: &TxInput) {
-        if let Err(err) = self.process_tx_inner(tx) {
-            debug!("failed to process transaction {}: {}", tx.id, err)
+    #[test]
+    fn rejected_resolve_keeps_dispute_state() {
+        let mut e = Engine::new();
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(1, 2, dec!(4)))
+            .expect("failed to deposit");
+        e.process_tx(&dispute(1, 1)).expect("failed to dispute");
+        e.process_tx(&dispute(1, 2)).expect("failed to dispute");
+        e.process_tx(&TxInput {
+            tx_type: TxType::Chargeback,
+            ..dispute(1, 2)
+        })
+        .expect("failed to chargeback");
+
+        // the account is locked, the dispute stays open with its funds held
+        for tx_type in [TxType::Resolve, TxType::Chargeback] {
+            let err = e
+                .process_tx(&TxInput {
+                    tx_type,
+                    ..dispute(1, 1)
+                })
+                .expect_err("a locked account should reject the dispute update");
+            assert_eq!(err.kind(), &ErrorKind::AccountLocked);
+            assert_eq!(
+                e.transaction(1).map(|tx| tx.dispute_state()),
+                Some(DisputeState::Open)
+            );
+            assert_eq!(e.client(1).map(|c| c.held()), Some(dec!(10)));
         }
     }
 
-    fn process_tx_inner(&mut self, tx_input: &TxInput) -> Result<(), Error> {
-        let client = self
-            .clients
-            .entry(tx_input.client_id)
-            .or_insert(Client::new(tx_input.client_id));
+    #[test]
+    fn apply_admin_commands() {
+        let mut e = Engine::new();
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(1, 2, dec!(4)))
+            .expect("failed to deposit");
+        e.process_tx(&dispute(1, 1)).expect("failed to dispute");
+        e.process_tx(&dispute(1, 2)).expect("failed to dispute");
+        e.process_tx(&TxInput {
+            tx_type: TxType::Chargeback,
+            ..dispute(1, 2)
+        })
+        .expect("failed to chargeback");
 
-        match tx_input.tx_type {
-            TxType::Deposit => {
-                if self.transactions.contains_key(&tx_input.id) {
-                    return Err(TxIdConflict);
-                }
+        let command = |action, tx_id, amount| AdminCommand {
+            action,
+            client_id: 1,
+            tx_id,
+            amount,
+            after: None,
+            reason: None,
+        };
 
-                let amount = match &tx_input.amount {
-                    Some(val) => val,
-                    None => return Err(TxInvalidAmount),
-                };
+        // a dispute is resolved on the locked account
+        e.apply_admin(&command(AdminAction::ForceResolve, Some(1), None))
+            .expect("failed to force resolve");
+        assert_eq!(
+            e.transaction(1).map(|tx| tx.dispute_state()),
+            Some(DisputeState::Resolved)
+        );
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(10)));
+
+        let err = e
+            .apply_admin(&command(AdminAction::Adjust, None, Some(dec!(-10.01))))
+            .expect_err("adjustment below zero should fail");
+        assert_eq!(err.kind(), &ErrorKind::InsufficientFunds);
+        e.apply_admin(&command(AdminAction::Adjust, None, Some(dec!(-10))))
+            .expect("failed to adjust");
+        let err = e
+            .apply_admin(&command(AdminAction::Adjust, None, None))
+            .expect_err("adjustment without amount should fail");
+        assert_eq!(err.kind(), &TxInvalidAmount);
+
+        e.apply_admin(&command(AdminAction::Unlock, None, None))
+            .expect("failed to unlock");
+        assert_eq!(e.client_report(1).and_then(|r| r.locked_by), None);
+        let err = e
+            .apply_admin(&command(AdminAction::Unlock, None, None))
+            .expect_err("unlocking an unlocked account should fail");
+        assert_eq!(err.kind(), &ErrorKind::AccountNotLocked);
+        e.process_tx(&deposit(1, 3, dec!(1)))
+            .expect("failed to deposit after unlock");
+
+        // a frozen account takes deposits but no withdrawals
+        e.apply_admin(&command(AdminAction::Freeze, None, None))
+            .expect("failed to freeze");
+        assert_eq!(
+            e.client(1).map(|c| (c.frozen(), c.locked())),
+            Some((true, false))
+        );
+        e.process_tx(&deposit(1, 4, dec!(1)))
+            .expect("failed to deposit while frozen");
+        let err = e
+            .process_tx(&TxInput {
+                tx_type: TxType::Withdrawal,
+                ..deposit(1, 5, dec!(1))
+            })
+            .expect_err("withdrawal while frozen should fail");
+        assert_eq!(err.kind(), &ErrorKind::AccountFrozen);
+        e.apply_admin(&command(AdminAction::Unfreeze, None, None))
+            .expect("failed to unfreeze");
+        assert_eq!(e.client(1).map(|c| c.frozen()), Some(false));
+
+        e.apply_admin(&command(AdminAction::Lock, None, None))
+            .expect("failed to lock");
+        assert_eq!(
+            e.client_report(1).and_then(|r| r.lock_reason),
+            Some(LockReason::Admin)
+        );
+    }
 
-                self.transactions.insert(tx_input.id, Tx::new(tx_input));
-                client.deposit(amount)
-            }
-            TxType::Withdrawal => {
-                if self.transactions.contains_key(&tx_input.id) {
-                    return Err(TxIdConflict);
-                }
+    #[test]
+    fn apply_admin_reverse_after() {
+        let mut e = Engine::new();
+        let withdrawal = |id, amount| TxInput {
+            tx_type: TxType::Withdrawal,
+            ..deposit(1, id, amount)
+        };
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&withdrawal(2, dec!(3)))
+            .expect("failed to withdraw");
+        e.process_tx(&deposit(1, 3, dec!(2)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(2, 4, dec!(7)))
+            .expect("failed to deposit");
+        e.process_tx(&withdrawal(5, dec!(9)))
+            .expect("failed to withdraw");
+        e.process_tx(&dispute(1, 3)).expect("failed to dispute");
+
+        let reverse_after = AdminCommand {
+            action: AdminAction::ReverseAfter,
+            client_id: 1,
+            tx_id: Some(1),
+            amount: None,
+            after: None,
+            reason: None,
+        };
+        let err = e
+            .apply_admin(&reverse_after)
+            .expect_err("reversal of a disputed deposit should fail");
+        assert_eq!(err.kind(), &TxAlreadyUnderDispute);
+        assert_eq!(e.transaction(5).map(|tx| tx.reversed()), Some(false));
 
-                let amount = match &tx_input.amount {
-                    Some(val) => val,
-                    None => return Err(TxInvalidAmount),
-                };
+        e.process_tx(&TxInput {
+            tx_type: TxType::Resolve,
+            ..dispute(1, 3)
+        })
+        .expect("failed to resolve");
+        e.apply_admin(&reverse_after)
+            .expect("failed to reverse after tx 1");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(10)));
+        assert_eq!(e.client(2).map(|c| c.available()), Some(dec!(7)));
+        assert_eq!(e.transaction(1).map(|tx| tx.reversed()), Some(false));
+        assert_eq!(e.transaction(3).map(|tx| tx.reversed()), Some(true));
+    }
 
-                self.transactions.insert(tx_input.id, Tx::new(tx_input));
-                client.withdraw(amount)
-            }
-            TxType::Dispute => {
-                let tx = self.transactions.get_mut(&tx_input.id);
-                match tx {
-                    None => Err(TxNotFound),
-                    Some(tx) => {
-                        if tx.client_id != tx_input.client_id {
-                            return Err(ClientIdNoMatch);
-                        }
+    #[test]
+    fn apply_admin_reverse_after_rejected() {
+        let mut e = Engine::new();
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        let err = e
+            .process_tx(&TxInput {
+                tx_type: TxType::Withdrawal,
+                ..deposit(1, 2, dec!(50))
+            })
+            .expect_err("withdrawal over the available funds should fail");
+        assert_eq!(err.kind(), &ErrorKind::InsufficientFunds);
+        let err = e
+            .process_tx(&deposit(1, 3, Decimal::MAX))
+            .expect_err("overflowing deposit should fail");
+        assert_eq!(err.kind(), &ErrorKind::Overflow);
+        e.process_tx(&deposit(1, 4, dec!(2)))
+            .expect("failed to deposit");
+
+        // the rejected txs after the cutoff were never applied, only tx 4 is reversed
+        e.apply_admin(&AdminCommand {
+            action: AdminAction::ReverseAfter,
+            client_id: 1,
+            tx_id: Some(1),
+            amount: None,
+            after: None,
+            reason: None,
+        })
+        .expect("failed to reverse after tx 1");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(10)));
+        assert!(e.transaction(2).is_none());
+        assert!(e.transaction(3).is_none());
+        assert_eq!(e.transaction(4).map(|tx| tx.reversed()), Some(true));
+    }
 
-                        if tx.tx_type != TxType::Deposit {
-                            return Err(TxNotADeposit);
-                        }
+    #[test]
+    fn apply_admin_reverse_after_order() {
+        let mut e = Engine::new();
+        let at = |id, amount, time: &str| TxInput {
+            timestamp: Some(format!("2024-01-01T{}:00Z", time).parse().unwrap()),
+            ..deposit(1, id, amount)
+        };
+        let reverse_after = |tx_id, after: Option<&str>| AdminCommand {
+            action: AdminAction::ReverseAfter,
+            client_id: 1,
+            tx_id,
+            amount: None,
+            after: after.map(|time| format!("2024-01-01T{}:00Z", time).parse().unwrap()),
+            reason: None,
+        };
+        e.process_tx(&at(10, dec!(1), "10:00"))
+            .expect("failed to deposit");
+        e.process_tx(&at(5, dec!(2), "11:00"))
+            .expect("failed to deposit");
+        e.process_tx(&at(7, dec!(4), "12:00"))
+            .expect("failed to deposit");
+
+        let err = e
+            .apply_admin(&reverse_after(None, None))
+            .expect_err("reversal without a cutoff should fail");
+        assert_eq!(err.kind(), &TxNotFound);
+
+        e.apply_admin(&reverse_after(None, Some("11:00")))
+            .expect("failed to reverse after 11:00");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(3)));
+        assert_eq!(e.transaction(5).map(|tx| tx.reversed()), Some(false));
+
+        // tx 5 is stored after tx 10 despite its lower id
+        e.apply_admin(&reverse_after(Some(10), None))
+            .expect("failed to reverse after tx 10");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(1)));
+        assert_eq!(e.transaction(5).map(|tx| tx.reversed()), Some(true));
+    }
 
-                        tx.under_dispute = true;
-                        client.dispute(&tx.amount)
-                    }
-                }
-            }
-            TxType::Resolve => {
-                let tx = self.transactions.get_mut(&tx_input.id);
-                match tx {
-                    None => Err(TxNotFound),
-                    Some(tx) => {
-                        if tx.client_id != tx_input.client_id {
-                            return Err(ClientIdNoMatch);
-                        }
+    #[test]
+    fn apply_admin_reverse_after_dropped() {
+        let mut e = Engine::with_config(EngineConfig {
+            retention: Retention {
+                after_txs: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        e.set_archive(MemoryArchive::default());
+        let at = |id, time: &str| TxInput {
+            timestamp: Some(format!("2024-01-01T{}:00Z", time).parse().unwrap()),
+            ..deposit(1, id, dec!(1))
+        };
+        let reverse_after = |after: &str| AdminCommand {
+            action: AdminAction::ReverseAfter,
+            client_id: 1,
+            tx_id: None,
+            amount: None,
+            after: Some(format!("2024-01-01T{}:00Z", after).parse().unwrap()),
+            reason: None,
+        };
+        e.process_tx(&at(1, "10:00")).expect("failed to deposit");
+        e.process_tx(&at(2, "11:00")).expect("failed to deposit");
+        e.process_tx(&at(3, "12:00")).expect("failed to deposit");
+        assert!(e.transaction(2).is_none());
+
+        // tx 2 was dropped from memory, it can't be reversed
+        let err = e
+            .apply_admin(&reverse_after("10:30"))
+            .expect_err("reversal of a dropped tx should fail");
+        assert_eq!(err.kind(), &ReversalIncomplete);
+        assert_eq!(e.transaction(3).map(|tx| tx.reversed()), Some(false));
+
+        e.apply_admin(&reverse_after("11:30"))
+            .expect("failed to reverse after 11:30");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(2)));
+    }
 
-                        if !tx.under_dispute {
-                            return Err(TxNotUnderDispute);
-                        }
+    #[test]
+    fn state_restore() {
+        let config = EngineConfig {
+            velocity: VelocityLimits {
+                max_withdrawals: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut e = Engine::with_config(config.clone());
+        e.apply_batch(&[
+            deposit(1, 1, dec!(10)),
+            deposit(2, 2, dec!(5)),
+            dispute(1, 1),
+        ])
+        .expect("failed to apply batch");
+        e.process_tx(&TxInput {
+            tx_type: TxType::Withdrawal,
+            client_id: 2,
+            id: 3,
+            amount: Some(dec!(1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        })
+        .expect("failed to withdraw");
+
+        let mut restored = Engine::with_config(config);
+        restored.restore(e.state());
+
+        assert_eq!(restored.state().clients, e.state().clients);
+        assert_eq!(restored.transaction_count(), 3);
+        assert_eq!(restored.client(1).map(|c| c.held()), Some(dec!(10)));
+        assert_eq!(
+            restored.transaction(1).map(|tx| tx.dispute_state()),
+            Some(DisputeState::Open)
+        );
+
+        // the restored velocity window still holds the withdrawal
+        let err = restored
+            .process_tx(&TxInput {
+                tx_type: TxType::Withdrawal,
+                client_id: 2,
+                id: 4,
+                amount: Some(dec!(1)),
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
+            })
+            .unwrap_err();
+        assert_eq!(err.kind(), &VelocityLimitExceeded);
+    }
 
-                        tx.under_dispute = false;
-                        client.resolve(&tx.amount)
-                    }
-                }
-            }
-            TxType::Chargeback => {
-                let tx = self.transactions.get_mut(&tx_input.id);
-                match tx {
-                    None => Err(TxNotFound),
-                    Some(tx) => {
-                        if tx.client_id != tx_input.client_id {
-                            return Err(ClientIdNoMatch);
-                        }
+    #[test]
+    fn apply_batch_commit() {
+        let mut e = Engine::new();
+        let batch = [
+            deposit(1, 1, dec!(10)),
+            deposit(2, 2, dec!(5)),
+            dispute(1, 1),
+        ];
 
-                        if !tx.under_dispute {
-                            return Err(TxNotUnderDispute);
-                        }
+        e.apply_batch(&batch).expect("failed to apply batch");
 
-                        client.chargeback(&tx.amount)
-                    }
-                }
-            }
-        }
+        let c = e.clients.get(1).unwrap();
+        assert_eq!(ClientReport::new(c).held, dec!(10));
+        assert_eq!(e.transaction_count(), 2);
     }
 
-    pub(crate) fn report(&self) -> impl Iterator<Item = ClientReport> + '_ {
-        return self.clients.values().map(ClientReport::new);
+    #[test]
+    fn process_tx_idempotency_key() {
+        let mut e = Engine::new();
+        let keyed = |tx: TxInput, key: &str| TxInput {
+            idempotency_key: Some(key.to_string()),
+            ..tx
+        };
+
+        e.process_tx(&keyed(deposit(1, 1, dec!(10)), "a"))
+            .expect("failed to deposit");
+        // a retry under a new tx id is acknowledged without being applied
+        e.process_tx(&keyed(deposit(1, 2, dec!(10)), "a"))
+            .expect("failed to acknowledge retry");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(10)));
+        assert_eq!(e.transaction_count(), 1);
+        assert_eq!(e.replays(), 1);
+
+        // a rejected transaction can be retried under its key
+        let withdrawal = TxInput {
+            tx_type: TxType::Withdrawal,
+            ..deposit(1, 3, dec!(15))
+        };
+        e.process_tx(&keyed(withdrawal, "b")).unwrap_err();
+        e.process_tx(&deposit(1, 4, dec!(5)))
+            .expect("failed to deposit");
+        let withdrawal = TxInput {
+            tx_type: TxType::Withdrawal,
+            ..deposit(1, 5, dec!(15))
+        };
+        e.process_tx(&keyed(withdrawal, "b"))
+            .expect("failed to withdraw");
+        assert_eq!(e.client(1).map(|c| c.available()), Some(dec!(0)));
+
+        // the keys of a rolled back batch are forgotten
+        let err = e
+            .apply_batch(&[keyed(deposit(2, 6, dec!(1)), "c"), dispute(2, 7)])
+            .unwrap_err();
+        assert_eq!(err.index(), 1);
+        e.process_tx(&keyed(deposit(2, 6, dec!(1)), "c"))
+            .expect("failed to deposit");
+        assert_eq!(e.client(2).map(|c| c.available()), Some(dec!(1)));
+        assert_eq!(e.replays(), 1);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use rust_decimal_macros::dec;
+    #[test]
+    fn process_tx_dedup_window() {
+        let config = EngineConfig {
+            dedup_window: Some(10),
+            ..Default::default()
+        };
+        let mut e = Engine::with_config(config.clone());
+        let resolve = TxInput {
+            tx_type: TxType::Resolve,
+            ..dispute(1, 1)
+        };
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&dispute(1, 1)).expect("failed to dispute");
+        e.process_tx(&resolve).expect("failed to resolve");
+        // a deposit can be disputed again once resolved
+        e.process_tx(&dispute(1, 1))
+            .expect("failed to dispute again");
+        assert_eq!(e.client(1).map(|c| c.held()), Some(dec!(10)));
+        assert_eq!(e.duplicates(), 0);
+
+        // the window is saved in the state
+        let mut resumed = Engine::with_config(config);
+        resumed.restore(e.state());
+        let err = resumed
+            .process_tx(&deposit(1, 1, dec!(10)))
+            .expect_err("redelivered deposit should fail");
+        assert_eq!(err.kind(), &DuplicateTx);
+        assert_eq!(resumed.duplicates(), 1);
+    }
 
-    use super::*;
+    #[test]
+    fn apply_batch_rollback() {
+        let mut e = Engine::with_config(EngineConfig {
+            dedup_window: Some(10),
+            ..Default::default()
+        });
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        let before = e.clone();
+
+        let batch = [
+            deposit(1, 2, dec!(5)),
+            dispute(1, 1),
+            deposit(3, 3, dec!(1)),
+            dispute(3, 4),
+        ];
+        let err = e.apply_batch(&batch).unwrap_err();
+
+        assert_eq!(err.index(), 3);
+        assert_eq!(err.error().kind(), &TxNotFound);
+        assert_eq!(e.clients.get(1), before.clients.get(1));
+        assert_eq!(e.clients.get(3), None);
+        assert_eq!(e.transaction_count(), 1);
+        assert_eq!(e.transaction(1), before.transaction(1));
+
+        // the rolled back transactions can be applied again
+        e.apply_batch(&batch[..3]).expect("failed to apply batch");
+        assert_eq!(e.transaction_count(), 3);
+
+        // the client of the rejected transaction keeps its rejection
+        let mut rejected = e.clients.get(3).cloned().expect("client not found");
+        rejected.reject(TxType::Dispute);
+        e.apply_batch(&[deposit(3, 5, dec!(1)), dispute(3, 6)])
+            .expect_err("dispute of an unknown tx should fail");
+        assert_eq!(e.clients.get(3), Some(&rejected));
+    }
+
+    #[test]
+    fn client_and_transaction_views() {
+        let mut e = Engine::new();
+        e.apply_batch(&[
+            deposit(1, 1, dec!(10)),
+            deposit(1, 2, dec!(2.5)),
+            dispute(1, 1),
+        ])
+        .expect("failed to apply batch");
+
+        let client = e.client(1).expect("client not found");
+        assert_eq!(client.id(), 1);
+        assert_eq!(client.available(), dec!(2.5));
+        assert_eq!(client.held(), dec!(10));
+        assert_eq!(client.total(), dec!(12.5));
+        assert!(!client.locked());
+        assert!(e.client(2).is_none());
+
+        let tx = e.transaction(1).expect("tx not found");
+        assert_eq!(tx.amount(), dec!(10));
+        assert_eq!(tx.client_id(), 1);
+        assert_eq!(tx.tx_type(), TxType::Deposit);
+        assert_eq!(tx.dispute_state(), DisputeState::Open);
+        assert_eq!(tx.disputes(), 1);
+        assert!(e.transaction(3).is_none());
+    }
+
+    #[test]
+    fn transactions() {
+        let mut e = Engine::new();
+        e.apply_batch(&[
+            deposit(1, 1, dec!(10)),
+            deposit(2, 2, dec!(2.5)),
+            dispute(1, 1),
+        ])
+        .expect("failed to apply batch");
+
+        let mut txs: Vec<_> = e
+            .transactions()
+            .map(|(id, tx)| (id, tx.client_id(), tx.amount(), tx.dispute_state()))
+            .collect();
+        txs.sort_by_key(|(id, ..)| *id);
+
+        assert_eq!(
+            txs,
+            vec![
+                (1, 1, dec!(10), DisputeState::Open),
+                (2, 2, dec!(2.5), DisputeState::None),
+            ]
+        );
+    }
+
+    #[test]
+    fn metrics() {
+        let mut e = Engine::new();
+        e.process_tx(&deposit(1, 1, dec!(10)))
+            .expect("failed to deposit");
+        e.process_tx(&deposit(2, 2, dec!(1)))
+            .expect("failed to deposit");
+        e.process_tx(&dispute(1, 1)).expect("failed to dispute");
+        let _ = e.process_tx(&dispute(1, 9));
+        // the deposit of the rejected batch is not counted
+        let _ = e.apply_batch(&[deposit(3, 3, dec!(1)), dispute(3, 4)]);
+
+        let metrics = e.metrics();
+        assert_eq!(
+            metrics.applied,
+            BTreeMap::from([(TxType::Deposit, 2), (TxType::Dispute, 1)])
+        );
+        assert_eq!(metrics.rejected, BTreeMap::from([("tx_not_found", 2)]));
+        assert_eq!(metrics.clients, 2);
+        assert_eq!(metrics.stored_txs, 2);
+        assert_eq!(metrics.memory_bytes, e.memory_usage());
+    }
+
+    fn deposit(client_id: ClientId, id: TxId, amount: Decimal) -> TxInput {
+        TxInput {
+            tx_type: TxType::Deposit,
+            client_id,
+            id,
+            amount: Some(amount),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        }
+    }
+
+    fn dispute(client_id: ClientId, id: TxId) -> TxInput {
+        TxInput {
+            tx_type: TxType::Dispute,
+            client_id,
+            id,
+            amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        }
+    }
 
     // process_tx_inner
     #[test]
-    fn process_tx_inner_success() -> Result<(), Error> {
+    fn process_tx_inner_success() -> Result<(), ErrorKind> {
         let txs = vec![
             TxInput {
                 tx_type: TxType::Deposit,
                 client_id: 0,
                 id: 1,
                 amount: Option::from(dec!(10)),
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
             },
             TxInput {
                 tx_type: TxType::Withdrawal,
                 client_id: 0,
                 id: 2,
                 amount: Option::from(dec!(1)),
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
             },
             TxInput {
                 tx_type: TxType::Dispute,
                 client_id: 0,
                 id: 1,
                 amount: None,
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
             },
             TxInput {
                 tx_type: TxType::Resolve,
                 client_id: 0,
                 id: 1,
                 amount: None,
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
             },
             TxInput {
                 tx_type: TxType::Deposit,
                 client_id: 0,
                 id: 3,
                 amount: Option::from(dec!(10)),
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
             },
             TxInput {
                 tx_type: TxType::Dispute,
                 client_id: 0,
                 id: 3,
                 amount: Option::from(dec!(10)),
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
             },
             TxInput {
                 tx_type: TxType::Chargeback,
                 client_id: 0,
                 id: 3,
                 amount: None,
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
             },
             TxInput {
                 tx_type: TxType::Deposit,
                 client_id: 1,
                 id: 4,
                 amount: Option::from(dec!(10)),
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
             },
             TxInput {
                 tx_type: TxType::Dispute,
                 client_id: 1,
                 id: 4,
                 amount: None,
+                timestamp: None,
+                idempotency_key: None,
+                batch: None,
+                effective_date: None,
             },
         ];
         let mut e = Engine::new();
@@ -199,8 +2569,8 @@ mod tests {
             }
         }
 
-        let c = e.clients.get(&0).expect("client not found");
-        let c1 = e.clients.get(&1).expect("client not found");
+        let c = e.clients.get(0).expect("client not found");
+        let c1 = e.clients.get(1).expect("client not found");
 
         assert_ne!(*c, Client::new(0));
         assert_ne!(*c1, Client::new(1));
@@ -209,22 +2579,22 @@ mod tests {
         let tx1 = e.transactions.get(&1).expect("tx not found");
         assert_eq!(tx1.client_id, 0);
         assert_eq!(tx1.amount, dec!(10));
-        assert_eq!(tx1.under_dispute, false);
+        assert_eq!(tx1.dispute_state(), DisputeState::Resolved);
 
         let tx2 = e.transactions.get(&2).expect("tx not found");
         assert_eq!(tx2.client_id, 0);
         assert_eq!(tx2.amount, dec!(1));
-        assert_eq!(tx2.under_dispute, false);
+        assert_eq!(tx2.dispute_state(), DisputeState::None);
 
         let tx3 = e.transactions.get(&3).expect("tx not found");
         assert_eq!(tx3.client_id, 0);
         assert_eq!(tx3.amount, dec!(10));
-        assert_eq!(tx3.under_dispute, true);
+        assert_eq!(tx3.dispute_state(), DisputeState::ChargedBack);
 
         let tx4 = e.transactions.get(&4).expect("tx not found");
         assert_eq!(tx4.client_id, 1);
         assert_eq!(tx4.amount, dec!(10));
-        assert_eq!(tx4.under_dispute, true);
+        assert_eq!(tx4.dispute_state(), DisputeState::Open);
 
         Ok(())
     }
@@ -237,6 +2607,10 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: Option::from(dec!(-1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let mut e = Engine::new();
         let result = e.process_tx_inner(&tx1);
@@ -251,12 +2625,20 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: Option::from(dec!(10)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Deposit,
             client_id: 1,
             id: 1,
             amount: Option::from(dec!(20)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let mut e = Engine::new();
         e.process_tx_inner(&tx1).expect("process tx failed");
@@ -273,6 +2655,10 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let mut e = Engine::new();
         let result = e.process_tx_inner(&tx1);
@@ -289,6 +2675,10 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: Option::from(dec!(-1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let mut e = Engine::new();
         let result = e.process_tx_inner(&tx1);
@@ -303,12 +2693,20 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: Option::from(dec!(11)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Withdrawal,
             client_id: 1,
             id: 1,
             amount: Option::from(dec!(20)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
 
         let mut e = Engine::new();
@@ -319,6 +2717,42 @@ mod tests {
         assert_eq!(result.unwrap_err(), TxIdConflict)
     }
 
+    #[test]
+    fn process_tx_inner_deposit_duplicate_ignore_if_identical() {
+        let tx1 = TxInput {
+            tx_type: TxType::Deposit,
+            client_id: 0,
+            id: 1,
+            amount: Option::from(dec!(10)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        };
+        let tx2 = TxInput {
+            tx_type: TxType::Deposit,
+            client_id: 0,
+            id: 1,
+            amount: Option::from(dec!(20)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        };
+        let mut e = Engine::with_config(EngineConfig {
+            duplicate_policy: DuplicatePolicy::IgnoreIfIdentical,
+            ..Default::default()
+        });
+        e.process_tx_inner(&tx1).expect("process tx failed");
+        e.process_tx_inner(&tx1).expect("process tx failed");
+        let result = e.process_tx_inner(&tx2);
+
+        assert_eq!(result.unwrap_err(), TxIdConflict);
+
+        let c = e.clients.get(0).expect("client not found");
+        assert_eq!(ClientReport::new(c).available, dec!(10));
+    }
+
     #[test]
     fn process_tx_inner_fail_withdrawal_tx_invalid_amount() {
         let tx1 = TxInput {
@@ -326,6 +2760,10 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let mut e = Engine::new();
         let result = e.process_tx_inner(&tx1);
@@ -342,24 +2780,40 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: Option::from(dec!(1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Dispute,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx3 = TxInput {
             tx_type: TxType::Chargeback,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx4 = TxInput {
             tx_type: TxType::Dispute,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let mut e = Engine::new();
         e.process_tx_inner(&tx1).expect("process tx failed");
@@ -377,6 +2831,10 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
 
         let mut e = Engine::new();
@@ -393,12 +2851,20 @@ mod tests {
             client_id: 1,
             id: 1,
             amount: Option::from(dec!(0)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Dispute,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
 
         let mut e = Engine::new();
@@ -416,12 +2882,20 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: Option::from(dec!(0)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Dispute,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
 
         let mut e = Engine::new();
@@ -432,6 +2906,117 @@ mod tests {
         assert_eq!(result.unwrap_err(), TxNotADeposit)
     }
 
+    #[test]
+    fn process_tx_inner_fail_dispute_state() {
+        let deposit = TxInput {
+            tx_type: TxType::Deposit,
+            client_id: 0,
+            id: 1,
+            amount: Option::from(dec!(1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        };
+        let dispute = TxInput {
+            tx_type: TxType::Dispute,
+            client_id: 0,
+            id: 1,
+            amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        };
+        let chargeback = TxInput {
+            tx_type: TxType::Chargeback,
+            client_id: 0,
+            id: 1,
+            amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        };
+        let mut e = Engine::new();
+        e.process_tx_inner(&deposit).expect("process tx failed");
+        e.process_tx_inner(&dispute).expect("process tx failed");
+
+        assert_eq!(
+            e.process_tx_inner(&dispute).unwrap_err(),
+            TxAlreadyUnderDispute
+        );
+
+        e.process_tx_inner(&chargeback).expect("process tx failed");
+
+        assert_eq!(e.process_tx_inner(&dispute).unwrap_err(), TxChargedBack);
+        assert_eq!(
+            e.transactions
+                .get(&1)
+                .expect("tx not found")
+                .dispute_state(),
+            DisputeState::ChargedBack
+        );
+    }
+
+    #[test]
+    fn process_tx_inner_fail_dispute_limit_reached() {
+        let deposit = TxInput {
+            tx_type: TxType::Deposit,
+            client_id: 0,
+            id: 1,
+            amount: Option::from(dec!(1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        };
+        let dispute = TxInput {
+            tx_type: TxType::Dispute,
+            client_id: 0,
+            id: 1,
+            amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        };
+        let resolve = TxInput {
+            tx_type: TxType::Resolve,
+            client_id: 0,
+            id: 1,
+            amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        };
+
+        let mut e = Engine::with_config(EngineConfig {
+            max_disputes: Some(2),
+            ..Default::default()
+        });
+        e.process_tx_inner(&deposit).expect("process tx failed");
+        e.process_tx_inner(&dispute).expect("process tx failed");
+        e.process_tx_inner(&resolve).expect("process tx failed");
+        e.process_tx_inner(&dispute).expect("process tx failed");
+        e.process_tx_inner(&resolve).expect("process tx failed");
+        let result = e.process_tx_inner(&dispute);
+
+        assert_eq!(result.unwrap_err(), DisputeLimitReached);
+
+        let mut e = Engine::with_config(EngineConfig {
+            allow_redispute: false,
+            ..Default::default()
+        });
+        e.process_tx_inner(&deposit).expect("process tx failed");
+        e.process_tx_inner(&dispute).expect("process tx failed");
+        e.process_tx_inner(&resolve).expect("process tx failed");
+        let result = e.process_tx_inner(&dispute);
+
+        assert_eq!(result.unwrap_err(), DisputeLimitReached);
+    }
+
     // process_tx_inner fail resolve
     #[test]
     fn process_tx_inner_fail_resolve() {
@@ -440,24 +3025,40 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: Option::from(dec!(1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Dispute,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx3 = TxInput {
             tx_type: TxType::Chargeback,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx4 = TxInput {
             tx_type: TxType::Resolve,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let mut e = Engine::new();
         e.process_tx_inner(&tx1).expect("process tx failed");
@@ -475,6 +3076,10 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
 
         let mut e = Engine::new();
@@ -491,12 +3096,20 @@ mod tests {
             client_id: 1,
             id: 1,
             amount: Option::from(dec!(0)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Resolve,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
 
         let mut e = Engine::new();
@@ -514,12 +3127,20 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: Option::from(dec!(0)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Resolve,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
 
         let mut e = Engine::new();
@@ -538,24 +3159,40 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: Option::from(dec!(1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Dispute,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx3 = TxInput {
             tx_type: TxType::Chargeback,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx4 = TxInput {
             tx_type: TxType::Chargeback,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let mut e = Engine::new();
         e.process_tx_inner(&tx1).expect("process tx failed");
@@ -573,6 +3210,10 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
 
         let mut e = Engine::new();
@@ -589,12 +3230,20 @@ mod tests {
             client_id: 1,
             id: 1,
             amount: Option::from(dec!(0)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Chargeback,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
 
         let mut e = Engine::new();
@@ -612,12 +3261,20 @@ mod tests {
             client_id: 0,
             id: 1,
             amount: Option::from(dec!(0)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
         let tx2 = TxInput {
             tx_type: TxType::Chargeback,
             client_id: 0,
             id: 1,
             amount: None,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
         };
 
         let mut e = Engine::new();
@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "cli")]
+use crate::models::client::ClientView;
+use crate::models::client::Dropped;
+#[cfg(feature = "cli")]
+use crate::models::errors::Error;
+use crate::models::tx::{ClientId, TxId};
+#[cfg(feature = "cli")]
+use crate::models::tx_result::TxStatus;
+
+/// manual intervention on the state of the engine, applied outside of the transaction flow
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminAction {
+    /// lifts the lock of a client and the chargeback that caused it, balances are unchanged
+    Unlock,
+    /// locks a client, balances are unchanged
+    Lock,
+    /// resolves an open dispute, also on a locked account
+    ForceResolve,
+    /// adds the signed amount to the available funds, also on a locked account
+    Adjust,
+    /// reverses every deposit and withdrawal of the client stored after `tx` and timestamped
+    /// after `after`, newest first and also on a locked account; none is reversed when one of
+    /// them can't be or when one may have been dropped from memory
+    ReverseAfter,
+    /// puts a compliance hold on the client, its withdrawals are rejected while deposits and
+    /// dispute handling still apply; unlike a lock, it doesn't come from a chargeback
+    Freeze,
+    /// lifts the compliance hold of the client
+    Unfreeze,
+}
+
+/// admin command, `tx` is the disputed transaction of a force resolve or the last transaction
+/// kept by a bulk reversal, `after` the time of the last transactions kept by a bulk reversal
+/// and `amount` the change of an adjustment
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+pub struct AdminCommand {
+    pub action: AdminAction,
+
+    #[serde(rename = "client")]
+    pub client_id: ClientId,
+
+    #[serde(rename = "tx", default)]
+    pub tx_id: Option<TxId>,
+
+    #[serde(default)]
+    pub amount: Option<Decimal>,
+
+    #[serde(default)]
+    pub after: Option<DateTime<Utc>>,
+
+    /// why the intervention was made, kept in the audit record
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// transactions a bulk reversal undoes: the ones stored after the store order of a transaction
+/// and timestamped after a time, whichever are given
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Cutoff {
+    pub(crate) seq: Option<u32>,
+    /// milliseconds since the epoch
+    pub(crate) timestamp: Option<i64>,
+}
+
+impl Cutoff {
+    /// whether the transaction of the store order and timestamp comes after the cutoff, None
+    /// when it can't be told
+    pub(crate) fn includes(&self, seq: Option<u32>, timestamp: Option<i64>) -> Option<bool> {
+        match (after(self.seq, seq), after(self.timestamp, timestamp)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        }
+    }
+
+    /// whether a dropped transaction of the client may come after the cutoff
+    pub(crate) fn misses(&self, dropped: Option<Dropped>) -> bool {
+        dropped.is_some_and(|dropped| {
+            self.includes(Some(dropped.seq), Some(dropped.timestamp)) == Some(true)
+        })
+    }
+}
+
+/// whether the value comes after the cutoff, any does without a cutoff
+fn after<T: PartialOrd>(cutoff: Option<T>, value: Option<T>) -> Option<bool> {
+    match (cutoff, value) {
+        (None, _) => Some(true),
+        (Some(cutoff), Some(value)) => Some(value > cutoff),
+        (Some(_), None) => None,
+    }
+}
+
+/// audit record of an admin command, with the balances of its client after it
+#[cfg(feature = "cli")]
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct AdminAudit<'a> {
+    /// line of the command in the command file
+    pub(crate) line: u64,
+    pub(crate) at: DateTime<Utc>,
+    pub(crate) action: AdminAction,
+    pub(crate) client: ClientId,
+    pub(crate) tx: Option<TxId>,
+    pub(crate) amount: Option<Decimal>,
+    pub(crate) reason: Option<&'a str>,
+    pub(crate) status: TxStatus,
+    pub(crate) code: Option<&'static str>,
+    pub(crate) available: Option<Decimal>,
+    pub(crate) held: Option<Decimal>,
+    pub(crate) locked: Option<bool>,
+}
+
+#[cfg(feature = "cli")]
+impl<'a> AdminAudit<'a> {
+    pub(crate) fn new(
+        line: u64,
+        at: DateTime<Utc>,
+        command: &'a AdminCommand,
+        result: &Result<(), Error>,
+        client: Option<ClientView>,
+    ) -> Self {
+        Self {
+            line,
+            at,
+            action: command.action,
+            client: command.client_id,
+            tx: command.tx_id,
+            amount: command.amount,
+            reason: command.reason.as_deref(),
+            status: match result {
+                Ok(_) => TxStatus::Accepted,
+                Err(_) => TxStatus::Rejected,
+            },
+            code: result.as_ref().err().map(Error::code),
+            available: client.map(|client| client.available()),
+            held: client.map(|client| client.held()),
+            locked: client.map(|client| client.locked()),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::models::client::ClientView;
+use crate::models::tx::{ClientId, TxId};
+
+/// account conditions reported to the alert observers when a transaction brings a client into
+/// them, a client staying in a condition isn't reported again
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct AlertRules {
+    /// held funds above the amount
+    pub held_above: Option<Decimal>,
+
+    /// available funds below zero, after a dispute or chargeback of spent funds
+    pub negative_available: bool,
+
+    /// account locked by a chargeback
+    pub locked: bool,
+}
+
+impl AlertRules {
+    pub fn is_enabled(&self) -> bool {
+        self.held_above.is_some() || self.negative_available || self.locked
+    }
+
+    /// conditions the client entered since the balances before its transaction
+    pub(crate) fn entered(
+        &self,
+        before: Balances,
+        after: Balances,
+    ) -> impl Iterator<Item = AlertKind> {
+        let held_above = self
+            .held_above
+            .is_some_and(|max| after.held > max && before.held <= max);
+        let negative_available = self.negative_available
+            && after.available < Decimal::ZERO
+            && before.available >= Decimal::ZERO;
+        let locked = self.locked && after.locked && !before.locked;
+
+        [
+            (held_above, AlertKind::HeldAbove),
+            (negative_available, AlertKind::NegativeAvailable),
+            (locked, AlertKind::Locked),
+        ]
+        .into_iter()
+        .filter_map(|(entered, kind)| entered.then_some(kind))
+    }
+}
+
+/// balances of a client compared before and after a transaction
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub(crate) struct Balances {
+    pub(crate) available: Decimal,
+    pub(crate) held: Decimal,
+    pub(crate) locked: bool,
+}
+
+impl Balances {
+    pub(crate) fn of(client: Option<ClientView>) -> Self {
+        client.map_or_else(Self::default, |client| Self {
+            available: client.available(),
+            held: client.held(),
+            locked: client.locked(),
+        })
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    HeldAbove,
+    NegativeAvailable,
+    Locked,
+}
+
+/// a client entering an alert condition, with its balances after the transaction
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct Alert {
+    #[serde(rename = "alert")]
+    pub kind: AlertKind,
+    #[serde(rename = "client")]
+    pub client_id: ClientId,
+    /// transaction that brought the client into the condition
+    #[serde(rename = "tx")]
+    pub tx_id: TxId,
+    pub available: Decimal,
+    pub held: Decimal,
+}
+
+/// receives the alerts while the transactions are processed, alerts of a batch are only sent
+/// once the whole batch is applied
+pub trait AlertObserver: Send + Sync {
+    fn notify(&self, alert: &Alert);
+}
+
+impl<T: AlertObserver + ?Sized> AlertObserver for Box<T> {
+    fn notify(&self, alert: &Alert) {
+        (**self).notify(alert)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn entered_conditions() {
+        let rules = AlertRules {
+            held_above: Some(dec!(100)),
+            negative_available: true,
+            locked: true,
+        };
+        let before = Balances {
+            available: dec!(10),
+            held: dec!(50),
+            locked: false,
+        };
+
+        let entered = |after| rules.entered(before, after).collect::<Vec<_>>();
+        assert_eq!(entered(before), vec![]);
+        assert_eq!(
+            entered(Balances {
+                available: dec!(-40),
+                held: dec!(100.01),
+                locked: true,
+            }),
+            vec![
+                AlertKind::HeldAbove,
+                AlertKind::NegativeAvailable,
+                AlertKind::Locked
+            ]
+        );
+
+        // a client already in a condition isn't reported again
+        let negative = Balances {
+            available: dec!(-1),
+            ..before
+        };
+        assert_eq!(
+            rules
+                .entered(
+                    negative,
+                    Balances {
+                        available: dec!(-2),
+                        ..before
+                    }
+                )
+                .count(),
+            0
+        );
+        assert_eq!(AlertRules::default().entered(before, negative).count(), 0);
+    }
+}
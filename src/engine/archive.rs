@@ -0,0 +1,72 @@
+use std::io;
+
+use crate::models::tx::{Tx, TxId};
+
+/// bytes of an archived transaction
+pub const ARCHIVED_TX_BYTES: usize = 20;
+
+/// storage of the transactions the retention policy drops from memory, consulted when a dispute,
+/// resolve or chargeback names a transaction the engine no longer holds; the latest stored
+/// version of a transaction is the one loaded
+pub trait TxArchive: Send {
+    fn store(&mut self, id: TxId, tx: &ArchivedTx) -> io::Result<()>;
+
+    fn load(&mut self, id: TxId) -> io::Result<Option<ArchivedTx>>;
+
+    /// writes the buffered transactions, called before the engine state is saved
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: TxArchive + ?Sized> TxArchive for Box<T> {
+    fn store(&mut self, id: TxId, tx: &ArchivedTx) -> io::Result<()> {
+        (**self).store(id, tx)
+    }
+
+    fn load(&mut self, id: TxId) -> io::Result<Option<ArchivedTx>> {
+        (**self).load(id)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+}
+
+/// stored transaction encoded for an archive: amount, client id, type and dispute state
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ArchivedTx([u8; ARCHIVED_TX_BYTES]);
+
+impl ArchivedTx {
+    pub fn from_bytes(bytes: [u8; ARCHIVED_TX_BYTES]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; ARCHIVED_TX_BYTES] {
+        &self.0
+    }
+
+    pub(crate) fn new(tx: &Tx) -> Self {
+        Self(tx.to_bytes())
+    }
+
+    pub(crate) fn tx(&self) -> Tx {
+        Tx::from_bytes(&self.0)
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct MemoryArchive(pub(crate) ahash::AHashMap<TxId, ArchivedTx>);
+
+#[cfg(test)]
+impl TxArchive for MemoryArchive {
+    fn store(&mut self, id: TxId, tx: &ArchivedTx) -> io::Result<()> {
+        self.0.insert(id, *tx);
+        Ok(())
+    }
+
+    fn load(&mut self, id: TxId) -> io::Result<Option<ArchivedTx>> {
+        Ok(self.0.get(&id).copied())
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use ahash::AHashMap;
+use chrono::{DateTime, Utc};
+
+use crate::models::tx::TxId;
+
+/// times of the open authorizations, an authorization can't be captured once it's older than
+/// the ttl by the capture timestamp; authorizations or captures without a timestamp don't expire
+#[derive(Debug, Clone)]
+pub(crate) struct Authorizations {
+    ttl: Duration,
+    opened: AHashMap<TxId, DateTime<Utc>>,
+}
+
+impl Authorizations {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            opened: AHashMap::new(),
+        }
+    }
+
+    pub(crate) fn open(&mut self, id: TxId, timestamp: Option<DateTime<Utc>>) {
+        if let Some(timestamp) = timestamp {
+            self.opened.insert(id, timestamp);
+        }
+    }
+
+    /// whether the authorization expired before the capture time
+    pub(crate) fn expired(&self, id: TxId, at: Option<DateTime<Utc>>) -> bool {
+        match (self.opened.get(&id), at) {
+            (Some(opened), Some(at)) => (at - *opened)
+                .to_std()
+                .is_ok_and(|elapsed| elapsed > self.ttl),
+            _ => false,
+        }
+    }
+
+    /// forgets a captured authorization
+    pub(crate) fn close(&mut self, id: TxId) {
+        self.opened.remove(&id);
+    }
+
+    /// open authorizations, saved in checkpoints
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn state(&self) -> Vec<(TxId, DateTime<Utc>)> {
+        self.opened
+            .iter()
+            .map(|(id, opened)| (*id, *opened))
+            .collect()
+    }
+
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn restore(&mut self, state: Vec<(TxId, DateTime<Utc>)>) {
+        self.opened = state.into_iter().collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> Option<DateTime<Utc>> {
+        Some(format!("2024-01-01T{}Z", time).parse().unwrap())
+    }
+
+    #[test]
+    fn expiry() {
+        let mut authorizations = Authorizations::new(Duration::from_secs(60));
+        authorizations.open(1, at("10:00:00"));
+        authorizations.open(2, None);
+
+        assert!(!authorizations.expired(1, at("10:01:00")));
+        assert!(authorizations.expired(1, at("10:01:01")));
+        assert!(!authorizations.expired(1, None));
+        assert!(!authorizations.expired(2, at("23:00:00")));
+
+        let mut restored = Authorizations::new(Duration::from_secs(60));
+        restored.restore(authorizations.state());
+        assert!(restored.expired(1, at("11:00:00")));
+        restored.close(1);
+        assert!(!restored.expired(1, at("11:00:00")));
+    }
+}
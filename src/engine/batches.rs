@@ -0,0 +1,91 @@
+use ahash::AHashMap;
+use rust_decimal::Decimal;
+
+use crate::models::errors::ErrorKind;
+use crate::models::errors::ErrorKind::{BatchNotFound, SettlementMismatch};
+
+/// net amount of the accepted transactions of each open partner batch, deposits count
+/// positive and withdrawals negative; a settlement closes the batch when its amount matches
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Batches {
+    open: AHashMap<String, Decimal>,
+}
+
+impl Batches {
+    /// adds the signed amount of an accepted transaction to its batch
+    pub(crate) fn record(&mut self, batch: &str, amount: Decimal) {
+        match self.open.get_mut(batch) {
+            Some(net) => *net = net.saturating_add(amount),
+            None => {
+                self.open.insert(batch.to_string(), amount);
+            }
+        }
+    }
+
+    /// net of an open batch
+    pub(crate) fn net(&self, batch: &str) -> Option<Decimal> {
+        self.open.get(batch).copied()
+    }
+
+    /// open batches and their net
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, Decimal)> + '_ {
+        self.open.iter().map(|(batch, net)| (batch.as_str(), *net))
+    }
+
+    /// closes the batch when the declared net matches, a mismatching batch stays open
+    pub(crate) fn settle(&mut self, batch: &str, declared: Decimal) -> Result<(), ErrorKind> {
+        match self.open.get(batch) {
+            None => Err(BatchNotFound),
+            Some(net) if *net != declared => Err(SettlementMismatch),
+            Some(_) => {
+                self.open.remove(batch);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.open.capacity() * (size_of::<(String, Decimal)>() + 1)
+            + self.open.keys().map(String::len).sum::<usize>()
+    }
+
+    /// open batches, saved in checkpoints
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn state(&self) -> Vec<(String, Decimal)> {
+        self.open
+            .iter()
+            .map(|(batch, net)| (batch.clone(), *net))
+            .collect()
+    }
+
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn restore(&mut self, state: Vec<(String, Decimal)>) {
+        self.open = state.into_iter().collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn settle() {
+        let mut batches = Batches::default();
+        batches.record("a", dec!(10));
+        batches.record("a", dec!(-2.5));
+        batches.record("b", dec!(1));
+
+        assert_eq!(batches.net("a"), Some(dec!(7.5)));
+        assert_eq!(batches.settle("a", dec!(7)), Err(SettlementMismatch));
+        assert_eq!(batches.settle("c", dec!(0)), Err(BatchNotFound));
+        assert_eq!(batches.settle("a", dec!(7.50)), Ok(()));
+        assert_eq!(batches.net("a"), None);
+        assert_eq!(batches.settle("a", dec!(7.5)), Err(BatchNotFound));
+
+        let mut restored = Batches::default();
+        restored.restore(batches.state());
+        assert_eq!(restored.net("b"), Some(dec!(1)));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::models::client::Client;
+#[cfg(any(feature = "cli", test))]
+use crate::models::client::ClientView;
+use crate::models::tx::ClientId;
+
+/// clients indexed by id, client ids are u16 so a dense table replaces hashing
+/// and iterates in id order
+#[derive(Default, Clone)]
+pub(crate) struct ClientTable {
+    slots: Vec<Option<Client>>,
+}
+
+impl ClientTable {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(capacity.min(ClientId::MAX as usize + 1)),
+        }
+    }
+
+    pub(crate) fn get(&self, id: ClientId) -> Option<&Client> {
+        self.slots.get(id as usize).and_then(Option::as_ref)
+    }
+
+    pub(crate) fn get_mut(&mut self, id: ClientId) -> Option<&mut Client> {
+        self.slots.get_mut(id as usize).and_then(Option::as_mut)
+    }
+
+    /// returns the client, created on first use
+    pub(crate) fn get_or_insert(&mut self, id: ClientId) -> &mut Client {
+        let index = id as usize;
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, || None);
+        }
+
+        self.slots[index].get_or_insert_with(|| Client::new(id))
+    }
+
+    /// puts back the state of a client saved before a rolled back change
+    pub(crate) fn restore(&mut self, id: ClientId, client: Option<Client>) {
+        match client {
+            Some(client) => *self.get_or_insert(id) = client,
+            None => {
+                if let Some(slot) = self.slots.get_mut(id as usize) {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    /// adds a client saved in a checkpoint, replacing any client with its id
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn insert(&mut self, client: Client) {
+        let id = ClientView(&client).id();
+        *self.get_or_insert(id) = client;
+    }
+
+    /// estimated heap bytes of the table
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.slots.capacity() * size_of::<Option<Client>>()
+    }
+
+    /// number of clients
+    pub(crate) fn len(&self) -> usize {
+        self.slots.iter().flatten().count()
+    }
+
+    /// clients sorted by id
+    #[cfg(any(feature = "cli", feature = "wasm", test))]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Client> + '_ {
+        self.slots.iter().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::client::ClientReport;
+
+    #[test]
+    fn get_or_insert() {
+        let mut table = ClientTable::default();
+        table.get_or_insert(5);
+        table.get_or_insert(2);
+        table.get_or_insert(5);
+        table.get_or_insert(ClientId::MAX);
+
+        assert!(table.get(2).is_some());
+        assert!(table.get(3).is_none());
+        assert!(table.get(6).is_none());
+        assert!(table.get_mut(ClientId::MAX).is_some());
+        assert_eq!(
+            table
+                .iter()
+                .map(|client| ClientReport::new(client).id)
+                .collect::<Vec<_>>(),
+            vec![2, 5, ClientId::MAX]
+        );
+    }
+}
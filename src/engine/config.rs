@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use chrono::NaiveDate;
+
+use serde::Deserialize;
+
+use crate::engine::alerts::AlertRules;
+use crate::engine::fees::FeeSchedule;
+use crate::engine::min_balance::MinBalances;
+use crate::engine::retention::Retention;
+use crate::engine::velocity::VelocityLimits;
+use crate::models::dispute_state::DisputeState;
+use crate::models::tx::Tx;
+use crate::models::tx_type::TxType;
+
+/// how a transaction reusing an already stored tx id is handled
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Deserialize, Debug, Default, PartialEq, Copy, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicatePolicy {
+    /// reject the duplicate, processing continues
+    #[default]
+    Reject,
+    /// acknowledge without re-applying when type, client and amount are identical, reject otherwise
+    IgnoreIfIdentical,
+    /// reject the duplicate and abort processing
+    Abort,
+}
+
+/// funds a withdrawal can take
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[derive(Deserialize, Debug, Default, PartialEq, Copy, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub enum WithdrawalPolicy {
+    /// the available funds only
+    #[default]
+    Available,
+    /// the total, the available funds go negative once run out while the dispute holds stay whole
+    Total,
+}
+
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    pub duplicate_policy: DuplicatePolicy,
+
+    /// number of recent transactions remembered to drop redeliveries, disabled when None
+    pub dedup_window: Option<usize>,
+
+    /// how long a transaction is remembered by the dedup window, measured with the transaction timestamps
+    pub dedup_ttl: Option<Duration>,
+
+    /// maximum number of disputes per transaction, unlimited when None
+    pub max_disputes: Option<u32>,
+
+    /// whether a resolved transaction can be disputed again
+    pub allow_redispute: bool,
+
+    /// number of clients allocated up front
+    pub expected_clients: Option<usize>,
+
+    /// number of stored transactions allocated up front
+    pub expected_txs: Option<usize>,
+
+    /// bytes the engine maps may grow to, transactions that would exceed it are rejected
+    pub max_memory: Option<usize>,
+
+    /// withdrawal count and amount limits per client
+    pub velocity: VelocityLimits,
+
+    /// when settled deposits are dropped from memory, they're kept for the whole input by default
+    pub retention: Retention,
+
+    /// account conditions reported to the alert observers
+    pub alerts: AlertRules,
+
+    /// how long an authorization can be captured by the transaction timestamps, it doesn't
+    /// expire when None
+    pub authorization_ttl: Option<Duration>,
+
+    /// funds withdrawals can take, pending withdrawals only hold available funds
+    pub withdrawal_policy: WithdrawalPolicy,
+
+    /// whether withdrawals and pending withdrawals are rejected while a dispute of the client is
+    /// open
+    pub block_withdrawals_on_dispute: bool,
+
+    /// available funds withdrawals and pending withdrawals can't take a client below
+    pub min_balance: MinBalances,
+
+    /// transactions with a later effective date are queued until the engine is advanced to
+    /// their date, effective dates are ignored when None
+    pub as_of: Option<NaiveDate>,
+
+    /// fees charged with withdrawals and chargebacks
+    pub fees: FeeSchedule,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            duplicate_policy: DuplicatePolicy::default(),
+            dedup_window: None,
+            dedup_ttl: None,
+            max_disputes: None,
+            allow_redispute: true,
+            expected_clients: None,
+            expected_txs: None,
+            max_memory: None,
+            velocity: VelocityLimits::default(),
+            retention: Retention::default(),
+            alerts: AlertRules::default(),
+            authorization_ttl: None,
+            withdrawal_policy: WithdrawalPolicy::default(),
+            block_withdrawals_on_dispute: false,
+            min_balance: MinBalances::default(),
+            as_of: None,
+            fees: FeeSchedule::default(),
+        }
+    }
+}
+
+impl EngineConfig {
+    /// whether a stored deposit can't be disputed again under the dispute options
+    pub(crate) fn is_settled(&self, tx: &Tx) -> bool {
+        tx.tx_type() == TxType::Deposit
+            && match tx.dispute_state() {
+                DisputeState::ChargedBack => true,
+                DisputeState::Resolved => {
+                    !self.allow_redispute
+                        || self.max_disputes.is_some_and(|max| tx.disputes() >= max)
+                }
+                _ => false,
+            }
+    }
+}
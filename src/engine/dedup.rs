@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use ahash::AHashMap;
+use chrono::{DateTime, Utc};
+#[cfg(any(feature = "cli", test))]
+use serde::{Deserialize, Serialize};
+
+use crate::models::tx::TxId;
+use crate::models::tx_type::TxType;
+
+type DedupKey = (TxType, TxId);
+
+/// bounded cache of recently seen transactions, used to drop redeliveries of at-least-once streams;
+/// the ttl is measured with the transaction timestamps so a replayed input is deduplicated the same way
+#[derive(Clone)]
+pub(crate) struct DedupWindow {
+    capacity: usize,
+    ttl: Option<Duration>,
+    // latest transaction timestamp seen, a transaction without one is seen at this time
+    clock: Option<DateTime<Utc>>,
+    seen: AHashMap<DedupKey, Option<DateTime<Utc>>>,
+    order: VecDeque<(DedupKey, Option<DateTime<Utc>>)>,
+    duplicates: u64,
+}
+
+/// transactions remembered by the dedup window, saved in checkpoints
+#[cfg(any(feature = "cli", test))]
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct DedupState {
+    clock: Option<DateTime<Utc>>,
+    seen: Vec<(DedupKey, Option<DateTime<Utc>>)>,
+    duplicates: u64,
+}
+
+impl DedupWindow {
+    pub(crate) fn new(capacity: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            capacity,
+            ttl,
+            clock: None,
+            seen: AHashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+            duplicates: 0,
+        }
+    }
+
+    /// records the transaction and returns whether it was already seen within the window; the
+    /// dispute lifecycle legitimately references a tx id again, its redeliveries are left to the
+    /// dispute state of the referenced transaction
+    pub(crate) fn check(
+        &mut self,
+        tx_type: TxType,
+        id: TxId,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> bool {
+        if matches!(
+            tx_type,
+            TxType::Dispute | TxType::Resolve | TxType::Chargeback
+        ) {
+            return false;
+        }
+
+        self.clock = self.clock.max(timestamp);
+        self.evict();
+
+        let key = (tx_type, id);
+        if self.seen.contains_key(&key) {
+            self.duplicates += 1;
+            return true;
+        }
+
+        if self.capacity == 0 {
+            return false;
+        }
+
+        if self.order.len() >= self.capacity {
+            if let Some((oldest, _)) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+
+        self.seen.insert(key, self.clock);
+        self.order.push_back((key, self.clock));
+        false
+    }
+
+    pub(crate) fn duplicates(&self) -> u64 {
+        self.duplicates
+    }
+
+    /// estimated heap bytes of the window
+    pub(crate) fn memory_usage(&self) -> usize {
+        type Entry = (DedupKey, Option<DateTime<Utc>>);
+        self.seen.capacity() * (size_of::<Entry>() + 1) + self.order.capacity() * size_of::<Entry>()
+    }
+
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn state(&self) -> DedupState {
+        DedupState {
+            clock: self.clock,
+            seen: self.order.iter().copied().collect(),
+            duplicates: self.duplicates,
+        }
+    }
+
+    /// replaces the remembered transactions, the capacity and ttl are kept
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn restore(&mut self, state: DedupState) {
+        self.clock = state.clock;
+        self.order = state.seen.into_iter().collect();
+        self.seen = self.order.iter().copied().collect();
+        self.duplicates = state.duplicates;
+    }
+
+    /// forgets the transactions seen at least a ttl before the clock, those seen before the first
+    /// timestamp included; they're seen in clock order
+    fn evict(&mut self) {
+        let (ttl, now) = match (self.ttl, self.clock) {
+            (Some(ttl), Some(now)) => (ttl, now),
+            _ => return,
+        };
+
+        while let Some((key, seen_at)) = self.order.front() {
+            let expired = seen_at.is_none_or(|seen_at| {
+                now.signed_duration_since(seen_at)
+                    .to_std()
+                    .is_ok_and(|age| age >= ttl)
+            });
+            if !expired {
+                break;
+            }
+
+            self.seen.remove(key);
+            self.order.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(secs, 0)
+    }
+
+    #[test]
+    fn check_capacity() {
+        let mut window = DedupWindow::new(2, None);
+
+        assert!(!window.check(TxType::Deposit, 1, None));
+        assert!(!window.check(TxType::Withdrawal, 1, None));
+        assert!(window.check(TxType::Deposit, 1, None));
+        assert!(!window.check(TxType::Deposit, 2, None));
+        // evicted by capacity
+        assert!(!window.check(TxType::Deposit, 1, None));
+        assert_eq!(window.duplicates(), 1);
+    }
+
+    #[test]
+    fn check_ttl() {
+        let mut window = DedupWindow::new(10, Some(Duration::from_secs(5)));
+
+        assert!(!window.check(TxType::Deposit, 1, at(0)));
+        assert!(window.check(TxType::Deposit, 1, at(4)));
+        // without a timestamp it's seen at the latest one
+        assert!(window.check(TxType::Deposit, 1, None));
+        assert!(!window.check(TxType::Deposit, 1, at(5)));
+        assert_eq!(window.duplicates(), 2);
+    }
+
+    #[test]
+    fn check_dispute_lifecycle() {
+        let mut window = DedupWindow::new(10, None);
+
+        assert!(!window.check(TxType::Deposit, 1, None));
+        for tx_type in [TxType::Dispute, TxType::Resolve, TxType::Dispute] {
+            assert!(!window.check(tx_type, 1, None));
+        }
+        assert_eq!(window.duplicates(), 0);
+    }
+
+    #[test]
+    fn state_restore() {
+        let mut window = DedupWindow::new(10, Some(Duration::from_secs(5)));
+        window.check(TxType::Deposit, 1, at(0));
+        window.check(TxType::Deposit, 2, at(3));
+
+        let mut restored = DedupWindow::new(10, Some(Duration::from_secs(5)));
+        restored.restore(window.state());
+        assert_eq!(restored.state(), window.state());
+        assert!(restored.check(TxType::Deposit, 2, at(5)));
+        assert!(!restored.check(TxType::Deposit, 1, at(5)));
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Datelike, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::models::money::PRECISION;
+use crate::models::tx::{ClientId, TxId, TxInput};
+
+/// basis points per unit
+const BPS: Decimal = Decimal::from_parts(10_000, 0, 0, false, 0);
+
+/// fees the engine charges with the transactions, no fee is charged by default
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct FeeSchedule {
+    /// percent of the amount of each withdrawal, taken with the withdrawal so a client that
+    /// can't pay both is rejected
+    pub withdrawal_percent: Option<Decimal>,
+
+    /// flat fee of each chargeback, taken from the available funds even though the chargeback
+    /// locks the account; the fee is skipped when the available funds can't cover it
+    pub chargeback_flat: Option<Decimal>,
+
+    /// withdrawals of each client charged no withdrawal fee before the others, counted like
+    /// the withdrawals of the report
+    pub free_withdrawals: Option<u32>,
+
+    /// flat fee of each withdrawal, charged with the percent one
+    pub withdrawal_flat: Option<Decimal>,
+
+    /// basis points of the deposits of each client over the monthly threshold, taken from the
+    /// deposit and skipped when the available funds can't cover it
+    pub deposit_bps: Option<Decimal>,
+
+    /// deposits of each client per utc month charged no deposit fee
+    pub deposit_threshold: Decimal,
+}
+
+impl FeeSchedule {
+    /// fee of a withdrawal after the client made `withdrawals` others, rounded up to the engine
+    /// precision
+    pub(crate) fn withdrawal(&self, amount: Decimal, withdrawals: u32) -> Option<Decimal> {
+        if self.free_withdrawals.is_some_and(|free| withdrawals < free) {
+            return None;
+        }
+
+        let percent = self
+            .withdrawal_percent
+            .map(|percent| round_up(amount.saturating_mul(percent) / Decimal::ONE_HUNDRED));
+        match (percent, self.withdrawal_flat) {
+            (Some(percent), Some(flat)) => Some(percent.saturating_add(flat)),
+            (fee, None) | (None, fee) => fee,
+        }
+        .filter(|fee| !fee.is_zero())
+    }
+
+    /// fee of a deposit taking the month deposits of its client from `before` over the
+    /// threshold, charged on the part over it only
+    pub(crate) fn deposit(&self, before: Decimal, amount: Decimal) -> Option<Decimal> {
+        let over = before
+            .saturating_add(amount)
+            .saturating_sub(before.max(self.deposit_threshold));
+        self.deposit_bps
+            .filter(|_| over.is_sign_positive())
+            .map(|bps| round_up(over.saturating_mul(bps) / BPS))
+            .filter(|fee| !fee.is_zero())
+    }
+
+    pub(crate) fn chargeback(&self) -> Option<Decimal> {
+        self.chargeback_flat.filter(|fee| !fee.is_zero())
+    }
+}
+
+fn round_up(fee: Decimal) -> Decimal {
+    fee.round_dp_with_strategy(PRECISION, RoundingStrategy::AwayFromZero)
+}
+
+/// deposits of a client in the utc month of its latest timestamped deposit, a deposit
+/// without a timestamp counts for that month
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Copy, Clone)]
+pub(crate) struct MonthlyDeposits {
+    month: Option<(i32, u32)>,
+    amount: Decimal,
+}
+
+impl MonthlyDeposits {
+    /// adds a deposit, a deposit of another month starts the count over; returns the
+    /// deposits of the month before it
+    pub(crate) fn add(&mut self, amount: Decimal, timestamp: Option<DateTime<Utc>>) -> Decimal {
+        if let Some(timestamp) = timestamp {
+            let month = Some((timestamp.year(), timestamp.month()));
+            if self.month != month {
+                self.month = month;
+                self.amount = Decimal::ZERO;
+            }
+        }
+
+        let before = self.amount;
+        self.amount = self.amount.saturating_add(amount);
+        before
+    }
+}
+
+/// transaction a fee is charged for
+#[derive(Serialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeKind {
+    Deposit,
+    Withdrawal,
+    Chargeback,
+}
+
+/// fee charged to a client, linked to the transaction it was charged for
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct Fee {
+    #[serde(rename = "client")]
+    pub client_id: ClientId,
+    #[serde(rename = "tx")]
+    pub tx_id: TxId,
+    pub kind: FeeKind,
+    pub amount: Decimal,
+}
+
+impl Fee {
+    pub(crate) fn new(tx_input: &TxInput, kind: FeeKind, amount: Decimal) -> Self {
+        Self {
+            client_id: tx_input.client_id,
+            tx_id: tx_input.id,
+            kind,
+            amount,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn fees() {
+        let schedule = FeeSchedule {
+            withdrawal_percent: Some(dec!(1.5)),
+            chargeback_flat: Some(dec!(15)),
+            ..Default::default()
+        };
+        assert_eq!(schedule.withdrawal(dec!(100), 0), Some(dec!(1.5)));
+        assert_eq!(schedule.withdrawal(dec!(0.00333), 0), Some(dec!(0.0001)));
+        assert_eq!(schedule.withdrawal(dec!(0), 0), None);
+        assert_eq!(schedule.chargeback(), Some(dec!(15)));
+        assert_eq!(schedule.deposit(dec!(0), dec!(100)), None);
+        assert_eq!(FeeSchedule::default().withdrawal(dec!(100), 0), None);
+    }
+
+    #[test]
+    fn tiers() {
+        let schedule = FeeSchedule {
+            withdrawal_percent: Some(dec!(1)),
+            free_withdrawals: Some(2),
+            withdrawal_flat: Some(dec!(0.5)),
+            deposit_bps: Some(dec!(25)),
+            deposit_threshold: dec!(1000),
+            ..Default::default()
+        };
+        assert_eq!(schedule.withdrawal(dec!(100), 1), None);
+        assert_eq!(schedule.withdrawal(dec!(100), 2), Some(dec!(1.5)));
+        assert_eq!(schedule.withdrawal(dec!(0), 3), Some(dec!(0.5)));
+
+        assert_eq!(schedule.deposit(dec!(0), dec!(1000)), None);
+        assert_eq!(schedule.deposit(dec!(800), dec!(400)), Some(dec!(0.5)));
+        assert_eq!(schedule.deposit(dec!(1200), dec!(100)), Some(dec!(0.25)));
+    }
+
+    #[test]
+    fn monthly_deposits() {
+        let at = |month: u32| Some(format!("2024-{:02}-15T12:00:00Z", month).parse().unwrap());
+        let mut deposits = MonthlyDeposits::default();
+        assert_eq!(deposits.add(dec!(5), None), dec!(0));
+        assert_eq!(deposits.add(dec!(10), at(1)), dec!(0));
+        assert_eq!(deposits.add(dec!(20), None), dec!(10));
+        assert_eq!(deposits.add(dec!(30), at(1)), dec!(30));
+        assert_eq!(deposits.add(dec!(40), at(2)), dec!(0));
+    }
+}
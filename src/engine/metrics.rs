@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::models::tx_type::TxType;
+
+/// counters of the engine since it was created, for services exporting its health to their
+/// monitoring; the counters are not saved in checkpoints, a resumed engine starts from zero
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct Metrics {
+    /// transactions applied per type, retries of an idempotency key and value-dated transactions
+    /// still queued are not counted
+    pub applied: BTreeMap<TxType, u64>,
+
+    /// transactions rejected per error code
+    pub rejected: BTreeMap<&'static str, u64>,
+
+    /// clients with at least one transaction
+    pub clients: usize,
+
+    /// deposits and withdrawals stored for disputes
+    pub stored_txs: usize,
+
+    /// estimated heap bytes of the engine, see [`crate::Engine::memory_usage`]
+    pub memory_bytes: usize,
+}
+
+/// applied and rejected transactions counted by the engine, rolled back with a rejected batch
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Counters {
+    applied: BTreeMap<TxType, u64>,
+    rejected: BTreeMap<&'static str, u64>,
+}
+
+impl Counters {
+    pub(crate) fn applied(&mut self, tx_type: TxType) {
+        *self.applied.entry(tx_type).or_default() += 1;
+    }
+
+    pub(crate) fn rejected(&mut self, code: &'static str) {
+        *self.rejected.entry(code).or_default() += 1;
+    }
+
+    /// metrics of the counters, with the gauges of the engine
+    pub(crate) fn metrics(
+        &self,
+        clients: usize,
+        stored_txs: usize,
+        memory_bytes: usize,
+    ) -> Metrics {
+        Metrics {
+            applied: self.applied.clone(),
+            rejected: self.rejected.clone(),
+            clients,
+            stored_txs,
+            memory_bytes,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+#[cfg(feature = "cli")]
+use std::error::Error;
+#[cfg(feature = "cli")]
+use std::io;
+
+use ahash::AHashMap;
+use rust_decimal::Decimal;
+
+use crate::models::tx::ClientId;
+
+/// available funds a withdrawal can't take a client below, a client floor overrides the
+/// global one
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MinBalances {
+    /// floor of the clients without their own
+    pub global: Option<Decimal>,
+
+    /// floors of single clients
+    pub clients: AHashMap<ClientId, Decimal>,
+}
+
+impl MinBalances {
+    /// floor of the client, if any
+    pub(crate) fn of(&self, client_id: ClientId) -> Option<Decimal> {
+        self.clients.get(&client_id).copied().or(self.global)
+    }
+
+    /// reads the client floors of a limits file, a csv with `client` and `min_balance` columns
+    #[cfg(feature = "cli")]
+    pub fn read_clients<R: io::Read>(&mut self, reader: R) -> Result<(), Box<dyn Error>> {
+        #[derive(serde::Deserialize)]
+        struct Row {
+            client: ClientId,
+            min_balance: Decimal,
+        }
+
+        for row in csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+            .deserialize()
+        {
+            let row: Row = row?;
+            self.clients.insert(row.client, row.min_balance);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn floors() {
+        let floors = MinBalances {
+            global: Some(dec!(10)),
+            clients: AHashMap::from_iter([(1, dec!(50)), (2, dec!(0))]),
+        };
+
+        assert_eq!(floors.of(1), Some(dec!(50)));
+        assert_eq!(floors.of(2), Some(dec!(0)));
+        assert_eq!(floors.of(3), Some(dec!(10)));
+        assert_eq!(MinBalances::default().of(1), None);
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn read_clients() {
+        let mut floors = MinBalances::default();
+        floors
+            .read_clients("client, min_balance\n1, 50\n2, 0\n".as_bytes())
+            .expect("failed to read the limits file");
+
+        assert_eq!(floors.of(1), Some(dec!(50)));
+        assert_eq!(floors.of(2), Some(dec!(0)));
+        assert!(floors.read_clients("client\n1\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::tx::TxId;
+
+/// when transactions are dropped from the transaction map: without an archive only the settled
+/// deposits, those that can't be disputed again (charged back, or resolved without redisputes or
+/// at the dispute limit), are dropped and they're unknown to later disputes; with an archive every
+/// stored transaction is dropped and loaded back by a later dispute, resolve or chargeback.
+/// The tx id of a dropped transaction can be reused
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Retention {
+    /// number of transactions processed after a transaction is retained before it's dropped
+    pub after_txs: Option<u64>,
+
+    /// time after it's retained a transaction is dropped, measured with the transaction timestamps,
+    /// transactions retained without a timestamp are only dropped by count
+    pub window: Option<Duration>,
+}
+
+impl Retention {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.after_txs.is_some() || self.window.is_some()
+    }
+}
+
+/// a transaction waiting to be dropped
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct RetainedTx {
+    id: TxId,
+    /// transactions processed when it was retained
+    seq: u64,
+    timestamp: Option<DateTime<Utc>>,
+}
+
+/// retained transactions in retention order, saved in checkpoints
+#[cfg(any(feature = "cli", test))]
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct RetainedState {
+    seq: u64,
+    retained: Vec<RetainedTx>,
+    dropped: u64,
+}
+
+/// tracks the retained transactions until the retention policy drops them, a transaction
+/// retained again is dropped at its first expiry
+#[derive(Debug, Clone)]
+pub(crate) struct Retained {
+    retention: Retention,
+    seq: u64,
+    retained: VecDeque<RetainedTx>,
+    dropped: u64,
+}
+
+impl Retained {
+    pub(crate) fn new(retention: Retention) -> Self {
+        Self {
+            retention,
+            seq: 0,
+            retained: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    /// counts a processed transaction
+    pub(crate) fn tick(&mut self) {
+        self.seq += 1;
+    }
+
+    pub(crate) fn retain(&mut self, id: TxId, timestamp: Option<DateTime<Utc>>) {
+        // without a count a transaction retained without a timestamp is never dropped
+        if self.retention.after_txs.is_none() && timestamp.is_none() {
+            return;
+        }
+
+        self.retained.push_back(RetainedTx {
+            id,
+            seq: self.seq,
+            timestamp,
+        });
+    }
+
+    /// the oldest retained transaction once it's due to be dropped at the time of the current transaction
+    pub(crate) fn pop_expired(&mut self, now: Option<DateTime<Utc>>) -> Option<TxId> {
+        let oldest = self.retained.front()?;
+
+        let by_count = self
+            .retention
+            .after_txs
+            .is_some_and(|after_txs| self.seq - oldest.seq >= after_txs);
+        let by_time = match (self.retention.window, oldest.timestamp, now) {
+            (Some(window), Some(retained_at), Some(now)) => now
+                .signed_duration_since(retained_at)
+                .to_std()
+                .is_ok_and(|age| age >= window),
+            _ => false,
+        };
+
+        if !by_count && !by_time {
+            return None;
+        }
+
+        self.retained.pop_front().map(|retained| retained.id)
+    }
+
+    /// counts a transaction removed from memory
+    pub(crate) fn record_drop(&mut self) {
+        self.dropped += 1;
+    }
+
+    /// transactions dropped by the retention policy
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// estimated heap bytes of the transactions waiting to be dropped
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.retained.capacity() * size_of::<RetainedTx>()
+    }
+
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn state(&self) -> RetainedState {
+        RetainedState {
+            seq: self.seq,
+            retained: self.retained.iter().cloned().collect(),
+            dropped: self.dropped,
+        }
+    }
+
+    /// replaces the tracked transactions, the retention policy is kept
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn restore(&mut self, state: RetainedState) {
+        self.seq = state.seq;
+        self.retained = state.retained.into();
+        self.dropped = state.dropped;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(secs, 0)
+    }
+
+    #[test]
+    fn pop_expired_after_txs() {
+        let mut retained = Retained::new(Retention {
+            after_txs: Some(2),
+            ..Default::default()
+        });
+
+        retained.tick();
+        retained.retain(1, None);
+        retained.tick();
+        assert_eq!(retained.pop_expired(None), None);
+        retained.tick();
+        assert_eq!(retained.pop_expired(None), Some(1));
+        assert_eq!(retained.pop_expired(None), None);
+    }
+
+    #[test]
+    fn pop_expired_window() {
+        let mut retained = Retained::new(Retention {
+            window: Some(Duration::from_secs(60)),
+            ..Default::default()
+        });
+
+        retained.retain(1, at(0));
+        retained.retain(2, None);
+        retained.retain(3, at(30));
+        assert_eq!(retained.pop_expired(at(59)), None);
+        assert_eq!(retained.pop_expired(at(60)), Some(1));
+        assert_eq!(retained.pop_expired(None), None);
+        // transactions retained without a timestamp are kept by a time window
+        assert_eq!(retained.pop_expired(at(1_000)), Some(3));
+        assert_eq!(retained.pop_expired(at(1_000)), None);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use rust_decimal::Decimal;
+
+use crate::engine::Engine;
+use crate::models::client::ClientView;
+use crate::models::errors::{Error, ErrorKind};
+use crate::models::tx::{ClientId, TxId, TxInput, TxView};
+
+/// custom check run on every transaction before it's applied, a rejected
+/// transaction leaves the engine unchanged
+pub trait ValidationRule: Send + Sync {
+    /// the kind of the returned error is reported, its context is replaced by the transaction's
+    fn validate(&self, tx: &TxInput, engine: &EngineView) -> Result<(), Error>;
+}
+
+/// read-only state of the engine available to validation rules
+#[derive(Copy, Clone)]
+pub struct EngineView<'a>(pub(crate) &'a Engine);
+
+impl EngineView<'_> {
+    pub fn client(&self, id: ClientId) -> Option<ClientView<'_>> {
+        self.0.client(id)
+    }
+
+    pub fn transaction(&self, id: TxId) -> Option<TxView<'_>> {
+        self.0.transaction(id)
+    }
+}
+
+/// rejects every transaction of the listed clients
+#[derive(Debug, Default, Clone)]
+pub struct BlockedClients(pub HashSet<ClientId>);
+
+impl ValidationRule for BlockedClients {
+    fn validate(&self, tx: &TxInput, _: &EngineView) -> Result<(), Error> {
+        match self.0.contains(&tx.client_id) {
+            true => Err(ErrorKind::RuleViolation("blocked_client").into()),
+            false => Ok(()),
+        }
+    }
+}
+
+/// rejects deposits and withdrawals above the amount
+#[derive(Debug, Clone)]
+pub struct MaxAmount(pub Decimal);
+
+impl ValidationRule for MaxAmount {
+    fn validate(&self, tx: &TxInput, _: &EngineView) -> Result<(), Error> {
+        match tx.amount {
+            Some(amount) if amount > self.0 => Err(ErrorKind::RuleViolation("max_amount").into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::models::tx_type::TxType;
+
+    fn tx(tx_type: TxType, client_id: ClientId, id: TxId, amount: Option<Decimal>) -> TxInput {
+        TxInput {
+            tx_type,
+            client_id,
+            id,
+            amount,
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        }
+    }
+
+    /// rejects withdrawals of clients with open disputes
+    struct NoWithdrawalUnderDispute;
+
+    impl ValidationRule for NoWithdrawalUnderDispute {
+        fn validate(&self, tx: &TxInput, engine: &EngineView) -> Result<(), Error> {
+            let held = engine.client(tx.client_id).map(|client| client.held());
+
+            match (tx.tx_type, held) {
+                (TxType::Withdrawal, Some(held)) if !held.is_zero() => {
+                    Err(ErrorKind::RuleViolation("withdrawal_under_dispute").into())
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[test]
+    fn validate_rules() {
+        let mut engine = Engine::new();
+        engine.add_rule(BlockedClients(HashSet::from([7])));
+        engine.add_rule(MaxAmount(dec!(100)));
+        engine.add_rule(NoWithdrawalUnderDispute);
+
+        let err = engine
+            .process_tx(&tx(TxType::Deposit, 7, 1, Some(dec!(1))))
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::RuleViolation("blocked_client"));
+        assert_eq!(err.code(), "rule_violation");
+        assert_eq!(err.tx_id(), Some(1));
+        assert!(engine.client(7).is_none());
+
+        let err = engine
+            .process_tx(&tx(TxType::Deposit, 1, 2, Some(dec!(100.01))))
+            .unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::RuleViolation("max_amount"));
+        assert!(engine.transaction(2).is_none());
+
+        for tx in [
+            tx(TxType::Deposit, 1, 3, Some(dec!(100))),
+            tx(TxType::Deposit, 1, 4, Some(dec!(10))),
+            tx(TxType::Dispute, 1, 4, None),
+        ] {
+            engine.process_tx(&tx).expect("failed to process tx");
+        }
+
+        let err = engine
+            .process_tx(&tx(TxType::Withdrawal, 1, 5, Some(dec!(1))))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "rejected by rule withdrawal_under_dispute (tx 5, client 1)"
+        );
+        assert_eq!(engine.client(1).map(|c| c.available()), Some(dec!(100)));
+    }
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::dedup::DedupState;
+use crate::engine::retention::RetainedState;
+use crate::engine::velocity::VelocityState;
+use crate::models::client::Client;
+use crate::models::tx::{Tx, TxId, TxInput};
+
+/// clients, stored transactions, velocity windows, retained transactions, authorization times, open batches, queued
+/// value-dated transactions, idempotency keys and dedup window of an
+/// engine, saved in checkpoints so an interrupted run continues with the same balances
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct EngineState {
+    pub(crate) clients: Vec<Client>,
+    pub(crate) transactions: Vec<(TxId, Tx)>,
+    pub(crate) velocity: Option<VelocityState>,
+    pub(crate) retained: Option<RetainedState>,
+    pub(crate) authorizations: Option<Vec<(TxId, DateTime<Utc>)>>,
+    /// net of the open settlement batches
+    #[serde(default)]
+    pub(crate) batches: Vec<(String, Decimal)>,
+    /// transactions dated after the as-of date
+    #[serde(default)]
+    pub(crate) queued: Vec<TxInput>,
+    pub(crate) idempotency_keys: Vec<String>,
+    pub(crate) replays: u64,
+    /// store order of the last stored transaction
+    #[serde(default)]
+    pub(crate) seq: u32,
+    #[serde(default)]
+    pub(crate) dedup: Option<DedupState>,
+}
@@ -0,0 +1,92 @@
+use chrono::NaiveDate;
+
+use crate::models::tx::TxInput;
+
+/// transactions dated after the as-of date of the engine, in input order
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ValueDated {
+    queued: Vec<TxInput>,
+}
+
+impl ValueDated {
+    pub(crate) fn queue(&mut self, tx: &TxInput) {
+        self.queued.push(tx.clone());
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// drops the transactions queued after the first `len`, rolls back a failed batch
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.queued.truncate(len);
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &TxInput> + '_ {
+        self.queued.iter()
+    }
+
+    /// removes the transactions effective by the date, ordered by date and then input order
+    pub(crate) fn release(&mut self, as_of: NaiveDate) -> Vec<TxInput> {
+        let (mut due, queued): (Vec<_>, Vec<_>) = std::mem::take(&mut self.queued)
+            .into_iter()
+            .partition(|tx| tx.effective_date.is_some_and(|date| date <= as_of));
+        self.queued = queued;
+        due.sort_by_key(|tx| tx.effective_date);
+        due
+    }
+
+    /// queued transactions, saved in checkpoints
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn state(&self) -> Vec<TxInput> {
+        self.queued.clone()
+    }
+
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn restore(&mut self, state: Vec<TxInput>) {
+        self.queued = state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::models::tx_type::TxType;
+
+    fn dated(id: u32, day: u32) -> TxInput {
+        TxInput {
+            tx_type: TxType::Deposit,
+            client_id: 1,
+            id,
+            amount: Some(dec!(1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: NaiveDate::from_ymd_opt(2024, 3, day),
+        }
+    }
+
+    #[test]
+    fn release() {
+        let mut value_dated = ValueDated::default();
+        for (id, day) in [(1, 5), (2, 3), (3, 9), (4, 3)] {
+            value_dated.queue(&dated(id, day));
+        }
+
+        let due = value_dated.release(NaiveDate::from_ymd_opt(2024, 3, 5).unwrap());
+        assert_eq!(
+            due.iter().map(|tx| tx.id).collect::<Vec<_>>(),
+            vec![2, 4, 1]
+        );
+        assert_eq!(value_dated.len(), 1);
+
+        value_dated.queue(&dated(5, 10));
+        value_dated.truncate(1);
+        assert_eq!(
+            value_dated.iter().map(|tx| tx.id).collect::<Vec<_>>(),
+            vec![3]
+        );
+    }
+}
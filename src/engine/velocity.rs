@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use ahash::AHashMap;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::tx::ClientId;
+
+/// withdrawal limits of each client, windows are measured with the transaction timestamps,
+/// withdrawals without a timestamp count for the whole input
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct VelocityLimits {
+    /// maximum number of withdrawals per client within the window
+    pub max_withdrawals: Option<u32>,
+
+    /// time window of the withdrawal count, the whole input when None
+    pub window: Option<Duration>,
+
+    /// maximum cumulative withdrawal amount per client and utc day
+    pub max_daily_amount: Option<Decimal>,
+}
+
+impl VelocityLimits {
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.max_withdrawals.is_some() || self.max_daily_amount.is_some()
+    }
+}
+
+/// recent withdrawals of a client
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+struct ClientVelocity {
+    // timestamps of the withdrawals still within the window, oldest first
+    withdrawals: VecDeque<Option<DateTime<Utc>>>,
+    day: Option<NaiveDate>,
+    day_amount: Decimal,
+}
+
+/// tracks the withdrawals of each client against the velocity limits
+#[derive(Debug, Clone)]
+pub(crate) struct Velocity {
+    limits: VelocityLimits,
+    clients: AHashMap<ClientId, ClientVelocity>,
+    rejected: u64,
+}
+
+/// withdrawals tracked by the velocity limits, saved in checkpoints
+#[cfg(any(feature = "cli", test))]
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct VelocityState {
+    clients: Vec<(ClientId, ClientVelocity)>,
+    rejected: u64,
+}
+
+impl Velocity {
+    pub(crate) fn new(limits: VelocityLimits) -> Self {
+        Self {
+            limits,
+            clients: AHashMap::new(),
+            rejected: 0,
+        }
+    }
+
+    /// whether the withdrawal stays within the limits, a withdrawal over them is counted as rejected
+    pub(crate) fn check(
+        &mut self,
+        client_id: ClientId,
+        amount: Decimal,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> bool {
+        let limits = self.limits;
+        let within = match self.clients.get_mut(&client_id) {
+            Some(client) => {
+                client.expire(limits.window, timestamp);
+                client.within(&limits, amount, timestamp)
+            }
+            None => ClientVelocity::default().within(&limits, amount, timestamp),
+        };
+
+        if !within {
+            self.rejected += 1;
+        }
+        within
+    }
+
+    /// records an accepted withdrawal
+    pub(crate) fn record(
+        &mut self,
+        client_id: ClientId,
+        amount: Decimal,
+        timestamp: Option<DateTime<Utc>>,
+    ) {
+        let client = self.clients.entry(client_id).or_default();
+        if self.limits.max_withdrawals.is_some() {
+            client.withdrawals.push_back(timestamp);
+        }
+
+        if self.limits.max_daily_amount.is_some() {
+            let day = timestamp.map(|ts| ts.date_naive());
+            if client.day != day {
+                client.day = day;
+                client.day_amount = Decimal::ZERO;
+            }
+            client.day_amount = client.day_amount.saturating_add(amount);
+        }
+    }
+
+    /// withdrawals rejected by the limits
+    pub(crate) fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn state(&self) -> VelocityState {
+        VelocityState {
+            clients: self
+                .clients
+                .iter()
+                .map(|(id, client)| (*id, client.clone()))
+                .collect(),
+            rejected: self.rejected,
+        }
+    }
+
+    /// replaces the tracked withdrawals, the limits are kept
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn restore(&mut self, state: VelocityState) {
+        self.clients = state.clients.into_iter().collect();
+        self.rejected = state.rejected;
+    }
+}
+
+impl ClientVelocity {
+    fn within(
+        &self,
+        limits: &VelocityLimits,
+        amount: Decimal,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> bool {
+        let spent = match self.day == timestamp.map(|ts| ts.date_naive()) {
+            true => self.day_amount,
+            false => Decimal::ZERO,
+        };
+
+        limits
+            .max_withdrawals
+            .is_none_or(|max| self.withdrawals.len() < max as usize)
+            && limits
+                .max_daily_amount
+                .is_none_or(|max| spent.checked_add(amount).is_some_and(|total| total <= max))
+    }
+
+    /// drops the withdrawals older than the window, those without a timestamp never expire
+    fn expire(&mut self, window: Option<Duration>, now: Option<DateTime<Utc>>) {
+        let (window, now) = match (window, now) {
+            (Some(window), Some(now)) => (window, now),
+            _ => return,
+        };
+
+        self.withdrawals.retain(|timestamp| {
+            timestamp.is_none_or(|ts| {
+                now.signed_duration_since(ts)
+                    .to_std()
+                    .is_ok_and(|age| age < window)
+            })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn at(secs: i64) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(secs, 0)
+    }
+
+    fn withdraw(
+        velocity: &mut Velocity,
+        amount: Decimal,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> bool {
+        let within = velocity.check(1, amount, timestamp);
+        if within {
+            velocity.record(1, amount, timestamp);
+        }
+        within
+    }
+
+    #[test]
+    fn check_max_withdrawals() {
+        let mut velocity = Velocity::new(VelocityLimits {
+            max_withdrawals: Some(2),
+            window: Some(Duration::from_secs(60)),
+            ..Default::default()
+        });
+
+        assert!(withdraw(&mut velocity, dec!(1), at(0)));
+        assert!(withdraw(&mut velocity, dec!(1), at(30)));
+        assert!(!withdraw(&mut velocity, dec!(1), at(59)));
+        // the first withdrawal left the window
+        assert!(withdraw(&mut velocity, dec!(1), at(60)));
+        assert!(velocity.check(2, dec!(1), at(60)));
+        assert_eq!(velocity.rejected(), 1);
+    }
+
+    #[test]
+    fn check_max_withdrawals_without_window() {
+        let mut velocity = Velocity::new(VelocityLimits {
+            max_withdrawals: Some(1),
+            ..Default::default()
+        });
+
+        assert!(withdraw(&mut velocity, dec!(1), at(0)));
+        assert!(!withdraw(&mut velocity, dec!(1), at(86_400 * 10)));
+        assert!(!withdraw(&mut velocity, dec!(1), None));
+        assert_eq!(velocity.rejected(), 2);
+    }
+
+    #[test]
+    fn check_max_daily_amount() {
+        let mut velocity = Velocity::new(VelocityLimits {
+            max_daily_amount: Some(dec!(100)),
+            ..Default::default()
+        });
+
+        assert!(!withdraw(&mut velocity, dec!(100.01), at(0)));
+        assert!(withdraw(&mut velocity, dec!(60), at(0)));
+        assert!(withdraw(&mut velocity, dec!(40), at(3_600)));
+        assert!(!withdraw(&mut velocity, dec!(0.01), at(86_399)));
+        // next utc day
+        assert!(withdraw(&mut velocity, dec!(100), at(86_400)));
+        // withdrawals without a timestamp share their own day
+        assert!(withdraw(&mut velocity, dec!(100), None));
+        assert!(!withdraw(&mut velocity, dec!(1), None));
+        assert_eq!(velocity.rejected(), 3);
+    }
+}
@@ -0,0 +1,120 @@
+pub(crate) mod encoding;
+pub(crate) mod fixed_width;
+pub(crate) mod mt940;
+pub(crate) mod ofx;
+pub(crate) mod open_banking;
+pub(crate) mod records;
+pub(crate) mod xml;
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::models::tx::TxId;
+use crate::models::tx_type::TxType;
+
+/// maps an external transaction reference to a tx id,
+/// numeric references are used as is and any other reference is hashed (FNV-1a)
+pub(crate) fn tx_id_from_reference(reference: &str) -> TxId {
+    if let Ok(id) = reference.parse::<TxId>() {
+        return id;
+    }
+
+    reference.bytes().fold(0x811c9dc5, |hash: TxId, b| {
+        (hash ^ b as TxId).wrapping_mul(0x01000193)
+    })
+}
+
+/// tx ids of the references of a statement, two references mapped to the same tx id (a numeric
+/// reference and a hash, or two hashes) fail the import
+#[derive(Debug, Default)]
+pub(crate) struct TxIds {
+    references: HashMap<TxId, String>,
+    collision: Option<String>,
+}
+
+impl TxIds {
+    /// tx id of the reference, the first collision is kept for [`TxIds::check`]
+    pub(crate) fn id(&mut self, reference: &str) -> TxId {
+        let id = tx_id_from_reference(reference);
+        match self.references.entry(id) {
+            Entry::Occupied(other) if other.get() != reference => {
+                self.collision.get_or_insert_with(|| {
+                    format!(
+                        "references {} and {} map to the same tx id {}",
+                        other.get(),
+                        reference,
+                        id
+                    )
+                });
+            }
+            Entry::Occupied(_) => {}
+            Entry::Vacant(entry) => {
+                entry.insert(reference.to_string());
+            }
+        }
+
+        id
+    }
+
+    /// fails with the first collision of the references
+    pub(crate) fn check(self) -> Result<(), String> {
+        self.collision.map_or(Ok(()), Err)
+    }
+}
+
+/// transaction type of a type field, by the csv type names or else looked up in the codes
+pub(crate) fn tx_type(value: &str, codes: &HashMap<String, TxType>) -> Result<TxType, String> {
+    let tx_type = match value {
+        "deposit" => Some(TxType::Deposit),
+        "withdrawal" => Some(TxType::Withdrawal),
+        "dispute" => Some(TxType::Dispute),
+        "resolve" => Some(TxType::Resolve),
+        "chargeback" => Some(TxType::Chargeback),
+        "withdrawal_pending" => Some(TxType::WithdrawalPending),
+        "withdrawal_settle" => Some(TxType::WithdrawalSettle),
+        "withdrawal_cancel" => Some(TxType::WithdrawalCancel),
+        "authorize" => Some(TxType::Authorize),
+        "capture" => Some(TxType::Capture),
+        "settlement" => Some(TxType::Settlement),
+        "reversal" => Some(TxType::Reversal),
+        _ => codes.get(value).copied(),
+    };
+
+    tx_type.ok_or_else(|| format!("invalid type: unknown transaction type `{}`", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tx_id_from_reference_numeric() {
+        assert_eq!(tx_id_from_reference("42"), 42);
+    }
+
+    #[test]
+    fn tx_id_from_reference_hashed() {
+        assert_eq!(tx_id_from_reference(""), 0x811c9dc5);
+        assert_eq!(tx_id_from_reference("a"), 0xe40c292c);
+        assert_eq!(tx_id_from_reference("FIT-1"), tx_id_from_reference("FIT-1"));
+        assert_ne!(tx_id_from_reference("FIT-1"), tx_id_from_reference("FIT-2"));
+    }
+
+    #[test]
+    fn tx_ids_collision() {
+        let mut ids = TxIds::default();
+        assert_eq!(ids.id("a"), 0xe40c292c);
+        assert_eq!(ids.id("a"), 0xe40c292c);
+        ids.id("FIT-1");
+        assert_eq!(ids.check(), Ok(()));
+
+        // the numeric reference is the hash of "a"
+        let mut ids = TxIds::default();
+        ids.id("a");
+        assert_eq!(ids.id("3826002220"), 0xe40c292c);
+        assert_eq!(
+            ids.check(),
+            Err("references a and 3826002220 map to the same tx id 3826002220".to_string())
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::io;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+const CHUNK: usize = 8 * 1024;
+const UTF8_BOM: &[u8] = &[0xef, 0xbb, 0xbf];
+const UTF16LE_BOM: &[u8] = &[0xff, 0xfe];
+
+/// character encoding of the input file
+#[derive(ValueEnum, Deserialize, Debug, Default, PartialEq, Copy, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub enum InputEncoding {
+    #[default]
+    Utf8,
+    Utf16le,
+    Latin1,
+}
+
+/// transcodes the input to utf-8 and strips a leading byte order mark,
+/// invalid utf-16 sequences are replaced with U+FFFD
+pub(crate) struct Decoder<R> {
+    inner: R,
+    encoding: InputEncoding,
+    // input bytes not decoded yet: a partial utf-16 unit or surrogate pair, or a possible bom
+    carry: Vec<u8>,
+    // decoded utf-8 not returned yet
+    pending: Vec<u8>,
+    pos: usize,
+    bom_checked: bool,
+    eof: bool,
+}
+
+impl<R: io::Read> Decoder<R> {
+    pub(crate) fn new(inner: R, encoding: InputEncoding) -> Self {
+        Self {
+            inner,
+            encoding,
+            carry: Vec::new(),
+            pending: Vec::new(),
+            pos: 0,
+            bom_checked: false,
+            eof: false,
+        }
+    }
+
+    /// decodes the next chunk of the input into pending
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; CHUNK];
+        let n = self.inner.read(&mut chunk)?;
+        self.eof = n == 0;
+
+        let mut data = std::mem::take(&mut self.carry);
+        data.extend_from_slice(&chunk[..n]);
+
+        if !self.bom_checked {
+            if data.len() < UTF8_BOM.len() && !self.eof {
+                self.carry = data;
+                return Ok(());
+            }
+
+            let bom = match self.encoding {
+                InputEncoding::Utf8 => UTF8_BOM,
+                InputEncoding::Utf16le => UTF16LE_BOM,
+                InputEncoding::Latin1 => &[],
+            };
+            if data.starts_with(bom) {
+                data.drain(..bom.len());
+            }
+            self.bom_checked = true;
+        }
+
+        self.pending.clear();
+        self.pos = 0;
+
+        match self.encoding {
+            InputEncoding::Utf8 => self.pending = data,
+            InputEncoding::Latin1 => {
+                let decoded: String = data.iter().map(|&b| b as char).collect();
+                self.pending = decoded.into_bytes();
+            }
+            InputEncoding::Utf16le => {
+                let mut units: Vec<u16> = data
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .collect();
+                let mut rest = data.len() - units.len() * 2;
+
+                // keep a trailing high surrogate until its pair is read
+                if !self.eof && units.last().is_some_and(|u| (0xd800..0xdc00).contains(u)) {
+                    units.pop();
+                    rest += 2;
+                }
+
+                if self.eof {
+                    rest = 0;
+                }
+                self.carry = data[data.len() - rest..].to_vec();
+
+                let decoded: String = char::decode_utf16(units)
+                    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect();
+                self.pending = decoded.into_bytes();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: io::Read> io::Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.pending.len() {
+            if self.eof {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+
+        let n = buf.len().min(self.pending.len() - self.pos);
+        buf[..n].copy_from_slice(&self.pending[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn decode(input: &[u8], encoding: InputEncoding) -> String {
+        let mut output = String::new();
+        Decoder::new(input, encoding)
+            .read_to_string(&mut output)
+            .expect("failed to decode");
+        output
+    }
+
+    /// reader returning a single byte per read, to split units and the bom across reads
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl io::Read for ByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((b, rest)) if !buf.is_empty() => {
+                    buf[0] = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn utf8_strips_bom() {
+        assert_eq!(
+            decode(b"\xef\xbb\xbftype,client", InputEncoding::Utf8),
+            "type,client"
+        );
+        assert_eq!(decode(b"type,client", InputEncoding::Utf8), "type,client");
+        assert_eq!(decode(b"", InputEncoding::Utf8), "");
+    }
+
+    #[test]
+    fn latin1() {
+        assert_eq!(decode(b"caf\xe9", InputEncoding::Latin1), "café");
+    }
+
+    #[test]
+    fn utf16le_split_reads() {
+        let mut input = vec![0xff, 0xfe];
+        for unit in "a,€𝄞\n".encode_utf16() {
+            input.extend_from_slice(&unit.to_le_bytes());
+        }
+
+        let mut output = String::new();
+        Decoder::new(ByteReader(&input), InputEncoding::Utf16le)
+            .read_to_string(&mut output)
+            .expect("failed to decode");
+
+        assert_eq!(output, "a,€𝄞\n");
+    }
+
+    #[test]
+    fn utf16le_invalid() {
+        assert_eq!(
+            decode(&[0x00, 0xd8, 0x41, 0x00], InputEncoding::Utf16le),
+            "\u{fffd}A"
+        );
+    }
+}
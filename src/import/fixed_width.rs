@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::import;
+use crate::models::tx::TxInput;
+use crate::models::tx_type::TxType;
+
+/// byte range of a field in a record line
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FieldRange {
+    pub offset: usize,
+    pub length: usize,
+}
+
+/// column offsets and lengths of fixed width records, read from a json file
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FixedWidthLayout {
+    #[serde(rename = "type")]
+    pub tx_type: FieldRange,
+    pub client: FieldRange,
+    pub tx: FieldRange,
+    pub amount: FieldRange,
+    #[serde(default)]
+    pub timestamp: Option<FieldRange>,
+
+    /// codes of the type field besides the csv type names, e.g. `{"D": "deposit"}`
+    #[serde(default)]
+    pub types: HashMap<String, TxType>,
+
+    /// decimal places implied by amounts written without a decimal point, `000012345` is 123.45 with 2
+    #[serde(default)]
+    pub implied_decimals: u32,
+}
+
+impl FixedWidthLayout {
+    pub fn from_reader<R: io::Read>(reader: R) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// parses a record line, padding around the values is trimmed and fields past the end of the line are empty
+    pub(crate) fn parse(&self, line: &str) -> Result<TxInput, String> {
+        let tx_type = import::tx_type(field(line, self.tx_type, "type")?, &self.types)?;
+
+        let client_id = field(line, self.client, "client")?
+            .parse()
+            .map_err(|err| invalid("client", err))?;
+
+        let id = field(line, self.tx, "tx")?
+            .parse()
+            .map_err(|err| invalid("tx", err))?;
+
+        let amount = match field(line, self.amount, "amount")? {
+            "" => None,
+            value if self.implied_decimals > 0 && !value.contains('.') => {
+                let mut amount = value
+                    .parse::<Decimal>()
+                    .map_err(|err| invalid("amount", err))?;
+                amount
+                    .set_scale(self.implied_decimals)
+                    .map_err(|err| invalid("amount", err))?;
+                Some(amount)
+            }
+            value => Some(
+                value
+                    .parse::<Decimal>()
+                    .map_err(|err| invalid("amount", err))?,
+            ),
+        };
+
+        let timestamp = match self.timestamp {
+            Some(range) => match field(line, range, "timestamp")? {
+                "" => None,
+                value => Some(
+                    value
+                        .parse::<DateTime<Utc>>()
+                        .map_err(|err| invalid("timestamp", err))?,
+                ),
+            },
+            None => None,
+        };
+
+        Ok(TxInput {
+            tx_type,
+            client_id,
+            id,
+            amount,
+            timestamp,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        })
+    }
+}
+
+/// trimmed value of a field, fails when the range splits a multi-byte character
+fn field<'l>(line: &'l str, range: FieldRange, name: &str) -> Result<&'l str, String> {
+    let start = range.offset.min(line.len());
+    let end = range.offset.saturating_add(range.length).min(line.len());
+
+    line.get(start..end)
+        .map(str::trim)
+        .ok_or_else(|| format!("invalid {}: field splits a character", name))
+}
+
+fn invalid(name: &str, err: impl ToString) -> String {
+    format!("invalid {}: {}", name, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    const LAYOUT: &str = r#"{
+        "type": {"offset": 0, "length": 1},
+        "client": {"offset": 1, "length": 5},
+        "tx": {"offset": 6, "length": 8},
+        "amount": {"offset": 14, "length": 10},
+        "types": {"D": "deposit", "W": "withdrawal", "X": "dispute"},
+        "implied_decimals": 2
+    }"#;
+
+    fn layout() -> FixedWidthLayout {
+        FixedWidthLayout::from_reader(LAYOUT.as_bytes()).expect("invalid layout")
+    }
+
+    #[test]
+    fn parse_record() {
+        let tx = layout()
+            .parse("D00001000000420000012345")
+            .expect("failed to parse");
+
+        assert_eq!(tx.tx_type, TxType::Deposit);
+        assert_eq!(tx.client_id, 1);
+        assert_eq!(tx.id, 42);
+        assert_eq!(tx.amount, Some(dec!(123.45)));
+        assert_eq!(tx.timestamp, None);
+
+        let tx = layout()
+            .parse("W00002000000430000001.5")
+            .expect("failed to parse");
+        assert_eq!(tx.tx_type, TxType::Withdrawal);
+        assert_eq!(tx.amount, Some(dec!(1.5)));
+    }
+
+    #[test]
+    fn parse_short_record() {
+        let tx = layout().parse("X0000100000042").expect("failed to parse");
+
+        assert_eq!(tx.tx_type, TxType::Dispute);
+        assert_eq!(tx.id, 42);
+        assert_eq!(tx.amount, None);
+    }
+
+    #[test]
+    fn parse_type_names() {
+        let mut layout = layout();
+        layout.types.clear();
+        layout.tx_type.length = 10;
+        layout.client.offset = 10;
+        layout.tx.offset = 15;
+        layout.amount.offset = 23;
+
+        let tx = layout
+            .parse("withdrawal0000300000007       100")
+            .expect("failed to parse");
+        assert_eq!(tx.tx_type, TxType::Withdrawal);
+        assert_eq!(tx.client_id, 3);
+        assert_eq!(tx.amount, Some(dec!(1.00)));
+    }
+
+    #[test]
+    fn parse_invalid_field() {
+        let layout = layout();
+
+        assert_eq!(
+            layout.parse("R00001000000420000012345").unwrap_err(),
+            "invalid type: unknown transaction type `R`"
+        );
+        assert_eq!(
+            layout.parse("D0000x000000420000012345").unwrap_err(),
+            "invalid client: invalid digit found in string"
+        );
+        assert!(layout
+            .parse("D000010000004200000123a5")
+            .unwrap_err()
+            .starts_with("invalid amount:"));
+    }
+
+    #[test]
+    fn layout_unknown_field() {
+        assert!(FixedWidthLayout::from_reader(
+            r#"{"type": {"offset": 0, "length": 1, "width": 2}}"#.as_bytes()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use std::error::Error;
+use std::io;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use tracing::debug;
+
+use crate::import::TxIds;
+use crate::models::tx::{ClientId, TxInput};
+use crate::models::tx_type::TxType;
+
+/// parses the :61: statement lines of MT940 messages into deposits and withdrawals,
+/// the client id defaults to the :25: account identification when not provided
+pub(crate) fn parse<R: io::Read>(
+    mut input: R,
+    client_id: Option<ClientId>,
+) -> Result<Vec<TxInput>, Box<dyn Error>> {
+    let mut content = String::new();
+    input.read_to_string(&mut content)?;
+
+    let mut account_id: Option<ClientId> = None;
+    let mut txs = Vec::new();
+    let mut ids = TxIds::default();
+
+    for line in content.lines() {
+        // fields start with :tag:, continuation lines are ignored
+        let (tag, value) = match line.strip_prefix(':').and_then(|l| l.split_once(':')) {
+            Some(field) => field,
+            None => continue,
+        };
+
+        match tag {
+            "25" => account_id = parse_account(value),
+            "61" => {
+                let client_id = client_id.or(account_id).ok_or(
+                    "mt940 statement has no numeric account identification, a client id must be provided",
+                )?;
+
+                match parse_statement_line(value, client_id, &mut ids) {
+                    Some(tx) => txs.push(tx),
+                    None => debug!(client_id, line = value, "statement line not parsed"),
+                }
+            }
+            _ => {}
+        }
+    }
+    ids.check()?;
+
+    Ok(txs)
+}
+
+/// account identification is either the account number or bank code/account number
+fn parse_account(value: &str) -> Option<ClientId> {
+    let value = value.trim();
+    let account = value.rsplit_once('/').map_or(value, |(_, account)| account);
+
+    account.parse::<ClientId>().ok()
+}
+
+/// value date, [entry date], D/C mark, [funds code], amount, type, reference[//bank reference]
+fn parse_statement_line(value: &str, client_id: ClientId, ids: &mut TxIds) -> Option<TxInput> {
+    let rest = value.trim().get(6..)?;
+    let rest = match rest.get(..4) {
+        Some(entry_date) if entry_date.bytes().all(|b| b.is_ascii_digit()) => &rest[4..],
+        _ => rest,
+    };
+
+    let (tx_type, rest) = if let Some(rest) = rest.strip_prefix("RC") {
+        (TxType::Withdrawal, rest)
+    } else if let Some(rest) = rest.strip_prefix("RD") {
+        (TxType::Deposit, rest)
+    } else if let Some(rest) = rest.strip_prefix('C') {
+        (TxType::Deposit, rest)
+    } else if let Some(rest) = rest.strip_prefix('D') {
+        (TxType::Withdrawal, rest)
+    } else {
+        return None;
+    };
+
+    // optional funds code
+    let rest = match rest.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => &rest[1..],
+        _ => rest,
+    };
+
+    let amount_len = rest
+        .find(|c: char| !c.is_ascii_digit() && c != ',')
+        .unwrap_or(rest.len());
+    let amount = rest[..amount_len].replace(',', ".");
+    let amount = Decimal::from_str(amount.trim_end_matches('.')).ok()?;
+
+    // transaction type identification code
+    let reference = rest.get(amount_len + 4..)?;
+    let (customer_ref, bank_ref) = match reference.split_once("//") {
+        Some((customer_ref, bank_ref)) => (customer_ref, Some(bank_ref.trim())),
+        None => (reference, None),
+    };
+
+    let reference = match (customer_ref.trim(), bank_ref) {
+        ("" | "NONREF", Some(bank_ref)) if !bank_ref.is_empty() => bank_ref,
+        ("" | "NONREF", _) => return None,
+        (customer_ref, _) => customer_ref,
+    };
+
+    Some(TxInput {
+        tx_type,
+        client_id,
+        id: ids.id(reference),
+        amount: Some(amount),
+        timestamp: None,
+        idempotency_key: None,
+        batch: None,
+        effective_date: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::import::tx_id_from_reference;
+
+    const STATEMENT: &str = r#"{1:F01BANKDEFFXXXX0000000000}{4:
+:20:STARTUMSE
+:25:10020030/7
+:28C:00001/001
+:60F:C240101EUR1000,00
+:61:2401010101C100,00NTRF1001//BANKREF1
+:86:166?00GUTSCHRIFT
+:61:240102D20,5NCHGNONREF//BANKREF2
+:61:240103RD3,NTRFREF-3
+:61:240104C1,00NTRFNONREF
+:62F:C240104EUR1082,50
+-}"#;
+
+    #[test]
+    fn parse_success() {
+        let txs = parse(STATEMENT.as_bytes(), None).expect("failed to parse");
+
+        assert_eq!(txs.len(), 3);
+        assert_eq!(txs[0].tx_type, TxType::Deposit);
+        assert_eq!(txs[0].client_id, 7);
+        assert_eq!(txs[0].id, 1001);
+        assert_eq!(txs[0].amount, Some(dec!(100.00)));
+        assert_eq!(txs[1].tx_type, TxType::Withdrawal);
+        assert_eq!(txs[1].id, tx_id_from_reference("BANKREF2"));
+        assert_eq!(txs[1].amount, Some(dec!(20.5)));
+        assert_eq!(txs[2].tx_type, TxType::Deposit);
+        assert_eq!(txs[2].id, tx_id_from_reference("REF-3"));
+        assert_eq!(txs[2].amount, Some(dec!(3)));
+    }
+
+    #[test]
+    fn parse_fail_no_client_id() {
+        let statement = ":25:DE89370400440532013000\n:61:240101C1,00NTRF1\n";
+
+        assert!(parse(statement.as_bytes(), None).is_err());
+        assert_eq!(
+            parse(statement.as_bytes(), Some(2))
+                .expect("failed to parse")
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use std::error::Error;
+use std::io;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use tracing::debug;
+
+use crate::import::TxIds;
+use crate::models::tx::{ClientId, TxInput};
+use crate::models::tx_type::TxType;
+
+#[derive(Default)]
+struct StmtTrn {
+    fit_id: Option<String>,
+    amount: Option<String>,
+}
+
+/// parses the STMTTRN records of an OFX statement (SGML or XML flavour) into deposits and withdrawals,
+/// the client id defaults to the statement ACCTID when not provided
+pub(crate) fn parse<R: io::Read>(
+    mut input: R,
+    client_id: Option<ClientId>,
+) -> Result<Vec<TxInput>, Box<dyn Error>> {
+    let mut content = String::new();
+    input.read_to_string(&mut content)?;
+
+    let mut account_id = None;
+    let mut records = Vec::new();
+    let mut current: Option<StmtTrn> = None;
+
+    // every element starts with '<', in SGML the value runs until the next tag
+    for element in content.split('<').skip(1) {
+        let (tag, value) = match element.split_once('>') {
+            Some((tag, value)) => (tag.trim(), value.trim()),
+            None => continue,
+        };
+
+        match tag {
+            "STMTTRN" => current = Some(StmtTrn::default()),
+            "/STMTTRN" => records.extend(current.take()),
+            "ACCTID" => account_id = Some(value.to_string()),
+            "FITID" => {
+                if let Some(trn) = current.as_mut() {
+                    trn.fit_id = Some(value.to_string())
+                }
+            }
+            "TRNAMT" => {
+                if let Some(trn) = current.as_mut() {
+                    trn.amount = Some(value.to_string())
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let client_id = match client_id {
+        Some(id) => id,
+        None => account_id
+            .and_then(|id| id.parse::<ClientId>().ok())
+            .ok_or("ofx statement has no numeric ACCTID, a client id must be provided")?,
+    };
+
+    let mut ids = TxIds::default();
+    let txs = records
+        .into_iter()
+        .filter_map(|trn| match to_tx_input(&trn, client_id, &mut ids) {
+            Some(tx) => Some(tx),
+            None => {
+                debug!(client_id, fit_id = ?trn.fit_id, "STMTTRN record not parsed");
+                None
+            }
+        })
+        .collect();
+    ids.check()?;
+
+    Ok(txs)
+}
+
+fn to_tx_input(trn: &StmtTrn, client_id: ClientId, ids: &mut TxIds) -> Option<TxInput> {
+    let amount = Decimal::from_str(trn.amount.as_deref()?).ok()?;
+    let id = ids.id(trn.fit_id.as_deref()?);
+
+    let tx_type = if amount.is_sign_negative() {
+        TxType::Withdrawal
+    } else {
+        TxType::Deposit
+    };
+
+    Some(TxInput {
+        tx_type,
+        client_id,
+        id,
+        amount: Some(amount.abs()),
+        timestamp: None,
+        idempotency_key: None,
+        batch: None,
+        effective_date: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::import::tx_id_from_reference;
+
+    const SGML: &str = r#"OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<BANKACCTFROM><BANKID>1234<ACCTID>7<ACCTTYPE>CHECKING</BANKACCTFROM>
+<BANKTRANLIST>
+<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20240101<TRNAMT>100.50<FITID>1001</STMTTRN>
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20240102<TRNAMT>-20.25<FITID>ABC-2</STMTTRN>
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20240103<TRNAMT>x<FITID>1003</STMTTRN>
+</BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>"#;
+
+    #[test]
+    fn parse_sgml_success() {
+        let txs = parse(SGML.as_bytes(), None).expect("failed to parse");
+
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[0].tx_type, TxType::Deposit);
+        assert_eq!(txs[0].client_id, 7);
+        assert_eq!(txs[0].id, 1001);
+        assert_eq!(txs[0].amount, Some(dec!(100.50)));
+        assert_eq!(txs[1].tx_type, TxType::Withdrawal);
+        assert_eq!(txs[1].id, tx_id_from_reference("ABC-2"));
+        assert_eq!(txs[1].amount, Some(dec!(20.25)));
+    }
+
+    #[test]
+    fn parse_xml_success() {
+        let xml = r#"<?xml version="1.0"?><OFX><BANKACCTFROM><ACCTID>ACC-9</ACCTID></BANKACCTFROM>
+<STMTTRN><TRNTYPE>CREDIT</TRNTYPE><TRNAMT>5</TRNAMT><FITID>1</FITID></STMTTRN></OFX>"#;
+        let txs = parse(xml.as_bytes(), Some(3)).expect("failed to parse");
+
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].client_id, 3);
+        assert_eq!(txs[0].id, 1);
+        assert_eq!(txs[0].amount, Some(dec!(5)));
+    }
+
+    #[test]
+    fn parse_fail_no_client_id() {
+        let xml = r#"<OFX><ACCTID>ACC-9</ACCTID></OFX>"#;
+
+        assert!(parse(xml.as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn parse_fail_tx_id_collision() {
+        // the hash of the reference "a" is 3826002220
+        let sgml = "<ACCTID>7<STMTTRN><TRNAMT>1<FITID>a</STMTTRN>\
+                    <STMTTRN><TRNAMT>2<FITID>3826002220</STMTTRN>";
+        let err = parse(sgml.as_bytes(), None).expect_err("colliding references should fail");
+
+        assert_eq!(
+            err.to_string(),
+            "references a and 3826002220 map to the same tx id 3826002220"
+        );
+    }
+}
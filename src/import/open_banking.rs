@@ -0,0 +1,216 @@
+use std::error::Error;
+use std::io;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::import::TxIds;
+use crate::models::tx::{ClientId, TxInput};
+use crate::models::tx_type::TxType;
+
+/// UK Open Banking or Berlin Group account transactions response
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Statement {
+    Uk {
+        #[serde(rename = "Data")]
+        data: UkData,
+    },
+    BerlinGroup {
+        transactions: BerlinGroupTransactions,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+struct UkData {
+    #[serde(rename = "Transaction", default)]
+    transactions: Vec<UkTransaction>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct UkTransaction {
+    account_id: Option<String>,
+    transaction_id: Option<String>,
+    credit_debit_indicator: String,
+    status: String,
+    amount: UkAmount,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct UkAmount {
+    amount: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct BerlinGroupTransactions {
+    #[serde(default)]
+    booked: Vec<BerlinGroupTransaction>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct BerlinGroupTransaction {
+    transaction_id: Option<String>,
+    entry_reference: Option<String>,
+    transaction_amount: BerlinGroupAmount,
+}
+
+#[derive(Deserialize, Debug)]
+struct BerlinGroupAmount {
+    amount: String,
+}
+
+/// parses the booked transactions of an account transactions response into deposits and withdrawals,
+/// UK transactions default to their AccountId as client id when not provided
+pub(crate) fn parse<R: io::Read>(
+    input: R,
+    client_id: Option<ClientId>,
+) -> Result<Vec<TxInput>, Box<dyn Error>> {
+    let statement: Statement = serde_json::from_reader(input)?;
+
+    let mut txs = Vec::new();
+    let mut ids = TxIds::default();
+
+    match statement {
+        Statement::Uk { data } => {
+            for tx in data.transactions {
+                if !tx.status.eq_ignore_ascii_case("booked") {
+                    continue;
+                }
+
+                let client_id = client_id
+                    .or_else(|| tx.account_id.as_deref()?.parse::<ClientId>().ok())
+                    .ok_or("transaction has no numeric AccountId, a client id must be provided")?;
+
+                let amount = match Decimal::from_str(&tx.amount.amount) {
+                    Ok(amount) => amount,
+                    Err(err) => {
+                        debug!(client_id, amount = tx.amount.amount, error = %err, "transaction amount not parsed");
+                        continue;
+                    }
+                };
+
+                let tx_type = if tx.credit_debit_indicator.eq_ignore_ascii_case("debit") {
+                    TxType::Withdrawal
+                } else {
+                    TxType::Deposit
+                };
+
+                match tx.transaction_id {
+                    Some(reference) => txs.push(TxInput {
+                        tx_type,
+                        client_id,
+                        id: ids.id(&reference),
+                        amount: Some(amount.abs()),
+                        timestamp: None,
+                        idempotency_key: None,
+                        batch: None,
+                        effective_date: None,
+                    }),
+                    None => debug!("transaction has no TransactionId"),
+                }
+            }
+        }
+        Statement::BerlinGroup { transactions } => {
+            let client_id =
+                client_id.ok_or("a client id must be provided for berlin group statements")?;
+
+            for tx in transactions.booked {
+                let amount = match Decimal::from_str(&tx.transaction_amount.amount) {
+                    Ok(amount) => amount,
+                    Err(err) => {
+                        debug!(client_id, amount = tx.transaction_amount.amount, error = %err, "transaction amount not parsed");
+                        continue;
+                    }
+                };
+
+                let tx_type = if amount.is_sign_negative() {
+                    TxType::Withdrawal
+                } else {
+                    TxType::Deposit
+                };
+
+                match tx.transaction_id.or(tx.entry_reference) {
+                    Some(reference) => txs.push(TxInput {
+                        tx_type,
+                        client_id,
+                        id: ids.id(&reference),
+                        amount: Some(amount.abs()),
+                        timestamp: None,
+                        idempotency_key: None,
+                        batch: None,
+                        effective_date: None,
+                    }),
+                    None => debug!("transaction has no transactionId or entryReference"),
+                }
+            }
+        }
+    }
+    ids.check()?;
+
+    Ok(txs)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::import::tx_id_from_reference;
+
+    #[test]
+    fn parse_uk_success() {
+        let json = r#"{"Data": {"Transaction": [
+            {"AccountId": "22", "TransactionId": "1", "CreditDebitIndicator": "Credit", "Status": "Booked",
+             "Amount": {"Amount": "10.00", "Currency": "GBP"}},
+            {"AccountId": "22", "TransactionId": "tx-2", "CreditDebitIndicator": "Debit", "Status": "Booked",
+             "Amount": {"Amount": "2.50", "Currency": "GBP"}},
+            {"AccountId": "22", "TransactionId": "3", "CreditDebitIndicator": "Credit", "Status": "Pending",
+             "Amount": {"Amount": "1.00", "Currency": "GBP"}}
+        ]}, "Links": {}, "Meta": {}}"#;
+        let txs = parse(json.as_bytes(), None).expect("failed to parse");
+
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[0].tx_type, TxType::Deposit);
+        assert_eq!(txs[0].client_id, 22);
+        assert_eq!(txs[0].id, 1);
+        assert_eq!(txs[0].amount, Some(dec!(10.00)));
+        assert_eq!(txs[1].tx_type, TxType::Withdrawal);
+        assert_eq!(txs[1].id, tx_id_from_reference("tx-2"));
+        assert_eq!(txs[1].amount, Some(dec!(2.50)));
+    }
+
+    #[test]
+    fn parse_berlin_group_success() {
+        let json = r#"{"account": {"iban": "DE2310010010123456788"}, "transactions": {
+            "booked": [
+                {"transactionId": "1", "transactionAmount": {"currency": "EUR", "amount": "256.67"}},
+                {"entryReference": "2", "transactionAmount": {"currency": "EUR", "amount": "-20.00"}}
+            ],
+            "pending": [
+                {"transactionId": "3", "transactionAmount": {"currency": "EUR", "amount": "5.00"}}
+            ]
+        }}"#;
+        let txs = parse(json.as_bytes(), Some(4)).expect("failed to parse");
+
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[0].tx_type, TxType::Deposit);
+        assert_eq!(txs[0].client_id, 4);
+        assert_eq!(txs[0].id, 1);
+        assert_eq!(txs[0].amount, Some(dec!(256.67)));
+        assert_eq!(txs[1].tx_type, TxType::Withdrawal);
+        assert_eq!(txs[1].id, 2);
+        assert_eq!(txs[1].amount, Some(dec!(20.00)));
+    }
+
+    #[test]
+    fn parse_berlin_group_fail_no_client_id() {
+        let json = r#"{"transactions": {"booked": []}}"#;
+
+        assert!(parse(json.as_bytes(), None).is_err());
+    }
+}
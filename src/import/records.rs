@@ -0,0 +1,321 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::str;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::ValueEnum;
+use csv::ByteRecord;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::import;
+use crate::models::tx::TxInput;
+use crate::models::tx_type::TxType;
+
+/// column order of csv input without a header row
+const POSITIONAL_COLUMNS: [&str; 8] = [
+    "type",
+    "client",
+    "tx",
+    "amount",
+    "timestamp",
+    "idempotency_key",
+    "batch",
+    "effective_date",
+];
+
+/// columns a csv input header must contain, other columns are ignored
+const REQUIRED_COLUMNS: [&str; 4] = ["type", "client", "tx", "amount"];
+
+/// decimal separator of input amounts
+#[derive(ValueEnum, Deserialize, Debug, Default, PartialEq, Copy, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub enum DecimalSeparator {
+    /// `1234.56` in comma delimited files
+    #[default]
+    Dot,
+    /// `1.234,56` in semicolon delimited files, dots group thousands
+    Comma,
+}
+
+impl DecimalSeparator {
+    pub(crate) fn delimiter(&self) -> u8 {
+        match self {
+            DecimalSeparator::Dot => b',',
+            DecimalSeparator::Comma => b';',
+        }
+    }
+
+    /// rewrites an amount in the dot notation parsed by Decimal
+    fn normalize<'a>(&self, amount: &'a str) -> Cow<'a, str> {
+        match self {
+            DecimalSeparator::Dot => Cow::Borrowed(amount),
+            DecimalSeparator::Comma => Cow::Owned(amount.replace('.', "").replace(',', ".")),
+        }
+    }
+}
+
+/// parses csv byte records into transactions in place, without per record allocations,
+/// errors name the offending column
+#[derive(Debug)]
+pub(crate) struct RecordParser {
+    // column index of type, client, tx, amount, timestamp, idempotency key, batch and effective date
+    columns: [Option<usize>; 8],
+    decimal_separator: DecimalSeparator,
+    type_aliases: HashMap<String, TxType>,
+}
+
+impl RecordParser {
+    /// columns located by header name, fails when a required column is missing
+    pub(crate) fn new(
+        headers: &ByteRecord,
+        decimal_separator: DecimalSeparator,
+    ) -> Result<Self, String> {
+        let columns = POSITIONAL_COLUMNS.map(|column| {
+            headers
+                .iter()
+                .position(|header| header == column.as_bytes())
+        });
+
+        let missing: Vec<_> = REQUIRED_COLUMNS
+            .iter()
+            .zip(columns)
+            .filter(|(_, index)| index.is_none())
+            .map(|(column, _)| *column)
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("missing required columns: {}", missing.join(", ")));
+        }
+
+        Ok(Self {
+            columns,
+            decimal_separator,
+            type_aliases: HashMap::new(),
+        })
+    }
+
+    /// columns read in order: type, client, tx, amount, timestamp, idempotency key, batch,
+    /// effective date
+    pub(crate) fn positional(decimal_separator: DecimalSeparator) -> Self {
+        Self {
+            columns: [
+                Some(0),
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(5),
+                Some(6),
+                Some(7),
+            ],
+            decimal_separator,
+            type_aliases: HashMap::new(),
+        }
+    }
+
+    /// partner names of the types, e.g. `payment` for deposits
+    pub(crate) fn with_type_aliases(mut self, type_aliases: HashMap<String, TxType>) -> Self {
+        self.type_aliases = type_aliases;
+        self
+    }
+
+    pub(crate) fn parse(&self, record: &ByteRecord) -> Result<TxInput, String> {
+        let tx_type = import::tx_type(self.field(record, 0)?, &self.type_aliases)?;
+
+        let client_id = self
+            .field(record, 1)?
+            .parse()
+            .map_err(|err| invalid(1, err))?;
+
+        let id = self
+            .field(record, 2)?
+            .parse()
+            .map_err(|err| invalid(2, err))?;
+
+        let amount = match self.field(record, 3)? {
+            "" => None,
+            value => Some(
+                self.decimal_separator
+                    .normalize(value)
+                    .parse::<Decimal>()
+                    .map_err(|err| invalid(3, err))?,
+            ),
+        };
+
+        let timestamp = match self.field(record, 4)? {
+            "" => None,
+            value => Some(
+                value
+                    .parse::<DateTime<Utc>>()
+                    .map_err(|err| invalid(4, err))?,
+            ),
+        };
+
+        let idempotency_key = match self.field(record, 5)? {
+            "" => None,
+            value => Some(value.to_string()),
+        };
+
+        let batch = match self.field(record, 6)? {
+            "" => None,
+            value => Some(value.to_string()),
+        };
+
+        let effective_date = match self.field(record, 7)? {
+            "" => None,
+            value => Some(value.parse::<NaiveDate>().map_err(|err| invalid(7, err))?),
+        };
+
+        Ok(TxInput {
+            tx_type,
+            client_id,
+            id,
+            amount,
+            timestamp,
+            idempotency_key,
+            batch,
+            effective_date,
+        })
+    }
+
+    /// value of a column, empty when the column or the field is absent
+    fn field<'r>(&self, record: &'r ByteRecord, column: usize) -> Result<&'r str, String> {
+        let value = self.columns[column]
+            .and_then(|index| record.get(index))
+            .unwrap_or_default();
+
+        str::from_utf8(value).map_err(|err| invalid(column, err))
+    }
+}
+
+fn invalid(column: usize, err: impl ToString) -> String {
+    format!(
+        "invalid {}: {}",
+        POSITIONAL_COLUMNS[column],
+        err.to_string()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn parse_by_header() {
+        let headers = ByteRecord::from(vec!["amount", "memo", "tx", "client", "type"]);
+        let parser = RecordParser::new(&headers, DecimalSeparator::Dot).expect("invalid headers");
+        let tx = parser
+            .parse(&ByteRecord::from(vec!["1.5", "x", "3", "2", "deposit"]))
+            .expect("failed to parse");
+
+        assert_eq!(tx.tx_type, TxType::Deposit);
+        assert_eq!(tx.client_id, 2);
+        assert_eq!(tx.id, 3);
+        assert_eq!(tx.amount, Some(dec!(1.5)));
+        assert_eq!(tx.timestamp, None);
+    }
+
+    #[test]
+    fn parse_missing_columns() {
+        let headers = ByteRecord::from(vec!["kind", "client", "id", "amount"]);
+
+        assert_eq!(
+            RecordParser::new(&headers, DecimalSeparator::Dot).unwrap_err(),
+            "missing required columns: type, tx"
+        );
+    }
+
+    #[test]
+    fn parse_positional() {
+        let parser = RecordParser::positional(DecimalSeparator::Comma);
+        let tx = parser
+            .parse(&ByteRecord::from(vec!["withdrawal", "1", "2", "1.234,5"]))
+            .expect("failed to parse");
+        assert_eq!(tx.amount, Some(dec!(1234.5)));
+
+        let tx = parser
+            .parse(&ByteRecord::from(vec!["dispute", "1", "2"]))
+            .expect("failed to parse");
+        assert_eq!(tx.tx_type, TxType::Dispute);
+        assert_eq!(tx.amount, None);
+        assert_eq!(tx.idempotency_key, None);
+
+        let tx = parser
+            .parse(&ByteRecord::from(vec![
+                "deposit", "1", "3", "1", "", "req-1",
+            ]))
+            .expect("failed to parse");
+        assert_eq!(tx.idempotency_key.as_deref(), Some("req-1"));
+        assert_eq!(tx.batch, None);
+
+        let tx = parser
+            .parse(&ByteRecord::from(vec![
+                "settlement",
+                "1",
+                "4",
+                "-2",
+                "",
+                "",
+                "b-7",
+            ]))
+            .expect("failed to parse");
+        assert_eq!(tx.tx_type, TxType::Settlement);
+        assert_eq!(tx.batch.as_deref(), Some("b-7"));
+        assert_eq!(tx.effective_date, None);
+
+        let tx = parser
+            .parse(&ByteRecord::from(vec![
+                "deposit",
+                "1",
+                "5",
+                "1",
+                "",
+                "",
+                "",
+                "2024-03-01",
+            ]))
+            .expect("failed to parse");
+        assert_eq!(tx.effective_date, NaiveDate::from_ymd_opt(2024, 3, 1));
+    }
+
+    #[test]
+    fn parse_invalid_column() {
+        let parser = RecordParser::positional(DecimalSeparator::Dot);
+        let err = |fields: Vec<&str>| parser.parse(&ByteRecord::from(fields)).unwrap_err();
+
+        assert_eq!(
+            err(vec!["deposit", "x", "1", "1.0"]),
+            "invalid client: invalid digit found in string"
+        );
+        assert!(err(vec!["deposit", "1", "1", "1.0a"]).starts_with("invalid amount:"));
+        assert!(
+            err(vec!["deposit", "1", "1", "1.0", "yesterday"]).starts_with("invalid timestamp:")
+        );
+        assert!(
+            err(vec!["deposit", "1", "1", "1.0", "", "", "", "03/01/2024"])
+                .starts_with("invalid effective_date:")
+        );
+        assert_eq!(
+            err(vec!["refund", "1", "1", "1.0"]),
+            "invalid type: unknown transaction type `refund`"
+        );
+    }
+
+    #[test]
+    fn parse_type_aliases() {
+        let parser = RecordParser::positional(DecimalSeparator::Dot)
+            .with_type_aliases(HashMap::from([("payment".to_string(), TxType::Deposit)]));
+
+        let tx = parser
+            .parse(&ByteRecord::from(vec!["payment", "1", "2", "1.0"]))
+            .expect("failed to parse");
+        assert_eq!(tx.tx_type, TxType::Deposit);
+
+        let tx = parser
+            .parse(&ByteRecord::from(vec!["withdrawal", "1", "3", "1.0"]))
+            .expect("failed to parse");
+        assert_eq!(tx.tx_type, TxType::Withdrawal);
+    }
+}
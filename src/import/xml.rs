@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io;
+
+use chrono::{DateTime, Utc};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::import;
+use crate::models::tx::TxInput;
+use crate::models::tx_type::TxType;
+
+/// field names in the order of the mapping values
+const FIELDS: [&str; 5] = ["type", "client", "tx", "amount", "timestamp"];
+
+/// elements and attributes of xml input holding the transaction fields, read from a json file,
+/// a field is the name of a child element of the record or `@name` for an attribute of the record element
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct XmlMapping {
+    /// name of the element of each transaction, at any depth
+    pub record: String,
+    #[serde(rename = "type")]
+    pub tx_type: String,
+    pub client: String,
+    pub tx: String,
+    pub amount: String,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+
+    /// codes of the type field besides the csv type names, e.g. `{"CR": "deposit"}`
+    #[serde(default)]
+    pub types: HashMap<String, TxType>,
+}
+
+impl XmlMapping {
+    pub fn from_reader<R: io::Read>(reader: R) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    fn fields(&self) -> [Option<&str>; 5] {
+        [
+            Some(&self.tx_type),
+            Some(&self.client),
+            Some(&self.tx),
+            Some(&self.amount),
+            self.timestamp.as_deref(),
+        ]
+    }
+
+    /// index of the field of a child element
+    fn element(&self, name: &[u8]) -> Option<usize> {
+        self.fields()
+            .iter()
+            .position(|field| field.is_some_and(|field| field.as_bytes() == name))
+    }
+
+    /// index of the field of a record attribute
+    fn attribute(&self, name: &[u8]) -> Option<usize> {
+        self.fields().iter().position(|field| {
+            field
+                .and_then(|field| field.strip_prefix('@'))
+                .is_some_and(|field| field.as_bytes() == name)
+        })
+    }
+
+    fn parse(&self, values: &[Option<String>; 5]) -> Result<TxInput, String> {
+        let value = |index: usize| values[index].as_deref().map(str::trim).unwrap_or_default();
+        let required = |index: usize| match value(index) {
+            "" => Err(format!("missing {}", FIELDS[index])),
+            value => Ok(value),
+        };
+
+        let tx_type = import::tx_type(required(0)?, &self.types)?;
+
+        let client_id = required(1)?.parse().map_err(|err| invalid(1, err))?;
+
+        let id = required(2)?.parse().map_err(|err| invalid(2, err))?;
+
+        let amount = match value(3) {
+            "" => None,
+            value => Some(value.parse::<Decimal>().map_err(|err| invalid(3, err))?),
+        };
+
+        let timestamp = match value(4) {
+            "" => None,
+            value => Some(
+                value
+                    .parse::<DateTime<Utc>>()
+                    .map_err(|err| invalid(4, err))?,
+            ),
+        };
+
+        Ok(TxInput {
+            tx_type,
+            client_id,
+            id,
+            amount,
+            timestamp,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        })
+    }
+}
+
+fn invalid(field: usize, err: impl ToString) -> String {
+    format!("invalid {}: {}", FIELDS[field], err.to_string())
+}
+
+/// transactions of the record elements of an xml document, read as a stream,
+/// malformed xml ends the records with an error
+pub(crate) struct Records<'m, R> {
+    reader: Reader<R>,
+    mapping: &'m XmlMapping,
+    buf: Vec<u8>,
+    // field values of the current record and the field of the open child element
+    record: Option<[Option<String>; 5]>,
+    field: Option<usize>,
+    // child elements open inside the record
+    depth: usize,
+    done: bool,
+}
+
+impl<'m, R: io::BufRead> Records<'m, R> {
+    pub(crate) fn new(input: R, mapping: &'m XmlMapping) -> Self {
+        Self {
+            reader: Reader::from_reader(input),
+            mapping,
+            buf: Vec::new(),
+            record: None,
+            field: None,
+            depth: 0,
+            done: false,
+        }
+    }
+
+    /// starts a record with the values of its mapped attributes
+    fn start(&self, element: &BytesStart) -> Result<[Option<String>; 5], quick_xml::Error> {
+        let mut values: [Option<String>; 5] = Default::default();
+        for attribute in element.attributes() {
+            let attribute = attribute?;
+            if let Some(index) = self.mapping.attribute(attribute.key.local_name().as_ref()) {
+                values[index] = Some(attribute.unescape_value()?.into_owned());
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn text(&mut self, text: &str) {
+        if let (Some(values), Some(index), 1) = (self.record.as_mut(), self.field, self.depth) {
+            values[index].get_or_insert_with(String::new).push_str(text);
+        }
+    }
+
+    fn next_record(&mut self) -> Result<Option<[Option<String>; 5]>, quick_xml::Error> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into(&mut self.buf)? {
+                Event::Start(element) => {
+                    let name = element.local_name();
+                    if self.record.is_some() {
+                        self.depth += 1;
+                        if self.depth == 1 {
+                            self.field = self.mapping.element(name.as_ref());
+                        }
+                    } else if name.as_ref() == self.mapping.record.as_bytes() {
+                        let element = element.into_owned();
+                        self.record = Some(self.start(&element)?);
+                        self.depth = 0;
+                    }
+                }
+                Event::Empty(element)
+                    if self.record.is_none()
+                        && element.local_name().as_ref() == self.mapping.record.as_bytes() =>
+                {
+                    let element = element.into_owned();
+                    return self.start(&element).map(Some);
+                }
+                Event::Text(text) => {
+                    let text = text.unescape()?.into_owned();
+                    self.text(&text);
+                }
+                Event::CData(data) => {
+                    let text = data.decode()?.into_owned();
+                    self.text(&text);
+                }
+                Event::End(_) if self.record.is_some() => match self.depth {
+                    0 => return Ok(self.record.take()),
+                    _ => {
+                        self.depth -= 1;
+                        self.field = None;
+                    }
+                },
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<R: io::BufRead> Iterator for Records<'_, R> {
+    type Item = Result<Result<TxInput, String>, quick_xml::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.next_record() {
+            Ok(Some(values)) => Some(Ok(self.mapping.parse(&values))),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    const MAPPING: &str = r#"{
+        "record": "Payment",
+        "type": "@kind",
+        "client": "Account",
+        "tx": "@id",
+        "amount": "Amount",
+        "types": {"CR": "deposit", "DB": "withdrawal", "DSP": "dispute"}
+    }"#;
+
+    fn mapping() -> XmlMapping {
+        XmlMapping::from_reader(MAPPING.as_bytes()).expect("invalid mapping")
+    }
+
+    fn records(xml: &str) -> Vec<Result<TxInput, String>> {
+        let mapping = mapping();
+        Records::new(xml.as_bytes(), &mapping)
+            .collect::<Result<_, _>>()
+            .expect("failed to read xml")
+    }
+
+    #[test]
+    fn read_records() {
+        let txs = records(
+            r#"<?xml version="1.0"?>
+<Batch><Payments>
+  <Payment kind="CR" id="1"><Account>7</Account><Amount>10.50</Amount></Payment>
+  <Payment kind="DB" id="2">
+    <Account> 7 </Account>
+    <Memo><Amount>99</Amount></Memo>
+    <Amount><![CDATA[2.25]]></Amount>
+  </Payment>
+  <Payment kind="DSP" id="1"><Account>7</Account></Payment>
+</Payments></Batch>"#,
+        );
+
+        assert_eq!(txs.len(), 3);
+        let tx = txs[0].as_ref().expect("failed to parse");
+        assert_eq!(tx.tx_type, TxType::Deposit);
+        assert_eq!(tx.client_id, 7);
+        assert_eq!(tx.id, 1);
+        assert_eq!(tx.amount, Some(dec!(10.50)));
+
+        let tx = txs[1].as_ref().expect("failed to parse");
+        assert_eq!(tx.tx_type, TxType::Withdrawal);
+        assert_eq!(tx.client_id, 7);
+        assert_eq!(tx.amount, Some(dec!(2.25)));
+
+        let tx = txs[2].as_ref().expect("failed to parse");
+        assert_eq!(tx.tx_type, TxType::Dispute);
+        assert_eq!(tx.amount, None);
+    }
+
+    #[test]
+    fn read_invalid_records() {
+        let txs = records(
+            r#"<Payments>
+  <Payment kind="XX" id="1"><Account>7</Account></Payment>
+  <Payment kind="CR" id="2"/>
+  <Payment kind="CR" id="3"><Account>7</Account><Amount>1,5</Amount></Payment>
+</Payments>"#,
+        );
+
+        let err = |index: usize| txs[index].as_ref().err().map(String::as_str);
+
+        assert_eq!(err(0), Some("invalid type: unknown transaction type `XX`"));
+        assert_eq!(err(1), Some("missing client"));
+        assert!(err(2).is_some_and(|err| err.starts_with("invalid amount:")));
+    }
+
+    #[test]
+    fn read_malformed_xml() {
+        let mapping = mapping();
+        let mut records = Records::new(
+            r#"<Payments><Payment kind="CR" id="1"></Account></Payments>"#.as_bytes(),
+            &mapping,
+        );
+
+        assert!(records.next().is_some_and(|record| record.is_err()));
+        assert!(records.next().is_none());
+    }
+}
@@ -0,0 +1,267 @@
+use std::error::Error;
+use std::io;
+
+use ahash::AHashMap;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use clap::ValueEnum;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::money::PRECISION;
+use crate::models::tx::ClientId;
+use crate::rollup::BalanceHistory;
+
+/// convention counting the days and the year of an accrual period
+#[derive(ValueEnum, Debug, Default, PartialEq, Copy, Clone)]
+pub enum DayCount {
+    /// actual days over a 365 day year
+    #[default]
+    #[value(name = "act-365")]
+    Act365,
+    /// 30 day months over a 360 day year, bond basis: a 31st counts as the 30th, at the end of
+    /// the period only when it starts on the 30th or 31st
+    #[value(name = "30-360")]
+    Thirty360,
+}
+
+impl DayCount {
+    /// days from the start to the end of the period, the end excluded
+    pub(crate) fn days(&self, from: NaiveDate, to: NaiveDate) -> i64 {
+        match self {
+            DayCount::Act365 => (to - from).num_days(),
+            DayCount::Thirty360 => {
+                let d1 = from.day().min(30);
+                let d2 = match to.day() {
+                    31 if d1 == 30 => 30,
+                    day => day,
+                };
+                360 * i64::from(to.year() - from.year())
+                    + 30 * (i64::from(to.month()) - i64::from(from.month()))
+                    + (i64::from(d2) - i64::from(d1))
+            }
+        }
+    }
+
+    fn year_days(&self) -> i64 {
+        match self {
+            DayCount::Act365 => 365,
+            DayCount::Thirty360 => 360,
+        }
+    }
+}
+
+/// annual interest rates in percent, per client segment; a client without a segment, or of a
+/// segment without a rate, accrues at the default rate
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct InterestRates {
+    pub day_count: DayCount,
+
+    /// rate of the clients without the rate of a segment
+    pub default_rate: Option<Decimal>,
+
+    /// rates of the segments
+    pub segments: AHashMap<String, Decimal>,
+
+    /// segments of the clients
+    pub clients: AHashMap<ClientId, String>,
+}
+
+impl InterestRates {
+    /// rate of the client, if any
+    pub(crate) fn rate(&self, client_id: ClientId) -> Option<Decimal> {
+        self.clients
+            .get(&client_id)
+            .and_then(|segment| self.segments.get(segment))
+            .copied()
+            .or(self.default_rate)
+    }
+
+    /// interest of a balance held over the period, rounded to the engine precision
+    pub(crate) fn accrue(
+        &self,
+        balance: Decimal,
+        rate: Decimal,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Decimal {
+        let days = Decimal::from(self.day_count.days(from, to));
+        let year = Decimal::from(self.day_count.year_days()) * Decimal::ONE_HUNDRED;
+        (balance.saturating_mul(rate).saturating_mul(days) / year).round_dp(PRECISION)
+    }
+
+    /// reads the segment rates of a csv with `segment` and `rate` columns
+    pub fn read_segments<R: io::Read>(&mut self, reader: R) -> Result<(), Box<dyn Error>> {
+        #[derive(Deserialize)]
+        struct Row {
+            segment: String,
+            rate: Decimal,
+        }
+
+        for row in csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+            .deserialize()
+        {
+            let row: Row = row?;
+            self.segments.insert(row.segment, row.rate);
+        }
+        Ok(())
+    }
+
+    /// reads the client segments of a csv with `client` and `segment` columns
+    pub fn read_clients<R: io::Read>(&mut self, reader: R) -> Result<(), Box<dyn Error>> {
+        #[derive(Deserialize)]
+        struct Row {
+            client: ClientId,
+            segment: String,
+        }
+
+        for row in csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+            .deserialize()
+        {
+            let row: Row = row?;
+            self.clients.insert(row.client, row.segment);
+        }
+        Ok(())
+    }
+}
+
+/// row of the interest accruals, the interest of the total funds of a client held from the end
+/// of a day to the next day of the timestamps
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct AccrualRow {
+    pub(crate) client: ClientId,
+    pub(crate) from: NaiveDate,
+    pub(crate) to: NaiveDate,
+    /// days of the period under the day count convention
+    pub(crate) days: i64,
+    pub(crate) balance: Decimal,
+    pub(crate) rate: Decimal,
+    pub(crate) interest: Decimal,
+}
+
+/// accrues interest from the end of each day of the transaction timestamps to the next one
+#[derive(Debug)]
+pub(crate) struct Accruals {
+    rates: InterestRates,
+    days: BalanceHistory,
+}
+
+impl Accruals {
+    pub(crate) fn new(rates: InterestRates) -> Self {
+        Self {
+            rates,
+            days: BalanceHistory::default(),
+        }
+    }
+
+    /// period closed by a transaction at the time, from the day it closes to the one it opens
+    pub(crate) fn advance(
+        &mut self,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Option<(NaiveDate, NaiveDate)> {
+        let closed = self.days.advance(timestamp)?;
+        Some((closed, self.days.day()?))
+    }
+
+    /// interest of the total funds of a client over the period, None without a rate or funds
+    pub(crate) fn row(
+        &self,
+        client: ClientId,
+        balance: Decimal,
+        (from, to): (NaiveDate, NaiveDate),
+    ) -> Option<AccrualRow> {
+        let rate = self.rates.rate(client)?;
+        (balance.is_sign_positive() && !balance.is_zero()).then(|| AccrualRow {
+            client,
+            from,
+            to,
+            days: self.rates.day_count.days(from, to),
+            balance,
+            rate,
+            interest: self.rates.accrue(balance, rate, from, to),
+        })
+    }
+
+    /// open day, saved in checkpoints
+    pub(crate) fn day(&self) -> Option<NaiveDate> {
+        self.days.day()
+    }
+
+    pub(crate) fn restore(&mut self, day: Option<NaiveDate>) {
+        self.days.restore(day);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn day_counts() {
+        let cases = [
+            ((date(2024, 1, 31), date(2024, 3, 1)), (31, 30)),
+            ((date(2024, 2, 28), date(2024, 3, 31)), (33, 32)),
+            ((date(2024, 3, 30), date(2024, 3, 31)), (0, 1)),
+            ((date(2023, 12, 15), date(2024, 1, 15)), (30, 31)),
+        ];
+        for ((from, to), (thirty_360, act_365)) in cases {
+            assert_eq!(DayCount::Thirty360.days(from, to), thirty_360);
+            assert_eq!(DayCount::Act365.days(from, to), act_365);
+        }
+    }
+
+    #[test]
+    fn rates() {
+        let mut rates = InterestRates {
+            default_rate: Some(dec!(1)),
+            ..Default::default()
+        };
+        rates
+            .read_segments("segment, rate\nretail, 3.65\npremium, 5\n".as_bytes())
+            .expect("failed to read the segment rates");
+        rates
+            .read_clients("client, segment\n1, retail\n2, staff\n".as_bytes())
+            .expect("failed to read the client segments");
+
+        assert_eq!(rates.rate(1), Some(dec!(3.65)));
+        assert_eq!(rates.rate(2), Some(dec!(1)));
+        assert_eq!(rates.rate(3), Some(dec!(1)));
+        assert_eq!(InterestRates::default().rate(1), None);
+        assert!(rates.read_clients("client\n1\n".as_bytes()).is_err());
+
+        let (from, to) = (date(2024, 1, 31), date(2024, 3, 1));
+        assert_eq!(rates.accrue(dec!(1000), dec!(3.65), from, to), dec!(3));
+        rates.day_count = DayCount::Thirty360;
+        assert_eq!(rates.accrue(dec!(1000), dec!(3.6), from, to), dec!(3.1));
+    }
+
+    #[test]
+    fn accruals() {
+        let at = |day: u32| Some(format!("2024-03-{:02}T12:00:00Z", day).parse().unwrap());
+        let mut accruals = Accruals::new(InterestRates {
+            default_rate: Some(dec!(3.65)),
+            ..Default::default()
+        });
+        assert_eq!(accruals.advance(at(1)), None);
+        let period = accruals
+            .advance(at(3))
+            .expect("a later day should close the period");
+        assert_eq!(period, (date(2024, 3, 1), date(2024, 3, 3)));
+
+        let row = accruals
+            .row(1, dec!(500), period)
+            .expect("funds should accrue");
+        assert_eq!((row.days, row.interest), (2, dec!(0.1)));
+        assert_eq!(accruals.row(1, dec!(0), period), None);
+        assert_eq!(accruals.row(1, dec!(-5), period), None);
+    }
+}
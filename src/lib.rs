@@ -1,3 +1,54 @@
+#[cfg(feature = "cli")]
+pub(crate) mod aml;
+#[cfg(feature = "cli")]
+pub(crate) mod anomaly;
+#[cfg(feature = "cli")]
+pub(crate) mod chargebacks;
+#[cfg(feature = "cli")]
 pub mod cli;
+#[cfg(feature = "difftest")]
+pub mod difftest;
+#[cfg(feature = "cli")]
+pub(crate) mod disputes;
 pub(crate) mod engine;
+#[cfg(feature = "cli")]
+pub(crate) mod import;
+#[cfg(feature = "cli")]
+pub(crate) mod interest;
 pub(crate) mod models;
+#[cfg(feature = "cli")]
+pub(crate) mod reconcile;
+#[cfg(feature = "cli")]
+pub(crate) mod report;
+#[cfg(feature = "cli")]
+pub(crate) mod rollup;
+#[cfg(feature = "cli")]
+pub(crate) mod settlements;
+#[cfg(feature = "cli")]
+pub(crate) mod shard;
+#[cfg(feature = "cli")]
+pub(crate) mod simulate;
+#[cfg(feature = "cli")]
+pub mod testing;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "cli")]
+pub(crate) mod workload;
+
+pub use engine::admin::{AdminAction, AdminCommand};
+pub use engine::alerts::{Alert, AlertKind, AlertObserver, AlertRules};
+pub use engine::archive::{ArchivedTx, TxArchive, ARCHIVED_TX_BYTES};
+pub use engine::config::{DuplicatePolicy, EngineConfig, WithdrawalPolicy};
+pub use engine::fees::{Fee, FeeKind, FeeSchedule};
+pub use engine::metrics::Metrics;
+pub use engine::min_balance::MinBalances;
+pub use engine::retention::Retention;
+pub use engine::rules::{BlockedClients, EngineView, MaxAmount, ValidationRule};
+pub use engine::velocity::VelocityLimits;
+pub use engine::Engine;
+pub use models::client::ClientView;
+pub use models::dispute_state::DisputeState;
+pub use models::errors::{BatchError, Error, ErrorKind};
+pub use models::money::{Currency, Money};
+pub use models::tx::{ClientId, TxId, TxInput, TxView};
+pub use models::tx_type::TxType;
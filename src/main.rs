@@ -1,13 +1,27 @@
 use clap::Parser;
+use signal_hook::consts::{SIGINT, SIGTERM, SIGUSR1};
+use signal_hook::flag;
 use std::error::Error;
+use std::fs;
 use std::fs::File;
 use std::io;
-use std::io::stdout;
-use tracing::Level;
+use std::io::{stdout, BufReader, BufWriter};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{error, Level};
 
 use ttx_eng::cli;
+use ttx_eng::cli::{
+    AlertLog, AmlOptions, AnomalyOptions, AnomalyThresholds, ArchiveLayout, CheckpointOptions,
+    Command, EngineSection, FileArchive, FixedWidthLayout, InputChecksum, InputFormat,
+    InterestOptions, InterestRates, ManifestOptions, OutputKind, PipelineConfig, ProcessOptions,
+    Pseudonyms, ReportSink, RollupOptions, SegmentArchive, SnapshotOptions, XmlMapping,
+};
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> Result<ExitCode, Box<dyn Error>> {
     //setup tracing subscriber that will output to stderr
     let collector = tracing_subscriber::fmt()
         .with_max_level(Level::ERROR)
@@ -18,7 +32,345 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     //parse cli args
     let args = cli::Cli::parse();
-    let input_file = File::open(&args.file_path)?;
 
-    cli::process_input(input_file, stdout())
+    // the first SIGINT/SIGTERM stops processing and writes a partial report, a second one exits
+    let signal = Arc::new(AtomicUsize::new(0));
+    let stopping = Arc::new(AtomicBool::new(false));
+    for sig in [SIGINT, SIGTERM] {
+        flag::register_conditional_shutdown(sig, 128 + sig, Arc::clone(&stopping))?;
+        flag::register(sig, Arc::clone(&stopping))?;
+        flag::register_usize(sig, Arc::clone(&signal), sig as usize)?;
+    }
+
+    // SIGUSR1 writes a full report and a stats dump at the next processed record
+    let snapshot_request = Arc::new(AtomicBool::new(false));
+    flag::register(SIGUSR1, Arc::clone(&snapshot_request))?;
+
+    let result = match args.command {
+        Some(Command::Reconcile {
+            report_path,
+            external_path,
+        }) => cli::reconcile(
+            File::open(report_path)?,
+            File::open(external_path)?,
+            stdout(),
+        ),
+        Some(Command::Simulate { base, extra_path }) => cli::simulate(
+            BufReader::new(File::open(base)?),
+            BufReader::new(File::open(extra_path)?),
+            stdout(),
+        ),
+        Some(Command::Shard {
+            input_path,
+            shards,
+            out_dir,
+        }) => {
+            fs::create_dir_all(&out_dir)?;
+            let mut outputs = (0..shards)
+                .map(|n| File::create(out_dir.join(format!("shard-{}.csv", n))).map(BufWriter::new))
+                .collect::<Result<Vec<_>, _>>()?;
+            cli::shard(BufReader::new(File::open(input_path)?), &mut outputs)
+        }
+        Some(Command::ChargebackReport { input_path }) => {
+            cli::chargeback_report(BufReader::new(File::open(input_path)?), stdout())
+        }
+        Some(Command::SettlementReport { input_path }) => {
+            cli::settlement_report(BufReader::new(File::open(input_path)?), stdout())
+        }
+        Some(Command::DisputeAging { input_path, as_of }) => cli::dispute_aging(
+            BufReader::new(File::open(input_path)?),
+            as_of.unwrap_or_else(|| SystemTime::now().into()),
+            stdout(),
+        ),
+        Some(Command::Admin {
+            checkpoint_path,
+            commands_path,
+            out,
+        }) => cli::admin(
+            &checkpoint_path,
+            File::open(commands_path)?,
+            &out,
+            SystemTime::now().into(),
+            stdout(),
+        ),
+        Some(Command::Disputes {
+            checkpoint_path,
+            out,
+        }) => {
+            let decisions = File::options().append(true).create(true).open(out)?;
+            let header = decisions.metadata()?.len() == 0;
+            cli::review_disputes(
+                &checkpoint_path,
+                io::stdin().lock(),
+                io::stderr(),
+                decisions,
+                header,
+                SystemTime::now().into(),
+            )
+        }
+        Some(Command::RunPipeline { config_path }) => cli::run_pipeline(
+            PipelineConfig::from_toml(&fs::read_to_string(config_path)?)?,
+            stdout(),
+            Some(signal),
+        ),
+        Some(Command::ReportCollect { report_paths }) => cli::report_collect(
+            report_paths
+                .iter()
+                .map(|path| File::open(path).map(BufReader::new))
+                .collect::<Result<Vec<_>, _>>()?,
+            stdout(),
+        ),
+        Some(Command::Generate { seed, txs, clients }) => {
+            cli::generate(seed, clients, txs, stdout())
+        }
+        None => {
+            let file_path = args.file_path.ok_or("missing input file path")?;
+            let format = args
+                .format
+                .unwrap_or_else(|| InputFormat::from_path(&file_path));
+            let checksum = match args.verify_input {
+                Some(_) if file_path == "-" => return Err("stdin cannot be verified".into()),
+                Some(Some(checksum)) => Some(InputChecksum::parse(&checksum)?),
+                Some(None) => Some(InputChecksum::parse(&format!("{}.sha256", file_path))?),
+                None => None,
+            };
+            if let Some(checksum) = checksum.as_ref() {
+                checksum.verify(Path::new(&file_path))?;
+            }
+            let input_file = cli::open_input(&file_path, args.mmap)?;
+
+            let mut options = ProcessOptions::default();
+            options.engine.duplicate_policy = args.duplicate_policy;
+            options.engine.withdrawal_policy = args.withdrawal_policy;
+            options.engine.block_withdrawals_on_dispute = args.no_withdrawal_on_dispute;
+            options.engine.min_balance.global = args.min_balance;
+            options.engine.as_of = args.as_of;
+            options.engine.fees.withdrawal_percent = args.withdrawal_fee_pct;
+            options.engine.fees.chargeback_flat = args.chargeback_fee;
+            options.engine.fees.withdrawal_flat = args.withdrawal_fee;
+            options.engine.fees.free_withdrawals = args.free_withdrawals;
+            options.engine.fees.deposit_bps = args.deposit_fee_bps;
+            options.engine.fees.deposit_threshold = args.deposit_fee_threshold;
+            if let Some(path) = args.min_balances.as_ref() {
+                options
+                    .engine
+                    .min_balance
+                    .read_clients(BufReader::new(File::open(path)?))?;
+            }
+            options.engine.dedup_window = args.dedup_window;
+            options.engine.dedup_ttl = args.dedup_ttl.map(Duration::from_secs);
+            options.engine.max_disputes = args.max_disputes;
+            options.engine.allow_redispute = !args.no_redispute;
+            options.engine.velocity.max_withdrawals = args.max_withdrawals;
+            options.engine.velocity.window = args.withdrawal_window.map(Duration::from_secs);
+            options.engine.velocity.max_daily_amount = args.max_daily_withdrawal;
+            options.engine.retention.after_txs = args.settled_retention_txs;
+            options.engine.retention.window = args.settled_retention_secs.map(Duration::from_secs);
+            options.engine.authorization_ttl = args.authorization_ttl.map(Duration::from_secs);
+            options.engine.expected_clients = args.expected_clients;
+            options.engine.max_memory = args
+                .max_memory_mb
+                .map(|mb| usize::try_from(mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX));
+            options.engine.expected_txs = match format {
+                InputFormat::Csv => args.expected_txs.or_else(|| cli::estimate_txs(&file_path)),
+                _ => args.expected_txs,
+            };
+            options.csv.no_header = args.no_header;
+            options.csv.decimal_separator = args.decimal_separator;
+            if let Some(layout_path) = args.layout {
+                let layout = BufReader::new(File::open(layout_path)?);
+                match format {
+                    InputFormat::Xml => options.xml = Some(XmlMapping::from_reader(layout)?),
+                    _ => options.fixed_width = Some(FixedWidthLayout::from_reader(layout)?),
+                }
+            }
+            options.encoding = args.encoding;
+            options.input_filter.blocked_clients = args.block_client.into_iter().collect();
+            options.input_filter.clamp_amount = args.clamp_amount;
+            options.input_filter.type_aliases = args.type_alias.into_iter().collect();
+            options.report_filter.only_locked = args.only_locked;
+            options.report_filter.only_nonzero = args.only_nonzero;
+            options.report_filter.min_total = args.min_total;
+            options.report_detail = args.report_detail;
+            options.risk_columns = args.risk_columns;
+            options.lock_reason = args.lock_reason;
+            options.output_scale = args.output_scale;
+            options.output_format = args.output_format;
+            options.sinks = args
+                .output
+                .map(|url| ReportSink::from_url(&url, &args.output_table))
+                .transpose()?
+                .into_iter()
+                .collect();
+            options.flush_every = args.flush_every;
+            options.stats = args.stats;
+            options.rejections_by_client = args.rejections_by_client;
+            options.locked_report = args.locked_report;
+            options.result_balances = args.result_balances;
+            options.queued = args.queued;
+            options.snapshots = Some(SnapshotOptions {
+                every: args.report_every,
+                dir: args.report_dir,
+                delta: args.report_delta,
+                request: Some(snapshot_request),
+            });
+            // a resumed run appends the rows of the records after its checkpoint
+            let create = |path: &Path| match args.resume {
+                true => File::options().append(true).create(true).open(path),
+                false => File::create(path),
+            };
+            if let Some(results_path) = args.results.as_ref() {
+                options.results = Some(Box::new(BufWriter::new(create(Path::new(results_path))?)));
+            }
+            #[cfg(feature = "clickhouse")]
+            if let Some(url) = args.results_clickhouse {
+                let inserts = cli::ClickHouseInserts::new(&url, &args.results_table)?;
+                options.results = Some(Box::new(inserts));
+            }
+
+            if let Some(path) = args.archive.as_ref() {
+                options.archive = Some(match args.archive_layout {
+                    ArchiveLayout::Slots => Box::new(FileArchive::open(path, args.resume)?),
+                    ArchiveLayout::Segments => Box::new(SegmentArchive::open(path, args.resume)?),
+                });
+            }
+
+            if let Some(threshold) = args.aml_threshold {
+                options.aml = Some(AmlOptions {
+                    threshold,
+                    output: Box::new(BufWriter::new(create(&args.aml_report)?)),
+                });
+            }
+
+            options.engine.alerts.held_above = args.alert_held_above;
+            options.engine.alerts.negative_available = args.alert_negative_available;
+            options.engine.alerts.locked = args.alert_locked;
+
+            // the second engine of a cross-check only differs by the options of its file
+            if let Some(path) = args.cross_check.as_ref() {
+                let mut config = options.engine.clone();
+                EngineSection::from_toml(&fs::read_to_string(path)?)?.apply(&mut config);
+                options.cross_check = Some(config);
+            }
+
+            if let Some(path) = args.alerts.as_ref() {
+                let log = File::options().append(true).create(true).open(path)?;
+                options
+                    .alert_observers
+                    .push(Box::new(AlertLog::new(Box::new(log))));
+            }
+            #[cfg(feature = "webhook")]
+            if let Some(url) = args.alert_webhook.as_ref() {
+                options
+                    .alert_observers
+                    .push(Box::new(cli::AlertWebhook::new(url)));
+            }
+
+            if let Some(factor) = args.anomaly_factor {
+                options.anomalies = Some(AnomalyOptions {
+                    thresholds: AnomalyThresholds {
+                        factor,
+                        window: usize::try_from(args.anomaly_window).unwrap_or(usize::MAX),
+                        min_history: usize::try_from(args.anomaly_min_history)
+                            .unwrap_or(usize::MAX),
+                    },
+                    output: Box::new(BufWriter::new(create(&args.anomaly_report)?)),
+                });
+            }
+
+            if let Some(path) = args.fees.as_ref() {
+                options.fees = Some(Box::new(BufWriter::new(create(path)?)));
+            }
+
+            if let Some(path) = args.balance_history.as_ref() {
+                options.balance_history = Some(Box::new(BufWriter::new(create(path)?)));
+            }
+
+            if let Some(path) = args.interest_accruals.as_ref() {
+                let mut rates = InterestRates {
+                    day_count: args.day_count,
+                    default_rate: args.interest_rate,
+                    ..Default::default()
+                };
+                if let Some(path) = args.interest_segments.as_ref() {
+                    rates.read_segments(BufReader::new(File::open(path)?))?;
+                }
+                if let Some(path) = args.client_segments.as_ref() {
+                    rates.read_clients(BufReader::new(File::open(path)?))?;
+                }
+                options.interest = Some(InterestOptions {
+                    rates,
+                    output: Box::new(BufWriter::new(create(path)?)),
+                });
+            }
+
+            options.rollup = args.rollup.map(|period| RollupOptions {
+                period,
+                path: args.rollup_report,
+            });
+
+            if let Some(key_path) = args.pseudonymize {
+                let key = fs::read(key_path)?;
+                options.pseudonyms = Some(Pseudonyms::new(key.trim_ascii_end())?);
+            }
+
+            #[cfg(feature = "encrypt")]
+            if let Some(key) = args.encrypt_to {
+                options.encryption = Some(cli::Encryption::from_hex(&key)?);
+            }
+
+            if let Some(path) = args.manifest {
+                let mut outputs = Vec::new();
+                if let Some(results_path) = args.results.as_ref() {
+                    outputs.push((OutputKind::Results, results_path.into()));
+                }
+                if args.aml_threshold.is_some() {
+                    outputs.push((OutputKind::Aml, args.aml_report));
+                }
+                if args.anomaly_factor.is_some() {
+                    outputs.push((OutputKind::Anomalies, args.anomaly_report));
+                }
+                options.manifest = Some(ManifestOptions {
+                    path,
+                    signing_key: args
+                        .manifest_key
+                        .map(|key_path| ManifestOptions::read_signing_key(&key_path))
+                        .transpose()?,
+                    inputs: (file_path != "-")
+                        .then(|| (file_path.clone().into(), checksum))
+                        .into_iter()
+                        .collect(),
+                    outputs,
+                });
+            }
+
+            options.checkpoint = args.checkpoint.map(|path| CheckpointOptions {
+                path,
+                every: args.checkpoint_every,
+                resume: args.resume,
+            });
+
+            options.stop = Some(signal);
+            #[cfg(feature = "tui")]
+            {
+                options.tui = args.tui;
+            }
+
+            match format {
+                InputFormat::Csv => cli::process_input_with_options(input_file, stdout(), options),
+                _ => cli::process_statement(input_file, format, args.client, stdout(), options),
+            }
+        }
+    };
+
+    match result {
+        Ok(_) => Ok(ExitCode::SUCCESS),
+        Err(err) => match err.downcast_ref::<cli::Interrupted>() {
+            Some(interrupted) => {
+                error!("{}", interrupted);
+                Ok(ExitCode::from(128 + interrupted.signal as u8))
+            }
+            None => Err(err),
+        },
+    }
 }
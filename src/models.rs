@@ -1,4 +1,10 @@
 pub(crate) mod client;
+pub(crate) mod dispute_state;
 pub(crate) mod errors;
+pub(crate) mod money;
+#[cfg(any(feature = "cli", test))]
+pub(crate) mod report_transform;
 pub(crate) mod tx;
+#[cfg(feature = "cli")]
+pub(crate) mod tx_result;
 pub(crate) mod tx_type;
@@ -1,167 +1,838 @@
+#[cfg(any(feature = "cli", test))]
+use std::fmt;
+
+use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
-use rust_decimal_macros::dec;
+#[cfg(feature = "cli")]
+use serde::ser::SerializeStruct;
+#[cfg(any(feature = "cli", test))]
+use serde::Serializer;
 use serde::{Deserialize, Serialize};
 
-use crate::models::errors::Error;
-use crate::models::errors::Error::{AccountLocked, InsufficientFunds, NegativeAmount, Overflow};
-use crate::models::tx::ClientId;
-
-const PRECISION: u32 = 4;
-
-#[derive(Debug, PartialEq)]
+use crate::engine::config::WithdrawalPolicy;
+use crate::engine::fees::MonthlyDeposits;
+use crate::models::errors::ErrorKind;
+use crate::models::errors::ErrorKind::{
+    AccountFrozen, AccountLocked, AccountNotFrozen, AccountNotLocked, BelowMinimumBalance,
+    InsufficientFunds, InsufficientHeldFunds, Overflow, TxNotReversible,
+};
+use crate::models::money::Money;
+#[cfg(any(feature = "cli", test))]
+use crate::models::money::PRECISION;
+#[cfg(feature = "cli")]
+use crate::models::report_transform::{ReportColumn, ReportTransform};
+use crate::models::tx::{ClientId, TxId};
+use crate::models::tx_type::TxType;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub(crate) struct Client {
     id: ClientId,
-    available: Decimal,
-    held: Decimal,
+    available: Money,
+    held: Money,
     locked: bool,
+    /// compliance hold blocking withdrawals, deposits and disputes still apply
+    #[serde(default)]
+    frozen: bool,
+    activity: ClientActivity,
+    last_activity: Option<DateTime<Utc>>,
+    locked_by: Option<LockedBy>,
+    /// deposits of the month, only counted when the fee schedule has deposit fees
+    #[serde(default)]
+    monthly_deposits: MonthlyDeposits,
+    /// latest of the transactions dropped from memory by the retention policy
+    #[serde(default)]
+    dropped: Option<Dropped>,
+}
+
+/// latest store order and timestamp of the transactions of a client dropped from memory, a
+/// transaction of unknown order or without a timestamp counts as the latest
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub(crate) struct Dropped {
+    pub(crate) seq: u32,
+    /// milliseconds since the epoch
+    pub(crate) timestamp: i64,
+}
+
+/// chargeback that locked a client
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub(crate) struct LockedBy {
+    pub(crate) tx: TxId,
+    /// amount of the charged back deposit
+    pub(crate) amount: Decimal,
+    pub(crate) timestamp: Option<DateTime<Utc>>,
+}
+
+/// why a client can't withdraw, written as `chargeback:<tx>`, `admin` or `compliance_freeze`
+#[cfg(any(feature = "cli", test))]
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub(crate) enum LockReason {
+    /// locked by the chargeback of the tx id
+    Chargeback(TxId),
+    /// locked by an admin command
+    Admin,
+    /// on a compliance hold without being locked
+    ComplianceFreeze,
+}
+
+#[cfg(any(feature = "cli", test))]
+impl fmt::Display for LockReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockReason::Chargeback(tx) => write!(f, "chargeback:{}", tx),
+            LockReason::Admin => f.write_str("admin"),
+            LockReason::ComplianceFreeze => f.write_str("compliance_freeze"),
+        }
+    }
+}
+
+#[cfg(any(feature = "cli", test))]
+impl Serialize for LockReason {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// per client activity counters, reported with the extended report detail
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Copy, Clone)]
+pub(crate) struct ClientActivity {
+    pub(crate) deposits: u32,
+    /// pending withdrawals count once settled
+    pub(crate) withdrawals: u32,
+    pub(crate) open_disputes: u32,
+    pub(crate) chargebacks: u32,
+    pub(crate) rejected: u32,
+    pub(crate) disputes_opened: u32,
+    pub(crate) rejections: Rejections,
+}
+
+/// rejected transactions of a client per type
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Copy, Clone)]
+pub(crate) struct Rejections {
+    pub(crate) deposits: u32,
+    pub(crate) withdrawals: u32,
+    pub(crate) disputes: u32,
+    pub(crate) resolves: u32,
+    pub(crate) chargebacks: u32,
+}
+
+/// columns of the rejections by client output
+#[cfg(feature = "cli")]
+pub(crate) const REJECTIONS_HEADER: [&str; 7] = [
+    "client",
+    "deposit",
+    "withdrawal",
+    "dispute",
+    "resolve",
+    "chargeback",
+    "total",
+];
+
+/// row of the rejections by client output, the client is its id or pseudonym
+#[cfg(feature = "cli")]
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct RejectionsRow<C> {
+    pub(crate) client: C,
+    pub(crate) deposit: u32,
+    pub(crate) withdrawal: u32,
+    pub(crate) dispute: u32,
+    pub(crate) resolve: u32,
+    pub(crate) chargeback: u32,
+    pub(crate) total: u32,
+}
+
+/// columns of the locked accounts output
+#[cfg(feature = "cli")]
+pub(crate) const LOCKED_HEADER: [&str; 4] = ["client", "tx", "amount", "timestamp"];
+
+/// row of the locked accounts output, the client is its id or pseudonym
+#[cfg(feature = "cli")]
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct LockedRow<C> {
+    pub(crate) client: C,
+    pub(crate) tx: TxId,
+    pub(crate) amount: Decimal,
+    pub(crate) timestamp: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "cli")]
+impl<C> LockedRow<C> {
+    pub(crate) fn new(client: C, locked_by: &LockedBy) -> Self {
+        Self {
+            client,
+            tx: locked_by.tx,
+            amount: locked_by.amount,
+            timestamp: locked_by.timestamp,
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl<C> RejectionsRow<C> {
+    pub(crate) fn new(client: C, activity: &ClientActivity) -> Self {
+        let rejections = activity.rejections;
+        Self {
+            client,
+            deposit: rejections.deposits,
+            withdrawal: rejections.withdrawals,
+            dispute: rejections.disputes,
+            resolve: rejections.resolves,
+            chargeback: rejections.chargebacks,
+            total: activity.rejected,
+        }
+    }
+}
+
+impl ClientActivity {
+    /// chargebacks per accepted deposit, zero without deposits
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn chargeback_ratio(&self) -> Decimal {
+        match self.deposits {
+            0 => Decimal::ZERO,
+            deposits => {
+                (Decimal::from(self.chargebacks) / Decimal::from(deposits)).round_dp(PRECISION)
+            }
+        }
+    }
 }
 
 impl Client {
     pub(crate) fn new(client_id: ClientId) -> Self {
         Self {
             id: client_id,
-            available: dec!(0),
-            held: dec!(0),
+            available: Money::ZERO,
+            held: Money::ZERO,
             locked: false,
+            frozen: false,
+            activity: ClientActivity::default(),
+            last_activity: None,
+            locked_by: None,
+            monthly_deposits: MonthlyDeposits::default(),
+            dropped: None,
         }
     }
 
-    pub(crate) fn deposit(&mut self, amount: &Decimal) -> Result<(), Error> {
-        if amount.is_sign_negative() {
-            return Err(NegativeAmount);
+    /// records a transaction of the client dropped from memory
+    pub(crate) fn record_drop(&mut self, seq: Option<u32>, timestamp: Option<i64>) {
+        let (seq, timestamp) = (seq.unwrap_or(u32::MAX), timestamp.unwrap_or(i64::MAX));
+        let dropped = self.dropped.get_or_insert(Dropped {
+            seq,
+            timestamp: i64::MIN,
+        });
+        dropped.seq = dropped.seq.max(seq);
+        dropped.timestamp = dropped.timestamp.max(timestamp);
+    }
+
+    /// latest of the transactions dropped from memory
+    pub(crate) fn dropped(&self) -> Option<Dropped> {
+        self.dropped
+    }
+
+    /// accepted withdrawals, a pending one counts once it's settled
+    pub(crate) fn withdrawals(&self) -> u32 {
+        self.activity.withdrawals
+    }
+
+    /// counts a deposit towards the deposits of its month, returns the ones of the month before it
+    pub(crate) fn count_monthly_deposit(
+        &mut self,
+        amount: Decimal,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Decimal {
+        self.monthly_deposits.add(amount, timestamp)
+    }
+
+    /// records the time of an accepted transaction, keeping the latest one
+    pub(crate) fn touch(&mut self, timestamp: DateTime<Utc>) {
+        if self.last_activity.is_none_or(|last| timestamp > last) {
+            self.last_activity = Some(timestamp);
         }
+    }
+
+    /// records the chargeback that locked the client, applied after its chargeback
+    pub(crate) fn lock_by(&mut self, locked_by: LockedBy) {
+        self.locked_by = Some(locked_by);
+    }
 
+    /// locks the account by an admin command, it stays locked until it's unlocked
+    pub(crate) fn lock(&mut self) -> Result<(), ErrorKind> {
         if self.locked {
             return Err(AccountLocked);
         }
 
-        match self.available.checked_add(*amount) {
-            None => Err(Overflow),
-            Some(val) => {
-                self.available = val.round_dp(PRECISION);
-                Ok(())
-            }
+        self.locked = true;
+        self.locked_by = None;
+        Ok(())
+    }
+
+    /// cause of the lock, the chargeback recorded with it or else an admin command, or the
+    /// compliance hold of an unlocked account
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn lock_reason(&self) -> Option<LockReason> {
+        match (self.locked, self.locked_by, self.frozen) {
+            (true, Some(locked_by), _) => Some(LockReason::Chargeback(locked_by.tx)),
+            (true, None, _) => Some(LockReason::Admin),
+            (false, _, true) => Some(LockReason::ComplianceFreeze),
+            (false, _, false) => None,
+        }
+    }
+
+    /// lifts the lock and forgets the chargeback that caused it
+    pub(crate) fn unlock(&mut self) -> Result<(), ErrorKind> {
+        if !self.locked {
+            return Err(AccountNotLocked);
+        }
+
+        self.locked = false;
+        self.locked_by = None;
+        Ok(())
+    }
+
+    /// puts the account on a compliance hold
+    pub(crate) fn freeze(&mut self) -> Result<(), ErrorKind> {
+        if self.frozen {
+            return Err(AccountFrozen);
+        }
+
+        self.frozen = true;
+        Ok(())
+    }
+
+    /// lifts the compliance hold
+    pub(crate) fn unfreeze(&mut self) -> Result<(), ErrorKind> {
+        if !self.frozen {
+            return Err(AccountNotFrozen);
+        }
+
+        self.frozen = false;
+        Ok(())
+    }
+
+    /// moves held funds back to available like a resolve, whether the account is locked or not
+    pub(crate) fn release(&mut self, amount: Money) -> Result<(), ErrorKind> {
+        let (available, held) = (
+            self.available.checked_add(amount)?,
+            self.held.checked_sub(amount)?,
+        );
+        self.available = available;
+        self.held = held;
+        self.activity.open_disputes = self.activity.open_disputes.saturating_sub(1);
+        Ok(())
+    }
+
+    /// adds a signed amount to the available funds, which can't go below zero
+    pub(crate) fn adjust(&mut self, amount: &Decimal) -> Result<(), ErrorKind> {
+        let available = match Money::new(*amount) {
+            Ok(credit) => self.available.checked_add(credit)?,
+            Err(_) => self.available.checked_sub(Money::new(-*amount)?)?,
+        };
+        if available.is_negative() {
+            return Err(InsufficientFunds);
         }
+
+        self.available = available;
+        Ok(())
     }
 
-    pub(crate) fn withdraw(&mut self, amount: &Decimal) -> Result<(), Error> {
-        if amount.is_sign_negative() {
-            return Err(NegativeAmount);
+    /// counts a transaction of this client rejected by the engine
+    pub(crate) fn reject(&mut self, tx_type: TxType) {
+        self.activity.rejected += 1;
+
+        let rejections = &mut self.activity.rejections;
+        match tx_type {
+            TxType::Deposit | TxType::Authorize | TxType::Capture => rejections.deposits += 1,
+            TxType::Withdrawal
+            | TxType::WithdrawalPending
+            | TxType::WithdrawalSettle
+            | TxType::WithdrawalCancel => rejections.withdrawals += 1,
+            TxType::Dispute => rejections.disputes += 1,
+            TxType::Resolve => rejections.resolves += 1,
+            TxType::Chargeback => rejections.chargebacks += 1,
+            // reversals only count in the total, a settlement checks the batch of a partner and
+            // not the account of the client
+            TxType::Reversal | TxType::Settlement => {}
         }
+    }
 
+    pub(crate) fn deposit(&mut self, amount: Money) -> Result<(), ErrorKind> {
         if self.locked {
             return Err(AccountLocked);
         }
 
-        if amount > &self.available {
+        self.available = self.available.checked_add(amount)?;
+        self.activity.deposits += 1;
+        Ok(())
+    }
+
+    pub(crate) fn withdraw(
+        &mut self,
+        amount: Money,
+        policy: WithdrawalPolicy,
+        floor: Option<Decimal>,
+    ) -> Result<(), ErrorKind> {
+        if self.locked {
+            return Err(AccountLocked);
+        }
+
+        if self.frozen {
+            return Err(AccountFrozen);
+        }
+
+        // under the total policy the funds held by disputes are taken last: the available funds go
+        // negative and the holds stay whole for the resolve or chargeback of their disputes
+        let funds = match policy {
+            WithdrawalPolicy::Available => self.available.amount(),
+            WithdrawalPolicy::Total => self
+                .available
+                .amount()
+                .checked_add(self.held.amount())
+                .ok_or(Overflow)?,
+        };
+        if amount.amount() > funds {
             return Err(InsufficientFunds);
         }
 
-        match self.available.checked_sub(*amount) {
-            None => Err(Overflow),
-            Some(val) => {
-                self.available = val.round_dp(PRECISION);
+        if floor
+            .is_some_and(|floor| self.available.amount().saturating_sub(amount.amount()) < floor)
+        {
+            return Err(BelowMinimumBalance);
+        }
+
+        self.available = self.available.checked_sub(amount)?;
+        self.activity.withdrawals += 1;
+        Ok(())
+    }
+
+    /// whether a dispute of the client is open
+    pub(crate) fn has_open_disputes(&self) -> bool {
+        self.activity.open_disputes > 0
+    }
+
+    /// undoes a deposit or withdrawal, the funds of a reversed deposit must still be available
+    pub(crate) fn reverse(&mut self, tx_type: TxType, amount: Money) -> Result<(), ErrorKind> {
+        if self.locked {
+            return Err(AccountLocked);
+        }
+
+        self.force_reverse(tx_type, amount)
+    }
+
+    /// undoes a deposit or withdrawal like a reversal, whether the account is locked or not
+    pub(crate) fn force_reverse(
+        &mut self,
+        tx_type: TxType,
+        amount: Money,
+    ) -> Result<(), ErrorKind> {
+        match tx_type {
+            TxType::Deposit if amount.amount() > self.available.amount() => Err(InsufficientFunds),
+            TxType::Deposit => {
+                self.available = self.available.checked_sub(amount)?;
+                self.activity.deposits = self.activity.deposits.saturating_sub(1);
+                Ok(())
+            }
+            TxType::Withdrawal => {
+                self.available = self.available.checked_add(amount)?;
+                self.activity.withdrawals = self.activity.withdrawals.saturating_sub(1);
                 Ok(())
             }
+            _ => Err(TxNotReversible),
         }
     }
 
-    pub(crate) fn dispute(&mut self, amount: &Decimal) -> Result<(), Error> {
-        if amount.is_sign_negative() {
-            return Err(NegativeAmount);
+    /// checks an authorization, its funds are only credited by the capture; the open
+    /// authorizations are stored by the engine, not on the client
+    pub(crate) fn authorize(&self) -> Result<(), ErrorKind> {
+        if self.locked {
+            return Err(AccountLocked);
         }
 
+        Ok(())
+    }
+
+    /// moves the funds of a pending withdrawal from available to held
+    pub(crate) fn hold_withdrawal(
+        &mut self,
+        amount: Money,
+        floor: Option<Decimal>,
+    ) -> Result<(), ErrorKind> {
         if self.locked {
             return Err(AccountLocked);
         }
 
-        match self.available.checked_sub(*amount) {
-            None => return Err(Overflow),
-            Some(val) => {
-                self.available = val.round_dp(PRECISION);
-            }
-        };
+        if self.frozen {
+            return Err(AccountFrozen);
+        }
 
-        match self.held.checked_add(*amount) {
-            None => Err(Overflow),
-            Some(val) => {
-                self.held = val.round_dp(PRECISION);
-                Ok(())
-            }
+        if amount.amount() > self.available.amount() {
+            return Err(InsufficientFunds);
+        }
+
+        if floor
+            .is_some_and(|floor| self.available.amount().saturating_sub(amount.amount()) < floor)
+        {
+            return Err(BelowMinimumBalance);
         }
+
+        let (available, held) = (
+            self.available.checked_sub(amount)?,
+            self.held.checked_add(amount)?,
+        );
+        self.available = available;
+        self.held = held;
+        Ok(())
+    }
+
+    /// removes the held funds of a settled withdrawal, also on a locked account since the payout
+    /// already left
+    pub(crate) fn settle_withdrawal(&mut self, amount: Money) -> Result<(), ErrorKind> {
+        if amount.amount() > self.held.amount() {
+            return Err(InsufficientHeldFunds);
+        }
+
+        self.held = self.held.checked_sub(amount)?;
+        self.activity.withdrawals += 1;
+        Ok(())
     }
 
-    pub(crate) fn resolve(&mut self, amount: &Decimal) -> Result<(), Error> {
-        if amount.is_sign_negative() {
-            return Err(NegativeAmount);
+    /// makes the held funds of a cancelled withdrawal available again
+    pub(crate) fn cancel_withdrawal(&mut self, amount: Money) -> Result<(), ErrorKind> {
+        if amount.amount() > self.held.amount() {
+            return Err(InsufficientHeldFunds);
         }
 
+        let (available, held) = (
+            self.available.checked_add(amount)?,
+            self.held.checked_sub(amount)?,
+        );
+        self.available = available;
+        self.held = held;
+        Ok(())
+    }
+
+    pub(crate) fn dispute(&mut self, amount: Money) -> Result<(), ErrorKind> {
         if self.locked {
             return Err(AccountLocked);
         }
 
-        match self.available.checked_add(*amount) {
-            None => return Err(Overflow),
-            Some(val) => {
-                self.available = val.round_dp(PRECISION);
-            }
-        };
+        self.available = self.available.checked_sub(amount)?;
+        self.held = self.held.checked_add(amount)?;
+        self.activity.open_disputes += 1;
+        self.activity.disputes_opened += 1;
+        Ok(())
+    }
 
-        match self.held.checked_sub(*amount) {
-            None => Err(Overflow),
-            Some(val) => {
-                self.held = val.round_dp(PRECISION);
-                Ok(())
-            }
+    pub(crate) fn resolve(&mut self, amount: Money) -> Result<(), ErrorKind> {
+        if self.locked {
+            return Err(AccountLocked);
         }
+
+        self.available = self.available.checked_add(amount)?;
+        self.held = self.held.checked_sub(amount)?;
+        self.activity.open_disputes = self.activity.open_disputes.saturating_sub(1);
+        Ok(())
     }
 
-    pub(crate) fn chargeback(&mut self, amount: &Decimal) -> Result<(), Error> {
-        if amount.is_sign_negative() {
-            return Err(NegativeAmount);
+    /// takes a fee from the available funds, also of a locked or frozen account; a fee the
+    /// available funds can't cover is not taken
+    pub(crate) fn charge_fee(&mut self, amount: Decimal) -> Result<(), ErrorKind> {
+        let amount = Money::new(amount)?;
+        if amount.amount() > self.available.amount() {
+            return Err(InsufficientFunds);
         }
 
+        self.available = self.available.checked_sub(amount)?;
+        Ok(())
+    }
+
+    pub(crate) fn chargeback(&mut self, amount: Money) -> Result<(), ErrorKind> {
         if self.locked {
             return Err(AccountLocked);
         }
 
         self.locked = true;
-        match self.held.checked_sub(*amount) {
-            None => Err(Overflow),
-            Some(val) => {
-                self.held = val.round_dp(PRECISION);
-                Ok(())
-            }
-        }
+        self.held = self.held.checked_sub(amount)?;
+        self.activity.open_disputes = self.activity.open_disputes.saturating_sub(1);
+        self.activity.chargebacks += 1;
+        Ok(())
+    }
+}
+
+/// read-only view of a client of the engine
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct ClientView<'a>(pub(crate) &'a Client);
+
+impl ClientView<'_> {
+    pub fn id(&self) -> ClientId {
+        self.0.id
+    }
+
+    pub fn available(&self) -> Decimal {
+        self.0.available.amount()
+    }
+
+    pub fn held(&self) -> Decimal {
+        self.0.held.amount()
+    }
+
+    pub fn total(&self) -> Decimal {
+        self.0.available.amount() + self.0.held.amount()
+    }
+
+    pub fn locked(&self) -> bool {
+        self.0.locked
+    }
+
+    /// whether a compliance hold blocks the withdrawals of the client
+    pub fn frozen(&self) -> bool {
+        self.0.frozen
+    }
+
+    /// time of the latest accepted transaction carrying a timestamp
+    pub fn last_activity(&self) -> Option<DateTime<Utc>> {
+        self.0.last_activity
     }
 }
 
+#[cfg(any(feature = "cli", feature = "wasm", test))]
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct ClientReport {
     #[serde(rename = "client")]
-    id: ClientId,
+    pub(crate) id: ClientId,
 
-    available: Decimal,
-    held: Decimal,
-    total: Decimal,
-    locked: bool,
+    pub(crate) available: Decimal,
+    pub(crate) held: Decimal,
+    pub(crate) total: Decimal,
+    pub(crate) locked: bool,
+
+    #[cfg(any(feature = "cli", test))]
+    #[serde(skip)]
+    pub(crate) frozen: bool,
+
+    #[cfg(feature = "cli")]
+    #[serde(skip)]
+    pub(crate) activity: ClientActivity,
+
+    #[cfg(feature = "cli")]
+    #[serde(skip)]
+    pub(crate) last_activity: Option<DateTime<Utc>>,
+
+    #[cfg(any(feature = "cli", test))]
+    #[serde(skip)]
+    pub(crate) locked_by: Option<LockedBy>,
+
+    #[cfg(any(feature = "cli", test))]
+    #[serde(skip)]
+    pub(crate) lock_reason: Option<LockReason>,
+}
+
+/// optional columns of the client report
+#[cfg(feature = "cli")]
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct ReportColumns {
+    pub(crate) activity: bool,
+    pub(crate) risk: bool,
+    pub(crate) last_activity: bool,
+    pub(crate) lock_reason: bool,
+}
+
+/// client report serialized with the selected optional columns,
+/// amounts are rescaled to a fixed number of decimal places when a scale is given
+#[cfg(feature = "cli")]
+pub(crate) struct ReportRow<'a> {
+    pub(crate) report: &'a ClientReport,
+    pub(crate) columns: ReportColumns,
+    pub(crate) scale: Option<u32>,
+    pub(crate) transform: Option<&'a ReportTransform>,
+    /// written in the client column instead of the id
+    pub(crate) client: Option<&'a str>,
+}
+
+#[cfg(feature = "cli")]
+impl ReportColumns {
+    pub(crate) fn contains(&self, column: ReportColumn) -> bool {
+        match column {
+            ReportColumn::Client
+            | ReportColumn::Available
+            | ReportColumn::Held
+            | ReportColumn::Total
+            | ReportColumn::Locked => true,
+            ReportColumn::Deposits
+            | ReportColumn::Withdrawals
+            | ReportColumn::OpenDisputes
+            | ReportColumn::Rejected => self.activity,
+            ReportColumn::Chargebacks => self.activity || self.risk,
+            ReportColumn::DisputesOpened | ReportColumn::ChargebackRatio => self.risk,
+            ReportColumn::LastActivity => self.last_activity,
+            ReportColumn::LockReason => self.lock_reason,
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl<'a> ReportRow<'a> {
+    pub(crate) fn new(
+        report: &'a ClientReport,
+        columns: ReportColumns,
+        scale: Option<u32>,
+    ) -> Self {
+        Self {
+            report,
+            columns,
+            scale,
+            transform: None,
+            client: None,
+        }
+    }
+
+    pub(crate) fn with_transform(mut self, transform: Option<&'a ReportTransform>) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub(crate) fn with_client(mut self, client: Option<&'a str>) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// decimal places of the amounts
+    #[cfg(feature = "arrow")]
+    pub(crate) fn scale(&self) -> Option<u32> {
+        self.transform
+            .and_then(|transform| transform.scale)
+            .or(self.scale)
+    }
+
+    pub(crate) fn amount(&self, value: Decimal) -> Decimal {
+        if let Some(transform) = self.transform {
+            return transform.amount(value, self.scale);
+        }
+
+        match self.scale {
+            Some(scale) => {
+                let mut value = value.round_dp(scale);
+                value.rescale(scale);
+                value
+            }
+            None => value,
+        }
+    }
+
+    fn serialize_column<S: SerializeStruct>(
+        &self,
+        row: &mut S,
+        column: ReportColumn,
+    ) -> Result<(), S::Error> {
+        let r = self.report;
+        let name = match self.transform {
+            Some(transform) => transform.name(column),
+            None => column.name(),
+        };
+
+        match column {
+            ReportColumn::Client => match self.client {
+                Some(client) => row.serialize_field(name, client),
+                None => row.serialize_field(name, &r.id),
+            },
+            ReportColumn::Available => row.serialize_field(name, &self.amount(r.available)),
+            ReportColumn::Held => row.serialize_field(name, &self.amount(r.held)),
+            ReportColumn::Total => row.serialize_field(name, &self.amount(r.total)),
+            ReportColumn::Locked => row.serialize_field(name, &r.locked),
+            ReportColumn::Deposits => row.serialize_field(name, &r.activity.deposits),
+            ReportColumn::Withdrawals => row.serialize_field(name, &r.activity.withdrawals),
+            ReportColumn::OpenDisputes => row.serialize_field(name, &r.activity.open_disputes),
+            ReportColumn::Chargebacks => row.serialize_field(name, &r.activity.chargebacks),
+            ReportColumn::Rejected => row.serialize_field(name, &r.activity.rejected),
+            ReportColumn::DisputesOpened => row.serialize_field(name, &r.activity.disputes_opened),
+            ReportColumn::ChargebackRatio => {
+                row.serialize_field(name, &r.activity.chargeback_ratio())
+            }
+            ReportColumn::LastActivity => row.serialize_field(name, &r.last_activity),
+            ReportColumn::LockReason => row.serialize_field(name, &r.lock_reason),
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Serialize for ReportRow<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut row = serializer.serialize_struct("ClientReport", ReportColumn::ALL.len())?;
+
+        // the columns of a transform are written whatever the report options
+        match self
+            .transform
+            .filter(|transform| !transform.columns.is_empty())
+        {
+            Some(transform) => {
+                for &column in &transform.columns {
+                    self.serialize_column(&mut row, column)?;
+                }
+            }
+            None => {
+                for column in ReportColumn::ALL {
+                    if self.columns.contains(column) {
+                        self.serialize_column(&mut row, column)?;
+                    }
+                }
+            }
+        }
+
+        row.end()
+    }
 }
 
+#[cfg(any(feature = "cli", test))]
+impl fmt::Display for ClientReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "client {}: available {}, held {}, total {}",
+            self.id, self.available, self.held, self.total
+        )?;
+
+        if self.locked {
+            write!(f, ", locked")?;
+        }
+
+        if self.frozen {
+            write!(f, ", frozen")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "cli", feature = "wasm", test))]
 impl ClientReport {
     pub(crate) fn new(c: &Client) -> Self {
         Self {
             id: c.id,
-            available: c.available,
-            held: c.held,
-            total: c.available + c.held,
+            available: c.available.amount(),
+            held: c.held.amount(),
+            total: c.available.amount() + c.held.amount(),
             locked: c.locked,
+            #[cfg(any(feature = "cli", test))]
+            frozen: c.frozen,
+            #[cfg(feature = "cli")]
+            activity: c.activity,
+            #[cfg(feature = "cli")]
+            last_activity: c.last_activity,
+            #[cfg(any(feature = "cli", test))]
+            locked_by: c.locked_by,
+            #[cfg(any(feature = "cli", test))]
+            lock_reason: c.lock_reason(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use rust_decimal_macros::dec;
+
     use super::*;
+    use crate::models::errors::ErrorKind::NegativeAmount;
+
+    fn money(amount: Decimal) -> Money {
+        Money::new(amount).expect("negative amount")
+    }
 
     //client new
     #[test]
@@ -179,7 +850,7 @@ mod tests {
     fn deposit_success() {
         let val = dec!(1);
         let mut client = Client::new(1);
-        client.deposit(&val).expect("failed to deposit");
+        client.deposit(money(val)).expect("failed to deposit");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available, val);
@@ -191,7 +862,7 @@ mod tests {
     fn deposit_success_round() {
         let val = dec!(3.12345);
         let mut client = Client::new(1);
-        client.deposit(&val).expect("failed to deposit");
+        client.deposit(money(val)).expect("failed to deposit");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available.to_string(), "3.1234");
@@ -203,7 +874,7 @@ mod tests {
     fn deposit_fail_locked() {
         let mut client = Client::new(1);
         client.locked = true;
-        let result = client.deposit(&dec!(1));
+        let result = client.deposit(money(dec!(1)));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), AccountLocked);
@@ -216,7 +887,7 @@ mod tests {
     #[test]
     fn deposit_fail_negative_amount() {
         let mut client = Client::new(1);
-        let result = client.deposit(&dec!(-1));
+        let result = Money::new(dec!(-1)).and_then(|amount| client.deposit(amount));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), NegativeAmount);
@@ -226,13 +897,14 @@ mod tests {
         assert!(!client.locked)
     }
 
+    #[cfg(not(feature = "minor-units"))]
     #[test]
     fn deposit_fail_overflow() {
         let mut client = Client::new(1);
         client
-            .deposit(&Decimal::MAX)
+            .deposit(money(Decimal::MAX))
             .expect("failed to deposit max");
-        let result = client.deposit(&Decimal::MAX);
+        let result = client.deposit(money(Decimal::MAX));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), Overflow);
@@ -247,8 +919,10 @@ mod tests {
     fn withdraw_success() {
         let val = dec!(1);
         let mut client = Client::new(1);
-        client.deposit(&val).expect("failed to deposit");
-        client.withdraw(&val).expect("failed to withdraw");
+        client.deposit(money(val)).expect("failed to deposit");
+        client
+            .withdraw(money(val), WithdrawalPolicy::Available, None)
+            .expect("failed to withdraw");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available, dec!(0));
@@ -260,8 +934,10 @@ mod tests {
     fn withdraw_success_round() {
         let val = dec!(3.12345);
         let mut client = Client::new(1);
-        client.deposit(&dec!(4)).expect("failed to deposit");
-        client.withdraw(&val).expect("failed to withdraw");
+        client.deposit(money(dec!(4))).expect("failed to deposit");
+        client
+            .withdraw(money(val), WithdrawalPolicy::Available, None)
+            .expect("failed to withdraw");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available.to_string(), "0.8766");
@@ -269,11 +945,58 @@ mod tests {
         assert!(!client.locked)
     }
 
+    #[test]
+    fn withdraw_success_total() {
+        let mut client = Client::new(1);
+        client.deposit(money(dec!(5))).expect("failed to deposit");
+        client.dispute(money(dec!(3))).expect("failed to dispute");
+        assert_eq!(
+            client.withdraw(money(dec!(4)), WithdrawalPolicy::Available, None),
+            Err(InsufficientFunds)
+        );
+        assert!(client.has_open_disputes());
+
+        client
+            .withdraw(money(dec!(4)), WithdrawalPolicy::Total, None)
+            .expect("failed to withdraw");
+        assert_eq!(client.available, dec!(-2));
+        assert_eq!(client.held, dec!(3));
+        assert_eq!(
+            client.withdraw(money(dec!(2)), WithdrawalPolicy::Total, None),
+            Err(InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn withdraw_fail_min_balance() {
+        let mut client = Client::new(1);
+        client.deposit(money(dec!(10))).expect("failed to deposit");
+        let floor = Some(dec!(4));
+
+        assert_eq!(
+            client.withdraw(money(dec!(7)), WithdrawalPolicy::Available, floor),
+            Err(BelowMinimumBalance)
+        );
+        assert_eq!(
+            client.hold_withdrawal(money(dec!(6.01)), floor),
+            Err(BelowMinimumBalance)
+        );
+        // funds the client doesn't have are still insufficient
+        assert_eq!(
+            client.withdraw(money(dec!(11)), WithdrawalPolicy::Available, floor),
+            Err(InsufficientFunds)
+        );
+        client
+            .withdraw(money(dec!(6)), WithdrawalPolicy::Available, floor)
+            .expect("failed to withdraw down to the floor");
+        assert_eq!(client.available, dec!(4));
+    }
+
     #[test]
     fn withdraw_fail_insufficient_funds() {
         let val = dec!(1);
         let mut client = Client::new(1);
-        let result = client.withdraw(&val);
+        let result = client.withdraw(money(val), WithdrawalPolicy::Available, None);
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), InsufficientFunds);
@@ -287,7 +1010,7 @@ mod tests {
     fn withdraw_fail_locked() {
         let mut client = Client::new(1);
         client.locked = true;
-        let result = client.withdraw(&dec!(1));
+        let result = client.withdraw(money(dec!(1)), WithdrawalPolicy::Available, None);
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), AccountLocked);
@@ -297,11 +1020,89 @@ mod tests {
         assert!(client.locked)
     }
 
+    #[test]
+    fn settle_cancel_withdrawal_fail_insufficient_held() {
+        let mut client = Client::new(1);
+        client.deposit(money(dec!(10))).expect("failed to deposit");
+        client
+            .hold_withdrawal(money(dec!(4)), None)
+            .expect("failed to hold withdrawal");
+
+        assert_eq!(
+            client.settle_withdrawal(money(dec!(5))),
+            Err(InsufficientHeldFunds)
+        );
+        assert_eq!(
+            client.cancel_withdrawal(money(dec!(5))),
+            Err(InsufficientHeldFunds)
+        );
+        assert_eq!(client.available, dec!(6));
+        assert_eq!(client.held, dec!(4));
+    }
+
+    #[test]
+    fn withdraw_fail_frozen() {
+        let mut client = Client::new(1);
+        client.deposit(money(dec!(5))).expect("failed to deposit");
+        client.freeze().expect("failed to freeze");
+        assert_eq!(client.freeze(), Err(AccountFrozen));
+
+        assert_eq!(
+            client.withdraw(money(dec!(1)), WithdrawalPolicy::Available, None),
+            Err(AccountFrozen)
+        );
+        assert_eq!(
+            client.hold_withdrawal(money(dec!(1)), None),
+            Err(AccountFrozen)
+        );
+        client
+            .deposit(money(dec!(1)))
+            .expect("failed to deposit while frozen");
+        client
+            .dispute(money(dec!(1)))
+            .expect("failed to dispute while frozen");
+        assert_eq!(client.available, dec!(5));
+
+        client.unfreeze().expect("failed to unfreeze");
+        assert_eq!(client.unfreeze(), Err(AccountNotFrozen));
+        client
+            .withdraw(money(dec!(1)), WithdrawalPolicy::Available, None)
+            .expect("failed to withdraw");
+    }
+
+    #[test]
+    fn lock_reasons() {
+        let mut client = Client::new(1);
+        assert_eq!(client.lock_reason(), None);
+        client.freeze().expect("failed to freeze");
+        assert_eq!(client.lock_reason(), Some(LockReason::ComplianceFreeze));
+
+        client.lock().expect("failed to lock");
+        assert_eq!(client.lock(), Err(AccountLocked));
+        assert_eq!(client.lock_reason(), Some(LockReason::Admin));
+        client.lock_by(LockedBy {
+            tx: 7,
+            amount: dec!(1),
+            timestamp: None,
+        });
+        assert_eq!(client.lock_reason(), Some(LockReason::Chargeback(7)));
+        assert_eq!(
+            client.lock_reason().map(|reason| reason.to_string()),
+            Some("chargeback:7".to_string())
+        );
+
+        client.unlock().expect("failed to unlock");
+        assert_eq!(client.lock_reason(), Some(LockReason::ComplianceFreeze));
+    }
+
     #[test]
     fn withdraw_fail_negative_amount() {
         let mut client = Client::new(1);
-        client.deposit(&dec!(1)).expect("failed to deposit max");
-        let result = client.withdraw(&Decimal::MIN);
+        client
+            .deposit(money(dec!(1)))
+            .expect("failed to deposit max");
+        let result = Money::new(Decimal::MIN)
+            .and_then(|amount| client.withdraw(amount, WithdrawalPolicy::Available, None));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), NegativeAmount);
@@ -316,8 +1117,8 @@ mod tests {
     fn dispute_success() {
         let val = dec!(1);
         let mut client = Client::new(1);
-        client.deposit(&val).expect("failed to deposit");
-        client.dispute(&val).expect("failed to dispute");
+        client.deposit(money(val)).expect("failed to deposit");
+        client.dispute(money(val)).expect("failed to dispute");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available, dec!(0));
@@ -329,8 +1130,8 @@ mod tests {
     fn dispute_success_round() {
         let val = dec!(3.12345);
         let mut client = Client::new(1);
-        client.deposit(&dec!(4)).expect("failed to deposit");
-        client.dispute(&val).expect("failed to dispute");
+        client.deposit(money(dec!(4))).expect("failed to deposit");
+        client.dispute(money(val)).expect("failed to dispute");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available.to_string(), "0.8766");
@@ -342,7 +1143,7 @@ mod tests {
     fn dispute_fail_locked() {
         let mut client = Client::new(1);
         client.locked = true;
-        let result = client.dispute(&dec!(1));
+        let result = client.dispute(money(dec!(1)));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), AccountLocked);
@@ -355,7 +1156,7 @@ mod tests {
     #[test]
     fn dispute_fail_negative_amount() {
         let mut client = Client::new(1);
-        let result = client.dispute(&dec!(-1));
+        let result = Money::new(dec!(-1)).and_then(|amount| client.dispute(amount));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), NegativeAmount);
@@ -365,12 +1166,13 @@ mod tests {
         assert!(!client.locked)
     }
 
+    #[cfg(not(feature = "minor-units"))]
     #[test]
     fn dispute_fail_overflow() {
         let mut client = Client::new(1);
-        client.deposit(&dec!(1)).expect("failed to deposit");
+        client.deposit(money(dec!(1))).expect("failed to deposit");
 
-        client.dispute(&Decimal::MAX).expect("failed dispute");
+        client.dispute(money(Decimal::MAX)).expect("failed dispute");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available, dec!(-79228162514264337593543950334));
@@ -378,7 +1180,7 @@ mod tests {
         assert!(!client.locked);
 
         //available overflow
-        let result = client.dispute(&Decimal::MAX);
+        let result = client.dispute(money(Decimal::MAX));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), Overflow);
@@ -388,8 +1190,10 @@ mod tests {
         assert!(!client.locked);
 
         //held overflow
-        client.deposit(&Decimal::MAX).expect("failed to deposit");
-        let result = client.dispute(&Decimal::MAX);
+        client
+            .deposit(money(Decimal::MAX))
+            .expect("failed to deposit");
+        let result = client.dispute(money(Decimal::MAX));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), Overflow);
@@ -404,9 +1208,9 @@ mod tests {
     fn resolve_success() {
         let val = dec!(1);
         let mut client = Client::new(1);
-        client.deposit(&val).expect("failed to deposit");
-        client.dispute(&val).expect("failed to dispute");
-        client.resolve(&val).expect("failed to dispute");
+        client.deposit(money(val)).expect("failed to deposit");
+        client.dispute(money(val)).expect("failed to dispute");
+        client.resolve(money(val)).expect("failed to dispute");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available, val);
@@ -418,9 +1222,11 @@ mod tests {
     fn resolve_success_round() {
         let val = dec!(3.12345);
         let mut client = Client::new(1);
-        client.deposit(&dec!(4)).expect("failed to deposit");
-        client.dispute(&val).expect("failed to dispute");
-        client.resolve(&dec!(1.23456)).expect("failed to dispute");
+        client.deposit(money(dec!(4))).expect("failed to deposit");
+        client.dispute(money(val)).expect("failed to dispute");
+        client
+            .resolve(money(dec!(1.23456)))
+            .expect("failed to dispute");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available.to_string(), "2.1112");
@@ -432,7 +1238,7 @@ mod tests {
     fn resolve_fail_locked() {
         let mut client = Client::new(1);
         client.locked = true;
-        let result = client.resolve(&dec!(1));
+        let result = client.resolve(money(dec!(1)));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), AccountLocked);
@@ -445,7 +1251,7 @@ mod tests {
     #[test]
     fn resolve_fail_negative_amount() {
         let mut client = Client::new(1);
-        let result = client.resolve(&dec!(-1));
+        let result = Money::new(dec!(-1)).and_then(|amount| client.resolve(amount));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), NegativeAmount);
@@ -455,13 +1261,14 @@ mod tests {
         assert!(!client.locked)
     }
 
+    #[cfg(not(feature = "minor-units"))]
     #[test]
     fn resolve_fail_overflow() {
         let mut client = Client::new(1);
-        client.deposit(&dec!(100)).expect("failed to deposit");
+        client.deposit(money(dec!(100))).expect("failed to deposit");
 
         //available overflow
-        let result = client.resolve(&Decimal::MAX);
+        let result = client.resolve(money(Decimal::MAX));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), Overflow);
@@ -471,9 +1278,11 @@ mod tests {
         assert!(!client.locked);
 
         //held overflow
-        client.resolve(&dec!(200)).expect("failed to resolve");
-        client.withdraw(&dec!(300)).expect("failed to withdraw");
-        let result = client.resolve(&Decimal::MAX);
+        client.resolve(money(dec!(200))).expect("failed to resolve");
+        client
+            .withdraw(money(dec!(300)), WithdrawalPolicy::Available, None)
+            .expect("failed to withdraw");
+        let result = client.resolve(money(Decimal::MAX));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), Overflow);
@@ -488,7 +1297,7 @@ mod tests {
     fn chargeback_success() {
         let val = dec!(1);
         let mut client = Client::new(1);
-        client.chargeback(&val).expect("failed to chargeback");
+        client.chargeback(money(val)).expect("failed to chargeback");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available, dec!(0));
@@ -500,7 +1309,7 @@ mod tests {
     fn chargeback_success_round() {
         let val = dec!(3.12345);
         let mut client = Client::new(1);
-        client.chargeback(&val).expect("failed to deposit");
+        client.chargeback(money(val)).expect("failed to deposit");
 
         assert_eq!(client.id, 1);
         assert_eq!(client.available.to_string(), "0");
@@ -512,7 +1321,7 @@ mod tests {
     fn chargeback_fail_locked() {
         let mut client = Client::new(1);
         client.locked = true;
-        let result = client.chargeback(&dec!(1));
+        let result = client.chargeback(money(dec!(1)));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), AccountLocked);
@@ -525,7 +1334,7 @@ mod tests {
     #[test]
     fn chargeback_fail_negative_amount() {
         let mut client = Client::new(1);
-        let result = client.chargeback(&dec!(-1));
+        let result = Money::new(dec!(-1)).and_then(|amount| client.chargeback(amount));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), NegativeAmount);
@@ -535,11 +1344,14 @@ mod tests {
         assert!(!client.locked)
     }
 
+    #[cfg(not(feature = "minor-units"))]
     #[test]
     fn chargeback_fail_overflow() {
         let mut client = Client::new(1);
-        client.resolve(&dec!(1)).expect("failed to deposit max");
-        let result = client.chargeback(&Decimal::MAX);
+        client
+            .resolve(money(dec!(1)))
+            .expect("failed to deposit max");
+        let result = client.chargeback(money(Decimal::MAX));
 
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), Overflow);
@@ -549,11 +1361,118 @@ mod tests {
         assert!(client.locked)
     }
 
+    //activity
+    #[test]
+    fn activity_success() {
+        let mut client = Client::new(1);
+        client.deposit(money(dec!(2))).expect("failed to deposit");
+        client.deposit(money(dec!(1))).expect("failed to deposit");
+        client
+            .withdraw(money(dec!(1)), WithdrawalPolicy::Available, None)
+            .expect("failed to withdraw");
+        client
+            .withdraw(money(dec!(5)), WithdrawalPolicy::Available, None)
+            .expect_err("withdraw should fail");
+        client.dispute(money(dec!(1))).expect("failed to dispute");
+        client.dispute(money(dec!(1))).expect("failed to dispute");
+        client.resolve(money(dec!(1))).expect("failed to resolve");
+        client
+            .chargeback(money(dec!(1)))
+            .expect("failed to chargeback");
+        client.reject(TxType::Withdrawal);
+
+        assert_eq!(
+            client.activity,
+            ClientActivity {
+                deposits: 2,
+                withdrawals: 1,
+                open_disputes: 0,
+                chargebacks: 1,
+                rejected: 1,
+                disputes_opened: 2,
+                rejections: Rejections {
+                    withdrawals: 1,
+                    ..Default::default()
+                },
+            }
+        );
+        assert_eq!(client.activity.chargeback_ratio(), dec!(0.5));
+        assert_eq!(Client::new(2).activity.chargeback_ratio(), dec!(0));
+    }
+
+    //last activity
+    #[test]
+    fn touch_keeps_latest() {
+        let mut client = Client::new(1);
+        let earlier: DateTime<Utc> = "2024-01-01T10:00:00Z".parse().unwrap();
+        let later: DateTime<Utc> = "2024-01-02T10:00:00Z".parse().unwrap();
+        client.touch(later);
+        client.touch(earlier);
+
+        assert_eq!(client.last_activity, Some(later));
+    }
+
+    //report row
+    #[cfg(feature = "cli")]
+    #[test]
+    fn report_row_scale() {
+        let mut client = Client::new(1);
+        client
+            .deposit(money(dec!(1.23456)))
+            .expect("failed to deposit");
+        let report = ClientReport::new(&client);
+
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        wtr.serialize(ReportRow::new(&report, ReportColumns::default(), Some(2)))
+            .expect("failed to serialize");
+        wtr.serialize(ReportRow::new(&report, ReportColumns::default(), None))
+            .expect("failed to serialize");
+        let output = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+
+        assert_eq!(
+            output,
+            "client,available,held,total,locked\n1,1.23,0.00,1.23,false\n1,1.2346,0,1.2346,false\n"
+        );
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn report_row_transform() {
+        use std::collections::HashMap;
+
+        let mut client = Client::new(1);
+        client
+            .deposit(money(dec!(10.005)))
+            .expect("failed to deposit");
+        let report = ClientReport::new(&client);
+        let transform = ReportTransform {
+            rate: Some(dec!(2)),
+            scale: Some(2),
+            columns: vec![
+                ReportColumn::Total,
+                ReportColumn::Client,
+                ReportColumn::Deposits,
+            ],
+            rename: HashMap::from([(ReportColumn::Total, "balance_eur")]),
+            ..Default::default()
+        };
+
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        wtr.serialize(
+            ReportRow::new(&report, ReportColumns::default(), Some(4))
+                .with_transform(Some(&transform)),
+        )
+        .expect("failed to serialize");
+        let output = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+
+        assert_eq!(output, "balance_eur,client,deposits\n20.01,1,1\n");
+    }
+
     //client report new
     #[test]
     fn client_report_new() {
         let mut client = Client::new(1);
-        client.deposit(&dec!(1)).expect("failed to deposit");
+        client.deposit(money(dec!(1))).expect("failed to deposit");
         let client_report = ClientReport::new(&client);
 
         assert_eq!(client_report.id, 1);
@@ -562,4 +1481,27 @@ mod tests {
         assert_eq!(client_report.total, dec!(1));
         assert!(!client_report.locked)
     }
+
+    #[test]
+    fn client_report_display() {
+        let mut unlocked = Client::new(1);
+        unlocked
+            .deposit(money(dec!(1.5)))
+            .expect("failed to deposit");
+        let mut locked = Client::new(12);
+        locked.deposit(money(dec!(20))).expect("failed to deposit");
+        locked.dispute(money(dec!(20))).expect("failed to dispute");
+        locked
+            .chargeback(money(dec!(20)))
+            .expect("failed to chargeback");
+
+        assert_eq!(
+            ClientReport::new(&unlocked).to_string(),
+            "client 1: available 1.5, held 0, total 1.5"
+        );
+        assert_eq!(
+            ClientReport::new(&locked).to_string(),
+            "client 12: available 0, held 0, total 0, locked"
+        );
+    }
 }
@@ -0,0 +1,79 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::errors::ErrorKind;
+use crate::models::errors::ErrorKind::{TxAlreadyUnderDispute, TxChargedBack, TxNotUnderDispute};
+
+/// dispute lifecycle of a deposit: None -> Open -> Resolved | ChargedBack, Resolved -> Open
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeState {
+    #[default]
+    None,
+    Open,
+    Resolved,
+    ChargedBack,
+}
+
+/// same names as the results output
+impl fmt::Display for DisputeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DisputeState::None => "none",
+            DisputeState::Open => "open",
+            DisputeState::Resolved => "resolved",
+            DisputeState::ChargedBack => "charged_back",
+        })
+    }
+}
+
+impl DisputeState {
+    pub(crate) fn dispute(self) -> Result<Self, ErrorKind> {
+        match self {
+            DisputeState::None | DisputeState::Resolved => Ok(DisputeState::Open),
+            DisputeState::Open => Err(TxAlreadyUnderDispute),
+            DisputeState::ChargedBack => Err(TxChargedBack),
+        }
+    }
+
+    pub(crate) fn resolve(self) -> Result<Self, ErrorKind> {
+        match self {
+            DisputeState::Open => Ok(DisputeState::Resolved),
+            _ => Err(TxNotUnderDispute),
+        }
+    }
+
+    pub(crate) fn chargeback(self) -> Result<Self, ErrorKind> {
+        match self {
+            DisputeState::Open => Ok(DisputeState::ChargedBack),
+            _ => Err(TxNotUnderDispute),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions() {
+        assert_eq!(DisputeState::None.dispute(), Ok(DisputeState::Open));
+        assert_eq!(DisputeState::Resolved.dispute(), Ok(DisputeState::Open));
+        assert_eq!(DisputeState::Open.dispute(), Err(TxAlreadyUnderDispute));
+        assert_eq!(DisputeState::ChargedBack.dispute(), Err(TxChargedBack));
+
+        assert_eq!(DisputeState::Open.resolve(), Ok(DisputeState::Resolved));
+        assert_eq!(DisputeState::None.resolve(), Err(TxNotUnderDispute));
+        assert_eq!(DisputeState::Resolved.resolve(), Err(TxNotUnderDispute));
+
+        assert_eq!(
+            DisputeState::Open.chargeback(),
+            Ok(DisputeState::ChargedBack)
+        );
+        assert_eq!(
+            DisputeState::ChargedBack.chargeback(),
+            Err(TxNotUnderDispute)
+        );
+    }
+}
@@ -1,63 +1,268 @@
-use core::fmt;
-use std::fmt::Formatter;
+use crate::models::tx::{ClientId, TxId};
 
-#[derive(Clone, Debug, PartialEq)]
-pub enum Error {
+/// business error kinds, each with a stable machine-readable code
+#[derive(thiserror::Error, Clone, Debug, PartialEq)]
+pub enum ErrorKind {
     /// insufficient available funds
+    #[error("insufficient available funds")]
     InsufficientFunds,
     /// calculation overflow
+    #[error("calculation overflow")]
     Overflow,
     /// Negative amount
+    #[error("negative amount")]
     NegativeAmount,
+    /// Amounts of different currencies
+    #[error("currency mismatch")]
+    CurrencyMismatch,
     /// Tx not found, partner error
+    #[error("tx not found, partner error")]
     TxNotFound,
     /// Tx not under dispute, partner error
+    #[error("tx not under dispute, partner error")]
     TxNotUnderDispute,
     /// Account locked
+    #[error("account locked")]
     AccountLocked,
+    /// Account not locked, admin error
+    #[error("account not locked")]
+    AccountNotLocked,
+    /// Account frozen by a compliance hold
+    #[error("account frozen")]
+    AccountFrozen,
+    /// Account not frozen, admin error
+    #[error("account not frozen")]
+    AccountNotFrozen,
+    /// Withdrawal while a dispute of the account is open
+    #[error("account under dispute")]
+    AccountUnderDispute,
+    /// Client not found, admin error
+    #[error("client not found")]
+    ClientNotFound,
     /// Client id doesn't match
+    #[error("client id doesn't match")]
     ClientIdNoMatch,
     /// Tx id conflict
+    #[error("tx id conflict")]
     TxIdConflict,
     /// Tx is not a deposit
+    #[error("tx is not a deposit")]
     TxNotADeposit,
     /// Tx invalid amount
+    #[error("tx invalid amount")]
     TxInvalidAmount,
+    /// Tx already seen within the dedup window
+    #[error("duplicate tx within the dedup window")]
+    DuplicateTx,
+    /// Tx is not a pending withdrawal
+    #[error("tx not a pending withdrawal")]
+    TxNotPending,
+    /// Tx is not an open authorization
+    #[error("tx not an open authorization")]
+    TxNotAuthorized,
+    /// Authorization older than the authorization ttl
+    #[error("authorization expired")]
+    AuthorizationExpired,
+    /// Settlement without a batch or of a batch without accepted transactions
+    #[error("batch not found")]
+    BatchNotFound,
+    /// Settlement amount differs from the net of its batch, partner error
+    #[error("settlement doesn't match its batch, partner error")]
+    SettlementMismatch,
+    /// Tx is not a deposit or withdrawal
+    #[error("tx not a deposit or withdrawal")]
+    TxNotReversible,
+    /// Tx was undone by a reversal
+    #[error("tx reversed")]
+    TxReversed,
+    /// Tx already under dispute
+    #[error("tx already under dispute")]
+    TxAlreadyUnderDispute,
+    /// Tx was charged back
+    #[error("tx charged back")]
+    TxChargedBack,
+    /// Tx reached the configured number of disputes
+    #[error("tx dispute limit reached")]
+    DisputeLimitReached,
+    /// Storing the tx would exceed the engine memory budget
+    #[error("engine memory budget exceeded")]
+    MemoryBudgetExceeded,
+    /// Withdrawal over the velocity limits of the client
+    #[error("velocity limit exceeded")]
+    VelocityLimitExceeded,
+    /// Withdrawal taking the available funds of the client below its minimum balance
+    #[error("below minimum balance")]
+    BelowMinimumBalance,
+    /// Settle or cancel of a pending withdrawal whose funds aren't held
+    #[error("insufficient held funds")]
+    InsufficientHeldFunds,
+    /// Bulk reversal of transactions that are dropped from memory or of unknown order
+    #[error("reversal incomplete, a tx is dropped from memory or of unknown order")]
+    ReversalIncomplete,
+    /// Tx archive failed to load a dropped tx
+    #[error("tx archive unavailable")]
+    ArchiveFailed,
+    /// Tx rejected by a validation rule, named by the rule
+    #[error("rejected by rule {0}")]
+    RuleViolation(&'static str),
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl ErrorKind {
+    /// stable code, safe to match on across releases and systems
+    pub fn code(&self) -> &'static str {
         match self {
-            Error::InsufficientFunds => {
-                write!(f, "insufficient available funds")
-            }
-            Error::Overflow => {
-                write!(f, "calculation overflow")
-            }
-            Error::NegativeAmount => {
-                write!(f, "negative amount")
-            }
-            Error::TxNotFound => {
-                write!(f, "tx not found, partner error")
-            }
-            Error::TxNotUnderDispute => {
-                write!(f, "tx not under dispute, partner error")
-            }
-            Error::AccountLocked => {
-                write!(f, "account locked")
-            }
-            Error::ClientIdNoMatch => {
-                write!(f, "client id doesn't match")
-            }
-            Error::TxIdConflict => {
-                write!(f, "tx id conflict")
-            }
-            Error::TxNotADeposit => {
-                write!(f, "tx is not a deposit")
-            }
-            Error::TxInvalidAmount => {
-                write!(f, "tx invalid amount")
-            }
+            ErrorKind::InsufficientFunds => "insufficient_funds",
+            ErrorKind::Overflow => "overflow",
+            ErrorKind::NegativeAmount => "negative_amount",
+            ErrorKind::CurrencyMismatch => "currency_mismatch",
+            ErrorKind::TxNotFound => "tx_not_found",
+            ErrorKind::TxNotUnderDispute => "tx_not_under_dispute",
+            ErrorKind::AccountLocked => "account_locked",
+            ErrorKind::AccountNotLocked => "account_not_locked",
+            ErrorKind::AccountFrozen => "account_frozen",
+            ErrorKind::AccountNotFrozen => "account_not_frozen",
+            ErrorKind::AccountUnderDispute => "account_under_dispute",
+            ErrorKind::ClientNotFound => "client_not_found",
+            ErrorKind::ClientIdNoMatch => "client_id_no_match",
+            ErrorKind::TxIdConflict => "tx_id_conflict",
+            ErrorKind::TxNotADeposit => "tx_not_a_deposit",
+            ErrorKind::TxInvalidAmount => "tx_invalid_amount",
+            ErrorKind::DuplicateTx => "duplicate_tx",
+            ErrorKind::TxNotPending => "tx_not_pending",
+            ErrorKind::TxNotAuthorized => "tx_not_authorized",
+            ErrorKind::AuthorizationExpired => "authorization_expired",
+            ErrorKind::BatchNotFound => "batch_not_found",
+            ErrorKind::SettlementMismatch => "settlement_mismatch",
+            ErrorKind::TxNotReversible => "tx_not_reversible",
+            ErrorKind::TxReversed => "tx_reversed",
+            ErrorKind::TxAlreadyUnderDispute => "tx_already_under_dispute",
+            ErrorKind::TxChargedBack => "tx_charged_back",
+            ErrorKind::DisputeLimitReached => "dispute_limit_reached",
+            ErrorKind::MemoryBudgetExceeded => "memory_budget_exceeded",
+            ErrorKind::VelocityLimitExceeded => "velocity_limit_exceeded",
+            ErrorKind::BelowMinimumBalance => "below_minimum_balance",
+            ErrorKind::InsufficientHeldFunds => "insufficient_held_funds",
+            ErrorKind::ReversalIncomplete => "reversal_incomplete",
+            ErrorKind::ArchiveFailed => "archive_failed",
+            ErrorKind::RuleViolation(_) => "rule_violation",
         }
     }
 }
+
+/// business error with the context of the offending transaction when available
+#[derive(thiserror::Error, Clone, Debug, PartialEq)]
+#[error("{kind}{}", context(.tx_id, .client_id, .line))]
+pub struct Error {
+    kind: ErrorKind,
+    tx_id: Option<TxId>,
+    client_id: Option<ClientId>,
+    line: Option<u64>,
+}
+
+impl Error {
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    pub fn tx_id(&self) -> Option<TxId> {
+        self.tx_id
+    }
+
+    pub fn client_id(&self) -> Option<ClientId> {
+        self.client_id
+    }
+
+    /// line of the input source the transaction was read from
+    pub fn line(&self) -> Option<u64> {
+        self.line
+    }
+
+    pub(crate) fn with_tx(mut self, tx_id: TxId, client_id: ClientId) -> Self {
+        self.tx_id = Some(tx_id);
+        self.client_id = Some(client_id);
+        self
+    }
+
+    #[cfg(any(feature = "cli", test))]
+    pub(crate) fn with_line(mut self, line: u64) -> Self {
+        self.line = Some(line);
+        self
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            tx_id: None,
+            client_id: None,
+            line: None,
+        }
+    }
+}
+
+/// batch rolled back because one of its transactions was rejected, none of it was applied
+#[derive(thiserror::Error, Clone, Debug, PartialEq)]
+#[error("batch rolled back at transaction {index}: {error}")]
+pub struct BatchError {
+    index: usize,
+    error: Error,
+}
+
+impl BatchError {
+    pub(crate) fn new(index: usize, error: Error) -> Self {
+        Self { index, error }
+    }
+
+    /// position of the rejected transaction in the batch
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+}
+
+fn context(tx_id: &Option<TxId>, client_id: &Option<ClientId>, line: &Option<u64>) -> String {
+    let mut context = Vec::new();
+
+    if let Some(tx_id) = tx_id {
+        context.push(format!("tx {}", tx_id));
+    }
+
+    if let Some(client_id) = client_id {
+        context.push(format!("client {}", client_id));
+    }
+
+    if let Some(line) = line {
+        context.push(format!("line {}", line));
+    }
+
+    if context.is_empty() {
+        return String::new();
+    }
+
+    format!(" ({})", context.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_display() {
+        let err = Error::from(ErrorKind::AccountLocked);
+
+        assert_eq!(err.to_string(), "account locked");
+        assert_eq!(err.code(), "account_locked");
+        assert_eq!(
+            err.with_tx(3, 1).with_line(4).to_string(),
+            "account locked (tx 3, client 1, line 4)"
+        );
+    }
+}
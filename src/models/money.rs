@@ -0,0 +1,343 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "minor-units")]
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::models::errors::ErrorKind;
+use crate::models::errors::ErrorKind::{CurrencyMismatch, NegativeAmount, Overflow};
+
+/// decimal places the results of money arithmetic are rounded to
+pub(crate) const PRECISION: u32 = 4;
+
+/// stored amount, a decimal or with the minor-units feature the number of 1/10^PRECISION units
+#[cfg(not(feature = "minor-units"))]
+type Repr = Decimal;
+#[cfg(feature = "minor-units")]
+type Repr = Units;
+
+#[cfg(not(feature = "minor-units"))]
+const REPR_ZERO: Repr = Decimal::ZERO;
+#[cfg(feature = "minor-units")]
+const REPR_ZERO: Repr = Units { units: 0, scale: 0 };
+
+/// minor units per unit
+#[cfg(feature = "minor-units")]
+const UNITS: Decimal = Decimal::from_parts(10_u32.pow(PRECISION), 0, 0, false, 0);
+
+/// amount in minor units, with the decimal places it's written with: those of the amount it
+/// was converted from, or the most of the amounts of an operation, like a decimal would be
+#[cfg(feature = "minor-units")]
+#[derive(Debug, Default, Copy, Clone)]
+struct Units {
+    units: i64,
+    scale: u32,
+}
+
+#[cfg(feature = "minor-units")]
+impl Units {
+    fn checked_add(self, other: Units) -> Option<Units> {
+        Some(Units {
+            units: self.units.checked_add(other.units)?,
+            scale: self.scale.max(other.scale),
+        })
+    }
+
+    fn checked_sub(self, other: Units) -> Option<Units> {
+        Some(Units {
+            units: self.units.checked_sub(other.units)?,
+            scale: self.scale.max(other.scale),
+        })
+    }
+}
+
+#[cfg(feature = "minor-units")]
+impl PartialEq for Units {
+    fn eq(&self, other: &Self) -> bool {
+        self.units == other.units
+    }
+}
+
+#[cfg(feature = "minor-units")]
+impl PartialOrd for Units {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.units.partial_cmp(&other.units)
+    }
+}
+
+#[cfg(not(feature = "minor-units"))]
+fn to_repr(amount: Decimal) -> Result<Repr, ErrorKind> {
+    Ok(amount)
+}
+
+/// rounds the amount to the precision, an amount out of the i64 range overflows
+#[cfg(feature = "minor-units")]
+fn to_repr(amount: Decimal) -> Result<Repr, ErrorKind> {
+    let amount = amount.round_dp(PRECISION);
+    Ok(Units {
+        units: amount
+            .checked_mul(UNITS)
+            .and_then(|units| units.to_i64())
+            .ok_or(Overflow)?,
+        scale: amount.scale(),
+    })
+}
+
+#[cfg(not(feature = "minor-units"))]
+fn from_repr(amount: Repr) -> Decimal {
+    amount
+}
+
+#[cfg(feature = "minor-units")]
+fn from_repr(amount: Repr) -> Decimal {
+    let mut decimal = Decimal::new(amount.units, PRECISION);
+    // only the trailing zeros of the minor units are dropped
+    decimal.rescale(amount.scale);
+    decimal
+}
+
+#[cfg(not(feature = "minor-units"))]
+fn round(amount: Repr) -> Repr {
+    amount.round_dp(PRECISION)
+}
+
+#[cfg(feature = "minor-units")]
+fn round(amount: Repr) -> Repr {
+    amount
+}
+
+/// iso 4217 alphabetic code of a currency
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Currency([u8; 3]);
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        match code.as_bytes() {
+            &[a, b, c] if [a, b, c].iter().all(u8::is_ascii_uppercase) => Ok(Self([a, b, c])),
+            _ => Err(format!("invalid currency code: {}", code)),
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the code is checked to be ascii when it's parsed
+        self.0.iter().try_for_each(|&c| write!(f, "{}", c as char))
+    }
+}
+
+/// amount of money, in a currency when it's known; the amount of a transaction can't be
+/// negative while a balance can, and the results of the arithmetic are rounded to the engine
+/// precision; with the minor-units feature the amounts are overflow checked i64 minor units,
+/// faster than decimals but limited to about 9.2e14
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct Money {
+    amount: Repr,
+    currency: Option<Currency>,
+}
+
+impl Money {
+    pub const ZERO: Money = Money {
+        amount: REPR_ZERO,
+        currency: None,
+    };
+
+    /// amount of a transaction, kept as given, or rounded to the precision in minor units
+    pub fn new(amount: Decimal) -> Result<Self, ErrorKind> {
+        if amount.is_sign_negative() {
+            return Err(NegativeAmount);
+        }
+
+        Ok(Self {
+            amount: to_repr(amount)?,
+            currency: None,
+        })
+    }
+
+    pub fn with_currency(self, currency: Currency) -> Self {
+        Self {
+            currency: Some(currency),
+            ..self
+        }
+    }
+
+    pub fn amount(&self) -> Decimal {
+        from_repr(self.amount)
+    }
+
+    pub fn currency(&self) -> Option<Currency> {
+        self.currency
+    }
+
+    /// whether the amount is below zero, a negative zero is not
+    pub fn is_negative(&self) -> bool {
+        self.amount < REPR_ZERO
+    }
+
+    pub fn checked_add(self, other: Money) -> Result<Money, ErrorKind> {
+        self.checked(other, self.amount.checked_add(other.amount))
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Money, ErrorKind> {
+        self.checked(other, self.amount.checked_sub(other.amount))
+    }
+
+    /// result of an operation of the two amounts, an amount without a currency takes the
+    /// currency of the other one
+    fn checked(self, other: Money, amount: Option<Repr>) -> Result<Money, ErrorKind> {
+        let currency = match (self.currency, other.currency) {
+            (Some(a), Some(b)) if a != b => return Err(CurrencyMismatch),
+            (a, b) => a.or(b),
+        };
+
+        match amount {
+            None => Err(Overflow),
+            Some(amount) => Ok(Money {
+                amount: round(amount),
+                currency,
+            }),
+        }
+    }
+}
+
+impl PartialEq<Decimal> for Money {
+    fn eq(&self, other: &Decimal) -> bool {
+        self.amount() == *other
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.currency {
+            None => write!(f, "{}", self.amount()),
+            Some(currency) => write!(f, "{} {}", self.amount(), currency),
+        }
+    }
+}
+
+/// written as its amount, followed by the currency when it's known
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.currency {
+            None => Serialize::serialize(&self.amount(), serializer),
+            Some(_) => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Amount(Decimal),
+            WithCurrency(String),
+        }
+
+        let (amount, currency) = match Repr::deserialize(deserializer)? {
+            Repr::Amount(amount) => (amount, None),
+            Repr::WithCurrency(money) => {
+                let (amount, currency) = money
+                    .split_once(' ')
+                    .ok_or_else(|| serde::de::Error::custom("invalid amount"))?;
+                (
+                    amount.parse().map_err(serde::de::Error::custom)?,
+                    Some(currency.parse().map_err(serde::de::Error::custom)?),
+                )
+            }
+        };
+        Ok(Money {
+            amount: to_repr(amount).map_err(serde::de::Error::custom)?,
+            currency,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(Money::new(dec!(-1)), Err(NegativeAmount));
+        assert!(!Money::new(dec!(-0)).unwrap().is_negative());
+
+        let one = Money::new(dec!(1)).unwrap();
+        let sum = one.checked_add(Money::new(dec!(0.00005)).unwrap()).unwrap();
+        assert_eq!(sum, dec!(1));
+        assert!(Money::ZERO.checked_sub(one).unwrap().is_negative());
+        assert_eq!(
+            Money::new(Decimal::MAX).and_then(|max| max.checked_add(one)),
+            Err(Overflow)
+        );
+
+        let eur = "EUR".parse().unwrap();
+        let usd = "USD".parse().unwrap();
+        assert_eq!(
+            Money::ZERO.checked_add(one.with_currency(eur)).unwrap(),
+            one.with_currency(eur)
+        );
+        assert_eq!(
+            one.with_currency(eur).checked_sub(one.with_currency(usd)),
+            Err(CurrencyMismatch)
+        );
+        assert!("eur".parse::<Currency>().is_err());
+        assert!("EURO".parse::<Currency>().is_err());
+    }
+
+    #[cfg(feature = "minor-units")]
+    #[test]
+    fn minor_units() {
+        let max = Money::new(Decimal::new(i64::MAX, PRECISION)).unwrap();
+        assert_eq!(max.amount(), dec!(922337203685477.5807));
+        assert_eq!(
+            max.checked_add(Money::new(dec!(0.0001)).unwrap()),
+            Err(Overflow)
+        );
+        assert_eq!(Money::new(dec!(922337203685478)), Err(Overflow));
+
+        // amounts are rounded half to even when they're converted, before they're added
+        let amount = Money::new(dec!(0.00015)).unwrap();
+        assert_eq!(amount.amount(), dec!(0.0002));
+        assert_eq!(amount.to_string(), "0.0002");
+
+        // amounts keep the decimal places they're written with
+        let sum = Money::new(dec!(1.50))
+            .unwrap()
+            .checked_add(Money::new(dec!(2)).unwrap())
+            .unwrap();
+        assert_eq!(sum.to_string(), "3.50");
+        assert_eq!(
+            Money::new(dec!(1.0))
+                .unwrap()
+                .checked_sub(sum)
+                .unwrap()
+                .to_string(),
+            "-2.50"
+        );
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn serde() {
+        let eur = "EUR".parse().unwrap();
+        for money in [
+            Money::new(dec!(1.5)).unwrap(),
+            Money::new(dec!(2)).unwrap().with_currency(eur),
+        ] {
+            let json = serde_json::to_string(&money).unwrap();
+            assert_eq!(serde_json::from_str::<Money>(&json).unwrap(), money);
+        }
+        assert_eq!(
+            serde_json::to_string(&Money::new(dec!(2)).unwrap().with_currency(eur)).unwrap(),
+            r#""2 EUR""#
+        );
+        assert!(serde_json::from_str::<Money>(r#""2 euros""#).is_err());
+    }
+}
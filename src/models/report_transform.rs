@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Deserializer};
+
+/// column of the client report
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportColumn {
+    Client,
+    Available,
+    Held,
+    Total,
+    Locked,
+    Deposits,
+    Withdrawals,
+    OpenDisputes,
+    Chargebacks,
+    Rejected,
+    DisputesOpened,
+    ChargebackRatio,
+    LastActivity,
+    LockReason,
+}
+
+impl ReportColumn {
+    /// columns in report order
+    #[cfg(feature = "cli")]
+    pub(crate) const ALL: [ReportColumn; 14] = [
+        ReportColumn::Client,
+        ReportColumn::Available,
+        ReportColumn::Held,
+        ReportColumn::Total,
+        ReportColumn::Locked,
+        ReportColumn::Deposits,
+        ReportColumn::Withdrawals,
+        ReportColumn::OpenDisputes,
+        ReportColumn::Chargebacks,
+        ReportColumn::Rejected,
+        ReportColumn::DisputesOpened,
+        ReportColumn::ChargebackRatio,
+        ReportColumn::LastActivity,
+        ReportColumn::LockReason,
+    ];
+
+    #[cfg(feature = "cli")]
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ReportColumn::Client => "client",
+            ReportColumn::Available => "available",
+            ReportColumn::Held => "held",
+            ReportColumn::Total => "total",
+            ReportColumn::Locked => "locked",
+            ReportColumn::Deposits => "deposits",
+            ReportColumn::Withdrawals => "withdrawals",
+            ReportColumn::OpenDisputes => "open_disputes",
+            ReportColumn::Chargebacks => "chargebacks",
+            ReportColumn::Rejected => "rejected",
+            ReportColumn::DisputesOpened => "disputes_opened",
+            ReportColumn::ChargebackRatio => "chargeback_ratio",
+            ReportColumn::LastActivity => "last_activity",
+            ReportColumn::LockReason => "lock_reason",
+        }
+    }
+}
+
+/// rounding of the amounts to the scale of a transform
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rounding {
+    #[default]
+    HalfEven,
+    HalfUp,
+    Down,
+    Up,
+}
+
+impl From<Rounding> for RoundingStrategy {
+    fn from(rounding: Rounding) -> Self {
+        match rounding {
+            Rounding::HalfEven => RoundingStrategy::MidpointNearestEven,
+            Rounding::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            Rounding::Down => RoundingStrategy::ToZero,
+            Rounding::Up => RoundingStrategy::AwayFromZero,
+        }
+    }
+}
+
+/// post-processing of the rows of a report sink: currency conversion of the amounts,
+/// their rounding, and the selection, order and names of the columns
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReportTransform {
+    /// exchange rate multiplying the amounts, e.g. into the reporting currency
+    pub rate: Option<Decimal>,
+
+    /// decimal places of the amounts, overrides `--scale`
+    pub scale: Option<u32>,
+
+    pub rounding: Rounding,
+
+    /// columns of the rows in order, the columns of the report options when empty
+    pub columns: Vec<ReportColumn>,
+
+    /// header names of columns
+    #[serde(deserialize_with = "static_names")]
+    pub rename: HashMap<ReportColumn, &'static str>,
+}
+
+impl ReportTransform {
+    #[cfg(feature = "cli")]
+    pub(crate) fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// whether the columns of the rows differ from the report options
+    #[cfg(feature = "arrow")]
+    pub(crate) fn changes_columns(&self) -> bool {
+        !self.columns.is_empty() || !self.rename.is_empty()
+    }
+
+    #[cfg(feature = "cli")]
+    pub(crate) fn name(&self, column: ReportColumn) -> &'static str {
+        self.rename
+            .get(&column)
+            .copied()
+            .unwrap_or_else(|| column.name())
+    }
+
+    /// converted amount, rounded with the transform scale or else the report scale
+    pub(crate) fn amount(&self, value: Decimal, scale: Option<u32>) -> Decimal {
+        let value = match self.rate {
+            Some(rate) => value * rate,
+            None => value,
+        };
+
+        match self.scale.or(scale) {
+            Some(scale) => {
+                let mut value = value.round_dp_with_strategy(scale, self.rounding.into());
+                value.rescale(scale);
+                value
+            }
+            None => value,
+        }
+    }
+}
+
+// the struct serializers of the formats take static field names, the names are read once per sink
+fn static_names<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<ReportColumn, &'static str>, D::Error> {
+    let names = HashMap::<ReportColumn, String>::deserialize(deserializer)?;
+
+    Ok(names
+        .into_iter()
+        .map(|(column, name)| (column, &*Box::leak(name.into_boxed_str())))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn transform_amount() {
+        let transform = ReportTransform {
+            rate: Some(dec!(0.5)),
+            scale: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(transform.amount(dec!(1.25), None), dec!(0.62));
+        assert_eq!(transform.amount(dec!(1.25), Some(4)), dec!(0.62));
+
+        let transform = ReportTransform {
+            rounding: Rounding::Up,
+            ..Default::default()
+        };
+        assert_eq!(transform.amount(dec!(1.231), Some(2)), dec!(1.24));
+        assert_eq!(transform.amount(dec!(1.231), None), dec!(1.231));
+        assert_eq!(transform.amount(dec!(1), Some(2)).to_string(), "1.00");
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn transform_names() {
+        let transform: ReportTransform = serde_json::from_str(
+            r#"{"columns": ["client", "total"], "rename": {"total": "balance"}}"#,
+        )
+        .expect("invalid transform");
+
+        assert_eq!(transform.name(ReportColumn::Total), "balance");
+        assert_eq!(transform.name(ReportColumn::Client), "client");
+        assert!(!transform.is_identity());
+        assert!(ReportTransform::default().is_identity());
+    }
+}
@@ -1,41 +1,339 @@
+use crate::models::dispute_state::DisputeState;
+use crate::models::errors::ErrorKind;
+use crate::models::money::Money;
 use crate::models::tx_type::TxType;
+use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 
-pub(crate) type ClientId = u16;
-pub(crate) type TxId = u32;
+pub type ClientId = u16;
+pub type TxId = u32;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct TxInput {
     #[serde(rename = "type")]
-    pub(crate) tx_type: TxType,
+    pub tx_type: TxType,
 
     #[serde(rename = "client")]
-    pub(crate) client_id: ClientId,
+    pub client_id: ClientId,
 
     #[serde(rename = "tx")]
-    pub(crate) id: TxId,
+    pub id: TxId,
+
+    pub amount: Option<Decimal>,
+
+    /// optional rfc3339 time of the transaction
+    #[serde(default)]
+    pub timestamp: Option<DateTime<Utc>>,
+
+    /// optional key of the upstream request, a transaction reusing the key of an accepted one
+    /// is a retry and is acknowledged without being applied again
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+
+    /// optional id of the partner batch of the transaction, closed by a settlement of the batch
+    #[serde(default)]
+    pub batch: Option<String>,
 
-    pub(crate) amount: Option<Decimal>,
+    /// optional value date, a transaction dated after the as-of date of the engine is queued
+    /// until the engine is advanced to its date
+    #[serde(default)]
+    pub effective_date: Option<NaiveDate>,
 }
 
-#[derive(Debug, PartialEq)]
+/// stored transaction, the type and dispute state are packed in a single byte
+/// to keep the per transaction memory of large inputs low
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub(crate) struct Tx {
-    //pub(crate) id: TxId, //unused
-    pub(crate) client_id: ClientId,
-    pub(crate) tx_type: TxType,
     pub(crate) amount: Decimal,
-    pub(crate) under_dispute: bool,
+    pub(crate) client_id: ClientId,
+    // bits 0-2 and 5 tx type, bits 3-4 dispute state, bit 6 reversed
+    flags: u8,
+    // saturates at u8::MAX
+    disputes: u8,
+    /// order the engine stored the transaction in from 1, 0 when unknown: archived transactions
+    /// and the ones of older checkpoints don't keep it
+    #[serde(default)]
+    seq: u32,
+    /// milliseconds since the epoch of the transaction timestamp, NO_TIMESTAMP without one
+    #[serde(default = "no_timestamp")]
+    timestamp: i64,
+}
+
+const NO_TIMESTAMP: i64 = i64::MIN;
+
+fn no_timestamp() -> i64 {
+    NO_TIMESTAMP
+}
+
+/// read-only view of a deposit or withdrawal stored by the engine
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct TxView<'a>(pub(crate) &'a Tx);
+
+impl TxView<'_> {
+    pub fn amount(&self) -> Decimal {
+        self.0.amount
+    }
+
+    pub fn client_id(&self) -> ClientId {
+        self.0.client_id
+    }
+
+    pub fn tx_type(&self) -> TxType {
+        self.0.tx_type()
+    }
+
+    pub fn dispute_state(&self) -> DisputeState {
+        self.0.dispute_state()
+    }
+
+    /// number of disputes opened on the transaction
+    pub fn disputes(&self) -> u32 {
+        self.0.disputes()
+    }
+
+    /// whether a reversal undid the transaction
+    pub fn reversed(&self) -> bool {
+        self.0.reversed()
+    }
 }
 
+// the fourth type bit was added after the dispute state bits, which keep their place
+const TX_TYPE_MASK: u8 = 0b0010_0111;
+const DISPUTE_STATE_MASK: u8 = 0b0000_0011;
+const DISPUTE_STATE_SHIFT: u8 = 3;
+const REVERSED: u8 = 0b0100_0000;
+
 impl Tx {
     pub(crate) fn new(tx_input: &TxInput) -> Self {
         Self {
-            client_id: tx_input.client_id,
-            tx_type: tx_input.tx_type,
             amount: tx_input.amount.unwrap_or_else(|| dec!(0)),
-            under_dispute: false,
+            client_id: tx_input.client_id,
+            flags: tx_type_bits(tx_input.tx_type),
+            disputes: 0,
+            seq: 0,
+            timestamp: tx_input
+                .timestamp
+                .map_or(NO_TIMESTAMP, |timestamp| timestamp.timestamp_millis()),
+        }
+    }
+
+    /// the transaction stored as the `seq`th one of the engine
+    pub(crate) fn stored_as(self, seq: u32) -> Self {
+        Self { seq, ..self }
+    }
+
+    /// order the engine stored the transaction in, None when unknown
+    pub(crate) fn seq(&self) -> Option<u32> {
+        (self.seq != 0).then_some(self.seq)
+    }
+
+    /// milliseconds since the epoch of the transaction timestamp
+    pub(crate) fn timestamp_millis(&self) -> Option<i64> {
+        (self.timestamp != NO_TIMESTAMP).then_some(self.timestamp)
+    }
+
+    /// amount moved by the transaction, the amount of a stored transaction rejected as negative
+    /// is rejected again
+    pub(crate) fn money(&self) -> Result<Money, ErrorKind> {
+        Money::new(self.amount)
+    }
+
+    pub(crate) fn tx_type(&self) -> TxType {
+        match self.flags & TX_TYPE_MASK {
+            0 => TxType::Deposit,
+            1 => TxType::Withdrawal,
+            2 => TxType::Dispute,
+            3 => TxType::Resolve,
+            4 => TxType::Chargeback,
+            5 => TxType::WithdrawalPending,
+            6 => TxType::WithdrawalSettle,
+            7 => TxType::WithdrawalCancel,
+            0b0010_0000 => TxType::Authorize,
+            0b0010_0001 => TxType::Capture,
+            0b0010_0010 => TxType::Settlement,
+            _ => TxType::Reversal,
+        }
+    }
+
+    /// phase of a two-phase withdrawal, kept as the type of its latest transaction, or a
+    /// captured authorization turned into a deposit
+    pub(crate) fn set_tx_type(&mut self, tx_type: TxType) {
+        self.flags = (self.flags & !TX_TYPE_MASK) | tx_type_bits(tx_type);
+    }
+
+    pub(crate) fn dispute_state(&self) -> DisputeState {
+        match (self.flags >> DISPUTE_STATE_SHIFT) & DISPUTE_STATE_MASK {
+            0 => DisputeState::None,
+            1 => DisputeState::Open,
+            2 => DisputeState::Resolved,
+            _ => DisputeState::ChargedBack,
+        }
+    }
+
+    pub(crate) fn set_dispute_state(&mut self, state: DisputeState) {
+        let bits = match state {
+            DisputeState::None => 0,
+            DisputeState::Open => 1,
+            DisputeState::Resolved => 2,
+            DisputeState::ChargedBack => 3,
+        };
+        self.flags = (self.flags & !(DISPUTE_STATE_MASK << DISPUTE_STATE_SHIFT))
+            | (bits << DISPUTE_STATE_SHIFT);
+    }
+
+    pub(crate) fn reversed(&self) -> bool {
+        self.flags & REVERSED != 0
+    }
+
+    /// marks a deposit or withdrawal undone by a reversal, its type and dispute state are kept
+    pub(crate) fn set_reversed(&mut self) {
+        self.flags |= REVERSED;
+    }
+
+    /// number of disputes opened on the transaction
+    pub(crate) fn disputes(&self) -> u32 {
+        self.disputes.into()
+    }
+
+    pub(crate) fn add_dispute(&mut self) {
+        self.disputes = self.disputes.saturating_add(1);
+    }
+
+    /// amount, client id, flags and dispute count, little endian; the store order and timestamp
+    /// are not archived
+    pub(crate) fn to_bytes(&self) -> [u8; 20] {
+        let mut bytes = [0; 20];
+        bytes[..16].copy_from_slice(&self.amount.serialize());
+        bytes[16..18].copy_from_slice(&self.client_id.to_le_bytes());
+        bytes[18] = self.flags;
+        bytes[19] = self.disputes;
+        bytes
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8; 20]) -> Self {
+        let mut amount = [0; 16];
+        amount.copy_from_slice(&bytes[..16]);
+        Self {
+            amount: Decimal::deserialize(amount),
+            client_id: ClientId::from_le_bytes([bytes[16], bytes[17]]),
+            flags: bytes[18],
+            disputes: bytes[19],
+            seq: 0,
+            timestamp: NO_TIMESTAMP,
+        }
+    }
+}
+
+fn tx_type_bits(tx_type: TxType) -> u8 {
+    match tx_type {
+        TxType::Deposit => 0,
+        TxType::Withdrawal => 1,
+        TxType::Dispute => 2,
+        TxType::Resolve => 3,
+        TxType::Chargeback => 4,
+        TxType::WithdrawalPending => 5,
+        TxType::WithdrawalSettle => 6,
+        TxType::WithdrawalCancel => 7,
+        TxType::Authorize => 0b0010_0000,
+        TxType::Capture => 0b0010_0001,
+        TxType::Settlement => 0b0010_0010,
+        TxType::Reversal => 0b0010_0011,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tx_packing() {
+        let mut tx = Tx::new(&TxInput {
+            tx_type: TxType::Withdrawal,
+            client_id: 7,
+            id: 1,
+            amount: Some(dec!(1.5)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        });
+
+        assert_eq!(tx.tx_type(), TxType::Withdrawal);
+        assert_eq!(tx.dispute_state(), DisputeState::None);
+
+        for state in [
+            DisputeState::Open,
+            DisputeState::Resolved,
+            DisputeState::ChargedBack,
+            DisputeState::None,
+        ] {
+            tx.set_dispute_state(state);
+            assert_eq!(tx.dispute_state(), state);
+            assert_eq!(tx.tx_type(), TxType::Withdrawal);
+        }
+
+        tx.set_dispute_state(DisputeState::Resolved);
+        for tx_type in [
+            TxType::WithdrawalPending,
+            TxType::WithdrawalSettle,
+            TxType::WithdrawalCancel,
+            TxType::Authorize,
+            TxType::Capture,
+            TxType::Settlement,
+            TxType::Reversal,
+        ] {
+            tx.set_tx_type(tx_type);
+            assert_eq!(tx.tx_type(), tx_type);
+            assert_eq!(tx.dispute_state(), DisputeState::Resolved);
+        }
+
+        tx.set_tx_type(TxType::Deposit);
+        assert!(!tx.reversed());
+        tx.set_reversed();
+        tx.set_dispute_state(DisputeState::Open);
+        assert!(tx.reversed());
+        assert_eq!(tx.tx_type(), TxType::Deposit);
+        assert_eq!(tx.dispute_state(), DisputeState::Open);
+
+        assert!(std::mem::size_of::<Tx>() <= 32);
+    }
+
+    #[test]
+    fn tx_disputes_saturate() {
+        let mut tx = Tx::new(&TxInput {
+            tx_type: TxType::Deposit,
+            client_id: 1,
+            id: 1,
+            amount: Some(dec!(1)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        });
+        for _ in 0..300 {
+            tx.add_dispute();
         }
+
+        assert_eq!(tx.disputes(), u8::MAX as u32);
+    }
+
+    #[test]
+    fn tx_bytes() {
+        let mut tx = Tx::new(&TxInput {
+            tx_type: TxType::Deposit,
+            client_id: 513,
+            id: 1,
+            amount: Some(dec!(-12.3456)),
+            timestamp: None,
+            idempotency_key: None,
+            batch: None,
+            effective_date: None,
+        });
+        tx.set_dispute_state(DisputeState::Resolved);
+        tx.add_dispute();
+
+        assert_eq!(Tx::from_bytes(&tx.to_bytes()), tx);
     }
 }
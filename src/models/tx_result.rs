@@ -0,0 +1,117 @@
+use rust_decimal::Decimal;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::models::dispute_state::DisputeState;
+use crate::models::errors::Error;
+use crate::models::tx::{ClientId, TxId, TxInput};
+use crate::models::tx_type::TxType;
+
+#[derive(Serialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TxStatus {
+    Accepted,
+    Rejected,
+    ParseError,
+}
+
+/// balances of the client after a record, empty when the client doesn't exist
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub(crate) struct ResultBalances {
+    pub(crate) available: Option<Decimal>,
+    pub(crate) held: Option<Decimal>,
+    pub(crate) total: Option<Decimal>,
+}
+
+/// disposition of a single input record
+#[derive(Debug, PartialEq)]
+pub(crate) struct TxResult {
+    pub(crate) line: Option<u64>,
+    pub(crate) tx_type: Option<TxType>,
+    pub(crate) client_id: Option<ClientId>,
+    pub(crate) id: Option<TxId>,
+
+    pub(crate) status: TxStatus,
+    pub(crate) code: Option<&'static str>,
+    pub(crate) reason: Option<String>,
+
+    /// dispute state of the referenced transaction after processing
+    pub(crate) dispute_state: Option<DisputeState>,
+
+    /// balance columns, only written when set
+    pub(crate) balances: Option<ResultBalances>,
+}
+
+impl TxResult {
+    pub(crate) fn new(
+        line: Option<u64>,
+        tx: &TxInput,
+        result: &Result<(), Error>,
+        dispute_state: Option<DisputeState>,
+    ) -> Self {
+        let (status, code, reason) = match result {
+            Ok(_) => (TxStatus::Accepted, None, None),
+            Err(err) => (
+                TxStatus::Rejected,
+                Some(err.code()),
+                Some(err.kind().to_string()),
+            ),
+        };
+
+        Self {
+            line,
+            tx_type: Some(tx.tx_type),
+            client_id: Some(tx.client_id),
+            id: Some(tx.id),
+            status,
+            code,
+            reason,
+            dispute_state,
+            balances: None,
+        }
+    }
+
+    pub(crate) fn parse_error(line: Option<u64>, reason: String) -> Self {
+        Self {
+            line,
+            tx_type: None,
+            client_id: None,
+            id: None,
+            status: TxStatus::ParseError,
+            code: Some("parse_error"),
+            reason: Some(reason),
+            dispute_state: None,
+            balances: None,
+        }
+    }
+
+    /// serializes the row with the value of the client column
+    pub(crate) fn serialize_with_client<S: Serializer, C: Serialize>(
+        &self,
+        client: &C,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let columns = if self.balances.is_some() { 11 } else { 8 };
+        let mut row = serializer.serialize_struct("TxResult", columns)?;
+        row.serialize_field("line", &self.line)?;
+        row.serialize_field("type", &self.tx_type)?;
+        row.serialize_field("client", client)?;
+        row.serialize_field("tx", &self.id)?;
+        row.serialize_field("status", &self.status)?;
+        row.serialize_field("code", &self.code)?;
+        row.serialize_field("reason", &self.reason)?;
+        row.serialize_field("dispute_state", &self.dispute_state)?;
+        if let Some(balances) = self.balances {
+            row.serialize_field("available", &balances.available)?;
+            row.serialize_field("held", &balances.held)?;
+            row.serialize_field("total", &balances.total)?;
+        }
+        row.end()
+    }
+}
+
+impl Serialize for TxResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.serialize_with_client(&self.client_id, serializer)
+    }
+}
@@ -1,11 +1,47 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
-#[serde(rename_all = "lowercase")]
-pub(crate) enum TxType {
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum TxType {
     Deposit,
     Withdrawal,
     Dispute,
     Resolve,
     Chargeback,
+    /// withdrawal holding its funds until it's settled or cancelled
+    WithdrawalPending,
+    /// completes the pending withdrawal of the tx id, its held funds leave the account
+    WithdrawalSettle,
+    /// cancels the pending withdrawal of the tx id, its held funds are available again
+    WithdrawalCancel,
+    /// pending credit, only available once it's captured
+    Authorize,
+    /// credits the authorization of the tx id, in full or the lower amount of the capture
+    Capture,
+    /// closes the batch of the batch column, its amount is the declared net of the batch
+    Settlement,
+    /// undoes the deposit or withdrawal of the tx id, which is marked as reversed
+    Reversal,
+}
+
+/// same names as the csv input
+impl fmt::Display for TxType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TxType::Deposit => "deposit",
+            TxType::Withdrawal => "withdrawal",
+            TxType::Dispute => "dispute",
+            TxType::Resolve => "resolve",
+            TxType::Chargeback => "chargeback",
+            TxType::WithdrawalPending => "withdrawal_pending",
+            TxType::WithdrawalSettle => "withdrawal_settle",
+            TxType::WithdrawalCancel => "withdrawal_cancel",
+            TxType::Authorize => "authorize",
+            TxType::Capture => "capture",
+            TxType::Settlement => "settlement",
+            TxType::Reversal => "reversal",
+        })
+    }
 }
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::client::ClientReport;
+use crate::models::tx::ClientId;
+
+/// balance of a client as reported by an external source
+#[derive(Deserialize, Debug)]
+pub(crate) struct ExternalBalance {
+    #[serde(rename = "client")]
+    pub(crate) id: ClientId,
+
+    pub(crate) total: Decimal,
+}
+
+#[derive(Serialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ReconcileStatus {
+    Matched,
+    Mismatched,
+    MissingInReport,
+    MissingInExternal,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct ReconcileRow {
+    pub(crate) client: ClientId,
+    pub(crate) status: ReconcileStatus,
+    pub(crate) report_total: Option<Decimal>,
+    pub(crate) external_total: Option<Decimal>,
+    pub(crate) delta: Option<Decimal>,
+}
+
+/// matches report totals against external totals, rows are sorted by client id
+pub(crate) fn reconcile(
+    report: impl IntoIterator<Item = ClientReport>,
+    external: impl IntoIterator<Item = ExternalBalance>,
+) -> Vec<ReconcileRow> {
+    let mut totals: BTreeMap<ClientId, (Option<Decimal>, Option<Decimal>)> = BTreeMap::new();
+
+    for r in report {
+        totals.entry(r.id).or_default().0 = Some(r.total);
+    }
+
+    for e in external {
+        totals.entry(e.id).or_default().1 = Some(e.total);
+    }
+
+    totals
+        .into_iter()
+        .map(|(client, (report_total, external_total))| {
+            let (status, delta) = match (report_total, external_total) {
+                (Some(r), Some(e)) if r == e => (ReconcileStatus::Matched, Some(Decimal::ZERO)),
+                (Some(r), Some(e)) => (ReconcileStatus::Mismatched, Some(r - e)),
+                (None, _) => (ReconcileStatus::MissingInReport, None),
+                (_, None) => (ReconcileStatus::MissingInExternal, None),
+            };
+
+            ReconcileRow {
+                client,
+                status,
+                report_total,
+                external_total,
+                delta,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::models::client::Client;
+    use crate::models::money::Money;
+
+    fn report(id: ClientId, amount: Decimal) -> ClientReport {
+        let mut client = Client::new(id);
+        client
+            .deposit(Money::new(amount).unwrap())
+            .expect("failed to deposit");
+        ClientReport::new(&client)
+    }
+
+    #[test]
+    fn reconcile_success() {
+        let rows = reconcile(
+            vec![report(3, dec!(1)), report(1, dec!(2)), report(2, dec!(5))],
+            vec![
+                ExternalBalance {
+                    id: 1,
+                    total: dec!(2.0),
+                },
+                ExternalBalance {
+                    id: 2,
+                    total: dec!(4.5),
+                },
+                ExternalBalance {
+                    id: 4,
+                    total: dec!(1),
+                },
+            ],
+        );
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].client, 1);
+        assert_eq!(rows[0].status, ReconcileStatus::Matched);
+        assert_eq!(rows[0].delta, Some(dec!(0)));
+        assert_eq!(rows[1].client, 2);
+        assert_eq!(rows[1].status, ReconcileStatus::Mismatched);
+        assert_eq!(rows[1].delta, Some(dec!(0.5)));
+        assert_eq!(rows[2].client, 3);
+        assert_eq!(rows[2].status, ReconcileStatus::MissingInExternal);
+        assert_eq!(rows[2].external_total, None);
+        assert_eq!(rows[3].client, 4);
+        assert_eq!(rows[3].status, ReconcileStatus::MissingInReport);
+        assert_eq!(rows[3].report_total, None);
+    }
+}
@@ -0,0 +1,248 @@
+use std::error::Error;
+use std::io;
+use std::io::{BufWriter, Write};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::models::client::ReportRow;
+
+#[cfg(feature = "arrow")]
+mod arrow;
+
+/// output format of the client report
+#[derive(ValueEnum, Deserialize, Debug, Default, PartialEq, Copy, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// comma separated rows with a header
+    #[default]
+    Csv,
+    /// array of client objects, amounts are strings to keep their precision
+    Json,
+    /// aligned columns for terminals, written once all the rows are known
+    Table,
+    /// arrow ipc stream of a single record batch, with typed columns
+    #[cfg(feature = "arrow")]
+    Arrow,
+}
+
+impl OutputFormat {
+    /// whether the format is text that can be preceded by comment lines
+    pub(crate) fn has_comments(self) -> bool {
+        matches!(self, OutputFormat::Csv | OutputFormat::Table)
+    }
+
+    /// whether the client column is an integer, which pseudonymized client ids are not
+    pub(crate) fn types_client_ids(self) -> bool {
+        match self {
+            OutputFormat::Csv | OutputFormat::Json | OutputFormat::Table => false,
+            #[cfg(feature = "arrow")]
+            OutputFormat::Arrow => true,
+        }
+    }
+
+    /// formatter of the report rows, writing through a buffer of the given capacity
+    pub(crate) fn formatter<'a, W: io::Write + 'a>(
+        self,
+        output: W,
+        capacity: usize,
+    ) -> Box<dyn ReportFormatter + 'a> {
+        match self {
+            OutputFormat::Csv => Box::new(CsvFormatter(
+                csv::WriterBuilder::new()
+                    .buffer_capacity(capacity)
+                    .from_writer(output),
+            )),
+            OutputFormat::Json => Box::new(JsonFormatter {
+                output: BufWriter::with_capacity(capacity, output),
+                rows: 0,
+            }),
+            OutputFormat::Table => Box::new(TableFormatter {
+                output: BufWriter::with_capacity(capacity, output),
+                rows: csv::Writer::from_writer(Vec::new()),
+            }),
+            #[cfg(feature = "arrow")]
+            OutputFormat::Arrow => Box::new(arrow::ArrowFormatter::new(BufWriter::with_capacity(
+                capacity, output,
+            ))),
+        }
+    }
+}
+
+/// writes the rows of a report in one output format
+pub(crate) trait ReportFormatter {
+    fn write_row(&mut self, row: &ReportRow) -> Result<(), Box<dyn Error>>;
+
+    /// flushes the rows written so far, formats laid out over all the rows may hold them until finish
+    fn flush(&mut self) -> io::Result<()>;
+
+    /// completes the report and flushes it
+    fn finish(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+struct CsvFormatter<W: io::Write>(csv::Writer<W>);
+
+impl<W: io::Write> ReportFormatter for CsvFormatter<W> {
+    fn write_row(&mut self, row: &ReportRow) -> Result<(), Box<dyn Error>> {
+        Ok(self.0.serialize(row)?)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(self.0.flush()?)
+    }
+}
+
+struct JsonFormatter<W: io::Write> {
+    output: BufWriter<W>,
+    rows: u64,
+}
+
+impl<W: io::Write> ReportFormatter for JsonFormatter<W> {
+    fn write_row(&mut self, row: &ReportRow) -> Result<(), Box<dyn Error>> {
+        let separator = match self.rows {
+            0 => "[\n  ",
+            _ => ",\n  ",
+        };
+        self.output.write_all(separator.as_bytes())?;
+        serde_json::to_writer(&mut self.output, row)?;
+        self.rows += 1;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        let end = match self.rows {
+            0 => "[]\n",
+            _ => "\n]\n",
+        };
+        self.output.write_all(end.as_bytes())?;
+
+        Ok(self.output.flush()?)
+    }
+}
+
+/// rows are serialized as csv records, with their header, and aligned once complete
+struct TableFormatter<W: io::Write> {
+    output: BufWriter<W>,
+    rows: csv::Writer<Vec<u8>>,
+}
+
+impl<W: io::Write> ReportFormatter for TableFormatter<W> {
+    fn write_row(&mut self, row: &ReportRow) -> Result<(), Box<dyn Error>> {
+        Ok(self.rows.serialize(row)?)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        let rows = std::mem::replace(&mut self.rows, csv::Writer::from_writer(Vec::new()));
+        let records = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(rows.into_inner()?.as_slice())
+            .into_records()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut widths: Vec<usize> = Vec::new();
+        for record in &records {
+            widths.resize(widths.len().max(record.len()), 0);
+            for (width, field) in widths.iter_mut().zip(record) {
+                *width = (*width).max(field.chars().count());
+            }
+        }
+
+        for record in &records {
+            let line = record
+                .iter()
+                .zip(&widths)
+                .map(|(field, width)| format!("{:<width$}", field, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(self.output, "{}", line.trim_end())?;
+        }
+
+        Ok(self.output.flush()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::models::client::{Client, ClientReport, ReportColumns};
+    use crate::models::money::Money;
+
+    fn reports() -> Vec<ClientReport> {
+        let mut first = Client::new(1);
+        first
+            .deposit(Money::new(dec!(1.5)).unwrap())
+            .expect("failed to deposit");
+        let mut second = Client::new(12);
+        second
+            .deposit(Money::new(dec!(20)).unwrap())
+            .expect("failed to deposit");
+        second
+            .dispute(Money::new(dec!(20)).unwrap())
+            .expect("failed to dispute");
+        second
+            .chargeback(Money::new(dec!(20)).unwrap())
+            .expect("failed to chargeback");
+
+        vec![ClientReport::new(&first), ClientReport::new(&second)]
+    }
+
+    fn format(format: OutputFormat, reports: &[ClientReport]) -> String {
+        let mut output = Vec::new();
+        let mut formatter = format.formatter(&mut output, 16);
+        for report in reports {
+            formatter
+                .write_row(&ReportRow::new(report, ReportColumns::default(), None))
+                .expect("failed to write row");
+        }
+        formatter.finish().expect("failed to finish");
+        drop(formatter);
+
+        String::from_utf8(output).expect("invalid utf8")
+    }
+
+    #[test]
+    fn format_csv() {
+        assert_eq!(
+            format(OutputFormat::Csv, &reports()),
+            "client,available,held,total,locked\n1,1.5,0,1.5,false\n12,0,0,0,true\n"
+        );
+    }
+
+    #[test]
+    fn format_json() {
+        assert_eq!(
+            format(OutputFormat::Json, &reports()),
+            r#"[
+  {"client":1,"available":"1.5","held":"0","total":"1.5","locked":false},
+  {"client":12,"available":"0","held":"0","total":"0","locked":true}
+]
+"#
+        );
+        assert_eq!(format(OutputFormat::Json, &[]), "[]\n");
+    }
+
+    #[test]
+    fn format_table() {
+        assert_eq!(
+            format(OutputFormat::Table, &reports()),
+            "client  available  held  total  locked\n\
+             1       1.5        0     1.5    false\n\
+             12      0          0     0      true\n"
+        );
+    }
+}
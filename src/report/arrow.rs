@@ -0,0 +1,333 @@
+use std::error::Error;
+use std::io;
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    BooleanBuilder, Decimal128Builder, StringBuilder, TimestampMillisecondBuilder, UInt16Builder,
+    UInt32Builder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use rust_decimal::Decimal;
+
+use crate::models::client::{ReportColumns, ReportRow};
+use crate::report::ReportFormatter;
+
+/// precision of the decimal columns, the largest decimal mantissa fits in it
+const DECIMAL_PRECISION: u8 = 38;
+
+/// scale of the amount columns when the report has no fixed scale, amounts are kept to 4 decimal places
+const DEFAULT_SCALE: u32 = 4;
+
+/// rows are buffered per column and written as a single record batch of an arrow ipc stream,
+/// with the columns and types of the report rows
+pub(crate) struct ArrowFormatter<W: io::Write> {
+    output: Option<W>,
+    columns: Option<(ReportColumns, u32)>,
+    client: UInt16Builder,
+    available: Decimal128Builder,
+    held: Decimal128Builder,
+    total: Decimal128Builder,
+    locked: BooleanBuilder,
+    activity: [UInt32Builder; 6],
+    chargeback_ratio: Decimal128Builder,
+    last_activity: TimestampMillisecondBuilder,
+    lock_reason: StringBuilder,
+}
+
+impl<W: io::Write> ArrowFormatter<W> {
+    pub(crate) fn new(output: W) -> Self {
+        Self {
+            output: Some(output),
+            columns: None,
+            client: UInt16Builder::new(),
+            available: Decimal128Builder::new(),
+            held: Decimal128Builder::new(),
+            total: Decimal128Builder::new(),
+            locked: BooleanBuilder::new(),
+            activity: Default::default(),
+            chargeback_ratio: Decimal128Builder::new(),
+            last_activity: TimestampMillisecondBuilder::new(),
+            lock_reason: StringBuilder::new(),
+        }
+    }
+
+    fn schema(columns: ReportColumns, scale: u32) -> Schema {
+        let amount = DataType::Decimal128(DECIMAL_PRECISION, scale as i8);
+        let mut fields = vec![
+            Field::new("client", DataType::UInt16, false),
+            Field::new("available", amount.clone(), false),
+            Field::new("held", amount.clone(), false),
+            Field::new("total", amount, false),
+            Field::new("locked", DataType::Boolean, false),
+        ];
+
+        fields.extend(
+            activity_columns(columns).map(|name| Field::new(name, DataType::UInt32, false)),
+        );
+
+        if columns.risk {
+            fields.push(Field::new(
+                "chargeback_ratio",
+                DataType::Decimal128(DECIMAL_PRECISION, DEFAULT_SCALE as i8),
+                false,
+            ));
+        }
+
+        if columns.last_activity {
+            fields.push(Field::new(
+                "last_activity",
+                DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+                true,
+            ));
+        }
+
+        if columns.lock_reason {
+            fields.push(Field::new("lock_reason", DataType::Utf8, true));
+        }
+
+        Schema::new(fields)
+    }
+}
+
+/// names of the counter columns of the report, in report order
+fn activity_columns(columns: ReportColumns) -> impl Iterator<Item = &'static str> {
+    let activity: &[&str] = match columns.activity {
+        true => &[
+            "deposits",
+            "withdrawals",
+            "open_disputes",
+            "chargebacks",
+            "rejected",
+        ],
+        false => &[],
+    };
+    let risk: &[&str] = match (columns.risk, columns.activity) {
+        (true, true) => &["disputes_opened"],
+        (true, false) => &["chargebacks", "disputes_opened"],
+        (false, _) => &[],
+    };
+
+    activity.iter().chain(risk).copied()
+}
+
+/// mantissa of the amount at the scale of its column
+fn mantissa(mut amount: Decimal, scale: u32) -> i128 {
+    amount.rescale(scale);
+    amount.mantissa()
+}
+
+impl<W: io::Write> ReportFormatter for ArrowFormatter<W> {
+    fn write_row(&mut self, row: &ReportRow) -> Result<(), Box<dyn Error>> {
+        let scale = row.scale().unwrap_or(DEFAULT_SCALE);
+        self.columns.get_or_insert((row.columns, scale));
+
+        let r = row.report;
+        self.client.append_value(r.id);
+        self.available
+            .append_value(mantissa(row.amount(r.available), scale));
+        self.held.append_value(mantissa(row.amount(r.held), scale));
+        self.total
+            .append_value(mantissa(row.amount(r.total), scale));
+        self.locked.append_value(r.locked);
+
+        let counters = activity_columns(row.columns).map(|name| match name {
+            "deposits" => r.activity.deposits,
+            "withdrawals" => r.activity.withdrawals,
+            "open_disputes" => r.activity.open_disputes,
+            "chargebacks" => r.activity.chargebacks,
+            "rejected" => r.activity.rejected,
+            _ => r.activity.disputes_opened,
+        });
+        for (builder, value) in self.activity.iter_mut().zip(counters) {
+            builder.append_value(value);
+        }
+
+        if row.columns.risk {
+            self.chargeback_ratio
+                .append_value(mantissa(r.activity.chargeback_ratio(), DEFAULT_SCALE));
+        }
+
+        if row.columns.last_activity {
+            self.last_activity
+                .append_option(r.last_activity.map(|ts| ts.timestamp_millis()));
+        }
+
+        if row.columns.lock_reason {
+            self.lock_reason
+                .append_option(r.lock_reason.map(|reason| reason.to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        let output = self.output.take().ok_or("report already finished")?;
+        // the columns are known from the rows, an empty report has the basic columns
+        let (columns, scale) = self
+            .columns
+            .unwrap_or((ReportColumns::default(), DEFAULT_SCALE));
+        let schema = Arc::new(Self::schema(columns, scale));
+        let amount =
+            |builder: &mut Decimal128Builder, scale: u32| -> Result<ArrayRef, Box<dyn Error>> {
+                Ok(Arc::new(builder.finish().with_precision_and_scale(
+                    DECIMAL_PRECISION,
+                    scale as i8,
+                )?))
+            };
+
+        let mut arrays: Vec<ArrayRef> = vec![
+            Arc::new(self.client.finish()),
+            amount(&mut self.available, scale)?,
+            amount(&mut self.held, scale)?,
+            amount(&mut self.total, scale)?,
+            Arc::new(self.locked.finish()),
+        ];
+        for builder in self
+            .activity
+            .iter_mut()
+            .take(activity_columns(columns).count())
+        {
+            arrays.push(Arc::new(builder.finish()));
+        }
+        if columns.risk {
+            arrays.push(amount(&mut self.chargeback_ratio, DEFAULT_SCALE)?);
+        }
+        if columns.last_activity {
+            arrays.push(Arc::new(self.last_activity.finish().with_timezone("UTC")));
+        }
+        if columns.lock_reason {
+            arrays.push(Arc::new(self.lock_reason.finish()));
+        }
+
+        let batch = RecordBatch::try_new(Arc::clone(&schema), arrays)?;
+        let mut writer = StreamWriter::try_new(output, &schema)?;
+        writer.write(&batch)?;
+        writer.finish()?;
+        writer.into_inner()?.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Decimal128Type, UInt16Type, UInt32Type};
+    use arrow_array::Array;
+    use arrow_ipc::reader::StreamReader;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::models::client::{Client, ClientReport, LockedBy};
+    use crate::models::money::Money;
+
+    fn read(output: &[u8]) -> RecordBatch {
+        let mut reader = StreamReader::try_new(output, None).expect("invalid stream");
+        let batch = reader
+            .next()
+            .expect("missing batch")
+            .expect("invalid batch");
+        assert!(reader.next().is_none());
+        batch
+    }
+
+    #[test]
+    fn format_arrow() {
+        let mut first = Client::new(1);
+        first
+            .deposit(Money::new(dec!(1.5)).unwrap())
+            .expect("failed to deposit");
+        let mut second = Client::new(12);
+        second
+            .deposit(Money::new(dec!(20.25)).unwrap())
+            .expect("failed to deposit");
+        second
+            .dispute(Money::new(dec!(20.25)).unwrap())
+            .expect("failed to dispute");
+        second
+            .chargeback(Money::new(dec!(20.25)).unwrap())
+            .expect("failed to chargeback");
+        second.lock_by(LockedBy {
+            tx: 3,
+            amount: dec!(20.25),
+            timestamp: None,
+        });
+        let reports = [ClientReport::new(&first), ClientReport::new(&second)];
+        let columns = ReportColumns {
+            risk: true,
+            lock_reason: true,
+            ..Default::default()
+        };
+
+        let mut output = Vec::new();
+        let mut formatter = ArrowFormatter::new(&mut output);
+        for report in &reports {
+            formatter
+                .write_row(&ReportRow::new(report, columns, Some(2)))
+                .expect("failed to write row");
+        }
+        formatter.finish().expect("failed to finish");
+
+        let batch = read(&output);
+        let names: Vec<_> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "client",
+                "available",
+                "held",
+                "total",
+                "locked",
+                "chargebacks",
+                "disputes_opened",
+                "chargeback_ratio",
+                "lock_reason"
+            ]
+        );
+        assert_eq!(
+            batch.column(0).as_primitive::<UInt16Type>().values(),
+            &[1, 12]
+        );
+        let available = batch.column(1).as_primitive::<Decimal128Type>();
+        assert_eq!(available.scale(), 2);
+        assert_eq!(available.value_as_string(0), "1.50");
+        assert!(batch.column(4).as_boolean().value(1));
+        assert_eq!(
+            batch.column(5).as_primitive::<UInt32Type>().values(),
+            &[0, 1]
+        );
+        assert_eq!(
+            batch
+                .column(7)
+                .as_primitive::<Decimal128Type>()
+                .value_as_string(1),
+            "1.0000"
+        );
+        let lock_reason = batch.column(8).as_string::<i32>();
+        assert!(lock_reason.is_null(0));
+        assert_eq!(lock_reason.value(1), "chargeback:3");
+    }
+
+    #[test]
+    fn format_arrow_empty() {
+        let mut output = Vec::new();
+        ArrowFormatter::new(&mut output)
+            .finish()
+            .expect("failed to finish");
+
+        let batch = read(&output);
+        assert_eq!(batch.num_rows(), 0);
+        assert_eq!(batch.num_columns(), 5);
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::ValueEnum;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::tx::ClientId;
+use crate::models::tx_type::TxType;
+
+/// period of the aggregates of the rollup report
+#[derive(ValueEnum, Debug, PartialEq, Copy, Clone)]
+pub enum Rollup {
+    /// one row per utc day of the transaction timestamps
+    Daily,
+}
+
+/// row of the rollup report, amounts are the changes of the client totals
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub(crate) struct RollupRow {
+    pub(crate) date: NaiveDate,
+    pub(crate) deposits: Decimal,
+    /// withdrawals and settled pending withdrawals
+    pub(crate) withdrawals: Decimal,
+    pub(crate) chargebacks: Decimal,
+    /// change of the total funds over the day, reversals included
+    pub(crate) net_flow: Decimal,
+    /// total funds of all the clients after the last transaction of the day
+    pub(crate) closing_total: Decimal,
+}
+
+/// aggregates the accepted transactions per day, a transaction without a timestamp counts
+/// for the day of the previous timestamp, or only for the closing totals before the first one
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub(crate) struct DailyRollup {
+    days: BTreeMap<NaiveDate, RollupRow>,
+    /// total funds of all the clients so far
+    total: Decimal,
+    day: Option<NaiveDate>,
+}
+
+impl DailyRollup {
+    /// records the change of the client total made by an accepted transaction
+    pub(crate) fn record(
+        &mut self,
+        tx_type: TxType,
+        timestamp: Option<DateTime<Utc>>,
+        change: Decimal,
+    ) {
+        self.total = self.total.saturating_add(change);
+        if let Some(timestamp) = timestamp {
+            self.day = Some(timestamp.date_naive());
+        }
+        let Some(date) = self.day else {
+            return;
+        };
+
+        let row = self.days.entry(date).or_insert_with(|| RollupRow {
+            date,
+            ..Default::default()
+        });
+        match tx_type {
+            TxType::Deposit => row.deposits = row.deposits.saturating_add(change),
+            TxType::Withdrawal | TxType::WithdrawalSettle => {
+                row.withdrawals = row.withdrawals.saturating_sub(change)
+            }
+            TxType::Chargeback => row.chargebacks = row.chargebacks.saturating_sub(change),
+            _ => {}
+        }
+        row.net_flow = row.net_flow.saturating_add(change);
+        row.closing_total = self.total;
+    }
+
+    /// rows of the days seen so far, oldest first
+    pub(crate) fn rows(&self) -> impl Iterator<Item = &RollupRow> + '_ {
+        self.days.values()
+    }
+}
+
+/// row of the balance history, the balances of a client at the end of a day
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct BalanceRow {
+    pub(crate) client: ClientId,
+    pub(crate) date: NaiveDate,
+    pub(crate) available: Decimal,
+    pub(crate) held: Decimal,
+}
+
+/// day of the balance history, closed once a transaction with a later timestamp arrives;
+/// a transaction without a timestamp or with an earlier one counts for the open day
+#[derive(Debug, Default)]
+pub(crate) struct BalanceHistory {
+    day: Option<NaiveDate>,
+}
+
+impl BalanceHistory {
+    /// day closed by a transaction at the time, before the transaction is applied
+    pub(crate) fn advance(&mut self, timestamp: Option<DateTime<Utc>>) -> Option<NaiveDate> {
+        let date = timestamp?.date_naive();
+        match self.day {
+            Some(day) if date <= day => None,
+            previous => {
+                self.day = Some(date);
+                previous
+            }
+        }
+    }
+
+    /// open day, saved in checkpoints
+    pub(crate) fn day(&self) -> Option<NaiveDate> {
+        self.day
+    }
+
+    pub(crate) fn restore(&mut self, day: Option<NaiveDate>) {
+        self.day = day;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn at(day: u32) -> Option<DateTime<Utc>> {
+        Some(format!("2024-03-{:02}T12:00:00Z", day).parse().unwrap())
+    }
+
+    #[test]
+    fn daily_rows() {
+        let mut rollup = DailyRollup::default();
+        rollup.record(TxType::Deposit, None, dec!(1));
+        rollup.record(TxType::Deposit, at(1), dec!(10));
+        rollup.record(TxType::Withdrawal, None, dec!(-2));
+        rollup.record(TxType::Dispute, at(2), dec!(0));
+        rollup.record(TxType::Chargeback, at(2), dec!(-3));
+        rollup.record(TxType::Reversal, at(2), dec!(-1));
+
+        let rows: Vec<_> = rollup.rows().cloned().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            (
+                rows[0].deposits,
+                rows[0].withdrawals,
+                rows[0].net_flow,
+                rows[0].closing_total
+            ),
+            (dec!(10), dec!(2), dec!(8), dec!(9))
+        );
+        assert_eq!(
+            (rows[1].chargebacks, rows[1].net_flow, rows[1].closing_total),
+            (dec!(3), dec!(-4), dec!(5))
+        );
+    }
+
+    #[test]
+    fn balance_days() {
+        let mut history = BalanceHistory::default();
+        assert_eq!(history.advance(None), None);
+        assert_eq!(history.advance(at(1)), None);
+        assert_eq!(history.advance(at(1)), None);
+        assert_eq!(history.advance(None), None);
+        assert_eq!(history.advance(at(3)), at(1).map(|at| at.date_naive()));
+        // late timestamps count for the open day
+        assert_eq!(history.advance(at(2)), None);
+        assert_eq!(history.day(), at(3).map(|at| at.date_naive()));
+    }
+}
@@ -0,0 +1,100 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::models::tx::TxId;
+
+/// outcome of the settlement of a batch
+#[derive(Serialize, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SettlementStatus {
+    /// declared amount matches the net of the batch, which is closed
+    Settled,
+    /// declared amount differs from the net of the batch, or is missing
+    Mismatch,
+    /// settlement without a batch, or of a batch without accepted transactions
+    UnknownBatch,
+    /// batch without a settlement at the end of the input
+    Unsettled,
+}
+
+/// row of the settlement report, one per settlement record and per batch left open
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct SettlementRow {
+    pub(crate) batch: Option<String>,
+    /// tx id of the settlement record
+    pub(crate) tx: Option<TxId>,
+    pub(crate) declared: Option<Decimal>,
+    /// net of the accepted transactions of the batch
+    pub(crate) computed: Option<Decimal>,
+    /// declared minus computed
+    pub(crate) difference: Option<Decimal>,
+    pub(crate) status: SettlementStatus,
+}
+
+impl SettlementRow {
+    /// settlement record checked against the net of its batch before it's applied
+    pub(crate) fn settlement(
+        batch: Option<&str>,
+        tx: TxId,
+        declared: Option<Decimal>,
+        computed: Option<Decimal>,
+    ) -> Self {
+        let difference = declared
+            .zip(computed)
+            .map(|(declared, computed)| declared.saturating_sub(computed));
+        Self {
+            batch: batch.map(str::to_string),
+            tx: Some(tx),
+            declared,
+            computed,
+            difference,
+            status: match (computed, difference) {
+                (None, _) => SettlementStatus::UnknownBatch,
+                (_, Some(difference)) if difference.is_zero() => SettlementStatus::Settled,
+                _ => SettlementStatus::Mismatch,
+            },
+        }
+    }
+
+    /// batch with accepted transactions and no matching settlement
+    pub(crate) fn unsettled(batch: &str, computed: Decimal) -> Self {
+        Self {
+            batch: Some(batch.to_string()),
+            tx: None,
+            declared: None,
+            computed: Some(computed),
+            difference: None,
+            status: SettlementStatus::Unsettled,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    #[test]
+    fn statuses() {
+        let row = SettlementRow::settlement(Some("a"), 9, Some(dec!(7.5)), Some(dec!(7.50)));
+        assert_eq!(row.status, SettlementStatus::Settled);
+        assert_eq!(row.difference, Some(dec!(0)));
+
+        let row = SettlementRow::settlement(Some("a"), 9, Some(dec!(7)), Some(dec!(7.5)));
+        assert_eq!(row.status, SettlementStatus::Mismatch);
+        assert_eq!(row.difference, Some(dec!(-0.5)));
+
+        let row = SettlementRow::settlement(Some("a"), 9, None, Some(dec!(7.5)));
+        assert_eq!(row.status, SettlementStatus::Mismatch);
+        assert_eq!(row.difference, None);
+
+        let row = SettlementRow::settlement(None, 9, Some(dec!(7)), None);
+        assert_eq!(row.status, SettlementStatus::UnknownBatch);
+
+        assert_eq!(
+            SettlementRow::unsettled("b", dec!(1)).status,
+            SettlementStatus::Unsettled
+        );
+    }
+}
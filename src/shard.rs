@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+use csv::StringRecord;
+
+use crate::models::tx::ClientId;
+
+/// shard of the client, all the transactions of a client are routed to the same shard
+pub(crate) fn shard_of(client: ClientId, shards: u16) -> usize {
+    (client % shards) as usize
+}
+
+/// rows of the shard reports merged by client id, the reports must share their header
+/// and a client can only be reported by one shard
+pub(crate) fn collect(
+    header: &StringRecord,
+    reports: impl IntoIterator<Item = (StringRecord, Vec<StringRecord>)>,
+) -> Result<Vec<StringRecord>, String> {
+    let client = header
+        .iter()
+        .position(|field| field == "client")
+        .ok_or("missing client column")?;
+    let mut rows: BTreeMap<ClientId, StringRecord> = BTreeMap::new();
+
+    for (report_header, report_rows) in reports {
+        if &report_header != header {
+            return Err(format!(
+                "report columns {:?} don't match {:?}",
+                report_header, header
+            ));
+        }
+
+        for row in report_rows {
+            let id = row
+                .get(client)
+                .and_then(|id| id.parse::<ClientId>().ok())
+                .ok_or_else(|| format!("invalid client id in {:?}", row))?;
+
+            if rows.insert(id, row).is_some() {
+                return Err(format!("client {} is reported by more than one shard", id));
+            }
+        }
+    }
+
+    Ok(rows.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(rows: &[&[&str]]) -> (StringRecord, Vec<StringRecord>) {
+        (
+            StringRecord::from(vec!["client", "total"]),
+            rows.iter()
+                .map(|row| StringRecord::from(row.to_vec()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn shard_of_client() {
+        assert_eq!(shard_of(7, 1), 0);
+        assert_eq!(shard_of(7, 4), 3);
+        assert_eq!(shard_of(8, 4), 0);
+    }
+
+    #[test]
+    fn collect_success() {
+        let header = StringRecord::from(vec!["client", "total"]);
+        let rows = collect(
+            &header,
+            [
+                report(&[&["2", "1.0"], &["4", "0"]]),
+                report(&[&["1", "3.5"]]),
+            ],
+        )
+        .expect("failed to collect");
+
+        let clients: Vec<_> = rows.iter().map(|row| &row[0]).collect();
+        assert_eq!(clients, ["1", "2", "4"]);
+    }
+
+    #[test]
+    fn collect_fail() {
+        let header = StringRecord::from(vec!["client", "total"]);
+
+        let err = collect(&header, [report(&[&["1", "1.0"]]), report(&[&["1", "0"]])]).unwrap_err();
+        assert_eq!(err, "client 1 is reported by more than one shard");
+
+        let other = (StringRecord::from(vec!["client", "held"]), Vec::new());
+        assert!(collect(&header, [other]).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::models::client::ClientReport;
+use crate::models::tx::ClientId;
+
+/// balances of a client after a simulated batch, with the changes from the base state
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct SimulationRow {
+    pub(crate) client: ClientId,
+    pub(crate) available: Decimal,
+    pub(crate) held: Decimal,
+    pub(crate) total: Decimal,
+    pub(crate) locked: bool,
+    pub(crate) available_delta: Decimal,
+    pub(crate) held_delta: Decimal,
+    pub(crate) total_delta: Decimal,
+    pub(crate) newly_locked: bool,
+}
+
+/// clients whose balances or lock changed between the base and the simulated report,
+/// clients missing from the base start with zero balances, rows are sorted by client id
+pub(crate) fn changes(
+    base: impl IntoIterator<Item = ClientReport>,
+    simulated: impl IntoIterator<Item = ClientReport>,
+) -> Vec<SimulationRow> {
+    let base: BTreeMap<ClientId, ClientReport> = base.into_iter().map(|r| (r.id, r)).collect();
+    let mut simulated: Vec<ClientReport> = simulated.into_iter().collect();
+    simulated.sort_by_key(|r| r.id);
+
+    simulated
+        .into_iter()
+        .filter_map(|after| {
+            let (available, held, total, locked) = match base.get(&after.id) {
+                Some(before) => (before.available, before.held, before.total, before.locked),
+                None => (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO, false),
+            };
+
+            let row = SimulationRow {
+                client: after.id,
+                available: after.available,
+                held: after.held,
+                total: after.total,
+                locked: after.locked,
+                available_delta: after.available - available,
+                held_delta: after.held - held,
+                total_delta: after.total - total,
+                newly_locked: after.locked && !locked,
+            };
+
+            let changed = !row.available_delta.is_zero()
+                || !row.held_delta.is_zero()
+                || !row.total_delta.is_zero()
+                || row.locked != locked;
+            changed.then_some(row)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::models::client::Client;
+    use crate::models::money::Money;
+
+    fn report(id: ClientId, deposit: Decimal, held: Decimal) -> ClientReport {
+        let mut client = Client::new(id);
+        client
+            .deposit(Money::new(deposit).unwrap())
+            .expect("failed to deposit");
+        client
+            .dispute(Money::new(held).unwrap())
+            .expect("failed to dispute");
+        ClientReport::new(&client)
+    }
+
+    #[test]
+    fn changes_success() {
+        let mut locked = Client::new(2);
+        locked
+            .deposit(Money::new(dec!(3)).unwrap())
+            .expect("failed to deposit");
+        locked
+            .dispute(Money::new(dec!(1)).unwrap())
+            .expect("failed to dispute");
+        locked
+            .chargeback(Money::new(dec!(1)).unwrap())
+            .expect("failed to chargeback");
+
+        let rows = changes(
+            vec![
+                report(1, dec!(1), dec!(0)),
+                report(2, dec!(3), dec!(1)),
+                report(3, dec!(4), dec!(0)),
+            ],
+            vec![
+                report(3, dec!(4), dec!(0)),
+                ClientReport::new(&locked),
+                report(1, dec!(1), dec!(0.5)),
+                report(4, dec!(2), dec!(0)),
+            ],
+        );
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].client, 1);
+        assert_eq!(rows[0].available_delta, dec!(-0.5));
+        assert_eq!(rows[0].held_delta, dec!(0.5));
+        assert_eq!(rows[0].total_delta, dec!(0));
+        assert!(!rows[0].newly_locked);
+        assert_eq!(rows[1].client, 2);
+        assert_eq!(rows[1].held_delta, dec!(-1));
+        assert_eq!(rows[1].total_delta, dec!(-1));
+        assert!(rows[1].newly_locked);
+        assert_eq!(rows[2].client, 4);
+        assert_eq!(rows[2].total, dec!(2));
+        assert_eq!(rows[2].total_delta, dec!(2));
+    }
+}
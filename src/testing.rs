@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use crate::cli;
+use crate::cli::ProcessOptions;
+use crate::models::tx::{ClientId, TxInput};
+use crate::workload::{generate, Rng, WorkloadOptions};
+
+/// acceptance case of a fixture directory, a subdirectory with the `input.csv` transactions
+/// and the `expected.csv` report
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    /// name of the subdirectory
+    pub name: String,
+    pub input: PathBuf,
+    pub expected: PathBuf,
+}
+
+/// workload generated from the seed, the same seed always gives the same transactions
+pub fn workload(seed: u64, clients: ClientId, txs: usize) -> Vec<TxInput> {
+    generate(&mut Rng::new(seed), WorkloadOptions { clients, txs }).collect()
+}
+
+/// rows of the expected report missing from the actual one and rows of the actual report not
+/// expected, the header included
+#[derive(Debug, Default, PartialEq)]
+pub struct Mismatch {
+    pub missing: Vec<String>,
+    pub unexpected: Vec<String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in &self.missing {
+            writeln!(f, "- {}", row)?;
+        }
+        for row in &self.unexpected {
+            writeln!(f, "+ {}", row)?;
+        }
+        Ok(())
+    }
+}
+
+/// compares two csv outputs row by row in any order, duplicated rows have to appear as many
+/// times in both; blank lines are ignored
+pub fn compare_rows(actual: &str, expected: &str) -> Result<(), Mismatch> {
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for row in expected.lines().filter(|row| !row.trim().is_empty()) {
+        *counts.entry(row).or_default() += 1;
+    }
+    for row in actual.lines().filter(|row| !row.trim().is_empty()) {
+        *counts.entry(row).or_default() -= 1;
+    }
+
+    let mut mismatch = Mismatch::default();
+    for (row, count) in counts {
+        let rows = match count > 0 {
+            true => &mut mismatch.missing,
+            false => &mut mismatch.unexpected,
+        };
+        rows.extend((0..count.abs()).map(|_| row.to_string()));
+    }
+    if mismatch.missing.is_empty() && mismatch.unexpected.is_empty() {
+        return Ok(());
+    }
+
+    mismatch.missing.sort();
+    mismatch.unexpected.sort();
+    Err(mismatch)
+}
+
+/// panics with the differing rows unless the outputs have the same rows in any order
+pub fn assert_same_rows(actual: &str, expected: &str, msg: &str) {
+    if let Err(mismatch) = compare_rows(actual, expected) {
+        panic!("failed: {}\n{}", msg, mismatch);
+    }
+}
+
+/// fixtures of the subdirectories of the directory, sorted by name; a subdirectory without
+/// both files is not a fixture
+pub fn fixtures(dir: &Path) -> Result<Vec<Fixture>, Box<dyn Error>> {
+    let mut fixtures = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let (input, expected) = (path.join("input.csv"), path.join("expected.csv"));
+        if input.is_file() && expected.is_file() {
+            fixtures.push(Fixture {
+                name: path
+                    .file_name()
+                    .map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+                input,
+                expected,
+            });
+        }
+    }
+
+    fixtures.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fixtures)
+}
+
+/// processes the input of the fixture with the options and compares the report to the
+/// expected one
+pub fn run_fixture(fixture: &Fixture, options: ProcessOptions) -> Result<(), Box<dyn Error>> {
+    let mut output = Vec::new();
+    cli::process_input_with_options(
+        BufReader::new(File::open(&fixture.input)?),
+        &mut output,
+        options,
+    )?;
+
+    compare_rows(
+        &String::from_utf8(output)?,
+        &fs::read_to_string(&fixture.expected)?,
+    )
+    .map_err(|mismatch| format!("fixture {} differs:\n{}", fixture.name, mismatch).into())
+}
+
+/// runs every fixture of the directory with the options of `options`, failing with the
+/// differences of all the failed ones
+pub fn run_fixtures_with(
+    dir: &Path,
+    mut options: impl FnMut(&Fixture) -> ProcessOptions,
+) -> Result<(), Box<dyn Error>> {
+    let fixtures = fixtures(dir)?;
+    if fixtures.is_empty() {
+        return Err(format!("no fixtures in {}", dir.display()).into());
+    }
+
+    let failures: Vec<String> = fixtures
+        .iter()
+        .filter_map(|fixture| {
+            run_fixture(fixture, options(fixture))
+                .err()
+                .map(|err| err.to_string())
+        })
+        .collect();
+    match failures.is_empty() {
+        true => Ok(()),
+        false => Err(failures.join("\n").into()),
+    }
+}
+
+/// runs every fixture of the directory with the default options
+pub fn run_fixtures(dir: &Path) -> Result<(), Box<dyn Error>> {
+    run_fixtures_with(dir, |_| ProcessOptions::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare() {
+        let expected = "client,total\n1,1.0\n2,2.0\n";
+        assert_eq!(
+            compare_rows("client,total\n2,2.0\n1,1.0\n\n", expected),
+            Ok(())
+        );
+        assert_eq!(
+            compare_rows("client,total\n1,1.0\n1,1.0\n3,3.0\n", expected),
+            Err(Mismatch {
+                missing: vec!["2,2.0".to_string()],
+                unexpected: vec!["1,1.0".to_string(), "3,3.0".to_string()],
+            })
+        );
+    }
+}
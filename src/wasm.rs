@@ -0,0 +1,63 @@
+use csv::Trim;
+use wasm_bindgen::prelude::*;
+
+use crate::engine::Engine;
+use crate::models::tx::TxInput;
+
+/// processes a csv of transactions and returns the client report csv, with the default engine
+/// configuration; like the cli, malformed rows and rejected transactions are skipped
+#[wasm_bindgen(js_name = processCsv)]
+pub fn process_csv(input: &str) -> Result<String, JsError> {
+    let mut engine = Engine::new();
+    let mut rdr = csv::ReaderBuilder::new()
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(input.as_bytes());
+    for tx in rdr.deserialize::<TxInput>().flatten() {
+        let _ = engine.process_tx(&tx);
+    }
+
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    for report in engine.report() {
+        wtr.serialize(report)?;
+    }
+    let output = wtr
+        .into_inner()
+        .map_err(|err| JsError::new(&err.to_string()))?;
+
+    Ok(String::from_utf8(output)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_csv_report() {
+        let report = process_csv("type,client,tx,amount\ndeposit,1,1,1.5\n").ok();
+
+        assert_eq!(
+            report.as_deref(),
+            Some("client,available,held,total,locked\n1,1.5,0,1.5,false\n")
+        );
+    }
+
+    #[test]
+    fn process_csv_skips() {
+        let input = "type, client, tx, amount
+deposit, 1, 1, 1.50
+bogus, 1, 2, 3
+deposit, 2, 3, 2.123456
+withdrawal, 1, 4, 5
+dispute, 2, 3,";
+        let report = process_csv(input).ok();
+
+        // the same report as the cli
+        assert_eq!(
+            report.as_deref(),
+            Some(
+                "client,available,held,total,locked\n1,1.50,0,1.50,false\n2,0.0000,2.1235,2.1235,false\n"
+            )
+        );
+    }
+}
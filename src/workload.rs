@@ -0,0 +1,192 @@
+#[cfg(any(feature = "difftest", test))]
+use std::ops::Range;
+#[cfg(any(feature = "difftest", test))]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(any(feature = "difftest", test))]
+use std::sync::{Mutex, PoisonError};
+#[cfg(any(feature = "difftest", test))]
+use std::thread;
+
+use rust_decimal::Decimal;
+
+use crate::models::tx::{ClientId, TxId, TxInput};
+use crate::models::tx_type::TxType;
+
+/// splitmix64, a small generator whose sequence only depends on its seed
+#[derive(Debug, Clone)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// number below the bound, which must not be zero
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// true once in `n` draws
+    fn one_in(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+}
+
+/// shape of a generated workload
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct WorkloadOptions {
+    pub(crate) clients: ClientId,
+    pub(crate) txs: usize,
+}
+
+/// deposits, withdrawals and disputes of random clients, the disputes, resolves and chargebacks
+/// mostly refer to earlier transactions of their client and sometimes to unknown ones, ones of
+/// another client or withdrawals, and some deposits reuse a tx id; the transactions are generated
+/// lazily so a large workload can be streamed
+pub(crate) fn generate(
+    rng: &mut Rng,
+    options: WorkloadOptions,
+) -> impl Iterator<Item = TxInput> + '_ {
+    let clients = u64::from(options.clients.max(1));
+    let mut issued: Vec<(TxId, ClientId)> = Vec::new();
+    let mut next_id: TxId = 1;
+
+    (0..options.txs).map(move |_| {
+        let client_id = rng.below(clients) as ClientId + 1;
+        match rng.below(100) {
+            // cents up to 10000 and 5000
+            roll @ 0..=69 => {
+                let (tx_type, max) = match roll < 40 {
+                    true => (TxType::Deposit, 1_000_000),
+                    false => (TxType::Withdrawal, 500_000),
+                };
+                let id = match issued.is_empty() || !rng.one_in(20) {
+                    true => {
+                        next_id += 1;
+                        next_id - 1
+                    }
+                    false => issued[rng.below(issued.len() as u64) as usize].0,
+                };
+                issued.push((id, client_id));
+                TxInput {
+                    tx_type,
+                    client_id,
+                    id,
+                    amount: Some(Decimal::new(rng.below(max) as i64, 2)),
+                    timestamp: None,
+                    idempotency_key: None,
+                    batch: None,
+                    effective_date: None,
+                }
+            }
+            roll => {
+                let tx_type = match roll {
+                    70..=83 => TxType::Dispute,
+                    84..=95 => TxType::Resolve,
+                    _ => TxType::Chargeback,
+                };
+                let (id, owner) = match issued.is_empty() || rng.one_in(20) {
+                    true => (next_id + 1_000, client_id),
+                    false => issued[rng.below(issued.len() as u64) as usize],
+                };
+                TxInput {
+                    tx_type,
+                    client_id: match rng.one_in(10) {
+                        true => client_id,
+                        false => owner,
+                    },
+                    id,
+                    amount: None,
+                    timestamp: None,
+                    idempotency_key: None,
+                    batch: None,
+                    effective_date: None,
+                }
+            }
+        }
+    })
+}
+
+/// lowest seed of the range whose check fails, with its error; the seeds are checked on `jobs`
+/// threads taking them in order, and a thread stops once a lower seed failed, so the result
+/// doesn't depend on how the threads are scheduled
+#[cfg(any(feature = "difftest", test))]
+pub(crate) fn first_failure<E: Send>(
+    seeds: Range<u64>,
+    jobs: usize,
+    check: impl Fn(u64) -> Result<(), E> + Sync,
+) -> Option<(u64, E)> {
+    let next = AtomicU64::new(seeds.start);
+    let failure: Mutex<Option<(u64, E)>> = Mutex::new(None);
+    let failed_below = |seed: u64| {
+        failure
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .is_some_and(|(failed, _)| *failed < seed)
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.max(1) {
+            scope.spawn(|| loop {
+                let seed = next.fetch_add(1, Ordering::Relaxed);
+                if seed >= seeds.end || failed_below(seed) {
+                    break;
+                }
+                if let Err(err) = check(seed) {
+                    let mut failure = failure.lock().unwrap_or_else(PoisonError::into_inner);
+                    if failure.as_ref().is_none_or(|(failed, _)| seed < *failed) {
+                        *failure = Some((seed, err));
+                    }
+                }
+            });
+        }
+    });
+
+    failure.into_inner().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded() {
+        let options = WorkloadOptions {
+            clients: 5,
+            txs: 200,
+        };
+        let workload: Vec<_> = generate(&mut Rng::new(7), options).collect();
+        assert_eq!(
+            workload,
+            generate(&mut Rng::new(7), options).collect::<Vec<_>>()
+        );
+        assert_ne!(
+            workload,
+            generate(&mut Rng::new(8), options).collect::<Vec<_>>()
+        );
+        assert_eq!(workload.len(), 200);
+        assert!(workload.iter().all(|tx| (1..=5).contains(&tx.client_id)));
+        assert!(workload.iter().any(|tx| tx.tx_type == TxType::Chargeback));
+    }
+
+    #[test]
+    fn first_failure_any_jobs() {
+        let check = |seed: u64| match seed % 7 == 3 && seed > 10 {
+            true => Err(seed * 2),
+            false => Ok(()),
+        };
+        for jobs in [1, 2, 8] {
+            assert_eq!(first_failure(0..100, jobs, check), Some((17, 34)));
+            assert_eq!(first_failure(0..17, jobs, check), None);
+        }
+    }
+}
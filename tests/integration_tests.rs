@@ -1,5 +1,22 @@
-use std::io::{BufReader, Cursor, Read};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::io;
+use std::io::{BufReader, Cursor, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::{env, fs};
 use ttx_eng::cli;
+use ttx_eng::cli::{
+    AmlOptions, AnomalyOptions, AnomalyThresholds, CheckpointOptions, CsvOptions, DecimalSeparator,
+    EngineSection, FixedWidthLayout, FlushInterval, InputEncoding, InputFormat, InterestOptions,
+    InterestRates, Interrupted, ManifestOptions, OutputFormat, OutputKind, PipelineConfig,
+    ProcessOptions, Pseudonyms, ReportDetail, ReportFilter, ReportInterval, Rollup, RollupOptions,
+    SnapshotOptions, XmlMapping,
+};
+use ttx_eng::testing::assert_same_rows;
+use ttx_eng::{testing, DuplicatePolicy, TxType};
 
 #[test]
 fn process_input_success() {
@@ -16,17 +33,1725 @@ fn process_input_success() {
             .read_to_string(&mut output)
             .expect("failed to read output");
 
-        assert_elements_no_order(output.as_str(), ts.expected_output, ts.msg)
+        assert_same_rows(output.as_str(), ts.expected_output, ts.msg)
     }
 }
 
-fn assert_elements_no_order(a: &str, b: &str, msg: &str) {
-    let mut a_vec: Vec<_> = a.lines().collect();
-    let mut b_vec: Vec<_> = b.lines().collect();
-    a_vec.sort();
-    b_vec.sort();
+#[test]
+fn fixtures() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    testing::run_fixtures(&dir).expect("fixtures failed");
+
+    // a fixture run with other engine options fails with the differing rows
+    let fixture = testing::fixtures(&dir)
+        .expect("failed to list the fixtures")
+        .into_iter()
+        .find(|fixture| fixture.name == "chargeback")
+        .expect("missing the chargeback fixture");
+    let mut options = ProcessOptions::default();
+    options.engine.max_disputes = Some(0);
+    let err = testing::run_fixture(&fixture, options).expect_err("the dispute should be rejected");
+    assert_eq!(
+        err.to_string(),
+        "fixture chargeback differs:\n- 1,0.0,0.0,0.0,true\n- 2,2.0,0.0,2.0,false\n+ 1,1.0,0,1.0,false\n+ 2,2.0,0,2.0,false\n"
+    );
+}
+
+#[test]
+fn reconcile_success() {
+    let report = r#"client,available,held,total,locked
+1,1.5,0,1.5,false
+2,1.0,1.0,2.0,false
+3,0,0,0,true
+"#;
+    let external = r#"client, total
+1, 1.50
+2, 1.0
+4, 3.0"#;
+    let mut writer = Cursor::new(Vec::new());
+
+    cli::reconcile(report.as_bytes(), external.as_bytes(), writer.get_mut())
+        .expect("failed to reconcile");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        r#"client,status,report_total,external_total,delta
+1,matched,1.5,1.50,0
+2,mismatched,2.0,1.0,1.0
+3,missing_in_external,0,,
+4,missing_in_report,,3.0,
+"#
+    )
+}
+
+#[test]
+fn simulate_success() {
+    let base = r#"type, client, tx, amount
+deposit, 1, 1, 5.0
+deposit, 2, 2, 3.0
+dispute, 2, 2,
+deposit, 3, 3, 1.0"#;
+    let extra = r#"type, client, tx, amount
+chargeback, 2, 2,
+dispute, 1, 1,
+withdrawal, 3, 4, 2.0
+deposit, 4, 5, 1.5"#;
+    let mut writer = Cursor::new(Vec::new());
+
+    cli::simulate(base.as_bytes(), extra.as_bytes(), writer.get_mut()).expect("failed to simulate");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        r#"client,available,held,total,locked,available_delta,held_delta,total_delta,newly_locked
+1,0.0,5.0,5.0,false,-5.0,5.0,0.0,false
+2,0.0,0.0,0.0,true,0.0,-3.0,-3.0,true
+4,1.5,0,1.5,false,1.5,0,1.5,false
+"#
+    )
+}
+
+#[test]
+fn chargeback_report_success() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 5.0
+deposit, 1, 2, 15.0
+deposit, 2, 3, 3.0
+dispute, 1, 1,
+chargeback, 1, 1,
+deposit, 1, 4, 2.0
+chargeback, 2, 3,
+withdrawal, 2, 5, 1.0"#;
+    let mut writer = Cursor::new(Vec::new());
+
+    cli::chargeback_report(input.as_bytes(), writer.get_mut())
+        .expect("failed to write chargeback report");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    // the deposit after the chargeback is rejected on the locked account, the chargeback of an
+    // undisputed deposit isn't counted
+    assert_eq!(
+        output,
+        r#"client,deposits,deposit_amount,chargebacks,chargeback_amount,chargeback_rate,chargeback_amount_rate
+1,2,20.0,1,5.0,0.5,0.25
+2,1,3.0,0,0,0,0
+total,3,23.0,1,5.0,0.3333,0.2174
+"#
+    )
+}
+
+#[test]
+fn settlement_report_success() {
+    let input = r#"type, client, tx, amount, timestamp, idempotency_key, batch
+deposit, 1, 1, 5.0,,, b1
+deposit, 2, 2, 3.0,,, b1
+withdrawal, 1, 3, 1.5,,, b1
+withdrawal, 2, 4, 10.0,,, b1
+settlement, 0, 5, 6.5,,, b1
+deposit, 1, 6, 2.0,,, b2
+settlement, 0, 7, 2.5,,, b2
+deposit, 3, 8, 1.0,,, b3
+settlement, 0, 9, 1.0,,, b4"#;
+    let mut writer = Cursor::new(Vec::new());
+
+    cli::settlement_report(input.as_bytes(), writer.get_mut())
+        .expect("failed to write settlement report");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    // the rejected withdrawal of b1 isn't part of its net, b2 stays open after its mismatch
+    assert_eq!(
+        output,
+        r#"batch,tx,declared,computed,difference,status
+b1,5,6.5,6.5,0.0,settled
+b2,7,2.5,2.0,0.5,mismatch
+b4,9,1.0,,,unknown_batch
+b2,,,2.0,,unsettled
+b3,,,1.0,,unsettled
+"#
+    )
+}
+
+#[test]
+fn dispute_aging_success() {
+    let input = r#"type, client, tx, amount, timestamp
+deposit, 1, 1, 5.0, 2024-01-01T00:00:00Z
+deposit, 1, 2, 2.5, 2024-01-01T00:00:00Z
+deposit, 2, 3, 3.0, 2024-01-01T00:00:00Z
+deposit, 2, 4, 1.0, 2024-01-01T00:00:00Z
+dispute, 1, 1,, 2024-01-02T10:00:00Z
+dispute, 2, 3,, 2024-02-20T00:00:00Z
+dispute, 1, 2,, 2024-02-28T00:00:00Z
+dispute, 2, 4,, 2024-02-28T00:00:00Z
+resolve, 2, 4,, 2024-02-29T00:00:00Z
+dispute, 2, 9,, 2024-02-29T00:00:00Z"#;
+    let mut writer = Cursor::new(Vec::new());
+
+    cli::dispute_aging(
+        input.as_bytes(),
+        "2024-03-01T00:00:00Z".parse().unwrap(),
+        writer.get_mut(),
+    )
+    .expect("failed to write dispute aging");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        r#"client,tx,held,opened,age_days,bucket
+1,1,5.0,2024-01-02T10:00:00Z,58,30+
+2,3,3.0,2024-02-20T00:00:00Z,10,8-30
+1,2,2.5,2024-02-28T00:00:00Z,2,0-7
+"#
+    )
+}
+
+#[test]
+fn generate_seeded() {
+    let generate = |seed| {
+        let mut output = Vec::new();
+        cli::generate(seed, 3, 50, &mut output).expect("failed to generate");
+        String::from_utf8(output).expect("invalid utf8")
+    };
+
+    let workload = generate(3);
+    assert_eq!(workload, generate(3));
+    assert_ne!(workload, generate(4));
+    assert!(workload.starts_with(
+        "type,client,tx,amount,timestamp,idempotency_key,batch,effective_date\n\
+         withdrawal,1,1,4377.29,,,,\n"
+    ));
+
+    // the generated file is an input of the engine
+    let mut report = Vec::new();
+    cli::process_input(workload.as_bytes(), &mut report).expect("failed to process workload");
+    assert_eq!(String::from_utf8(report).unwrap().lines().count(), 4);
+}
+
+#[test]
+fn shard_and_report_collect() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 2, 2, 2.0
+deposit, 3, 3, 3.0
+withdrawal, 1, 4, 0.5
+deposit, x, 5, 1.0
+dispute, 2, 2,"#;
+    let mut shards = vec![Vec::new(), Vec::new()];
+    cli::shard(input.as_bytes(), &mut shards).expect("failed to shard");
+
+    let shards: Vec<String> = shards
+        .into_iter()
+        .map(|shard| String::from_utf8(shard).expect("invalid utf8"))
+        .collect();
+    assert_eq!(
+        shards[0],
+        "type,client,tx,amount\ndeposit,2,2,2.0\ndeposit,x,5,1.0\ndispute,2,2,\n"
+    );
+    assert_eq!(
+        shards[1],
+        "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,3,3,3.0\nwithdrawal,1,4,0.5\n"
+    );
+
+    let reports: Vec<Vec<u8>> = shards
+        .iter()
+        .map(|shard| {
+            let mut report = Vec::new();
+            cli::process_input(shard.as_bytes(), &mut report).expect("failed to process shard");
+            report
+        })
+        .collect();
+
+    let mut writer = Cursor::new(Vec::new());
+    cli::report_collect(
+        reports.iter().map(|report| report.as_slice()).collect(),
+        writer.get_mut(),
+    )
+    .expect("failed to collect reports");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+    assert_eq!(
+        output,
+        r#"client,available,held,total,locked
+1,0.5,0,0.5,false
+2,0.0,2.0,2.0,false
+3,3.0,0,3.0,false
+"#
+    );
+}
+
+#[test]
+fn process_ofx_success() {
+    let input = r#"OFXHEADER:100
+<OFX>
+<BANKACCTFROM><ACCTID>9</BANKACCTFROM>
+<STMTTRN><TRNTYPE>CREDIT<TRNAMT>10.00<FITID>1</STMTTRN>
+<STMTTRN><TRNTYPE>DEBIT<TRNAMT>-2.50<FITID>2</STMTTRN>
+</OFX>"#;
+    let mut writer = Cursor::new(Vec::new());
+
+    cli::process_statement(
+        input.as_bytes(),
+        InputFormat::Ofx,
+        Some(1),
+        writer.get_mut(),
+        ProcessOptions::default(),
+    )
+    .expect("failed to process ofx");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        r#"client,available,held,total,locked
+1,7.50,0,7.50,false
+"#
+    )
+}
+
+#[test]
+fn process_fixed_width_success() {
+    let layout = r#"{
+        "type": {"offset": 0, "length": 1},
+        "client": {"offset": 1, "length": 4},
+        "tx": {"offset": 5, "length": 6},
+        "amount": {"offset": 11, "length": 9},
+        "types": {"D": "deposit", "W": "withdrawal"},
+        "implied_decimals": 2
+    }"#;
+    let input = "D0001000001000001000\n\nW0001000002000000250\nQ0001000003000000100\n";
+    let mut writer = Cursor::new(Vec::new());
+
+    cli::process_statement(
+        input.as_bytes(),
+        InputFormat::FixedWidth,
+        None,
+        writer.get_mut(),
+        ProcessOptions {
+            fixed_width: Some(
+                FixedWidthLayout::from_reader(layout.as_bytes()).expect("invalid layout"),
+            ),
+            ..Default::default()
+        },
+    )
+    .expect("failed to process fixed width input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        r#"client,available,held,total,locked
+1,7.50,0,7.50,false
+"#
+    )
+}
+
+#[test]
+fn process_xml_success() {
+    let mapping = r#"{
+        "record": "Payment",
+        "type": "@kind",
+        "client": "Account",
+        "tx": "@id",
+        "amount": "Amount",
+        "types": {"CR": "deposit", "DB": "withdrawal"}
+    }"#;
+    let input = r#"<?xml version="1.0"?>
+<Payments>
+  <Payment kind="CR" id="1"><Account>1</Account><Amount>10.00</Amount></Payment>
+  <Payment kind="DB" id="2"><Account>1</Account><Amount>2.50</Amount></Payment>
+</Payments>"#;
+    let mut writer = Cursor::new(Vec::new());
+
+    cli::process_statement(
+        input.as_bytes(),
+        InputFormat::Xml,
+        None,
+        writer.get_mut(),
+        ProcessOptions {
+            xml: Some(XmlMapping::from_reader(mapping.as_bytes()).expect("invalid mapping")),
+            ..Default::default()
+        },
+    )
+    .expect("failed to process xml input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        r#"client,available,held,total,locked
+1,7.50,0,7.50,false
+"#
+    )
+}
+
+#[test]
+fn process_mt940_success() {
+    let input = r#":20:STARTUMSE
+:25:10020030/2
+:60F:C240101EUR0,00
+:61:240101C10,00NTRF1
+:61:240102D2,5NCHG2
+:62F:C240102EUR7,50"#;
+    let mut writer = Cursor::new(Vec::new());
+
+    cli::process_statement(
+        input.as_bytes(),
+        InputFormat::Mt940,
+        None,
+        writer.get_mut(),
+        ProcessOptions::default(),
+    )
+    .expect("failed to process mt940");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        r#"client,available,held,total,locked
+2,7.50,0,7.50,false
+"#
+    )
+}
+
+#[test]
+fn process_input_results_success() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 1, 2, 2.0a
+withdrawal, 1, 3, 5.0
+dispute, 1, 1"#;
+    let results = SharedBuffer::default();
+    let options = ProcessOptions {
+        results: Some(Box::new(results.clone())),
+        ..Default::default()
+    };
+
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    let output = results.to_string();
+    let lines: Vec<_> = output.lines().collect();
+
+    assert_eq!(lines.len(), 5);
+    assert_eq!(
+        lines[0],
+        "line,type,client,tx,status,code,reason,dispute_state"
+    );
+    assert_eq!(lines[1], "2,deposit,1,1,accepted,,,none");
+    assert!(lines[2].starts_with("3,,,,parse_error,parse_error,invalid amount:"));
+    assert_eq!(
+        lines[3],
+        "4,withdrawal,1,3,rejected,insufficient_funds,insufficient available funds,"
+    );
+    assert_eq!(lines[4], "5,dispute,1,1,accepted,,,open");
+}
+
+#[test]
+fn process_input_result_balances() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 2.0
+deposit, 1, 2, x
+withdrawal, 1, 3, 5.0
+dispute, 1, 1
+withdrawal, 2, 4, 1.0"#;
+    let results = SharedBuffer::default();
+    let options = ProcessOptions {
+        results: Some(Box::new(results.clone())),
+        result_balances: true,
+        ..Default::default()
+    };
+
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    let output = results.to_string();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(
+        lines[0],
+        "line,type,client,tx,status,code,reason,dispute_state,available,held,total"
+    );
+    assert_eq!(lines[1], "2,deposit,1,1,accepted,,,none,2.0,0,2.0");
+    assert!(lines[2].ends_with(",,,"));
+    assert_eq!(
+        lines[3],
+        "4,withdrawal,1,3,rejected,insufficient_funds,insufficient available funds,,2.0,0,2.0"
+    );
+    assert_eq!(lines[4], "5,dispute,1,1,accepted,,,open,0.0,2.0,2.0");
+    assert_eq!(
+        lines[5],
+        "6,withdrawal,2,4,rejected,insufficient_funds,insufficient available funds,,0,0,0"
+    );
+}
+
+/// in memory writer that can be read after being moved into the process options
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for SharedBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.0.lock().unwrap()))
+    }
+}
+
+/// records the output length at every flush
+#[derive(Default)]
+struct FlushLog {
+    buffer: Vec<u8>,
+    flushes: Vec<usize>,
+}
+
+impl Write for FlushLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes.push(self.buffer.len());
+        Ok(())
+    }
+}
+
+#[test]
+fn process_input_flush_every() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 2, 2, 1.0
+deposit, 3, 3, 1.0"#;
+    let test_cases = vec![
+        (None, vec![89, 89]),
+        (Some(FlushInterval::Rows(2)), vec![71, 89, 89]),
+        (Some(FlushInterval::Bytes(40)), vec![40, 80, 89, 89, 89]),
+    ];
+
+    for (flush_every, expected_flushes) in test_cases {
+        let options = ProcessOptions {
+            flush_every,
+            ..Default::default()
+        };
+        let mut output = FlushLog::default();
+        cli::process_input_with_options(input.as_bytes(), &mut output, options)
+            .expect("failed to process input");
+
+        assert_eq!(output.flushes, expected_flushes, "{:?}", flush_every);
+    }
+}
+
+#[test]
+fn process_input_duplicate_policy() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 1, 1, 1.0
+deposit, 1, 1, 2.0
+deposit, 1, 2, 2.0"#;
+
+    let mut options = ProcessOptions::default();
+    options.engine.duplicate_policy = DuplicatePolicy::IgnoreIfIdentical;
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+    assert_eq!(
+        output,
+        "client,available,held,total,locked\n1,3.0,0,3.0,false\n"
+    );
+
+    let mut options = ProcessOptions::default();
+    options.engine.duplicate_policy = DuplicatePolicy::Abort;
+    let result = cli::process_input_with_options(input.as_bytes(), io::sink(), options);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn process_input_dedup_window() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+dispute, 1, 1,
+deposit, 1, 1, 1.0
+dispute, 1, 1,
+deposit, 1, 2, 2.0"#;
+
+    let mut options = ProcessOptions::default();
+    options.engine.dedup_window = Some(10);
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+    assert_eq!(
+        output,
+        "client,available,held,total,locked\n1,2.0,1.0,3.0,false\n"
+    );
+}
+
+#[test]
+fn process_input_idempotency_key() {
+    let input = r#"type, client, tx, amount, timestamp, idempotency_key
+deposit, 1, 1, 10.0, , req-1
+deposit, 1, 2, 10.0, , req-1
+withdrawal, 1, 3, 5.0
+withdrawal, 1, 4, 5.0, , req-2"#;
+
+    let results = SharedBuffer::default();
+    let options = ProcessOptions {
+        results: Some(Box::new(results.clone())),
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    cli::process_input_with_options(input.as_bytes(), &mut output, options)
+        .expect("failed to process input");
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked\n1,0.0,0,0,false\n"
+    );
+    // the retry of the first deposit is acknowledged without a second credit
+    assert_eq!(
+        results.to_string().lines().nth(2),
+        Some("3,deposit,1,2,accepted,,,")
+    );
+}
+
+#[test]
+fn process_input_settled_retention() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 10.0
+dispute, 1, 1,
+chargeback, 1, 1,
+deposit, 2, 2, 5.0
+dispute, 1, 1,"#;
+
+    let results = SharedBuffer::default();
+    let mut options = ProcessOptions {
+        results: Some(Box::new(results.clone())),
+        ..Default::default()
+    };
+    options.engine.retention.after_txs = Some(1);
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    // the charged back deposit was dropped, the late dispute doesn't find it
+    assert_eq!(
+        results.to_string().lines().last(),
+        Some("6,dispute,1,1,rejected,tx_not_found,\"tx not found, partner error\",")
+    );
+}
+
+#[test]
+fn process_input_archive() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 10.0
+deposit, 2, 2, 5.0
+deposit, 2, 3, 5.0
+dispute, 1, 1,
+resolve, 1, 1,"#;
+
+    let path = std::env::temp_dir().join(format!("ttx-archive-it-{}.bin", std::process::id()));
+    let mut options = ProcessOptions {
+        archive: Some(Box::new(
+            cli::FileArchive::open(&path, false).expect("failed to create archive"),
+        )),
+        ..Default::default()
+    };
+    options.engine.retention.after_txs = Some(1);
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    // the dispute and resolve of the archived deposit are applied
+    assert_eq!(
+        String::from_utf8(writer.into_inner()).unwrap(),
+        "client,available,held,total,locked\n1,10.0,0.0,10.0,false\n2,10.0,0,10.0,false\n"
+    );
+    std::fs::remove_file(path).expect("failed to remove archive");
+}
+
+#[test]
+fn process_input_velocity_limits() {
+    let input = r#"type, client, tx, amount, timestamp
+deposit, 1, 1, 10.0, 2024-01-01T08:00:00Z
+withdrawal, 1, 2, 1.0, 2024-01-01T08:00:00Z
+withdrawal, 1, 3, 1.0, 2024-01-01T08:30:00Z
+withdrawal, 1, 4, 1.0, 2024-01-01T09:00:00Z
+withdrawal, 1, 5, 4.0, 2024-01-01T10:00:00Z"#;
+
+    let mut options = ProcessOptions::default();
+    options.engine.velocity.max_withdrawals = Some(1);
+    options.engine.velocity.window = Some(Duration::from_secs(3600));
+    options.engine.velocity.max_daily_amount = Some(Decimal::new(5, 0));
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+    // tx 3 is within the hour of tx 2, tx 5 is over the daily amount
+    assert_eq!(
+        output,
+        "client,available,held,total,locked,last_activity\n1,8.0,0,8.0,false,2024-01-01T09:00:00Z\n"
+    );
+}
+
+#[test]
+fn process_input_filter() {
+    let input = r#"type, client, tx, amount
+payment, 1, 1, 500.0
+deposit, 2, 2, 5.0
+withdrawal, 1, 3, 1.0
+refund, 1, 4, 1.0"#;
+
+    let mut options = ProcessOptions::default();
+    options.input_filter.blocked_clients.insert(2);
+    options.input_filter.clamp_amount = Some(Decimal::new(100, 0));
+    options
+        .input_filter
+        .type_aliases
+        .insert("payment".to_string(), TxType::Deposit);
+    let results = SharedBuffer::default();
+    options.results = Some(Box::new(results.clone()));
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+    assert_eq!(
+        output,
+        "client,available,held,total,locked\n1,99.0,0,99.0,false\n"
+    );
+    // the blocked client's deposit has no result, unaliased types are still parse errors
+    assert_eq!(
+        results.to_string(),
+        r#"line,type,client,tx,status,code,reason,dispute_state
+2,deposit,1,1,accepted,,,none
+4,withdrawal,1,3,accepted,,,none
+5,,,,parse_error,parse_error,invalid type: unknown transaction type `refund`,
+"#
+    );
+}
+
+#[test]
+fn process_pseudonymized() {
+    let input = "type, client, tx, amount\ndeposit, 1, 1, 5.0\nwithdrawal, 1, 2, 9.0";
+
+    let process = || {
+        let mut options = ProcessOptions {
+            pseudonyms: Some(Pseudonyms::new(b"secret").expect("invalid key")),
+            ..Default::default()
+        };
+        let results = SharedBuffer::default();
+        options.results = Some(Box::new(results.clone()));
+        let mut output = Vec::new();
+        cli::process_input_with_options(input.as_bytes(), &mut output, options)
+            .expect("failed to process input");
+
+        (String::from_utf8(output).unwrap(), results.to_string())
+    };
+
+    let (output, results) = process();
+    let token = output
+        .lines()
+        .nth(1)
+        .and_then(|row| row.split(',').next())
+        .expect("missing report row")
+        .to_string();
+    assert_eq!(token.len(), 16);
+    assert_eq!(
+        output,
+        format!(
+            "client,available,held,total,locked\n{},5.0,0,5.0,false\n",
+            token
+        )
+    );
+    assert_eq!(
+        results,
+        format!(
+            "line,type,client,tx,status,code,reason,dispute_state\n\
+2,deposit,{0},1,accepted,,,none\n\
+3,withdrawal,{0},2,rejected,insufficient_funds,insufficient available funds,\n",
+            token
+        )
+    );
+    assert_eq!(process(), (output, results));
+}
+
+#[test]
+fn process_input_aml_threshold() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 6000
+deposit, 2, 2, 12000
+withdrawal, 1, 3, 1000
+deposit, 1, 4, 5000
+deposit, 3, 5, 10000"#;
+
+    let aml_report = SharedBuffer::default();
+    let options = ProcessOptions {
+        aml: Some(AmlOptions {
+            threshold: Decimal::new(10000, 0),
+            output: Box::new(aml_report.clone()),
+        }),
+        ..Default::default()
+    };
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    assert_eq!(
+        aml_report.to_string(),
+        "client,tx,amount,cumulative,trigger\n2,2,12000,12000,single\n1,4,5000,11000,cumulative\n"
+    );
+}
+
+#[test]
+fn process_input_anomalies() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 100
+withdrawal, 1, 2, 1
+withdrawal, 1, 3, 2
+withdrawal, 1, 4, 50
+withdrawal, 1, 5, 1000
+withdrawal, 1, 6, 40"#;
+
+    let anomalies = SharedBuffer::default();
+    let options = ProcessOptions {
+        anomalies: Some(AnomalyOptions {
+            thresholds: AnomalyThresholds {
+                min_history: 2,
+                ..Default::default()
+            },
+            output: Box::new(anomalies.clone()),
+        }),
+        ..Default::default()
+    };
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    // the flagged withdrawal is still applied, the rejected one isn't checked
+    assert_eq!(
+        anomalies.to_string(),
+        "client,tx,type,amount,average\n1,4,withdrawal,50,1.5\n"
+    );
+    assert_eq!(
+        String::from_utf8(writer.into_inner()).unwrap(),
+        "client,available,held,total,locked\n1,7,0,7,false\n"
+    );
+}
+
+#[test]
+fn process_input_report_filter() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 2, 2, 5.0
+deposit, 3, 3, 0
+deposit, 4, 4, 3.0
+dispute, 4, 4,
+chargeback, 4, 4,"#;
+
+    let test_cases = vec![
+        (
+            ReportFilter {
+                only_nonzero: true,
+                ..Default::default()
+            },
+            "client,available,held,total,locked\n1,1.0,0,1.0,false\n2,5.0,0,5.0,false\n",
+        ),
+        (
+            ReportFilter {
+                only_locked: true,
+                ..Default::default()
+            },
+            "client,available,held,total,locked\n4,0.0,0.0,0.0,true\n",
+        ),
+        (
+            ReportFilter {
+                min_total: Some(Decimal::from(2)),
+                ..Default::default()
+            },
+            "client,available,held,total,locked\n2,5.0,0,5.0,false\n",
+        ),
+    ];
+
+    for (report_filter, expected_output) in test_cases {
+        let options = ProcessOptions {
+            report_filter,
+            ..Default::default()
+        };
+        let mut writer = Cursor::new(Vec::new());
+        cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+            .expect("failed to process input");
+
+        let mut output = String::new();
+        writer
+            .read_to_string(&mut output)
+            .expect("failed to read output");
+        assert_same_rows(&output, expected_output, "test case report filter");
+    }
+}
+
+#[test]
+fn process_input_report_detail_extended() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 1, 2, 2.0
+withdrawal, 1, 3, 1.0
+withdrawal, 1, 4, 9.0
+dispute, 1, 1,
+dispute, 1, 2,
+chargeback, 1, 2,"#;
+    let options = ProcessOptions {
+        report_detail: ReportDetail::Extended,
+        ..Default::default()
+    };
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        r#"client,available,held,total,locked,deposits,withdrawals,open_disputes,chargebacks,rejected
+1,-1.0,1.0,0.0,true,2,1,1,1,1
+"#
+    );
+}
+
+#[test]
+fn process_input_risk_columns() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 1, 2, 2.0
+deposit, 1, 3, 4.0
+dispute, 1, 1,
+resolve, 1, 1,
+dispute, 1, 2,
+chargeback, 1, 2,
+deposit, 2, 4, 1.0"#;
+    let options = ProcessOptions {
+        risk_columns: true,
+        ..Default::default()
+    };
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        r#"client,available,held,total,locked,chargebacks,disputes_opened,chargeback_ratio
+1,5.0,0.0,5.0,true,1,2,0.3333
+2,1.0,0,1.0,false,0,0,0
+"#
+    );
+}
+
+#[test]
+fn process_input_lock_reason() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 1, 2, 2.0
+dispute, 1, 2,
+chargeback, 1, 2,
+deposit, 2, 3, 1.0"#;
+    let options = ProcessOptions {
+        lock_reason: true,
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    cli::process_input_with_options(input.as_bytes(), &mut output, options)
+        .expect("failed to process input");
 
-    assert_eq!(a_vec, b_vec, "failed: {}", msg)
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        r#"client,available,held,total,locked,lock_reason
+1,1.0,0.0,1.0,true,chargeback:2
+2,1.0,0,1.0,false,
+"#
+    );
+}
+
+#[test]
+fn process_input_output_format() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 2, 2, 20.5"#;
+
+    for (output_format, expected) in [
+        (
+            OutputFormat::Json,
+            r#"[
+  {"client":1,"available":"1.0","held":"0","total":"1.0","locked":false},
+  {"client":2,"available":"20.5","held":"0","total":"20.5","locked":false}
+]
+"#,
+        ),
+        (
+            OutputFormat::Table,
+            r#"client  available  held  total  locked
+1       1.0        0     1.0    false
+2       20.5       0     20.5   false
+"#,
+        ),
+    ] {
+        let options = ProcessOptions {
+            output_format,
+            ..Default::default()
+        };
+        let mut writer = Cursor::new(Vec::new());
+        cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+            .expect("failed to process input");
+
+        let mut output = String::new();
+        writer
+            .read_to_string(&mut output)
+            .expect("failed to read output");
+        assert_eq!(output, expected);
+    }
+}
+
+#[test]
+fn process_input_extra_columns() {
+    let input = r#"type, client, tx, amount, currency, memo
+deposit, 1, 1, 1.0, EUR, first
+withdrawal, 1, 2, 0.5, EUR,"#;
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input(input.as_bytes(), writer.get_mut()).expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        "client,available,held,total,locked\n1,0.5,0,0.5,false\n"
+    );
+}
+
+#[test]
+fn process_input_missing_columns() {
+    let input = r#"kind, client, id, amount
+deposit, 1, 1, 1.0"#;
+    let err = cli::process_input(input.as_bytes(), io::sink()).expect_err("input should fail");
+
+    assert_eq!(err.to_string(), "missing required columns: type, tx");
+}
+
+#[test]
+fn process_input_utf16le() {
+    let input = "type,client,tx,amount\r\ndeposit,1,1,1.5\r\n";
+    let mut encoded = vec![0xff, 0xfe];
+    for unit in input.encode_utf16() {
+        encoded.extend_from_slice(&unit.to_le_bytes());
+    }
+    let options = ProcessOptions {
+        encoding: InputEncoding::Utf16le,
+        ..Default::default()
+    };
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(encoded.as_slice(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_eq!(
+        output,
+        "client,available,held,total,locked\n1,1.5,0,1.5,false\n"
+    );
+}
+
+#[test]
+fn process_input_decimal_comma() {
+    let input = r#"type;client;tx;amount
+deposit;1;1;1.234,56
+withdrawal;1;2;0,5
+deposit;2;3;7"#;
+    let options = ProcessOptions {
+        csv: CsvOptions {
+            decimal_separator: DecimalSeparator::Comma,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_same_rows(
+        &output,
+        r#"client,available,held,total,locked
+1,1234.06,0,1234.06,false
+2,7,0,7,false"#,
+        "test case decimal comma",
+    );
+}
+
+#[test]
+fn process_input_no_header() {
+    let input = r#"deposit, 1, 1, 1.0
+deposit, 2, 2, 2.0
+withdrawal, 1, 3, 0.5
+dispute, 2, 2"#;
+    let options = ProcessOptions {
+        csv: CsvOptions {
+            no_header: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_same_rows(
+        &output,
+        r#"client,available,held,total,locked
+1,0.5,0,0.5,false
+2,0.0,2.0,2.0,false"#,
+        "test case no header",
+    );
+}
+
+#[test]
+fn process_input_last_activity() {
+    let input = r#"type, client, tx, amount, timestamp
+deposit, 1, 1, 1.0, 2024-03-01T09:00:00Z
+deposit, 2, 2, 2.0,
+withdrawal, 1, 3, 1.0, 2024-03-02T09:00:00+02:00
+withdrawal, 1, 4, 9.0, 2024-03-05T09:00:00Z"#;
+    let mut writer = Cursor::new(Vec::new());
+    cli::process_input(input.as_bytes(), writer.get_mut()).expect("failed to process input");
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+
+    assert_same_rows(
+        &output,
+        r#"client,available,held,total,locked,last_activity
+1,0.0,0,0,false,2024-03-02T07:00:00Z
+2,2.0,0,2.0,false,"#,
+        "test case last activity",
+    );
+}
+
+#[test]
+fn process_input_report_snapshots() {
+    let snapshots = run_snapshots(false);
+
+    assert_eq!(snapshots.len(), 2);
+    assert_same_rows(
+        &snapshots[0],
+        "client,available,held,total,locked\n1,3.0,0,3.0,false\n",
+        "test case first snapshot",
+    );
+    assert_same_rows(
+        &snapshots[1],
+        "client,available,held,total,locked\n1,3.0,0,3.0,false\n2,4.0,0,4.0,false\n",
+        "test case second snapshot",
+    );
+}
+
+#[test]
+fn process_input_report_delta_snapshots() {
+    let snapshots = run_snapshots(true);
+
+    assert_eq!(snapshots.len(), 2);
+    assert_eq!(
+        snapshots[0],
+        "client,available,held,total,locked\n1,3.0,0,3.0,false\n"
+    );
+    assert_eq!(
+        snapshots[1],
+        "client,available,held,total,locked\n2,4.0,0,4.0,false\n"
+    );
+}
+
+#[test]
+fn process_input_requested_snapshot() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 2, 2, 2.0"#;
+    let dir = env::temp_dir().join(format!("ttx-eng-requested-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("failed to create snapshot dir");
+
+    let options = ProcessOptions {
+        snapshots: Some(SnapshotOptions {
+            every: None,
+            dir: dir.clone(),
+            delta: false,
+            request: Some(Arc::new(AtomicBool::new(true))),
+        }),
+        ..Default::default()
+    };
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .expect("failed to read snapshot dir")
+        .map(|entry| entry.expect("failed to read entry").path())
+        .collect();
+    paths.sort();
+    assert_eq!(paths.len(), 2);
+    assert_eq!(
+        fs::read_to_string(&paths[0]).expect("failed to read snapshot"),
+        "client,available,held,total,locked\n1,1.0,0,1.0,false\n"
+    );
+
+    let stats: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&paths[1]).expect("failed to read stats"))
+            .expect("invalid stats");
+    assert_eq!(
+        stats,
+        serde_json::json!({
+            "records": 1,
+            "clients": 1,
+            "locked_clients": 0,
+            "transactions": 1,
+            "duplicates": 0,
+            "replays": 0,
+            "velocity_rejections": 0,
+            "filtered": 0,
+            "clamped": 0,
+            "errors": {},
+            "tx_types": {"deposit": {"accepted": 1, "rejected": 0}},
+        })
+    );
+    fs::remove_dir_all(&dir).expect("failed to remove snapshot dir");
+}
+
+#[test]
+fn process_input_rejections_by_client() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+withdrawal, 1, 2, 5.0
+withdrawal, 1, 3, 5.0
+dispute, 2, 1,
+deposit, 3, 4, 1.0"#;
+    let path = env::temp_dir().join(format!("ttx-eng-rejections-{}.csv", std::process::id()));
+
+    let options = ProcessOptions {
+        rejections_by_client: Some(path.clone()),
+        ..Default::default()
+    };
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    assert_eq!(
+        fs::read_to_string(&path).expect("failed to read rejections"),
+        "client,deposit,withdrawal,dispute,resolve,chargeback,total\n1,0,2,0,0,0,2\n2,0,0,1,0,0,1\n"
+    );
+    fs::remove_file(path).expect("failed to remove rejections");
+}
+
+#[test]
+fn process_input_locked_report() {
+    let input = r#"type, client, tx, amount, timestamp
+deposit, 1, 1, 1.0, 2024-01-01T00:00:00Z
+deposit, 2, 2, 2.5,
+deposit, 3, 3, 4.0,
+dispute, 2, 2,,
+chargeback, 2, 2,,
+dispute, 1, 1,, 2024-01-02T00:00:00Z
+chargeback, 1, 1,, 2024-01-03T00:00:00Z
+dispute, 3, 3,,"#;
+    let path = env::temp_dir().join(format!("ttx-eng-locked-{}.csv", std::process::id()));
+
+    let options = ProcessOptions {
+        locked_report: Some(path.clone()),
+        ..Default::default()
+    };
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    assert_eq!(
+        fs::read_to_string(&path).expect("failed to read locked report"),
+        "client,tx,amount,timestamp\n1,1,1.0,2024-01-03T00:00:00Z\n2,2,2.5,\n"
+    );
+    fs::remove_file(path).expect("failed to remove locked report");
+}
+
+#[test]
+fn process_input_value_dated() {
+    let input = r#"type, client, tx, amount, effective_date
+deposit, 1, 1, 5.0, 2024-03-01
+deposit, 1, 2, 2.0, 2024-03-02
+withdrawal, 1, 3, 1.0,
+deposit, 2, 4, 3.0, 2024-04-01"#;
+    let path = env::temp_dir().join(format!("ttx-eng-queued-{}.csv", std::process::id()));
+
+    let mut options = ProcessOptions {
+        queued: Some(path.clone()),
+        ..Default::default()
+    };
+    options.engine.as_of = NaiveDate::from_ymd_opt(2024, 3, 1);
+    let mut output = Vec::new();
+    cli::process_input_with_options(input.as_bytes(), &mut output, options)
+        .expect("failed to process input");
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked\n1,4.0,0,4.0,false\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&path).expect("failed to read queued transactions"),
+        "type,client,tx,amount,timestamp,idempotency_key,batch,effective_date
+deposit,1,2,2.0,,,,2024-03-02
+deposit,2,4,3.0,,,,2024-04-01
+"
+    );
+    fs::remove_file(path).expect("failed to remove queued transactions");
+}
+
+#[test]
+fn process_input_rollup() {
+    let input = r#"type, client, tx, amount, timestamp
+deposit, 1, 1, 10.0, 2024-03-01T09:00:00Z
+deposit, 2, 2, 5.0, 2024-03-01T10:00:00Z
+withdrawal, 1, 3, 2.0,
+withdrawal, 2, 4, 50.0, 2024-03-01T11:00:00Z
+dispute, 2, 2,, 2024-03-02T08:00:00Z
+chargeback, 2, 2,, 2024-03-02T09:00:00Z
+deposit, 1, 5, 1.5, 2024-03-02T10:00:00Z"#;
+    let path = env::temp_dir().join(format!("ttx-eng-rollup-{}.csv", std::process::id()));
+
+    let options = ProcessOptions {
+        rollup: Some(RollupOptions {
+            period: Rollup::Daily,
+            path: path.clone(),
+        }),
+        ..Default::default()
+    };
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    assert_eq!(
+        fs::read_to_string(&path).expect("failed to read rollup"),
+        "date,deposits,withdrawals,chargebacks,net_flow,closing_total
+2024-03-01,15.0,2.0,0,13.0,13.0
+2024-03-02,1.5,0,5.0,-3.5,9.5
+"
+    );
+    fs::remove_file(path).expect("failed to remove rollup");
+}
+
+#[test]
+fn process_input_balance_history() {
+    let input = r#"type, client, tx, amount, timestamp
+deposit, 1, 1, 10.0, 2024-03-01T09:00:00Z
+deposit, 2, 2, 5.0,
+dispute, 1, 1,, 2024-03-03T10:00:00Z
+withdrawal, 2, 3, 1.0, 2024-03-02T10:00:00Z"#;
+    let history = SharedBuffer::default();
+    let options = ProcessOptions {
+        balance_history: Some(Box::new(history.clone())),
+        ..Default::default()
+    };
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    assert_eq!(
+        history.to_string(),
+        "client,date,available,held
+1,2024-03-01,10.0,0
+2,2024-03-01,5.0,0
+1,2024-03-03,0.0,10.0
+2,2024-03-03,4.0,0
+"
+    );
+}
+
+#[test]
+fn process_input_fees() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 100.0
+withdrawal, 1, 2, 10.0
+withdrawal, 1, 3, 89.5
+dispute, 1, 1,
+chargeback, 1, 1,"#;
+    let fees = SharedBuffer::default();
+    let mut options = ProcessOptions {
+        fees: Some(Box::new(fees.clone())),
+        ..Default::default()
+    };
+    options.engine.fees.withdrawal_percent = Some(Decimal::ONE);
+    options.engine.fees.chargeback_flat = Some(Decimal::TEN);
+    let mut output = Vec::new();
+    cli::process_input_with_options(input.as_bytes(), &mut output, options)
+        .expect("failed to process input");
+
+    assert_eq!(
+        fees.to_string(),
+        "client,tx,kind,amount\n1,2,withdrawal,0.1\n"
+    );
+    // the chargeback fee isn't taken from the negative available funds
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked\n1,-10.1,0.0,-10.1,true\n"
+    );
+}
+
+#[test]
+fn process_input_fee_tiers() {
+    let input = r#"type, client, tx, amount, timestamp
+deposit, 1, 1, 60.0, 2024-03-01T10:00:00Z
+deposit, 1, 2, 60.0, 2024-03-02T10:00:00Z
+deposit, 2, 3, 150.0, 2024-03-02T10:00:00Z
+withdrawal, 1, 4, 5.0,
+withdrawal, 1, 5, 5.0,
+deposit, 1, 6, 60.0, 2024-04-01T10:00:00Z"#;
+    let fees = SharedBuffer::default();
+    let mut options = ProcessOptions {
+        fees: Some(Box::new(fees.clone())),
+        ..Default::default()
+    };
+    options.engine.fees.free_withdrawals = Some(1);
+    options.engine.fees.withdrawal_flat = Some(Decimal::ONE);
+    options.engine.fees.deposit_bps = Some(Decimal::ONE_HUNDRED);
+    options.engine.fees.deposit_threshold = Decimal::ONE_HUNDRED;
+    let mut output = Vec::new();
+    cli::process_input_with_options(input.as_bytes(), &mut output, options)
+        .expect("failed to process input");
+
+    assert_eq!(
+        fees.to_string(),
+        "client,tx,kind,amount\n1,2,deposit,0.2\n2,3,deposit,0.5\n1,5,withdrawal,1\n"
+    );
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked,last_activity\n\
+         1,168.8,0,168.8,false,2024-04-01T10:00:00Z\n\
+         2,149.5,0,149.5,false,2024-03-02T10:00:00Z\n"
+    );
+}
+
+#[test]
+fn process_input_interest_accruals() {
+    let input = r#"type, client, tx, amount, timestamp
+deposit, 1, 1, 1000.0, 2024-03-01T10:00:00Z
+deposit, 2, 2, 500.0, 2024-03-01T11:00:00Z
+withdrawal, 1, 3, 500.0, 2024-03-03T10:00:00Z
+deposit, 3, 4, 100.0, 2024-03-03T11:00:00Z"#;
+    let accruals = SharedBuffer::default();
+    let mut rates = InterestRates {
+        default_rate: Some(Decimal::from(73)),
+        ..Default::default()
+    };
+    rates.segments.insert("staff".to_string(), Decimal::ZERO);
+    rates.clients.insert(2, "staff".to_string());
+    let mut options = ProcessOptions {
+        interest: Some(InterestOptions {
+            rates,
+            output: Box::new(accruals.clone()),
+        }),
+        ..Default::default()
+    };
+    options.engine.as_of = NaiveDate::from_ymd_opt(2024, 3, 4);
+    cli::process_input_with_options(input.as_bytes(), &mut Vec::new(), options)
+        .expect("failed to process input");
+
+    // a zero rate accrues nothing, the open day accrues up to the as-of date
+    assert_eq!(
+        accruals.to_string(),
+        "client,from,to,days,balance,rate,interest\n\
+         1,2024-03-01,2024-03-03,2,1000.0,73,4.0\n\
+         2,2024-03-01,2024-03-03,2,500.0,0,0\n\
+         1,2024-03-03,2024-03-04,1,500.0,73,1.0\n\
+         2,2024-03-03,2024-03-04,1,500.0,0,0\n\
+         3,2024-03-03,2024-03-04,1,100.0,73,0.2\n"
+    );
+}
+
+#[test]
+fn process_input_cross_check() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 10.0
+dispute, 1, 1,
+resolve, 1, 1,
+dispute, 1, 1,
+deposit, 2, 2, 5.0"#;
+    let run = |engine: &str| {
+        let mut options = ProcessOptions::default();
+        let mut config = options.engine.clone();
+        EngineSection::from_toml(engine)
+            .expect("invalid engine options")
+            .apply(&mut config);
+        options.cross_check = Some(config);
+        cli::process_input_with_options(input.as_bytes(), &mut Vec::new(), options)
+    };
+
+    assert!(run("max_disputes = 2").is_ok());
+    // the second engine rejects the second dispute, the report is not written
+    let err = run("no_redispute = true").expect_err("the reports should differ");
+    assert_eq!(
+        err.to_string(),
+        "cross-check failed: the reports differ for clients 1"
+    );
+}
+
+/// events written by a fmt subscriber, without colors
+#[derive(Clone, Default)]
+struct Events(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Events {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn process_input_tracing_fields() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+withdrawal, 1, 2, 5.0
+deposit, x, 3, 1.0"#;
+    let events = Events::default();
+    let writer = events.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        cli::process_input(input.as_bytes(), &mut Vec::new()).expect("failed to process input")
+    });
+
+    let events = String::from_utf8(events.0.lock().unwrap().clone()).unwrap();
+    assert!(events.contains("transaction applied tx_id=1 client_id=1 tx_type=deposit"));
+    assert!(events.contains(
+        "transaction rejected: insufficient available funds tx_id=2 client_id=1 tx_type=withdrawal error_code=\"insufficient_funds\""
+    ));
+    assert!(events.contains("record not parsed line=4"));
+}
+
+#[test]
+fn process_input_stats() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+withdrawal, 1, 2, 5.0
+dispute, 2, 1,
+dispute, 1, 1,
+deposit, 1, x, 1.0
+dispute, 3, 1,"#;
+    let path = env::temp_dir().join(format!("ttx-eng-stats-{}.json", std::process::id()));
+
+    let options = ProcessOptions {
+        stats: Some(path.clone()),
+        ..Default::default()
+    };
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    let stats: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).expect("failed to read stats"))
+            .expect("invalid stats");
+    assert_eq!(stats["records"], 6);
+    assert_eq!(
+        stats["errors"],
+        serde_json::json!({
+            "client_id_no_match": 2,
+            "insufficient_funds": 1,
+            "parse_error": 1,
+        })
+    );
+    assert_eq!(
+        stats["tx_types"],
+        serde_json::json!({
+            "deposit": {"accepted": 1, "rejected": 0},
+            "withdrawal": {"accepted": 0, "rejected": 1},
+            "dispute": {"accepted": 1, "rejected": 2},
+        })
+    );
+    fs::remove_file(path).expect("failed to remove stats");
+}
+
+#[test]
+fn process_input_mmap() {
+    let dir = env::temp_dir().join(format!("ttx-eng-mmap-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("failed to create dir");
+    let path = dir.join("input.csv");
+    fs::write(&path, "type,client,tx,amount\ndeposit,1,1,1.0\n").expect("failed to write input");
+    let empty = dir.join("empty.csv");
+    fs::write(&empty, "").expect("failed to write input");
+
+    for mmap in [true, false] {
+        let input = cli::open_input(path.to_str().unwrap(), mmap).expect("failed to open input");
+        let mut writer = Cursor::new(Vec::new());
+        cli::process_input(input, writer.get_mut()).expect("failed to process input");
+
+        let mut output = String::new();
+        writer
+            .read_to_string(&mut output)
+            .expect("failed to read output");
+        assert_eq!(
+            output,
+            "client,available,held,total,locked\n1,1.0,0,1.0,false\n"
+        );
+    }
+
+    assert_eq!(cli::estimate_txs(path.to_str().unwrap()), Some(1));
+    assert_eq!(cli::estimate_txs("-"), None);
+
+    // empty files can't be mapped and are read through a buffer
+    let mut input = cli::open_input(empty.to_str().unwrap(), true).expect("failed to open input");
+    let mut content = String::new();
+    input
+        .read_to_string(&mut content)
+        .expect("failed to read input");
+    assert!(content.is_empty());
+
+    fs::remove_dir_all(&dir).expect("failed to remove dir");
+}
+
+#[test]
+fn process_input_stopped() {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0"#;
+    let options = ProcessOptions {
+        stop: Some(Arc::new(AtomicUsize::new(15))),
+        ..Default::default()
+    };
+    let mut writer = Cursor::new(Vec::new());
+    let err = cli::process_input_with_options(input.as_bytes(), writer.get_mut(), options)
+        .expect_err("processing should be interrupted");
+
+    let interrupted = err
+        .downcast_ref::<Interrupted>()
+        .expect("error is not an interruption");
+    assert_eq!(interrupted.signal, 15);
+    assert_eq!(interrupted.records, 0);
+
+    let mut output = String::new();
+    writer
+        .read_to_string(&mut output)
+        .expect("failed to read output");
+    assert_eq!(
+        output,
+        "# partial report: interrupted by signal 15 after 0 records\n"
+    );
+}
+
+/// processes a fixed input with a snapshot every 2 rows and returns the snapshots in order
+fn run_snapshots(delta: bool) -> Vec<String> {
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 1, 2, 2.0
+deposit, 2, 3, 2.0
+deposit, 2, 4, 2.0
+deposit, 2, 5, 2.0"#;
+    let dir = env::temp_dir().join(format!(
+        "ttx-eng-snapshots-{}-{}",
+        std::process::id(),
+        delta
+    ));
+    fs::create_dir_all(&dir).expect("failed to create snapshot dir");
+
+    let options = ProcessOptions {
+        snapshots: Some(SnapshotOptions {
+            every: Some(ReportInterval::Rows(2)),
+            dir: dir.clone(),
+            delta,
+            request: None,
+        }),
+        ..Default::default()
+    };
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect("failed to process input");
+
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .expect("failed to read snapshot dir")
+        .map(|entry| entry.expect("failed to read entry").path())
+        .collect();
+    paths.sort();
+    let snapshots = paths
+        .iter()
+        .map(|path| fs::read_to_string(path).expect("failed to read snapshot"))
+        .collect();
+    fs::remove_dir_all(&dir).expect("failed to remove snapshot dir");
+
+    snapshots
 }
 
 struct TestCase<'a> {
@@ -93,6 +1818,20 @@ deposit, 1, 1, 1.0"#,
         },
         TestCase {
             input: r#"type, client, tx, amount
+deposit, 1, 1, 5.0
+withdrawal, 1, 2, 1.0
+deposit, 1, 3, 2.0
+reversal, 1, 2,
+reversal, 1, 3,
+reversal, 1, 3,
+dispute, 1, 3,"#,
+            expected_output: r#"client,available,held,total,locked
+1,5.0,0,5.0,false
+"#,
+            msg: "test case reversal",
+        },
+        TestCase {
+            input: r#"type, client, tx, amount
 deposit, 1, 1, 1.12345678"#,
             expected_output: r#"client,available,held,total,locked
 1,1.1235,0,1.1235,false
@@ -185,3 +1924,327 @@ dispute, 1, 2"#,
         },
     ]
 }
+
+#[test]
+fn run_pipeline_sources_filter_sinks() {
+    let dir = env::temp_dir().join(format!("ttx-eng-pipeline-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("failed to create dir");
+    fs::write(
+        dir.join("a.csv"),
+        "type,client,tx,amount\ndeposit,1,1,10\ndeposit,2,2,5\n",
+    )
+    .expect("failed to write input");
+    fs::write(dir.join("b.csv"), "withdrawal;1;3;2,5\ndeposit;3;4;7\n")
+        .expect("failed to write input");
+
+    let config = format!(
+        r#"
+[[source]]
+path = "{dir}/a.csv"
+
+[[source]]
+path = "{dir}/b.csv"
+no_header = true
+decimal_separator = "comma"
+
+[filter]
+clients = [1, 3]
+
+[[sink]]
+path = "{dir}/report.json"
+format = "json"
+"#,
+        dir = dir.display()
+    );
+    let config = PipelineConfig::from_toml(&config).expect("invalid pipeline");
+
+    let mut writer = Cursor::new(Vec::new());
+    cli::run_pipeline(config, writer.get_mut(), None).expect("failed to run pipeline");
+    assert!(writer.get_ref().is_empty());
+
+    assert_eq!(
+        fs::read_to_string(dir.join("report.json")).expect("failed to read report"),
+        r#"[
+  {"client":1,"available":"7.5","held":"0","total":"7.5","locked":false},
+  {"client":3,"available":"7","held":"0","total":"7","locked":false}
+]
+"#
+    );
+
+    fs::remove_dir_all(&dir).expect("failed to remove dir");
+}
+
+#[test]
+fn process_manifest() {
+    let dir = env::temp_dir().join(format!("ttx-eng-manifest-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("failed to create dir");
+    let input = "type,client,tx,amount\ndeposit,1,1,10\ndeposit,2,2,5\nwithdrawal,2,3,1\n";
+    fs::write(dir.join("input.csv"), input).expect("failed to write input");
+
+    let options = ProcessOptions {
+        results: Some(Box::new(
+            fs::File::create(dir.join("results.csv")).expect("failed to create results"),
+        )),
+        manifest: Some(ManifestOptions {
+            path: dir.join("manifest.json"),
+            signing_key: None,
+            inputs: vec![(dir.join("input.csv"), None)],
+            outputs: vec![(OutputKind::Results, dir.join("results.csv"))],
+        }),
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    cli::process_input_with_options(input.as_bytes(), &mut output, options)
+        .expect("failed to process input");
+
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&fs::read(dir.join("manifest.json")).expect("missing manifest"))
+            .expect("invalid manifest");
+    assert!(manifest["engine"]
+        .as_str()
+        .is_some_and(|engine| engine.starts_with("ttx-eng ")));
+    assert_eq!(manifest["inputs"][0]["bytes"], input.len());
+    assert_eq!(manifest["outputs"][0]["kind"], "report");
+    assert_eq!(manifest["outputs"][0]["path"], "-");
+    assert_eq!(manifest["outputs"][0]["rows"], 2);
+    assert_eq!(manifest["outputs"][0]["bytes"], output.len());
+    assert_eq!(manifest["outputs"][1]["kind"], "results");
+    assert_eq!(manifest["outputs"][1]["rows"], 3);
+    assert!(manifest.get("signer").is_none());
+
+    fs::remove_dir_all(dir).expect("failed to remove dir");
+}
+
+/// sets the stop signal once flushed, the first checkpoint interrupts the run
+struct StopOnFlush {
+    buffer: SharedBuffer,
+    stop: Arc<AtomicUsize>,
+}
+
+impl Write for StopOnFlush {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stop.store(15, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[test]
+fn process_resumed_checkpoint() {
+    let dir = env::temp_dir().join(format!("ttx-eng-checkpoint-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("failed to create dir");
+    let checkpoint = CheckpointOptions {
+        path: dir.join("checkpoint.json"),
+        every: Some(2),
+        resume: false,
+    };
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 10.0
+deposit, 2, 2, 5.0
+dispute, 1, 1
+withdrawal, 2, 3, 2.5
+resolve, 1, 1"#;
+
+    let results = SharedBuffer::default();
+    let stop = Arc::new(AtomicUsize::new(0));
+    let options = ProcessOptions {
+        results: Some(Box::new(StopOnFlush {
+            buffer: results.clone(),
+            stop: Arc::clone(&stop),
+        })),
+        checkpoint: Some(checkpoint.clone()),
+        stop: Some(stop),
+        ..Default::default()
+    };
+    let err = cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect_err("processing should be interrupted");
+    assert_eq!(
+        err.downcast_ref::<Interrupted>().map(|i| i.records),
+        Some(2)
+    );
+
+    let options = ProcessOptions {
+        results: Some(Box::new(results.clone())),
+        checkpoint: Some(CheckpointOptions {
+            resume: true,
+            ..checkpoint.clone()
+        }),
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    cli::process_input_with_options(input.as_bytes(), &mut output, options)
+        .expect("failed to resume");
+
+    assert_same_rows(
+        &String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked\n1,10.0,0.0,10.0,false\n2,2.5,0,2.5,false\n",
+        "resumed report",
+    );
+    assert_eq!(
+        results.to_string(),
+        "line,type,client,tx,status,code,reason,dispute_state
+2,deposit,1,1,accepted,,,none
+3,deposit,2,2,accepted,,,none
+4,dispute,1,1,accepted,,,open
+5,withdrawal,2,3,accepted,,,none
+6,resolve,1,1,accepted,,,resolved
+"
+    );
+    // a completed run removes its checkpoint
+    assert!(!checkpoint.path.exists());
+
+    fs::remove_dir_all(dir).expect("failed to remove dir");
+}
+
+#[test]
+fn admin_commands_on_checkpoint() {
+    let dir = env::temp_dir().join(format!("ttx-eng-admin-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("failed to create dir");
+    let checkpoint = CheckpointOptions {
+        path: dir.join("checkpoint.json"),
+        every: Some(5),
+        resume: false,
+    };
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 10.0
+deposit, 2, 2, 5.0
+dispute, 2, 2
+chargeback, 2, 2
+dispute, 1, 1
+deposit, 2, 3, 1.0"#;
+
+    let stop = Arc::new(AtomicUsize::new(0));
+    let options = ProcessOptions {
+        results: Some(Box::new(StopOnFlush {
+            buffer: SharedBuffer::default(),
+            stop: Arc::clone(&stop),
+        })),
+        checkpoint: Some(checkpoint.clone()),
+        stop: Some(stop),
+        ..Default::default()
+    };
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect_err("processing should be interrupted");
+
+    let commands = r#"action, client, tx, amount, reason
+unlock, 2, , , chargeback reversed by the network
+force_resolve, 1, 1, ,
+adjust, 1, , -2.5, fee refund
+force_resolve, 1, 1, ,
+unlock, 3, , ,"#;
+    let adjusted = dir.join("adjusted.json");
+    let mut audit = Vec::new();
+    cli::admin(
+        &checkpoint.path,
+        commands.as_bytes(),
+        &adjusted,
+        "2024-03-01T00:00:00Z".parse().unwrap(),
+        &mut audit,
+    )
+    .expect("failed to apply admin commands");
+
+    assert_eq!(
+        String::from_utf8(audit).unwrap(),
+        "line,at,action,client,tx,amount,reason,status,code,available,held,locked
+2,2024-03-01T00:00:00Z,unlock,2,,,chargeback reversed by the network,accepted,,0.0,0.0,false
+3,2024-03-01T00:00:00Z,force_resolve,1,1,,,accepted,,10.0,0.0,false
+4,2024-03-01T00:00:00Z,adjust,1,,-2.5,fee refund,accepted,,7.5,0.0,false
+5,2024-03-01T00:00:00Z,force_resolve,1,1,,,rejected,tx_not_under_dispute,7.5,0.0,false
+6,2024-03-01T00:00:00Z,unlock,3,,,,rejected,client_not_found,,,
+"
+    );
+
+    // the unlocked client takes the deposit after the checkpoint
+    let options = ProcessOptions {
+        checkpoint: Some(CheckpointOptions {
+            path: adjusted,
+            every: None,
+            resume: true,
+        }),
+        ..Default::default()
+    };
+    let mut output = Vec::new();
+    cli::process_input_with_options(input.as_bytes(), &mut output, options)
+        .expect("failed to resume");
+    assert_same_rows(
+        &String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked\n1,7.5,0.0,7.5,false\n2,1.0,0.0,1.0,false\n",
+        "adjusted report",
+    );
+
+    fs::remove_dir_all(dir).expect("failed to remove dir");
+}
+
+#[test]
+fn review_disputes_of_checkpoint() {
+    let dir = env::temp_dir().join(format!("ttx-eng-disputes-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("failed to create dir");
+    let checkpoint = CheckpointOptions {
+        path: dir.join("checkpoint.json"),
+        every: Some(6),
+        resume: false,
+    };
+    let input = r#"type, client, tx, amount
+deposit, 1, 1, 10.0
+deposit, 2, 2, 5.0
+deposit, 1, 3, 2.0
+dispute, 1, 3
+dispute, 2, 2
+dispute, 1, 1
+deposit, 2, 4, 1.0"#;
+
+    let stop = Arc::new(AtomicUsize::new(0));
+    let options = ProcessOptions {
+        results: Some(Box::new(StopOnFlush {
+            buffer: SharedBuffer::default(),
+            stop: Arc::clone(&stop),
+        })),
+        checkpoint: Some(checkpoint.clone()),
+        stop: Some(stop),
+        ..Default::default()
+    };
+    cli::process_input_with_options(input.as_bytes(), io::sink(), options)
+        .expect_err("processing should be interrupted");
+
+    let mut prompts = Vec::new();
+    let mut decisions = Vec::new();
+    cli::review_disputes(
+        &checkpoint.path,
+        "maybe\nr\n\nc\n".as_bytes(),
+        &mut prompts,
+        &mut decisions,
+        true,
+        "2024-03-01T00:00:00Z".parse().unwrap(),
+    )
+    .expect("failed to review disputes");
+
+    let prompts = String::from_utf8(prompts).unwrap();
+    assert!(prompts
+        .starts_with("3 open disputes\nclient 1 tx 1 held 10.0 (available 0.0, locked false)"));
+    assert!(prompts.contains("unknown answer \"maybe\""));
+    assert_eq!(
+        String::from_utf8(decisions).unwrap(),
+        "type,client,tx,amount,timestamp
+resolve,1,1,,2024-03-01T00:00:00Z
+chargeback,1,3,,2024-03-01T00:00:00Z
+"
+    );
+
+    // quitting or running out of answers writes nothing more
+    let mut decisions = Vec::new();
+    cli::review_disputes(
+        &checkpoint.path,
+        "s\nq\nr\n".as_bytes(),
+        io::sink(),
+        &mut decisions,
+        false,
+        "2024-03-01T00:00:00Z".parse().unwrap(),
+    )
+    .expect("failed to review disputes");
+    assert!(decisions.is_empty());
+
+    fs::remove_dir_all(dir).expect("failed to remove dir");
+}